@@ -5,16 +5,22 @@ import { buildCurrency, currencyToString } from './lib/utils'
 import { useSubstrateState } from './substrate-lib'
 import { TxButton } from './substrate-lib/components'
 
-const parseItem = ({ owner, pair, lpToken, fee }) => ({
+// The UI doesn't expose slippage limits or deadlines yet, so the widest bounds are used.
+const NO_MINIMUM = 0
+const NO_MAXIMUM = '340282366920938463463374607431768211455'
+const NO_DEADLINE = null
+
+const parseItem = ({ owner, pair, lpToken, fee, status }) => ({
   owner,
   lpToken: lpToken.toJSON(),
   fee,
   pair: pair.toJSON(),
+  status,
 })
 
 function Pool(props) {
   const { id, account, tokenQuery } = props
-  const { owner, pair, lpToken, fee } = props.pool
+  const { owner, pair, lpToken, fee, status: poolStatus } = props.pool
   const [balanceMap, setBalanceMap] = useState([])
   const [status, setStatus] = useState('')
 
@@ -70,6 +76,7 @@ function Pool(props) {
         </Card.Description>
         <Card.Description>Fee: {fee.toHuman()}</Card.Description>
         <Card.Description>Owner: {owner.toHuman()}</Card.Description>
+        <Card.Description>Status: {poolStatus.toHuman()}</Card.Description>
 
         <Divider horizontal>Add Liquidity</Divider>
         <Form style={{ margin: '1em 0' }}>
@@ -100,8 +107,10 @@ function Pool(props) {
                   id,
                   addLiquidityAmount,
                   buildCurrency(addLiquidityCurrency),
+                  NO_MINIMUM,
+                  NO_DEADLINE,
                 ],
-                paramFields: [true, true, true],
+                paramFields: [true, true, true, true, true],
               }}
             />
           </Form.Group>
@@ -127,8 +136,11 @@ function Pool(props) {
                 inputParams: [
                   id,
                   removeLiquidityAmount,
+                  NO_MINIMUM,
+                  NO_MINIMUM,
+                  NO_DEADLINE,
                 ],
-                paramFields: [true, true],
+                paramFields: [true, true, true, true, true],
               }}
             />
           </Form.Group>
@@ -159,8 +171,14 @@ function Pool(props) {
               attrs={{
                 palletRpc: 'dex',
                 callable: 'buy',
-                inputParams: [id, buildCurrency(swapCurrency), swapAmount],
-                paramFields: [true, true, true],
+                inputParams: [
+                  id,
+                  buildCurrency(swapCurrency),
+                  swapAmount,
+                  NO_MAXIMUM,
+                  NO_DEADLINE,
+                ],
+                paramFields: [true, true, true, true, true],
               }}
             />
             <TxButton
@@ -170,8 +188,14 @@ function Pool(props) {
               attrs={{
                 palletRpc: 'dex',
                 callable: 'sell',
-                inputParams: [id, buildCurrency(swapCurrency), swapAmount],
-                paramFields: [true, true, true],
+                inputParams: [
+                  id,
+                  buildCurrency(swapCurrency),
+                  swapAmount,
+                  NO_MINIMUM,
+                  NO_DEADLINE,
+                ],
+                paramFields: [true, true, true, true, true],
               }}
             />
           </Form.Group>
@@ -249,6 +273,9 @@ export default function Dex(props) {
   const [tokenA, setTokenA] = useState('')
   const [tokenB, setTokenB] = useState('')
   const [fee, setFee] = useState('')
+  const [amplification, setAmplification] = useState('')
+  const [weightA, setWeightA] = useState('')
+  const [tickSpacing, setTickSpacing] = useState('')
 
   const buildPoolCreationParams = () => {
     let owner = currentAccount?.address
@@ -256,10 +283,23 @@ export default function Dex(props) {
       token_a: buildCurrency(tokenA),
       token_b: buildCurrency(tokenB),
     }
+    // Pools with an amplification coefficient use the StableSwap curve, pools with a weight
+    // use the weighted curve and pools with a tick spacing use concentrated liquidity
+    let kind = 'ConstantProduct'
+    if (amplification) {
+      kind = { StableSwap: { amplification } }
+    } else if (weightA) {
+      kind = {
+        Weighted: { weight_a: weightA, weight_b: 1000000 - Number(weightA) },
+      }
+    } else if (tickSpacing) {
+      kind = { Concentrated: { tick_spacing: tickSpacing } }
+    }
     return {
       owner,
       pair,
       fee,
+      kind,
     }
   }
 
@@ -301,6 +341,30 @@ export default function Dex(props) {
             onChange={e => setFee(e.target.value)}
             style={{ flexGrow: 1 }}
           />
+          <Form.Input
+            fluid
+            label="Amplification (StableSwap only)"
+            placeholder="100"
+            value={amplification}
+            onChange={e => setAmplification(e.target.value)}
+            style={{ flexGrow: 1 }}
+          />
+          <Form.Input
+            fluid
+            label="Weight of Token A in Permill (Weighted only)"
+            placeholder="800000 -> 80%"
+            value={weightA}
+            onChange={e => setWeightA(e.target.value)}
+            style={{ flexGrow: 1 }}
+          />
+          <Form.Input
+            fluid
+            label="Tick Spacing (Concentrated only)"
+            placeholder="10"
+            value={tickSpacing}
+            onChange={e => setTickSpacing(e.target.value)}
+            style={{ flexGrow: 1 }}
+          />
           <TxButton
             label="Create Pool"
             type="SIGNED-TX"
@@ -61,6 +61,8 @@ export function currencyToString(currency) {
     } else {
       return hexToAscii(currency.token.long)
     }
+  } else if (currency?.lpToken !== undefined) {
+    return `LP-${currency.lpToken}`
   } else {
     return 'Native'
   }
@@ -1,12 +1,17 @@
 use node_template_runtime::{
-	AccountId, AuraConfig, CurrencyId, GenesisConfig, GrandpaConfig, NftsConfig,
-	Signature, SudoConfig, SystemConfig, TokenSymbol, TokensConfig, WASM_BINARY,
+	pallet_dex::traits::{CurrencyPair, PoolCreationParams, PoolKind},
+	AccountId, AssetRegistryConfig, AuraConfig, Balance, CurrencyId, DexConfig, GenesisConfig,
+	GrandpaConfig, NftsConfig, Signature, SudoConfig, SystemConfig, TokenSymbol, TokensConfig,
+	WASM_BINARY,
 };
 use sc_service::ChainType;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{sr25519, Pair, Public};
 use sp_finality_grandpa::AuthorityId as GrandpaId;
-use sp_runtime::traits::{IdentifyAccount, Verify};
+use sp_runtime::{
+	traits::{IdentifyAccount, Verify},
+	Permill,
+};
 
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
@@ -124,6 +129,37 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 	))
 }
 
+const USDC: CurrencyId = CurrencyId::Token(TokenSymbol::Short(*b"USDC"));
+const EURT: CurrencyId = CurrencyId::Token(TokenSymbol::Short(*b"EURT"));
+
+/// Every endowed account deposits this amount of each asset into every pool at genesis.
+const GENESIS_LIQUIDITY: Balance = 1 << 50;
+
+/// Constant product pools for all pairs of the endowed assets. Each of the endowed accounts
+/// provides the same amount of both assets, so all assets start at the same price.
+fn genesis_pools(
+	owner: AccountId,
+	endowed_accounts: &[AccountId],
+) -> Vec<(PoolCreationParams<AccountId, CurrencyId>, Vec<(AccountId, Balance, Balance)>)> {
+	[(CurrencyId::Native, USDC), (CurrencyId::Native, EURT), (USDC, EURT)]
+		.into_iter()
+		.map(|(token_a, token_b)| {
+			let pool_params = PoolCreationParams {
+				owner: owner.clone(),
+				pair: CurrencyPair { token_a, token_b },
+				fee: Permill::from_rational(3u32, 1000u32),
+				kind: PoolKind::ConstantProduct,
+				initial_sqrt_price: None,
+			};
+			let deposits = endowed_accounts
+				.iter()
+				.map(|k| (k.clone(), GENESIS_LIQUIDITY, GENESIS_LIQUIDITY))
+				.collect();
+			(pool_params, deposits)
+		})
+		.collect()
+}
+
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
@@ -145,21 +181,30 @@ fn testnet_genesis(
 		},
 		sudo: SudoConfig {
 			// Assign network admin rights.
-			key: Some(root_key),
+			key: Some(root_key.clone()),
 		},
 		transaction_payment: Default::default(),
+		asset_registry: AssetRegistryConfig {
+			// (asset_id, name, symbol, decimals, existential_deposit)
+			assets: vec![
+				(CurrencyId::Native, b"Native".to_vec(), b"UNIT".to_vec(), 12, 1_000_000),
+				(USDC, b"USD Coin".to_vec(), b"USDC".to_vec(), 6, 1_000),
+				(EURT, b"Euro Tether".to_vec(), b"EURT".to_vec(), 6, 1_000),
+			],
+		},
 		tokens: TokensConfig {
 			balances: endowed_accounts
 				.iter()
 				.flat_map(|k| {
 					vec![
 						(k.clone(), CurrencyId::Native, 1 << 60),
-						(k.clone(), CurrencyId::Token(TokenSymbol::Short(*b"USDC")), 1 << 60),
-						(k.clone(), CurrencyId::Token(TokenSymbol::Short(*b"EURT")), 1 << 60),
+						(k.clone(), USDC, 1 << 60),
+						(k.clone(), EURT, 1 << 60),
 					]
 				})
 				.collect(),
 		},
 		nfts: NftsConfig { unique_items: vec![] },
+		dex: DexConfig { pools: genesis_pools(root_key, &endowed_accounts) },
 	}
 }
@@ -0,0 +1,48 @@
+//! Benchmarking setup for pallet-asset-registry
+
+use super::*;
+
+use crate::Pallet as AssetRegistry;
+use frame_benchmarking::benchmarks;
+use frame_support::traits::{EnsureOrigin, Get};
+use sp_std::vec;
+
+/// A valid name or symbol of the maximum length.
+fn string<T: Config>() -> Vec<u8> {
+	vec![b'A'; T::StringLimit::get() as usize]
+}
+
+benchmarks! {
+	register_asset {
+		let asset_id = T::BenchmarkHelper::asset_id();
+		let origin = T::RegistryOrigin::successful_origin();
+	}: _<T::Origin>(origin, asset_id, string::<T>(), string::<T>(), 12, 1_000u32.into())
+	verify {
+		assert!(Assets::<T>::contains_key(asset_id));
+	}
+
+	// Every value of the metadata is updated
+	update_asset {
+		let asset_id = T::BenchmarkHelper::asset_id();
+		AssetRegistry::<T>::do_register_asset(asset_id, vec![b'B'], vec![b'B'], 6, 1u32.into())
+			.unwrap();
+		let origin = T::RegistryOrigin::successful_origin();
+	}: _<T::Origin>(
+		origin,
+		asset_id,
+		Some(string::<T>()),
+		Some(string::<T>()),
+		Some(12),
+		Some(1_000u32.into()),
+		Some(AssetStatus::Deprecated)
+	)
+	verify {
+		assert_eq!(Assets::<T>::get(asset_id).unwrap().status, AssetStatus::Deprecated);
+	}
+
+	impl_benchmark_test_suite!(
+		AssetRegistry,
+		crate::mock::new_test_ext(),
+		crate::mock::Test
+	);
+}
@@ -0,0 +1,255 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::FullCodec;
+use frame_support::{dispatch::DispatchResult, ensure, traits::Contains};
+use sp_runtime::traits::{AtLeast32BitUnsigned, Zero};
+use sp_std::{fmt::Debug, vec::Vec};
+
+pub use pallet::*;
+pub use types::{AssetMetadata, AssetStatus};
+pub use weights::WeightInfo;
+
+use types::*;
+
+mod types;
+pub mod weights;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	use super::*;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		type Balance: AtLeast32BitUnsigned
+			+ MaybeSerializeDeserialize
+			+ FullCodec
+			+ Copy
+			+ Default
+			+ TypeInfo
+			+ MaxEncodedLen
+			+ Debug;
+
+		/// The type of the registered assets.
+		type AssetId: FullCodec
+			+ MaxEncodedLen
+			+ Eq
+			+ PartialEq
+			+ Copy
+			+ Clone
+			+ MaybeSerializeDeserialize
+			+ Debug
+			+ TypeInfo
+			+ Ord;
+
+		/// The origin that can register assets and update their metadata.
+		type RegistryOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The maximum length of the name and the symbol of an asset.
+		#[pallet::constant]
+		type StringLimit: Get<u32>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Provides the assets that are registered in the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<Self::AssetId>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub (super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// Map the asset id to the metadata of the registered asset.
+	#[pallet::storage]
+	#[pallet::getter(fn asset_metadata)]
+	pub type Assets<T: Config> = StorageMap<_, Blake2_128Concat, AssetIdOf<T>, AssetMetadata<T>>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		/// The `(asset_id, name, symbol, decimals, existential_deposit)` of the assets that are
+		/// registered at genesis.
+		pub assets: Vec<(AssetIdOf<T>, Vec<u8>, Vec<u8>, u8, BalanceOf<T>)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> GenesisConfig<T> {
+			GenesisConfig { assets: vec![] }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			for (asset_id, name, symbol, decimals, existential_deposit) in &self.assets {
+				Pallet::<T>::do_register_asset(
+					*asset_id,
+					name.clone(),
+					symbol.clone(),
+					*decimals,
+					*existential_deposit,
+				)
+				.expect("Genesis asset can't be registered");
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub (super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A new asset was registered.
+		AssetRegistered { asset_id: AssetIdOf<T>, metadata: AssetMetadata<T> },
+		/// The metadata of an asset was updated.
+		AssetUpdated { asset_id: AssetIdOf<T>, metadata: AssetMetadata<T> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The asset is already registered.
+		AssetAlreadyRegistered,
+		/// The asset is not registered.
+		AssetNotRegistered,
+		/// The name or the symbol is empty, too long or not valid UTF-8.
+		InvalidMetadata,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Register a new asset with the given metadata. The asset starts out as `Active`.
+		///
+		/// Can only be called by the `RegistryOrigin`.
+		///
+		/// Emits `AssetRegistered` event when successful.
+		#[pallet::weight(T::WeightInfo::register_asset())]
+		pub fn register_asset(
+			origin: OriginFor<T>,
+			asset_id: AssetIdOf<T>,
+			name: Vec<u8>,
+			symbol: Vec<u8>,
+			decimals: u8,
+			existential_deposit: BalanceOf<T>,
+		) -> DispatchResult {
+			T::RegistryOrigin::ensure_origin(origin)?;
+			Self::do_register_asset(asset_id, name, symbol, decimals, existential_deposit)
+		}
+
+		/// Update the metadata of a registered asset. Only the given values are changed.
+		///
+		/// Can only be called by the `RegistryOrigin`.
+		///
+		/// Emits `AssetUpdated` event when successful.
+		#[pallet::weight(T::WeightInfo::update_asset())]
+		pub fn update_asset(
+			origin: OriginFor<T>,
+			asset_id: AssetIdOf<T>,
+			name: Option<Vec<u8>>,
+			symbol: Option<Vec<u8>>,
+			decimals: Option<u8>,
+			existential_deposit: Option<BalanceOf<T>>,
+			status: Option<AssetStatus>,
+		) -> DispatchResult {
+			T::RegistryOrigin::ensure_origin(origin)?;
+
+			let metadata = Assets::<T>::try_mutate(
+				asset_id,
+				|maybe_metadata| -> Result<AssetMetadata<T>, DispatchError> {
+					let metadata = maybe_metadata.as_mut().ok_or(Error::<T>::AssetNotRegistered)?;
+					if let Some(name) = name {
+						metadata.name = Self::bounded_string(name)?;
+					}
+					if let Some(symbol) = symbol {
+						metadata.symbol = Self::bounded_string(symbol)?;
+					}
+					if let Some(decimals) = decimals {
+						metadata.decimals = decimals;
+					}
+					if let Some(existential_deposit) = existential_deposit {
+						metadata.existential_deposit = existential_deposit;
+					}
+					if let Some(status) = status {
+						metadata.status = status;
+					}
+					Ok(metadata.clone())
+				},
+			)?;
+
+			Self::deposit_event(Event::<T>::AssetUpdated { asset_id, metadata });
+			Ok(())
+		}
+	}
+}
+
+/// Provides the assets the benchmarks of the asset registry register.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AssetId> {
+	/// Returns an asset that isn't registered yet.
+	fn asset_id() -> AssetId;
+}
+
+impl<T: Config> Pallet<T> {
+	/// Registers the asset without checking the origin. Other pallets can use this to register
+	/// assets they create, e.g. in their benchmarks.
+	pub fn do_register_asset(
+		asset_id: AssetIdOf<T>,
+		name: Vec<u8>,
+		symbol: Vec<u8>,
+		decimals: u8,
+		existential_deposit: BalanceOf<T>,
+	) -> DispatchResult {
+		ensure!(!Assets::<T>::contains_key(asset_id), Error::<T>::AssetAlreadyRegistered);
+
+		let metadata = AssetMetadata {
+			name: Self::bounded_string(name)?,
+			symbol: Self::bounded_string(symbol)?,
+			decimals,
+			existential_deposit,
+			status: AssetStatus::Active,
+		};
+		Assets::<T>::insert(asset_id, metadata.clone());
+
+		Self::deposit_event(Event::<T>::AssetRegistered { asset_id, metadata });
+		Ok(())
+	}
+
+	/// The existential deposit of the asset. Assets that aren't registered, like the liquidity
+	/// tokens of the DEX, don't have one.
+	pub fn existential_deposit(asset_id: AssetIdOf<T>) -> BalanceOf<T> {
+		Self::asset_metadata(asset_id)
+			.map(|metadata| metadata.existential_deposit)
+			.unwrap_or_else(Zero::zero)
+	}
+
+	/// Converts a non-empty UTF-8 string to the bounded representation used in storage.
+	fn bounded_string(bytes: Vec<u8>) -> Result<StringOf<T>, Error<T>> {
+		ensure!(
+			!bytes.is_empty() && sp_std::str::from_utf8(&bytes).is_ok(),
+			Error::<T>::InvalidMetadata
+		);
+		bytes.try_into().map_err(|_| Error::<T>::InvalidMetadata)
+	}
+}
+
+/// Contains the registered assets that are `Active`.
+impl<T: Config> Contains<AssetIdOf<T>> for Pallet<T> {
+	fn contains(asset_id: &AssetIdOf<T>) -> bool {
+		Self::asset_metadata(asset_id)
+			.map_or(false, |metadata| metadata.status == AssetStatus::Active)
+	}
+}
@@ -0,0 +1,108 @@
+use frame_support::{
+	parameter_types,
+	traits::{ConstU16, ConstU64, GenesisBuild},
+};
+use frame_system as system;
+use primitives::{CurrencyId, TokenSymbol};
+
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+use crate as pallet_asset_registry;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		AssetRegistry: pallet_asset_registry::{Pallet, Call, Storage, Config<T>, Event<T>},
+	}
+);
+
+impl system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = ConstU64<250>;
+	type DbWeight = ();
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+pub type AccountId = u64;
+pub type AssetId = CurrencyId;
+pub type Balance = u128;
+
+parameter_types! {
+	pub const StringLimit: u32 = 8;
+}
+
+impl pallet_asset_registry::Config for Test {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type RegistryOrigin = system::EnsureRoot<AccountId>;
+	type StringLimit = StringLimit;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = BenchmarkHelper;
+}
+
+/// Provides the unregistered `ASSET_2` for the benchmarks.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct BenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::BenchmarkHelper<AssetId> for BenchmarkHelper {
+	fn asset_id() -> AssetId {
+		ASSET_2
+	}
+}
+
+pub const ALICE: AccountId = 1;
+
+pub const ASSET_1: AssetId = CurrencyId::Token(TokenSymbol::Short([0; 4]));
+pub const ASSET_2: AssetId = CurrencyId::Token(TokenSymbol::Short([1; 4]));
+
+/// Builds the test externalities with `ASSET_1` registered as "Asset 1" with 6 decimals and an
+/// existential deposit of 100.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+	pallet_asset_registry::GenesisConfig::<Test> {
+		assets: vec![(ASSET_1, b"Asset 1".to_vec(), b"AS1".to_vec(), 6, 100)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	// set block number to 1 to make sure that events are populated
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
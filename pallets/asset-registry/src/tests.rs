@@ -0,0 +1,167 @@
+use frame_support::{assert_noop, assert_ok, traits::Contains};
+use primitives::CurrencyInfo;
+use sp_runtime::DispatchError;
+
+use crate::{mock::*, AssetStatus, Error, Event};
+
+#[test]
+fn genesis_assets_should_be_registered() {
+	new_test_ext().execute_with(|| {
+		let metadata = AssetRegistry::asset_metadata(ASSET_1).unwrap();
+		assert_eq!(metadata.name(), "Asset 1");
+		assert_eq!(metadata.symbol(), "AS1");
+		assert_eq!(metadata.decimals(), 6);
+		assert_eq!(metadata.existential_deposit, 100);
+		assert_eq!(metadata.status, AssetStatus::Active);
+
+		assert!(AssetRegistry::contains(&ASSET_1));
+		assert!(!AssetRegistry::contains(&ASSET_2));
+	});
+}
+
+#[test]
+fn register_asset_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetRegistry::register_asset(
+			Origin::root(),
+			ASSET_2,
+			b"Asset 2".to_vec(),
+			b"AS2".to_vec(),
+			12,
+			1_000,
+		));
+
+		let metadata = AssetRegistry::asset_metadata(ASSET_2).unwrap();
+		assert_eq!(metadata.name(), "Asset 2");
+		assert_eq!(metadata.symbol(), "AS2");
+		assert_eq!(metadata.decimals(), 12);
+		assert_eq!(AssetRegistry::existential_deposit(ASSET_2), 1_000);
+		assert!(AssetRegistry::contains(&ASSET_2));
+		System::assert_last_event(Event::AssetRegistered { asset_id: ASSET_2, metadata }.into());
+	});
+}
+
+#[test]
+fn register_asset_should_fail_for_invalid_calls() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			AssetRegistry::register_asset(
+				Origin::signed(ALICE),
+				ASSET_2,
+				b"Asset 2".to_vec(),
+				b"AS2".to_vec(),
+				12,
+				0,
+			),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			AssetRegistry::register_asset(
+				Origin::root(),
+				ASSET_1,
+				b"Asset 1".to_vec(),
+				b"AS1".to_vec(),
+				6,
+				100,
+			),
+			Error::<Test>::AssetAlreadyRegistered
+		);
+
+		// Empty, longer than the `StringLimit` and not UTF-8
+		for name in [vec![], b"Too long name".to_vec(), vec![0xff, 0xfe]] {
+			assert_noop!(
+				AssetRegistry::register_asset(
+					Origin::root(),
+					ASSET_2,
+					name,
+					b"AS2".to_vec(),
+					12,
+					0,
+				),
+				Error::<Test>::InvalidMetadata
+			);
+		}
+	});
+}
+
+#[test]
+fn update_asset_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetRegistry::update_asset(
+			Origin::root(),
+			ASSET_1,
+			None,
+			Some(b"NEW".to_vec()),
+			None,
+			Some(10),
+			None,
+		));
+
+		let metadata = AssetRegistry::asset_metadata(ASSET_1).unwrap();
+		// Only the given values are changed
+		assert_eq!(metadata.name(), "Asset 1");
+		assert_eq!(metadata.symbol(), "NEW");
+		assert_eq!(metadata.decimals(), 6);
+		assert_eq!(AssetRegistry::existential_deposit(ASSET_1), 10);
+		System::assert_last_event(Event::AssetUpdated { asset_id: ASSET_1, metadata }.into());
+	});
+}
+
+#[test]
+fn deprecated_assets_should_not_be_contained() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetRegistry::update_asset(
+			Origin::root(),
+			ASSET_1,
+			None,
+			None,
+			None,
+			None,
+			Some(AssetStatus::Deprecated),
+		));
+		assert!(!AssetRegistry::contains(&ASSET_1));
+		// The existential deposit still applies
+		assert_eq!(AssetRegistry::existential_deposit(ASSET_1), 100);
+	});
+}
+
+#[test]
+fn update_asset_should_fail_for_invalid_calls() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			AssetRegistry::update_asset(
+				Origin::signed(ALICE),
+				ASSET_1,
+				None,
+				None,
+				None,
+				None,
+				None
+			),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			AssetRegistry::update_asset(Origin::root(), ASSET_2, None, None, None, None, None),
+			Error::<Test>::AssetNotRegistered
+		);
+		assert_noop!(
+			AssetRegistry::update_asset(
+				Origin::root(),
+				ASSET_1,
+				Some(vec![]),
+				None,
+				None,
+				None,
+				None,
+			),
+			Error::<Test>::InvalidMetadata
+		);
+	});
+}
+
+#[test]
+fn unregistered_assets_should_have_no_existential_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(AssetRegistry::existential_deposit(ASSET_2), 0);
+	});
+}
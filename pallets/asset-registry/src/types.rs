@@ -0,0 +1,45 @@
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{BoundedVec, RuntimeDebug};
+use primitives::CurrencyInfo;
+use scale_info::TypeInfo;
+
+use crate::Config;
+
+pub(crate) type BalanceOf<T> = <T as Config>::Balance;
+pub(crate) type AssetIdOf<T> = <T as Config>::AssetId;
+pub(crate) type StringOf<T> = BoundedVec<u8, <T as Config>::StringLimit>;
+
+/// Defines whether new pools can be created for an asset.
+#[derive(RuntimeDebug, Encode, Decode, MaxEncodedLen, Copy, Clone, PartialEq, Eq, TypeInfo)]
+pub enum AssetStatus {
+	/// The asset can be used everywhere.
+	Active,
+	/// No new pools can be created for the asset, existing pools keep working.
+	Deprecated,
+}
+
+/// The metadata of a registered asset. `name` and `symbol` are valid UTF-8.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(T))]
+pub struct AssetMetadata<T: Config> {
+	pub name: StringOf<T>,
+	pub symbol: StringOf<T>,
+	pub decimals: u8,
+	/// The minimum balance an account has to hold, smaller balances are removed as dust.
+	pub existential_deposit: BalanceOf<T>,
+	pub status: AssetStatus,
+}
+
+impl<T: Config> CurrencyInfo for AssetMetadata<T> {
+	fn name(&self) -> &str {
+		sp_std::str::from_utf8(&self.name).unwrap_or_default()
+	}
+
+	fn symbol(&self) -> &str {
+		sp_std::str::from_utf8(&self.symbol).unwrap_or_default()
+	}
+
+	fn decimals(&self) -> u8 {
+		self.decimals
+	}
+}
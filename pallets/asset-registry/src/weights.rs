@@ -0,0 +1,55 @@
+//! Placeholder weights for pallet_asset_registry
+//!
+//! The values were estimated by hand and have not been measured. The times are rounded guesses.
+//! The storage annotations list the accesses of the most expensive path through each call with
+//! the configuration of the runtime in this repository, every key is counted once and a component
+//! like `n` stands for the value of the component of the call. Replace this file with the output
+//! of the benchmarks in `benchmarking.rs` on the reference hardware before relying on the
+//! weights, e.g. with
+//! `./target/release/node-template benchmark pallet --chain=dev --steps=50 --repeat=20
+//! --pallet=pallet_asset_registry --extrinsic=* --execution=wasm --wasm-execution=compiled
+//! --output=pallets/asset-registry/src/weights.rs`.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_asset_registry.
+pub trait WeightInfo {
+	fn register_asset() -> Weight;
+	fn update_asset() -> Weight;
+}
+
+/// Placeholder weights for pallet_asset_registry, see the module documentation.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: AssetRegistry Assets (r:1 w:1)
+	fn register_asset() -> Weight {
+		(17_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: AssetRegistry Assets (r:1 w:1)
+	fn update_asset() -> Weight {
+		(19_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn register_asset() -> Weight {
+		(17_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn update_asset() -> Weight {
+		(19_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+}
@@ -0,0 +1,197 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::FullCodec;
+use frame_support::{ensure, traits::Get, transactional, PalletId};
+use orml_traits::MultiCurrency;
+use pallet_dex::traits::{Amm, CurrencyPair};
+use pallet_transaction_payment::OnChargeTransaction;
+use sp_runtime::{
+	traits::{AccountIdConversion, Zero},
+	DispatchError,
+};
+use sp_std::fmt::Debug;
+
+pub use pallet::*;
+pub use payment::{ChargeAssetTxPayment, InitialPayment};
+
+mod payment;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub(crate) type OnChargeTransactionOf<T> =
+	<T as pallet_transaction_payment::Config>::OnChargeTransaction;
+pub(crate) type BalanceOf<T> = <OnChargeTransactionOf<T> as OnChargeTransaction<T>>::Balance;
+pub(crate) type LiquidityInfoOf<T> =
+	<OnChargeTransactionOf<T> as OnChargeTransaction<T>>::LiquidityInfo;
+pub(crate) type AssetIdOf<T> = <T as Config>::AssetId;
+pub(crate) type PoolIdOf<T> = <T as Config>::PoolId;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::pallet_prelude::*;
+
+	use super::*;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_transaction_payment::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The type of assets used by the Assets handler.
+		type AssetId: FullCodec
+			+ MaxEncodedLen
+			+ Eq
+			+ PartialEq
+			+ Copy
+			+ Clone
+			+ MaybeSerializeDeserialize
+			+ Debug
+			+ Default
+			+ TypeInfo
+			+ Ord;
+
+		/// The type of a pools ID
+		type PoolId: FullCodec + MaxEncodedLen + Debug + TypeInfo + Eq + PartialEq + Copy;
+
+		/// The asset that `OnChargeTransaction` of `pallet_transaction_payment` charges the fees
+		/// in.
+		#[pallet::constant]
+		type NativeAssetId: Get<Self::AssetId>;
+
+		/// The MultiCurrency handler, used to take the fee assets and to burn the native asset
+		/// they are swapped into.
+		type Assets: MultiCurrency<
+			Self::AccountId,
+			Balance = BalanceOf<Self>,
+			CurrencyId = Self::AssetId,
+		>;
+
+		/// The AMM that converts the fee assets into the native asset.
+		type Amm: Amm<
+			AccountId = Self::AccountId,
+			AssetId = Self::AssetId,
+			Balance = BalanceOf<Self>,
+			PoolId = Self::PoolId,
+		>;
+
+		/// The id used to derive the account that swaps the fee assets. The account only holds
+		/// assets while a fee is converted, so it has to be exempt from the existential deposits.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub (super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub (super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A transaction fee was paid in an asset other than the native asset. The `actual_fee`
+		/// is the amount of `asset_id` that was kept after the refund, the `tip` is in the native
+		/// asset and part of the fee.
+		AssetTxFeePaid {
+			who: T::AccountId,
+			asset_id: AssetIdOf<T>,
+			pool_id: PoolIdOf<T>,
+			actual_fee: BalanceOf<T>,
+			tip: BalanceOf<T>,
+		},
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// There is no pool that can swap the asset into the amount of the native asset.
+		NoPoolForAsset,
+		/// The fee costs more of the asset than the signer allowed with `max_fee_in_asset`.
+		FeeLimitExceeded,
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The account that swaps the fees that are paid in other assets into the native asset.
+	pub fn account_id() -> T::AccountId {
+		T::PalletId::get().into_account_truncating()
+	}
+
+	/// Returns the pool of `asset_id` and the native asset that sells `fee` of the native asset
+	/// for the least amount of `asset_id`, and that amount. Pools in batch auction mode are
+	/// skipped, their swaps are only executed at the end of the block.
+	pub fn quote_fee(
+		asset_id: AssetIdOf<T>,
+		fee: BalanceOf<T>,
+	) -> Result<(PoolIdOf<T>, BalanceOf<T>), DispatchError> {
+		let native = T::NativeAssetId::get();
+		T::Amm::pools_for_pair(CurrencyPair { token_a: asset_id, token_b: native })
+			.into_iter()
+			.filter(|pool_id| !T::Amm::is_batch_auction_pool(*pool_id))
+			.filter_map(|pool_id| {
+				T::Amm::quote_exact_out(pool_id, asset_id, native, fee)
+					.ok()
+					.map(|amount| (pool_id, amount))
+			})
+			.min_by_key(|(_, amount)| *amount)
+			.ok_or_else(|| Error::<T>::NoPoolForAsset.into())
+	}
+
+	/// Buys `fee` of the native asset with the `asset_id` of `who` and burns it, like the fees
+	/// that are paid in the native asset. Fails if that costs more than `max_amount` of
+	/// `asset_id`. Returns the pool that was used and the amount of `asset_id` that was paid.
+	#[transactional]
+	pub(crate) fn withdraw_fee_in_asset(
+		who: &T::AccountId,
+		asset_id: AssetIdOf<T>,
+		fee: BalanceOf<T>,
+		max_amount: Option<BalanceOf<T>>,
+	) -> Result<(PoolIdOf<T>, BalanceOf<T>), DispatchError> {
+		let native = T::NativeAssetId::get();
+		let account = Self::account_id();
+		let (pool_id, amount) = Self::quote_fee(asset_id, fee)?;
+		if let Some(max_amount) = max_amount {
+			ensure!(amount <= max_amount, Error::<T>::FeeLimitExceeded);
+		}
+
+		T::Assets::transfer(asset_id, who, &account, amount)?;
+		T::Amm::swap_exact_out(&account, pool_id, asset_id, native, fee)?;
+		T::Assets::withdraw(native, &account, fee)?;
+		Ok((pool_id, amount))
+	}
+
+	/// Swaps the unused part of a fee that was paid in `asset_id` back on the pool that was used
+	/// to pay it and sends it to `who`. If the swap fails, e.g. because the transaction paused the
+	/// pool, the refund is paid in the native asset instead. Returns the amount of `asset_id` that
+	/// was refunded.
+	pub(crate) fn refund_fee(
+		who: &T::AccountId,
+		asset_id: AssetIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		refund: BalanceOf<T>,
+	) -> BalanceOf<T> {
+		Self::refund_fee_in_asset(who, asset_id, pool_id, refund).unwrap_or_else(|_| {
+			// Nothing is refunded if `who` can't hold the native asset either
+			let _ = T::Assets::deposit(T::NativeAssetId::get(), who, refund);
+			Zero::zero()
+		})
+	}
+
+	#[transactional]
+	fn refund_fee_in_asset(
+		who: &T::AccountId,
+		asset_id: AssetIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		refund: BalanceOf<T>,
+	) -> Result<BalanceOf<T>, DispatchError> {
+		let native = T::NativeAssetId::get();
+		let account = Self::account_id();
+
+		T::Assets::deposit(native, &account, refund)?;
+		let amount = T::Amm::swap_exact_in(&account, pool_id, native, asset_id, refund)?;
+		T::Assets::transfer(asset_id, &account, who, amount)?;
+		Ok(amount)
+	}
+}
@@ -0,0 +1,197 @@
+use frame_support::{
+	parameter_types,
+	traits::{ConstU128, ConstU16, ConstU32, ConstU64, ConstU8, Everything, GenesisBuild},
+	weights::{DispatchClass, IdentityFee},
+	PalletId,
+};
+use frame_system as system;
+use orml_traits::parameter_type_with_key;
+use primitives::{CurrencyId, TokenSymbol};
+
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, ConvertInto, IdentityLookup, Zero},
+	Permill,
+};
+
+use crate as pallet_asset_tx_payment;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		TransactionPayment: pallet_transaction_payment::{Pallet, Storage, Event<T>},
+		Tokens: orml_tokens::{Pallet, Storage, Config<T>, Event<T>},
+
+		Dex: pallet_dex::{Pallet, Call, Storage, Event<T>},
+		AssetTxPayment: pallet_asset_tx_payment::{Pallet, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	// Extrinsics have a base fee of 10, the fees of the length and the weight are added 1:1
+	pub BlockWeights: system::limits::BlockWeights = system::limits::BlockWeights::builder()
+		.base_block(0)
+		.for_class(DispatchClass::all(), |weights| weights.base_extrinsic = 10)
+		.for_class(DispatchClass::non_mandatory(), |weights| weights.max_total = Some(1_024))
+		.build_or_panic();
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = BlockWeights;
+	type BlockLength = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = ConstU64<250>;
+	type DbWeight = ();
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+pub type AccountId = u64;
+pub type AssetId = CurrencyId;
+pub type Balance = u128;
+pub type PoolId = u128;
+
+parameter_types! {
+	pub const MaxLocks: u32 = 50;
+	pub const DexPalletId: PalletId = PalletId(*b"dex_pall");
+	pub const ProtocolFeeShare: Permill = Permill::zero();
+	pub const ProtocolFeeReceiver: Option<AccountId> = None;
+	pub const AssetTxPaymentPalletId: PalletId = PalletId(*b"fee_pall");
+	pub const GetNativeAssetId: AssetId = NATIVE;
+}
+
+type NativeCurrency = orml_tokens::CurrencyAdapter<Test, GetNativeAssetId>;
+
+impl pallet_transaction_payment::Config for Test {
+	type Event = Event;
+	type OnChargeTransaction = pallet_transaction_payment::CurrencyAdapter<NativeCurrency, ()>;
+	type OperationalFeeMultiplier = ConstU8<5>;
+	type WeightToFee = IdentityFee<Balance>;
+	type LengthToFee = IdentityFee<Balance>;
+	type FeeMultiplierUpdate = ();
+}
+
+impl pallet_dex::Config for Test {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type PoolId = PoolId;
+	type PalletId = DexPalletId;
+	type Assets = Tokens;
+	type Convert = ConvertInto;
+	type LiquidityTokenConversion = primitives::token_conversion::LiquidityTokenConversion;
+	type MinimumLiquidity = ConstU128<1_000>;
+	type MaxAssetsInPool = ConstU32<4>;
+	type MaxTicksPerPool = ConstU32<16>;
+	type MaxPathLength = ConstU32<4>;
+	type MaxPoolsPerPair = ConstU32<3>;
+	type MaxOrdersPerBatch = ConstU32<4>;
+	type ProtocolFeeShare = ProtocolFeeShare;
+	type ProtocolFeeReceiver = ProtocolFeeReceiver;
+	type AdminOrigin = system::EnsureRoot<AccountId>;
+	type MaxObservations = ConstU32<16>;
+	type OnSwap = ();
+	type FlashSwapHandler = ();
+	type RegisteredAssets = Everything;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+impl pallet_asset_tx_payment::Config for Test {
+	type Event = Event;
+	type AssetId = AssetId;
+	type PoolId = PoolId;
+	type NativeAssetId = GetNativeAssetId;
+	type Assets = Tokens;
+	type Amm = Dex;
+	type PalletId = AssetTxPaymentPalletId;
+}
+
+parameter_type_with_key! {
+	pub ExistentialDeposits: |_a: AssetId| -> Balance {
+		Zero::zero()
+	};
+}
+
+impl orml_tokens::Config for Test {
+	type Event = Event;
+	type Balance = Balance;
+	type Amount = primitives::Amount;
+	type CurrencyId = AssetId;
+	type WeightInfo = ();
+	type ExistentialDeposits = ExistentialDeposits;
+	type OnDust = ();
+	type OnNewTokenAccount = ();
+	type OnKilledTokenAccount = ();
+	type MaxLocks = MaxLocks;
+	type MaxReserves = ConstU32<0>;
+	type ReserveIdentifier = [u8; 8];
+	type DustRemovalWhitelist = Everything;
+}
+
+pub const ALICE: AccountId = 1;
+pub const BOB: AccountId = 2;
+
+pub const NATIVE: AssetId = CurrencyId::Native;
+pub const ASSET_1: AssetId = CurrencyId::Token(TokenSymbol::Short([0; 4]));
+pub const ASSET_2: AssetId = CurrencyId::Token(TokenSymbol::Short([1; 4]));
+pub const ASSET_3: AssetId = CurrencyId::Token(TokenSymbol::Short([2; 4]));
+
+pub fn new_test_ext_multi_currency() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into();
+
+	let base_balance = 1_000_000;
+
+	let balances: Vec<(AccountId, AssetId, Balance)> = vec![
+		(ALICE, NATIVE, base_balance),
+		(ALICE, ASSET_1, base_balance),
+		(ALICE, ASSET_2, base_balance),
+		(ALICE, ASSET_3, base_balance),
+		(BOB, ASSET_1, base_balance),
+	];
+
+	orml_tokens::GenesisConfig::<Test> { balances }
+		.assimilate_storage(&mut t)
+		.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	// set block number to 1 to make sure that events are populated
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+pub fn run_test<T>(test: T)
+where
+	T: FnOnce(),
+{
+	new_test_ext_multi_currency().execute_with(|| {
+		test();
+	});
+}
@@ -0,0 +1,187 @@
+use crate::{AssetIdOf, BalanceOf, Config, Event, LiquidityInfoOf, Pallet, PoolIdOf};
+use codec::{Decode, Encode};
+use frame_support::{
+	dispatch::DispatchResult,
+	traits::Get,
+	weights::{DispatchInfo, PostDispatchInfo},
+};
+use pallet_transaction_payment::{ChargeTransactionPayment, OnChargeTransaction};
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{DispatchInfoOf, Dispatchable, PostDispatchInfoOf, Saturating, SignedExtension, Zero},
+	transaction_validity::{
+		InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+	},
+	FixedPointOperand,
+};
+
+/// The fee that was withdrawn before a transaction was dispatched.
+pub enum InitialPayment<T: Config> {
+	/// No fee had to be paid.
+	Nothing,
+	/// The fee was paid in the native asset by `OnChargeTransaction`.
+	Native(LiquidityInfoOf<T>),
+	/// The `fee` in the native asset was bought with `amount` of `asset_id` on `pool_id`.
+	Asset { asset_id: AssetIdOf<T>, pool_id: PoolIdOf<T>, amount: BalanceOf<T>, fee: BalanceOf<T> },
+}
+
+/// Replaces `ChargeTransactionPayment` of `pallet_transaction_payment`. The signer pays the fee
+/// and the `tip` in the native asset, or in `asset_id`, which is swapped into the native asset
+/// on the dex. The part of the fee that isn't used by the transaction is refunded afterwards.
+///
+/// The price of `asset_id` can be moved by the swaps before the transaction in the same block,
+/// so the signer can limit the amount of `asset_id` that is paid with `max_fee_in_asset`. The
+/// transaction is invalid if the fee costs more.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct ChargeAssetTxPayment<T: Config> {
+	#[codec(compact)]
+	tip: BalanceOf<T>,
+	asset_id: Option<AssetIdOf<T>>,
+	max_fee_in_asset: Option<BalanceOf<T>>,
+}
+
+impl<T: Config> ChargeAssetTxPayment<T>
+where
+	T::Call: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+	BalanceOf<T>: Send + Sync + From<u64> + FixedPointOperand,
+	AssetIdOf<T>: Send + Sync,
+{
+	/// Creates the extension with the `tip` and the asset the fee is paid in. `None` pays it in
+	/// the native asset. `max_fee_in_asset` is the most that is paid of the asset, `None` pays
+	/// whatever the fee costs.
+	pub fn from(
+		tip: BalanceOf<T>,
+		asset_id: Option<AssetIdOf<T>>,
+		max_fee_in_asset: Option<BalanceOf<T>>,
+	) -> Self {
+		Self { tip, asset_id, max_fee_in_asset }
+	}
+
+	fn withdraw_fee(
+		&self,
+		who: &T::AccountId,
+		call: &T::Call,
+		info: &DispatchInfoOf<T::Call>,
+		len: usize,
+	) -> Result<(BalanceOf<T>, InitialPayment<T>), TransactionValidityError> {
+		let fee = pallet_transaction_payment::Pallet::<T>::compute_fee(len as u32, info, self.tip);
+		if fee.is_zero() {
+			return Ok((fee, InitialPayment::Nothing))
+		}
+
+		match self.asset_id {
+			Some(asset_id) if asset_id != T::NativeAssetId::get() => {
+				let (pool_id, amount) =
+					Pallet::<T>::withdraw_fee_in_asset(who, asset_id, fee, self.max_fee_in_asset)
+						.map_err(|_| InvalidTransaction::Payment)?;
+				Ok((fee, InitialPayment::Asset { asset_id, pool_id, amount, fee }))
+			},
+			_ => <T::OnChargeTransaction as OnChargeTransaction<T>>::withdraw_fee(
+				who, call, info, fee, self.tip,
+			)
+			.map(|liquidity_info| (fee, InitialPayment::Native(liquidity_info))),
+		}
+	}
+}
+
+impl<T: Config> sp_std::fmt::Debug for ChargeAssetTxPayment<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(
+			f,
+			"ChargeAssetTxPayment<{:?}, {:?}, {:?}>",
+			self.tip, self.asset_id, self.max_fee_in_asset
+		)
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Config> SignedExtension for ChargeAssetTxPayment<T>
+where
+	T::Call: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+	BalanceOf<T>: Send + Sync + From<u64> + FixedPointOperand,
+	AssetIdOf<T>: Send + Sync,
+{
+	const IDENTIFIER: &'static str = "ChargeAssetTxPayment";
+	type AccountId = T::AccountId;
+	type Call = T::Call;
+	type AdditionalSigned = ();
+	/// The tip, the signer and the fee that was withdrawn.
+	type Pre = (BalanceOf<T>, Self::AccountId, InitialPayment<T>);
+
+	fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		let (fee, _) = self.withdraw_fee(who, call, info, len)?;
+		let priority = ChargeTransactionPayment::<T>::get_priority(info, len, self.tip, fee);
+		Ok(ValidTransaction { priority, ..Default::default() })
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		let (_, initial_payment) = self.withdraw_fee(who, call, info, len)?;
+		Ok((self.tip, who.clone(), initial_payment))
+	}
+
+	fn post_dispatch(
+		pre: Option<Self::Pre>,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		let (tip, who, initial_payment) = match pre {
+			Some(pre) => pre,
+			None => return Ok(()),
+		};
+
+		match initial_payment {
+			InitialPayment::Nothing => Ok(()),
+			InitialPayment::Native(liquidity_info) => ChargeTransactionPayment::<T>::post_dispatch(
+				Some((tip, who, liquidity_info)),
+				info,
+				post_info,
+				len,
+				result,
+			),
+			InitialPayment::Asset { asset_id, pool_id, amount, fee } => {
+				let actual_fee = pallet_transaction_payment::Pallet::<T>::compute_actual_fee(
+					len as u32, info, post_info, tip,
+				);
+				let refund = fee.saturating_sub(actual_fee);
+				let refunded = if refund.is_zero() {
+					Zero::zero()
+				} else {
+					Pallet::<T>::refund_fee(&who, asset_id, pool_id, refund)
+				};
+
+				Pallet::<T>::deposit_event(Event::AssetTxFeePaid {
+					who,
+					asset_id,
+					pool_id,
+					actual_fee: amount.saturating_sub(refunded),
+					tip,
+				});
+				Ok(())
+			},
+		}
+	}
+}
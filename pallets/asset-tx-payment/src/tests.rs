@@ -0,0 +1,213 @@
+use frame_support::{
+	assert_ok,
+	weights::{DispatchInfo, PostDispatchInfo, Weight},
+};
+use frame_system::{Config, EventRecord};
+use orml_traits::MultiCurrency;
+use pallet_dex::traits::{Amm, CurrencyPair, PoolCreationParams, PoolKind, PoolStatus};
+
+use sp_core::H256;
+use sp_runtime::{
+	traits::SignedExtension,
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+	Permill,
+};
+
+use crate::{mock, mock::*, ChargeAssetTxPayment, InitialPayment};
+
+pub fn assert_last_event<T, F>(matcher: F)
+where
+	T: Config,
+	F: Fn(&EventRecord<mock::Event, H256>) -> bool,
+{
+	assert!(matcher(System::events().last().expect("events expected")));
+}
+
+const BASE_BALANCE: Balance = 1_000_000;
+const LEN: usize = 10;
+
+/// The fee of a transaction with `LEN` bytes and the given weight, see the `BlockWeights` of the
+/// mock.
+fn fee(weight: Weight) -> Balance {
+	10 + LEN as Balance + weight as Balance
+}
+
+fn call() -> mock::Call {
+	mock::Call::System(frame_system::Call::remark { remark: vec![] })
+}
+
+fn info_from_weight(weight: Weight) -> DispatchInfo {
+	DispatchInfo { weight, ..Default::default() }
+}
+
+fn post_info_from_weight(weight: Weight) -> PostDispatchInfo {
+	PostDispatchInfo { actual_weight: Some(weight), pays_fee: Default::default() }
+}
+
+/// Creates a constant product pool for the native asset and `asset_id` with the given fee.
+fn create_pool(asset_id: AssetId, fee: Permill) -> PoolId {
+	let pair = CurrencyPair { token_a: NATIVE, token_b: asset_id };
+	let params = PoolCreationParams {
+		owner: ALICE,
+		pair,
+		fee,
+		kind: PoolKind::ConstantProduct,
+		initial_sqrt_price: None,
+	};
+	assert_ok!(Dex::create_pool_with_liquidity(Origin::signed(ALICE), params, 100_000, 100_000));
+	<Dex as Amm>::pool_for_pair(pair, fee).unwrap()
+}
+
+fn pre_dispatch(
+	who: AccountId,
+	asset_id: Option<AssetId>,
+	weight: Weight,
+) -> Result<(Balance, AccountId, InitialPayment<Test>), TransactionValidityError> {
+	ChargeAssetTxPayment::<Test>::from(0, asset_id, None).pre_dispatch(
+		&who,
+		&call(),
+		&info_from_weight(weight),
+		LEN,
+	)
+}
+
+fn post_dispatch(pre: (Balance, AccountId, InitialPayment<Test>), weight: Weight, actual: Weight) {
+	assert_ok!(ChargeAssetTxPayment::<Test>::post_dispatch(
+		Some(pre),
+		&info_from_weight(weight),
+		&post_info_from_weight(actual),
+		LEN,
+		&Ok(())
+	));
+}
+
+#[test]
+fn fee_should_be_paid_in_native_asset() {
+	run_test(|| {
+		let pre = pre_dispatch(ALICE, None, 100).unwrap();
+		assert_eq!(Tokens::free_balance(NATIVE, &ALICE), BASE_BALANCE - fee(100));
+
+		post_dispatch(pre, 100, 50);
+		assert_eq!(Tokens::free_balance(NATIVE, &ALICE), BASE_BALANCE - fee(50));
+
+		// Choosing the native asset is the same as choosing no asset
+		let pre = pre_dispatch(ALICE, Some(NATIVE), 100).unwrap();
+		post_dispatch(pre, 100, 100);
+		assert_eq!(Tokens::free_balance(NATIVE, &ALICE), BASE_BALANCE - fee(50) - fee(100));
+		assert_eq!(Tokens::free_balance(ASSET_1, &ALICE), BASE_BALANCE);
+	});
+}
+
+#[test]
+fn fee_should_be_paid_in_other_asset() {
+	run_test(|| {
+		let pool_id = create_pool(ASSET_1, Permill::from_percent(1));
+		let native_issuance = Tokens::total_issuance(NATIVE);
+		let amount = <Dex as Amm>::quote_exact_out(pool_id, ASSET_1, NATIVE, fee(100)).unwrap();
+
+		// BOB doesn't hold the native asset
+		let pre = pre_dispatch(BOB, Some(ASSET_1), 100).unwrap();
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), BASE_BALANCE - amount);
+		assert_eq!(Tokens::free_balance(NATIVE, &BOB), 0);
+		assert_eq!(Tokens::total_issuance(NATIVE), native_issuance - fee(100));
+
+		// The unused part of the fee is swapped back
+		let refund = fee(100) - fee(50);
+		let refunded = <Dex as Amm>::quote_exact_in(pool_id, NATIVE, ASSET_1, refund).unwrap();
+		post_dispatch(pre, 100, 50);
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), BASE_BALANCE - amount + refunded);
+		assert_eq!(Tokens::free_balance(NATIVE, &BOB), 0);
+		assert_eq!(Tokens::total_issuance(NATIVE), native_issuance - fee(50));
+
+		let account = AssetTxPayment::account_id();
+		assert_eq!(Tokens::free_balance(NATIVE, &account), 0);
+		assert_eq!(Tokens::free_balance(ASSET_1, &account), 0);
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::AssetTxPayment(crate::Event::AssetTxFeePaid { who, asset_id, pool_id: id, actual_fee, tip })
+            if who == BOB && asset_id == ASSET_1 && id == pool_id && actual_fee == amount - refunded && tip == 0)
+		});
+	});
+}
+
+#[test]
+fn fee_should_be_bought_on_cheapest_pool() {
+	run_test(|| {
+		create_pool(ASSET_1, Permill::from_percent(3));
+		let pool_id = create_pool(ASSET_1, Permill::from_percent(1));
+
+		let amount = <Dex as Amm>::quote_exact_out(pool_id, ASSET_1, NATIVE, fee(100)).unwrap();
+		assert_eq!(AssetTxPayment::quote_fee(ASSET_1, fee(100)).unwrap(), (pool_id, amount));
+
+		let pre = pre_dispatch(BOB, Some(ASSET_1), 100).unwrap();
+		assert!(matches!(pre.2, InitialPayment::Asset { pool_id: id, .. } if id == pool_id));
+	});
+}
+
+#[test]
+fn fee_should_not_be_bought_on_batch_auction_pool() {
+	run_test(|| {
+		let pool_id = create_pool(ASSET_1, Permill::from_percent(3));
+		let batch_auction_pool = create_pool(ASSET_1, Permill::from_percent(1));
+		assert_ok!(Dex::set_batch_auction(Origin::root(), batch_auction_pool, true));
+
+		let amount = <Dex as Amm>::quote_exact_out(pool_id, ASSET_1, NATIVE, fee(100)).unwrap();
+		assert_eq!(AssetTxPayment::quote_fee(ASSET_1, fee(100)).unwrap(), (pool_id, amount));
+
+		let pre = pre_dispatch(BOB, Some(ASSET_1), 100).unwrap();
+		assert!(matches!(pre.2, InitialPayment::Asset { pool_id: id, .. } if id == pool_id));
+	});
+}
+
+#[test]
+fn fee_payment_should_respect_max_fee_in_asset() {
+	run_test(|| {
+		let pool_id = create_pool(ASSET_1, Permill::from_percent(1));
+		let amount = <Dex as Amm>::quote_exact_out(pool_id, ASSET_1, NATIVE, fee(100)).unwrap();
+		let pre_dispatch = |max_fee_in_asset| {
+			ChargeAssetTxPayment::<Test>::from(0, Some(ASSET_1), Some(max_fee_in_asset))
+				.pre_dispatch(&BOB, &call(), &info_from_weight(100), LEN)
+				.map(|_| ())
+		};
+
+		let payment_error = TransactionValidityError::from(InvalidTransaction::Payment);
+		assert_eq!(pre_dispatch(amount - 1), Err(payment_error));
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), BASE_BALANCE);
+
+		assert_ok!(pre_dispatch(amount));
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), BASE_BALANCE - amount);
+	});
+}
+
+#[test]
+fn refund_should_be_paid_in_native_asset_if_swap_fails() {
+	run_test(|| {
+		let pool_id = create_pool(ASSET_1, Permill::from_percent(1));
+		let amount = <Dex as Amm>::quote_exact_out(pool_id, ASSET_1, NATIVE, fee(100)).unwrap();
+
+		let pre = pre_dispatch(BOB, Some(ASSET_1), 100).unwrap();
+		assert_ok!(Dex::set_pool_status(Origin::signed(ALICE), pool_id, PoolStatus::Paused));
+		post_dispatch(pre, 100, 50);
+
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), BASE_BALANCE - amount);
+		assert_eq!(Tokens::free_balance(NATIVE, &BOB), fee(100) - fee(50));
+	});
+}
+
+#[test]
+fn fee_payment_should_fail() {
+	run_test(|| {
+		create_pool(ASSET_1, Permill::from_percent(1));
+		let payment_error = TransactionValidityError::from(InvalidTransaction::Payment);
+
+		// BOB doesn't hold the native asset
+		assert_eq!(pre_dispatch(BOB, None, 100).map(|_| ()), Err(payment_error));
+		// There is no pool for ASSET_2 and the native asset
+		assert_eq!(pre_dispatch(ALICE, Some(ASSET_2), 100).map(|_| ()), Err(payment_error));
+		// BOB doesn't hold ASSET_3
+		create_pool(ASSET_3, Permill::from_percent(1));
+		assert_eq!(pre_dispatch(BOB, Some(ASSET_3), 100).map(|_| ()), Err(payment_error));
+
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), BASE_BALANCE);
+	});
+}
@@ -0,0 +1,62 @@
+//! Runtime API definition for the DEX pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+// The `too_many_arguments` warning originates from `decl_runtime_apis` macro.
+#![allow(clippy::too_many_arguments)]
+// The `unnecessary_mut_passed` warning originates from `decl_runtime_apis` macro.
+#![allow(clippy::unnecessary_mut_passed)]
+
+use codec::Codec;
+use pallet_dex::traits::Pool;
+use sp_runtime::DispatchError;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	pub trait DexApi<AccountId, AssetId, Balance, PoolId> where
+		AccountId: Codec,
+		AssetId: Codec,
+		Balance: Codec,
+		PoolId: Codec,
+	{
+		/// Returns all pools together with their ids.
+		fn pools() -> Vec<(PoolId, Pool<AccountId, AssetId>)>;
+
+		/// Returns the reserves of both assets of the given pool.
+		fn pool_reserves(pool_id: PoolId) -> Result<(Balance, Balance), DispatchError>;
+
+		/// Returns the value of `amount` of `asset_id` in terms of the other asset of the pool,
+		/// based on the current reserves.
+		fn get_exchange_value(
+			pool_id: PoolId,
+			asset_id: AssetId,
+			amount: Balance,
+		) -> Result<Balance, DispatchError>;
+
+		/// Returns the liquidity token of the given pool.
+		fn lp_token(pool_id: PoolId) -> Result<AssetId, DispatchError>;
+
+		/// Returns the amount of the other asset that has to be paid to buy `amount` of
+		/// `asset_id`.
+		fn quote_buy(
+			pool_id: PoolId,
+			asset_id: AssetId,
+			amount: Balance,
+		) -> Result<Balance, DispatchError>;
+
+		/// Returns the amount of the other asset that is received when selling `amount` of
+		/// `asset_id`.
+		fn quote_sell(
+			pool_id: PoolId,
+			asset_id: AssetId,
+			amount: Balance,
+		) -> Result<Balance, DispatchError>;
+
+		/// Returns the amounts of `asset_in` the best-price router sells on each pool of the pair
+		/// and the total amount of `asset_out` that is received.
+		fn quote_best_route(
+			asset_in: AssetId,
+			asset_out: AssetId,
+			amount_in: Balance,
+		) -> Result<(Vec<(PoolId, Balance)>, Balance), DispatchError>;
+	}
+}
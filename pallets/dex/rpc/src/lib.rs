@@ -0,0 +1,265 @@
+//! RPC interface for the DEX pallet.
+
+use std::{convert::TryInto, fmt::Debug, sync::Arc};
+
+use codec::Codec;
+use jsonrpsee::{
+	core::{Error as JsonRpseeError, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use pallet_dex::traits::Pool;
+pub use pallet_dex_rpc_runtime_api::DexApi as DexRuntimeApi;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT, DispatchError};
+
+/// The RPC methods of the DEX. `Balance` is the balance type of the runtime, the balances are
+/// passed as `NumberOrHex`, so values that don't fit into a JSON number are sent as hex strings.
+#[rpc(client, server)]
+pub trait DexApi<BlockHash, AccountId, AssetId, Balance, PoolId> {
+	#[method(name = "dex_pools")]
+	fn pools(&self, at: Option<BlockHash>) -> RpcResult<Vec<(PoolId, Pool<AccountId, AssetId>)>>;
+
+	#[method(name = "dex_poolReserves")]
+	fn pool_reserves(
+		&self,
+		pool_id: PoolId,
+		at: Option<BlockHash>,
+	) -> RpcResult<(NumberOrHex, NumberOrHex)>;
+
+	#[method(name = "dex_getExchangeValue")]
+	fn get_exchange_value(
+		&self,
+		pool_id: PoolId,
+		asset_id: AssetId,
+		amount: NumberOrHex,
+		at: Option<BlockHash>,
+	) -> RpcResult<NumberOrHex>;
+
+	#[method(name = "dex_lpToken")]
+	fn lp_token(&self, pool_id: PoolId, at: Option<BlockHash>) -> RpcResult<AssetId>;
+
+	#[method(name = "dex_quoteBuy")]
+	fn quote_buy(
+		&self,
+		pool_id: PoolId,
+		asset_id: AssetId,
+		amount: NumberOrHex,
+		at: Option<BlockHash>,
+	) -> RpcResult<NumberOrHex>;
+
+	#[method(name = "dex_quoteSell")]
+	fn quote_sell(
+		&self,
+		pool_id: PoolId,
+		asset_id: AssetId,
+		amount: NumberOrHex,
+		at: Option<BlockHash>,
+	) -> RpcResult<NumberOrHex>;
+
+	#[method(name = "dex_quoteBestRoute")]
+	fn quote_best_route(
+		&self,
+		asset_in: AssetId,
+		asset_out: AssetId,
+		amount_in: NumberOrHex,
+		at: Option<BlockHash>,
+	) -> RpcResult<(Vec<(PoolId, NumberOrHex)>, NumberOrHex)>;
+}
+
+/// Provides RPC methods to query the pools of the DEX pallet.
+pub struct Dex<C, Block> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<Block>,
+}
+
+impl<C, Block> Dex<C, Block> {
+	/// Create new `Dex` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to the runtime failed.
+	RuntimeError,
+	/// The runtime call succeeded but the pallet returned an error.
+	DispatchError,
+	/// A balance doesn't fit into the balance type of the runtime or into `NumberOrHex`.
+	InvalidBalance,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::DispatchError => 2,
+			Error::InvalidBalance => 3,
+		}
+	}
+}
+
+fn runtime_error_into_rpc_error(err: impl std::fmt::Debug) -> JsonRpseeError {
+	CallError::Custom(ErrorObject::owned(
+		Error::RuntimeError.into(),
+		"Runtime error",
+		Some(format!("{:?}", err)),
+	))
+	.into()
+}
+
+fn dispatch_error_into_rpc_error(err: DispatchError) -> JsonRpseeError {
+	CallError::Custom(ErrorObject::owned(
+		Error::DispatchError.into(),
+		"Dispatch error",
+		Some(format!("{:?}", err)),
+	))
+	.into()
+}
+
+fn invalid_balance_into_rpc_error(value: impl Debug) -> JsonRpseeError {
+	CallError::Custom(ErrorObject::owned(
+		Error::InvalidBalance.into(),
+		"Invalid balance",
+		Some(format!("{:?} doesn't fit into the balance type", value)),
+	))
+	.into()
+}
+
+/// Converts a balance of the RPC into a balance of the runtime.
+fn balance_from_rpc<Balance: TryFrom<NumberOrHex>>(value: NumberOrHex) -> RpcResult<Balance> {
+	value.try_into().map_err(|_| invalid_balance_into_rpc_error(value))
+}
+
+/// Converts a balance of the runtime into a `NumberOrHex`, so values that don't fit into a
+/// JSON number are returned as hex strings.
+fn balance_into_rpc<Balance: TryInto<NumberOrHex> + Copy + Debug>(
+	value: Balance,
+) -> RpcResult<NumberOrHex> {
+	value.try_into().map_err(|_| invalid_balance_into_rpc_error(value))
+}
+
+impl<C, Block, AccountId, AssetId, Balance, PoolId>
+	DexApiServer<<Block as BlockT>::Hash, AccountId, AssetId, Balance, PoolId> for Dex<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: DexRuntimeApi<Block, AccountId, AssetId, Balance, PoolId>,
+	AccountId: Codec,
+	AssetId: Codec,
+	Balance: Codec + Copy + Debug + TryFrom<NumberOrHex> + TryInto<NumberOrHex>,
+	PoolId: Codec,
+{
+	fn pools(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<(PoolId, Pool<AccountId, AssetId>)>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.pools(&at).map_err(runtime_error_into_rpc_error)
+	}
+
+	fn pool_reserves(
+		&self,
+		pool_id: PoolId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<(NumberOrHex, NumberOrHex)> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let (reserve_a, reserve_b) = api
+			.pool_reserves(&at, pool_id)
+			.map_err(runtime_error_into_rpc_error)?
+			.map_err(dispatch_error_into_rpc_error)?;
+		Ok((balance_into_rpc(reserve_a)?, balance_into_rpc(reserve_b)?))
+	}
+
+	fn get_exchange_value(
+		&self,
+		pool_id: PoolId,
+		asset_id: AssetId,
+		amount: NumberOrHex,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<NumberOrHex> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let amount = balance_from_rpc(amount)?;
+		let value = api
+			.get_exchange_value(&at, pool_id, asset_id, amount)
+			.map_err(runtime_error_into_rpc_error)?
+			.map_err(dispatch_error_into_rpc_error)?;
+		balance_into_rpc(value)
+	}
+
+	fn lp_token(&self, pool_id: PoolId, at: Option<<Block as BlockT>::Hash>) -> RpcResult<AssetId> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.lp_token(&at, pool_id)
+			.map_err(runtime_error_into_rpc_error)?
+			.map_err(dispatch_error_into_rpc_error)
+	}
+
+	fn quote_buy(
+		&self,
+		pool_id: PoolId,
+		asset_id: AssetId,
+		amount: NumberOrHex,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<NumberOrHex> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let amount = balance_from_rpc(amount)?;
+		let value = api
+			.quote_buy(&at, pool_id, asset_id, amount)
+			.map_err(runtime_error_into_rpc_error)?
+			.map_err(dispatch_error_into_rpc_error)?;
+		balance_into_rpc(value)
+	}
+
+	fn quote_sell(
+		&self,
+		pool_id: PoolId,
+		asset_id: AssetId,
+		amount: NumberOrHex,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<NumberOrHex> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let amount = balance_from_rpc(amount)?;
+		let value = api
+			.quote_sell(&at, pool_id, asset_id, amount)
+			.map_err(runtime_error_into_rpc_error)?
+			.map_err(dispatch_error_into_rpc_error)?;
+		balance_into_rpc(value)
+	}
+
+	fn quote_best_route(
+		&self,
+		asset_in: AssetId,
+		asset_out: AssetId,
+		amount_in: NumberOrHex,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<(Vec<(PoolId, NumberOrHex)>, NumberOrHex)> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let amount_in = balance_from_rpc(amount_in)?;
+		let (route, amount_out) = api
+			.quote_best_route(&at, asset_in, asset_out, amount_in)
+			.map_err(runtime_error_into_rpc_error)?
+			.map_err(dispatch_error_into_rpc_error)?;
+		let route = route
+			.into_iter()
+			.map(|(pool_id, amount)| balance_into_rpc(amount).map(|amount| (pool_id, amount)))
+			.collect::<RpcResult<Vec<_>>>()?;
+		Ok((route, balance_into_rpc(amount_out)?))
+	}
+}
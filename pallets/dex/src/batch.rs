@@ -0,0 +1,312 @@
+use crate::{
+	traits::{Amm, BatchOrder, OnSwap, PoolStatus},
+	types::*,
+	BatchAuctionPools, BatchOrders, Config, Error, Event, Pallet, WeightInfo,
+};
+use frame_support::{
+	dispatch::DispatchResult, ensure, traits::Get, transactional, weights::Weight,
+};
+use orml_traits::MultiCurrency;
+use sp_core::U256;
+use sp_runtime::{
+	traits::{AccountIdConversion, Convert, Zero},
+	ArithmeticError, DispatchError, FixedPointNumber, FixedU128,
+};
+use sp_std::vec::Vec;
+
+/// The seed of the account that holds the queued orders. It is longer than an encoded pool id,
+/// so it can't collide with the account of a pool.
+const BATCH_ACCOUNT_SEED: &[u8; 20] = b"batch_auction_orders";
+
+/// The maximum number of steps of the search for the amount that the side in excess of a batch
+/// sells to the pool. Each step halves the searched range, so amounts below 2^64 are found
+/// exactly and larger ones are rounded down by less than one part in 2^64.
+pub(crate) const MAX_BATCH_SEARCH_STEPS: u32 = 64;
+
+/// The outcome of clearing the batch of a pair pool, in the pool's token_a and token_b. The
+/// difference between the sold and the paid amount of one token is what the pool received of it.
+#[derive(Default)]
+struct BatchClearing {
+	/// The total amount of token_a sold by the orders.
+	sold_a: u128,
+	/// The total amount of token_b sold by the orders.
+	sold_b: u128,
+	/// The total amount of token_a paid to the orders that sold token_b.
+	paid_a: u128,
+	/// The total amount of token_b paid to the orders that sold token_a.
+	paid_b: u128,
+}
+
+impl BatchClearing {
+	/// The share of an order that sold `amount_in` of token_a (or token_b if `sells_a` is false)
+	/// in the paid amount of the other token. Every order receives the same price.
+	fn amount_out(&self, sells_a: bool, amount_in: u128) -> u128 {
+		let (sold, paid) =
+			if sells_a { (self.sold_a, self.paid_b) } else { (self.sold_b, self.paid_a) };
+		if sold == 0 {
+			return 0
+		}
+		// The share is at most `paid`, so it fits into 128 bits
+		(U256::from(amount_in) * U256::from(paid) / U256::from(sold)).low_u128()
+	}
+
+	/// The price of token_a in token_b that the orders were executed at.
+	fn price(&self) -> FixedU128 {
+		if self.sold_a > 0 {
+			FixedU128::checked_from_rational(self.paid_b, self.sold_a)
+		} else {
+			FixedU128::checked_from_rational(self.sold_b, self.paid_a)
+		}
+		.unwrap_or_default()
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The account that holds the assets of the queued orders until their batch is cleared.
+	pub fn batch_account() -> T::AccountId {
+		T::PalletId::get().into_sub_account_truncating(BATCH_ACCOUNT_SEED)
+	}
+
+	/// Returns whether the swaps of the pool are executed in batch auctions.
+	pub fn is_batch_auction_pool(pool_id: PoolIdOf<T>) -> bool {
+		BatchAuctionPools::<T>::contains_key(pool_id)
+	}
+
+	/// The weight of queuing an order and clearing it in `on_finalize`, which is charged to the
+	/// swap that submits the order. Queuing the order and paying it out or refunding it cost at
+	/// most a swap each, and every order is also charged the swap that nets the opposing flows of
+	/// its batch. The amount swapped with the pool is searched for with at most
+	/// `MAX_BATCH_SEARCH_STEPS` quotes plus a final one, and the search is repeated after every
+	/// round of refunds, so at most once per order.
+	pub(crate) fn batch_order_weight() -> Weight {
+		let quotes = MAX_BATCH_SEARCH_STEPS as Weight + 1;
+		T::WeightInfo::swap()
+			.saturating_mul(3)
+			.saturating_add(T::WeightInfo::batch_search_step().saturating_mul(quotes))
+	}
+
+	/// Takes `amount_in` of `asset_in` from `who` and queues the order until the batch of the
+	/// pool is cleared at the end of the block.
+	pub(crate) fn submit_batch_order(
+		who: &T::AccountId,
+		pool_id: PoolIdOf<T>,
+		asset_in: AssetIdOf<T>,
+		amount_in: BalanceOf<T>,
+		min_amount_out: BalanceOf<T>,
+	) -> DispatchResult {
+		let pool = Self::get_pool(pool_id)?;
+		ensure!(pool.pair.contains(asset_in), Error::<T>::InvalidAsset);
+		match pool.status {
+			PoolStatus::Active => {},
+			PoolStatus::SwapsPaused => return Err(Error::<T>::SwapsPaused.into()),
+			PoolStatus::Paused => return Err(Error::<T>::PoolPaused.into()),
+		}
+		ensure!(!amount_in.is_zero(), Error::<T>::InvalidAmount);
+
+		let order = BatchOrder { who: who.clone(), asset_in, amount_in, min_amount_out };
+		BatchOrders::<T>::try_mutate(pool_id, |orders| orders.try_push(order))
+			.map_err(|_| Error::<T>::TooManyBatchOrders)?;
+		T::Assets::transfer(asset_in, who, &Self::batch_account(), amount_in)?;
+
+		Self::deposit_event(Event::<T>::BatchOrderSubmitted {
+			who: who.clone(),
+			pool_id,
+			asset_in,
+			amount_in,
+			min_amount_out,
+		});
+		Ok(())
+	}
+
+	/// Clears the batches of all pools that received orders in this block. If a batch can't be
+	/// cleared, e.g. because the pool was paused after the orders were submitted, all of its
+	/// orders are refunded.
+	pub(crate) fn clear_batches() {
+		for (pool_id, orders) in BatchOrders::<T>::drain() {
+			if Self::clear_batch(pool_id, orders.clone().into_inner()).is_err() {
+				for order in orders {
+					// Nothing is refunded if the order's account can't receive it anymore
+					let _ = Self::refund_batch_order(pool_id, &order);
+				}
+			}
+		}
+	}
+
+	/// Executes the orders of a pool at a single price. The opposing orders are matched with each
+	/// other first, only the excess of one side is swapped with the pool. Orders that would
+	/// receive less than their minimum are refunded and the batch is cleared without them.
+	#[transactional]
+	fn clear_batch(pool_id: PoolIdOf<T>, mut orders: Vec<BatchOrderOf<T>>) -> DispatchResult {
+		let pool = Self::get_pool(pool_id)?;
+		match pool.status {
+			PoolStatus::Active => {},
+			PoolStatus::SwapsPaused => return Err(Error::<T>::SwapsPaused.into()),
+			PoolStatus::Paused => return Err(Error::<T>::PoolPaused.into()),
+		}
+
+		let clearing = loop {
+			let clearing = Self::compute_batch_clearing(pool_id, &pool, &orders)?;
+			let (filled, refunded): (Vec<_>, Vec<_>) = orders.into_iter().partition(|order| {
+				let amount_out = clearing.amount_out(
+					order.asset_in == pool.pair.token_a,
+					T::Convert::convert(order.amount_in),
+				);
+				let min_amount_out: u128 = T::Convert::convert(order.min_amount_out);
+				amount_out > 0 && amount_out >= min_amount_out
+			});
+			orders = filled;
+			if refunded.is_empty() {
+				break clearing
+			}
+			for order in refunded {
+				Self::refund_batch_order(pool_id, &order)?;
+			}
+		};
+		if orders.is_empty() {
+			return Ok(())
+		}
+
+		// The pool receives everything that was sold and pays out every order, so the rounding
+		// remainders of the shares stay in the pool.
+		Self::update_price_cumulatives(pool_id)?;
+		let batch_account = Self::batch_account();
+		let pool_account = Self::account_id(&pool_id);
+		let (sold_a, sold_b): (BalanceOf<T>, BalanceOf<T>) =
+			(T::Convert::convert(clearing.sold_a), T::Convert::convert(clearing.sold_b));
+		T::Assets::transfer(pool.pair.token_a, &batch_account, &pool_account, sold_a)?;
+		T::Assets::transfer(pool.pair.token_b, &batch_account, &pool_account, sold_b)?;
+
+		for order in orders {
+			let sells_a = order.asset_in == pool.pair.token_a;
+			let asset_out = if sells_a { pool.pair.token_b } else { pool.pair.token_a };
+			let amount_out: BalanceOf<T> = T::Convert::convert(
+				clearing.amount_out(sells_a, T::Convert::convert(order.amount_in)),
+			);
+			T::Assets::transfer(asset_out, &pool_account, &order.who, amount_out)?;
+
+			Self::deposit_event(Event::<T>::BatchOrderExecuted {
+				who: order.who,
+				pool_id,
+				asset_in: order.asset_in,
+				amount_in: order.amount_in,
+				amount_out,
+			});
+		}
+
+		Self::deposit_event(Event::<T>::BatchAuctionCleared {
+			pool_id,
+			price: clearing.price(),
+			sold_a,
+			sold_b,
+		});
+		T::OnSwap::on_swap(pool_id);
+		Ok(())
+	}
+
+	/// Computes the amounts that are paid to the two sides of a batch. The side whose sold
+	/// amount is worth more at the spot price of the pool sells part of it to the pool, just
+	/// enough that the average price of that swap matches the price of the amounts that are
+	/// matched with the other side.
+	fn compute_batch_clearing(
+		pool_id: PoolIdOf<T>,
+		pool: &PoolOf<T>,
+		orders: &[BatchOrderOf<T>],
+	) -> Result<BatchClearing, DispatchError> {
+		let mut clearing = BatchClearing::default();
+		for order in orders {
+			let amount_in: u128 = T::Convert::convert(order.amount_in);
+			let sold = if order.asset_in == pool.pair.token_a {
+				&mut clearing.sold_a
+			} else {
+				&mut clearing.sold_b
+			};
+			*sold = sold.checked_add(amount_in).ok_or(ArithmeticError::Overflow)?;
+		}
+		if clearing.sold_a == 0 && clearing.sold_b == 0 {
+			return Ok(clearing)
+		}
+
+		let (reserve_a, reserve_b) = <Self as Amm>::pool_reserves(pool_id)?;
+		let (reserve_a, reserve_b): (u128, u128) =
+			(T::Convert::convert(reserve_a), T::Convert::convert(reserve_b));
+
+		let token_a_in_excess = U256::from(clearing.sold_a) * U256::from(reserve_b) >=
+			U256::from(clearing.sold_b) * U256::from(reserve_a);
+		if token_a_in_excess {
+			let (amount_in, amount_out) = Self::batch_curve_swap(
+				pool,
+				pool.pair.token_a,
+				(clearing.sold_a, clearing.sold_b),
+				(reserve_a, reserve_b),
+			)?;
+			clearing.paid_a = clearing.sold_a - amount_in;
+			clearing.paid_b =
+				clearing.sold_b.checked_add(amount_out).ok_or(ArithmeticError::Overflow)?;
+		} else {
+			let (amount_in, amount_out) = Self::batch_curve_swap(
+				pool,
+				pool.pair.token_b,
+				(clearing.sold_b, clearing.sold_a),
+				(reserve_b, reserve_a),
+			)?;
+			clearing.paid_b = clearing.sold_b - amount_in;
+			clearing.paid_a =
+				clearing.sold_a.checked_add(amount_out).ok_or(ArithmeticError::Overflow)?;
+		}
+		Ok(clearing)
+	}
+
+	/// Returns the amount of `asset_in` that the side in excess sells to the pool and the amount
+	/// it receives for it. This is the largest `x` whose average price is still at least the
+	/// price of the matched amounts, `amount_out(x) / x >= sold_out / (sold_in - x)`. Below that
+	/// the matched price would be worse than the pool's, above it the swap would be. The search
+	/// stops after `MAX_BATCH_SEARCH_STEPS` steps with an `x` that still fulfills the condition.
+	fn batch_curve_swap(
+		pool: &PoolOf<T>,
+		asset_in: AssetIdOf<T>,
+		(sold_in, sold_out): (u128, u128),
+		(reserve_in, reserve_out): (u128, u128),
+	) -> Result<(u128, u128), DispatchError> {
+		let amount_out =
+			|amount_in: u128| Self::amount_out(pool, asset_in, amount_in, reserve_in, reserve_out);
+		if sold_out == 0 {
+			return Ok((sold_in, amount_out(sold_in)?))
+		}
+
+		// The condition holds for 0 and fails for `sold_in`, amounts the pool can't take fail it
+		// as well
+		let (mut low, mut high) = (0u128, sold_in);
+		for _ in 0..MAX_BATCH_SEARCH_STEPS {
+			if high - low <= 1 {
+				break
+			}
+			let mid = low + (high - low) / 2;
+			let holds = amount_out(mid).map_or(false, |out| {
+				U256::from(out) * U256::from(sold_in - mid) >=
+					U256::from(sold_out) * U256::from(mid)
+			});
+			if holds {
+				low = mid;
+			} else {
+				high = mid;
+			}
+		}
+
+		if low == 0 {
+			return Ok((0, 0))
+		}
+		Ok((low, amount_out(low)?))
+	}
+
+	/// Sends the `amount_in` of an order that wasn't executed back to its account.
+	fn refund_batch_order(pool_id: PoolIdOf<T>, order: &BatchOrderOf<T>) -> DispatchResult {
+		T::Assets::transfer(order.asset_in, &Self::batch_account(), &order.who, order.amount_in)?;
+		Self::deposit_event(Event::<T>::BatchOrderRefunded {
+			who: order.who.clone(),
+			pool_id,
+			asset_in: order.asset_in,
+			amount_in: order.amount_in,
+		});
+		Ok(())
+	}
+}
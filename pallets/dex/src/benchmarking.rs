@@ -0,0 +1,470 @@
+//! Benchmarking setup for pallet-dex
+
+use super::*;
+
+use crate::{
+	traits::{BenchmarkHelper, CurrencyPair, PoolCreationParams, PoolKind, PoolStatus},
+	Pallet as Dex,
+};
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_support::BoundedVec;
+use frame_system::RawOrigin;
+use primitives::{CurrencyId, TokenSymbol};
+use sp_std::vec;
+
+const SEED: u32 = 0;
+/// The amount of every asset the accounts of the benchmarks are funded with.
+const FUNDS: u128 = 1_000_000_000_000_000_000_000;
+/// The amount of every asset that is deposited into a pool.
+const LIQUIDITY: u128 = 1_000_000_000_000_000_000;
+/// The amount that is swapped, deposited or withdrawn in the benchmarked calls.
+const AMOUNT: u128 = 1_000_000_000_000_000;
+/// The tick spacing of concentrated-liquidity pools.
+const TICK_SPACING: i32 = 10;
+/// The most expensive kind of pair pool, its invariant is calculated iteratively.
+const STABLE_SWAP: PoolKind = PoolKind::StableSwap { amplification: 100 };
+
+/// The `i`-th asset of the benchmarks. It is registered, so pools can be created for it.
+fn asset<T: Config>(i: u32) -> AssetIdOf<T>
+where
+	T::AssetId: From<CurrencyId>,
+{
+	let asset_id: AssetIdOf<T> = CurrencyId::Token(TokenSymbol::Short(i.to_be_bytes())).into();
+	T::BenchmarkHelper::register_asset(asset_id);
+	asset_id
+}
+
+fn balance<T: Config>(amount: u128) -> BalanceOf<T> {
+	<T::Convert as Convert<u128, BalanceOf<T>>>::convert(amount)
+}
+
+/// Funds `who` with `FUNDS` of the first `assets` assets.
+fn fund<T: Config>(who: &T::AccountId, assets: u32)
+where
+	T::AssetId: From<CurrencyId>,
+{
+	for i in 0..assets {
+		T::Assets::deposit(asset::<T>(i), who, balance::<T>(FUNDS)).unwrap();
+	}
+}
+
+/// The params of a pool for the pair of the assets `2 * i` and `2 * i + 1`.
+fn pair_params<T: Config>(owner: &T::AccountId, i: u32, kind: PoolKind) -> PoolCreationParamsOf<T>
+where
+	T::AssetId: From<CurrencyId>,
+{
+	let pair = CurrencyPair { token_a: asset::<T>(2 * i), token_b: asset::<T>(2 * i + 1) };
+	// Concentrated-liquidity pools start at tick zero
+	let initial_sqrt_price = matches!(kind, PoolKind::Concentrated { .. }).then(FixedU128::one);
+	PoolCreationParams {
+		owner: owner.clone(),
+		pair,
+		fee: Permill::from_parts(3_000),
+		kind,
+		initial_sqrt_price,
+	}
+}
+
+/// Moves to the next block, so the next change of the reserves of a pool records an observation.
+fn next_block<T: Config>() {
+	let n = frame_system::Pallet::<T>::block_number();
+	frame_system::Pallet::<T>::set_block_number(n + One::one());
+}
+
+/// Creates a pair pool for the assets `2 * i` and `2 * i + 1` holding `LIQUIDITY` of each. The
+/// block is advanced afterwards, see `next_block`.
+fn create_pair_pool<T: Config>(owner: &T::AccountId, i: u32, kind: PoolKind) -> PoolIdOf<T>
+where
+	T::AssetId: From<CurrencyId>,
+{
+	let pool_id = Dex::<T>::pool_count();
+	Dex::<T>::create_pool_with_liquidity(
+		RawOrigin::Signed(owner.clone()).into(),
+		pair_params::<T>(owner, i, kind),
+		balance::<T>(LIQUIDITY),
+		balance::<T>(LIQUIDITY),
+	)
+	.unwrap();
+	next_block::<T>();
+	pool_id
+}
+
+/// Creates a multi-asset pool for the first `n` assets holding `LIQUIDITY` of each.
+fn create_multi_asset_pool<T: Config>(owner: &T::AccountId, n: u32) -> PoolIdOf<T>
+where
+	T::AssetId: From<CurrencyId>,
+{
+	let pool_id = Dex::<T>::pool_count();
+	let assets: Vec<AssetIdOf<T>> = (0..n).map(asset::<T>).collect();
+	let amounts: Vec<BalanceOf<T>> = (0..n).map(|_| balance::<T>(LIQUIDITY)).collect();
+	Dex::<T>::create_multi_asset_pool_with_liquidity(
+		RawOrigin::Signed(owner.clone()).into(),
+		BoundedVec::try_from(assets).unwrap(),
+		Permill::from_parts(3_000),
+		BoundedVec::try_from(amounts).unwrap(),
+	)
+	.unwrap();
+	pool_id
+}
+
+/// The range of the position that provides liquidity around the current price of a
+/// concentrated-liquidity pool. It ends above all ranges returned by `narrow_range`.
+fn wide_range<T: Config>() -> (i32, i32) {
+	(-TICK_SPACING, TICK_SPACING * T::MaxTicksPerPool::get() as i32)
+}
+
+/// The range of the `i`-th position above the current price. The ranges don't share any ticks.
+fn narrow_range(i: u32) -> (i32, i32) {
+	let tick_lower = TICK_SPACING * (2 * i as i32 + 1);
+	(tick_lower, tick_lower + TICK_SPACING)
+}
+
+fn mint_position<T: Config>(
+	owner: &T::AccountId,
+	pool_id: PoolIdOf<T>,
+	(tick_lower, tick_upper): (i32, i32),
+) {
+	Dex::<T>::mint_position(
+		RawOrigin::Signed(owner.clone()).into(),
+		pool_id,
+		tick_lower,
+		tick_upper,
+		balance::<T>(LIQUIDITY),
+		balance::<T>(LIQUIDITY),
+		None,
+	)
+	.unwrap();
+}
+
+/// Creates a concentrated-liquidity pool with a position in the `wide_range` and `positions`
+/// positions in the narrow ranges above the current price, each initializing two ticks. The block
+/// is advanced afterwards, see `next_block`.
+fn create_concentrated_pool<T: Config>(owner: &T::AccountId, positions: u32) -> PoolIdOf<T>
+where
+	T::AssetId: From<CurrencyId>,
+{
+	let pool_id = Dex::<T>::pool_count();
+	let kind = PoolKind::Concentrated { tick_spacing: TICK_SPACING as u32 };
+	Dex::<T>::create_pool(
+		RawOrigin::Signed(owner.clone()).into(),
+		pair_params::<T>(owner, 0, kind),
+	)
+	.unwrap();
+
+	mint_position::<T>(owner, pool_id, wide_range::<T>());
+	for i in 0..positions {
+		mint_position::<T>(owner, pool_id, narrow_range(i));
+	}
+	next_block::<T>();
+	pool_id
+}
+
+benchmarks! {
+	where_clause { where T::AssetId: From<CurrencyId> }
+
+	create_pool {
+		let caller: T::AccountId = whitelisted_caller();
+		let kind = PoolKind::Concentrated { tick_spacing: TICK_SPACING as u32 };
+		let pool_params = pair_params::<T>(&caller, 0, kind);
+	}: _(RawOrigin::Signed(caller), pool_params)
+	verify {
+		assert!(ConcentratedStates::<T>::contains_key(PoolIdOf::<T>::zero()));
+	}
+
+	create_pool_with_liquidity {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		let pool_params = pair_params::<T>(&caller, 0, STABLE_SWAP);
+	}: _(RawOrigin::Signed(caller), pool_params, balance::<T>(LIQUIDITY), balance::<T>(LIQUIDITY))
+	verify {
+		assert!(Pools::<T>::contains_key(PoolIdOf::<T>::zero()));
+	}
+
+	create_multi_asset_pool {
+		let n in 3 .. T::MaxAssetsInPool::get();
+
+		let caller: T::AccountId = whitelisted_caller();
+		let assets: Vec<AssetIdOf<T>> = (0..n).map(asset::<T>).collect();
+		let assets = BoundedVec::try_from(assets).unwrap();
+	}: _(RawOrigin::Signed(caller.clone()), caller, assets, Permill::from_parts(3_000))
+	verify {
+		assert!(MultiAssetPools::<T>::contains_key(PoolIdOf::<T>::zero()));
+	}
+
+	create_multi_asset_pool_with_liquidity {
+		let n in 3 .. T::MaxAssetsInPool::get();
+
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+		let assets: Vec<AssetIdOf<T>> = (0..n).map(asset::<T>).collect();
+		let amounts: Vec<BalanceOf<T>> = (0..n).map(|_| balance::<T>(LIQUIDITY)).collect();
+		let assets = BoundedVec::try_from(assets).unwrap();
+		let amounts = BoundedVec::try_from(amounts).unwrap();
+	}: _(RawOrigin::Signed(caller.clone()), assets, Permill::from_parts(3_000), amounts)
+	verify {
+		let lp_token = MultiAssetPools::<T>::get(PoolIdOf::<T>::zero()).unwrap().lp_token;
+		assert!(!T::Assets::free_balance(lp_token, &caller).is_zero());
+	}
+
+	add_liquidity {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		let pool_id = create_pair_pool::<T>(&caller, 0, STABLE_SWAP);
+	}: _(
+		RawOrigin::Signed(caller),
+		pool_id,
+		balance::<T>(AMOUNT),
+		asset::<T>(0),
+		Zero::zero(),
+		None
+	)
+
+	add_multi_asset_liquidity {
+		let n in 3 .. T::MaxAssetsInPool::get();
+
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+		let pool_id = create_multi_asset_pool::<T>(&caller, n);
+	}: add_liquidity(
+		RawOrigin::Signed(caller),
+		pool_id,
+		balance::<T>(AMOUNT),
+		asset::<T>(0),
+		Zero::zero(),
+		None
+	)
+
+	remove_liquidity {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		let pool_id = create_pair_pool::<T>(&caller, 0, STABLE_SWAP);
+	}: _(RawOrigin::Signed(caller), pool_id, balance::<T>(AMOUNT), Zero::zero(), Zero::zero(), None)
+
+	add_single_asset_liquidity {
+		let n in 3 .. T::MaxAssetsInPool::get();
+
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+		let pool_id = create_multi_asset_pool::<T>(&caller, n);
+	}: _(
+		RawOrigin::Signed(caller),
+		pool_id,
+		asset::<T>(0),
+		balance::<T>(AMOUNT),
+		Zero::zero(),
+		None
+	)
+
+	remove_multi_asset_liquidity {
+		let n in 3 .. T::MaxAssetsInPool::get();
+
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+		let pool_id = create_multi_asset_pool::<T>(&caller, n);
+		let min_amounts_out = BoundedVec::try_from(vec![Zero::zero(); n as usize]).unwrap();
+	}: _(RawOrigin::Signed(caller), pool_id, balance::<T>(AMOUNT), min_amounts_out, None)
+
+	remove_single_asset_liquidity {
+		let n in 3 .. T::MaxAssetsInPool::get();
+
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+		let pool_id = create_multi_asset_pool::<T>(&caller, n);
+	}: _(
+		RawOrigin::Signed(caller),
+		pool_id,
+		asset::<T>(0),
+		balance::<T>(AMOUNT),
+		Zero::zero(),
+		None
+	)
+
+	mint_position {
+		// The position of the `wide_range` and the minted position initialize two ticks each
+		let t in 0 .. T::MaxTicksPerPool::get() / 2 - 2;
+
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		let pool_id = create_concentrated_pool::<T>(&caller, t);
+		let (tick_lower, tick_upper) = narrow_range(t);
+	}: _(
+		RawOrigin::Signed(caller.clone()),
+		pool_id,
+		tick_lower,
+		tick_upper,
+		balance::<T>(LIQUIDITY),
+		balance::<T>(LIQUIDITY),
+		None
+	)
+	verify {
+		assert!(Positions::<T>::contains_key(caller, (pool_id, tick_lower, tick_upper)));
+	}
+
+	burn_position {
+		let t in 1 .. T::MaxTicksPerPool::get() / 2 - 1;
+
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		let pool_id = create_concentrated_pool::<T>(&caller, t);
+		let (tick_lower, tick_upper) = narrow_range(t - 1);
+		let liquidity =
+			Positions::<T>::get(&caller, (pool_id, tick_lower, tick_upper)).unwrap().liquidity;
+	}: _(
+		RawOrigin::Signed(caller.clone()),
+		pool_id,
+		tick_lower,
+		tick_upper,
+		liquidity,
+		Zero::zero(),
+		Zero::zero(),
+		None
+	)
+	verify {
+		assert!(!Ticks::<T>::contains_key(pool_id, tick_upper));
+	}
+
+	collect_fees {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		let pool_id = create_concentrated_pool::<T>(&caller, 0);
+		<Dex<T> as Amm>::sell(&caller, pool_id, asset::<T>(1), balance::<T>(AMOUNT)).unwrap();
+		let (tick_lower, tick_upper) = wide_range::<T>();
+	}: _(RawOrigin::Signed(caller.clone()), pool_id, tick_lower, tick_upper)
+	verify {
+		let position = Positions::<T>::get(&caller, (pool_id, tick_lower, tick_upper)).unwrap();
+		assert!(position.fees_owed_b.is_zero());
+	}
+
+	swap {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		let pool_id = create_pair_pool::<T>(&caller, 0, STABLE_SWAP);
+	}: swap_exact_out(
+		RawOrigin::Signed(caller),
+		pool_id,
+		asset::<T>(1),
+		asset::<T>(0),
+		balance::<T>(AMOUNT),
+		balance::<T>(FUNDS),
+		None
+	)
+
+	swap_concentrated {
+		// Every position above the current price initializes two ticks that are crossed
+		let t in 0 .. T::MaxTicksPerPool::get() / 2 - 1;
+
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		let pool_id = create_concentrated_pool::<T>(&caller, t);
+
+		// Buy everything up to the start of the next narrow range, only the position in the
+		// `wide_range` keeps its token_a above it
+		let (target_tick, _) = narrow_range(t);
+		let (tick_lower, tick_upper) = wide_range::<T>();
+		let liquidity =
+			Positions::<T>::get(&caller, (pool_id, tick_lower, tick_upper)).unwrap().liquidity;
+		let amount_kept = calc::get_amount_a_delta(
+			calc::get_sqrt_price_at_tick(target_tick).unwrap(),
+			calc::get_sqrt_price_at_tick(tick_upper).unwrap(),
+			liquidity,
+			true,
+		)
+		.unwrap();
+		let pool_account = Dex::<T>::account_id(&pool_id);
+		let reserve_a: u128 =
+			T::Convert::convert(T::Assets::free_balance(asset::<T>(0), &pool_account));
+		let amount_out = balance::<T>(reserve_a - amount_kept);
+	}: swap_exact_out(
+		RawOrigin::Signed(caller),
+		pool_id,
+		asset::<T>(1),
+		asset::<T>(0),
+		amount_out,
+		balance::<T>(FUNDS),
+		None
+	)
+	verify {
+		// The price moved past the ranges of all narrow positions
+		let tick = ConcentratedStates::<T>::get(pool_id).unwrap().tick;
+		assert!(tick >= 2 * TICK_SPACING * t as i32);
+	}
+
+	swap_multi_asset {
+		let n in 3 .. T::MaxAssetsInPool::get();
+
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+		let pool_id = create_multi_asset_pool::<T>(&caller, n);
+	}: swap_exact_out(
+		RawOrigin::Signed(caller),
+		pool_id,
+		asset::<T>(1),
+		asset::<T>(0),
+		balance::<T>(AMOUNT),
+		balance::<T>(FUNDS),
+		None
+	)
+
+	flash_swap {
+		let l in 0 .. 100_000;
+
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		let pool_id = create_pair_pool::<T>(&caller, 0, PoolKind::ConstantProduct);
+		let data = vec![0u8; l as usize];
+	}: {
+		// The `FlashSwapHandler` of the runtime is not known here, so it might not pay the pool
+		// back. The call is measured up to the check of the repayment either way.
+		let _ = Dex::<T>::flash_swap(
+			RawOrigin::Signed(caller).into(),
+			pool_id,
+			balance::<T>(AMOUNT),
+			Zero::zero(),
+			data,
+		);
+	}
+
+	set_pool_fee {
+		let caller: T::AccountId = whitelisted_caller();
+		let pool_params = pair_params::<T>(&caller, 0, PoolKind::ConstantProduct);
+		Dex::<T>::create_pool(RawOrigin::Signed(caller.clone()).into(), pool_params).unwrap();
+		let pool_id = PoolIdOf::<T>::zero();
+		let fee = Permill::from_percent(1);
+	}: _(RawOrigin::Signed(caller), pool_id, fee)
+	verify {
+		assert_eq!(Pools::<T>::get(pool_id).unwrap().fee, fee);
+	}
+
+	set_pool_status {
+		let caller: T::AccountId = whitelisted_caller();
+		let pool_params = pair_params::<T>(&caller, 0, PoolKind::ConstantProduct);
+		Dex::<T>::create_pool(RawOrigin::Signed(caller.clone()).into(), pool_params).unwrap();
+		let pool_id = PoolIdOf::<T>::zero();
+	}: _(RawOrigin::Signed(caller), pool_id, PoolStatus::Paused)
+	verify {
+		assert_eq!(Pools::<T>::get(pool_id).unwrap().status, PoolStatus::Paused);
+	}
+
+	transfer_pool_ownership {
+		let caller: T::AccountId = whitelisted_caller();
+		let pool_params = pair_params::<T>(&caller, 0, PoolKind::ConstantProduct);
+		Dex::<T>::create_pool(RawOrigin::Signed(caller.clone()).into(), pool_params).unwrap();
+		let pool_id = PoolIdOf::<T>::zero();
+		let new_owner: T::AccountId = account("new_owner", 0, SEED);
+	}: _(RawOrigin::Signed(caller), pool_id, new_owner.clone())
+	verify {
+		assert_eq!(Pools::<T>::get(pool_id).unwrap().owner, new_owner);
+	}
+
+	// One quote of the search for the clearing price of a batch, on the most expensive pool kind
+	batch_search_step {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		let pool_id = create_pair_pool::<T>(&caller, 0, STABLE_SWAP);
+		let pool = Pools::<T>::get(pool_id).unwrap();
+	}: {
+		Dex::<T>::amount_out(&pool, asset::<T>(0), AMOUNT, LIQUIDITY, LIQUIDITY).unwrap();
+	}
+
+	impl_benchmark_test_suite!(Dex, crate::mock::new_test_ext_multi_currency(), crate::mock::Test);
+}
@@ -1,45 +1,943 @@
 use crate::{Config, Error};
 use frame_support::ensure;
 use sp_arithmetic::Permill;
+use sp_core::U256;
 use sp_runtime::{
-	PerThing,
+	traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Saturating, Zero},
+	ArithmeticError, DispatchError, FixedPointNumber, FixedU128, PerThing,
 };
+use sp_std::convert::TryInto;
 
+/// The pool fees are applied with the full precision of a `Permill`.
+const FEE_ACCURACY: u128 = Permill::ACCURACY as u128;
+
+/// Returns the parts per `FEE_ACCURACY` of an amount that are left after deducting `fee`.
+fn get_fee_complement(fee: Permill) -> U256 {
+	U256::from(FEE_ACCURACY - fee.deconstruct() as u128)
+}
+
+/// Calculates the amount that has to be paid to buy `amount_out` from a constant product pool,
+/// including the fee: `Δx = x * Δy / ((y - Δy) * (1 - f))`. The amount is rounded up in favour
+/// of the pool.
 pub(crate) fn get_amount_in<T: Config>(
 	amount_out: u128,
 	reserve_in: u128,
 	reserve_out: u128,
 	fee: Permill,
-) -> Result<u128, Error<T>> {
+) -> Result<u128, DispatchError> {
 	ensure!(amount_out > 0, Error::<T>::InsufficientOutputAmount);
 	ensure!(reserve_in > 0 && reserve_out > 0, Error::<T>::InsufficientLiquidity);
+	ensure!(amount_out < reserve_out, Error::<T>::InsufficientLiquidity);
 
-	let multiplier: u128 = 1000;
-	let fee_multiplier: u128 = 1000u128.saturating_sub(fee.mul_floor(1000));
+	let numerator = U256::from(reserve_in)
+		.checked_mul(U256::from(FEE_ACCURACY))
+		.ok_or(ArithmeticError::Overflow)?;
+	let denominator = U256::from(reserve_out - amount_out)
+		.checked_mul(get_fee_complement(fee))
+		.ok_or(ArithmeticError::Overflow)?;
+	ensure!(!denominator.is_zero(), ArithmeticError::DivisionByZero);
 
-	let numerator = reserve_in.saturating_mul(amount_out).saturating_mul(multiplier);
-	let denominator = fee_multiplier.saturating_mul(reserve_out.saturating_sub(amount_out));
-	let result = numerator.checked_div(denominator).and_then(|x| x.checked_add(1)).unwrap_or(0);
-
-	Ok(result)
+	mul_div(numerator, U256::from(amount_out), denominator, true)
+		.and_then(|amount_in| amount_in.try_into().ok())
+		.ok_or_else(|| ArithmeticError::Overflow.into())
 }
 
+/// Calculates the amount received when selling `amount_in` to a constant product pool, after
+/// deducting the fee from `amount_in`: `Δy = y * Δx * (1 - f) / (x + Δx * (1 - f))`. The amount
+/// is rounded down in favour of the pool.
 pub(crate) fn get_amount_out<T: Config>(
 	amount_in: u128,
 	reserve_in: u128,
 	reserve_out: u128,
 	fee: Permill,
-) -> Result<u128, Error<T>> {
+) -> Result<u128, DispatchError> {
+	ensure!(amount_in > 0, Error::<T>::InsufficientInputAmount);
+	ensure!(reserve_in > 0 && reserve_out > 0, Error::<T>::InsufficientLiquidity);
+
+	// Both amounts are scaled by `FEE_ACCURACY`
+	let amount_in_with_fee = U256::from(amount_in)
+		.checked_mul(get_fee_complement(fee))
+		.ok_or(ArithmeticError::Overflow)?;
+	let denominator = U256::from(reserve_in)
+		.checked_mul(U256::from(FEE_ACCURACY))
+		.and_then(|reserve_in| reserve_in.checked_add(amount_in_with_fee))
+		.ok_or(ArithmeticError::Overflow)?;
+
+	mul_div(amount_in_with_fee, U256::from(reserve_out), denominator, false)
+		.and_then(|amount_out| amount_out.try_into().ok())
+		.ok_or_else(|| ArithmeticError::Overflow.into())
+}
+
+/// Calculates the part of `amount_in` that is kept by the pool as fee. The fee is rounded down,
+/// so it is at most the fee deducted from the input amount in `get_amount_out`.
+pub(crate) fn get_fee_amount(amount_in: u128, fee: Permill) -> u128 {
+	fee.mul_floor(amount_in)
+}
+
+/// Checks that a flash swap paid the pool back. Like in `UniswapV2Pair.swap`, the fee is deducted
+/// from the amounts paid in, and the product of the adjusted balances can't be lower than the
+/// product of the reserves before the swap. The fee is rounded up in favour of the pool.
+pub(crate) fn is_flash_swap_repaid(
+	reserves: (u128, u128),
+	balances: (u128, u128),
+	amounts_in: (u128, u128),
+	fee: Permill,
+) -> bool {
+	let adjusted_balance = |balance: u128, amount_in: u128| {
+		U256::from(balance.saturating_sub(fee.mul_ceil(amount_in)))
+	};
+
+	let (balance_a, balance_b) =
+		(adjusted_balance(balances.0, amounts_in.0), adjusted_balance(balances.1, amounts_in.1));
+	balance_a * balance_b >= U256::from(reserves.0) * U256::from(reserves.1)
+}
+
+/// Calculates the amount of the other asset that has to be deposited along with `amount` to keep
+/// the ratio of the reserves. The amount is rounded up in favour of the pool.
+pub(crate) fn get_deposit_amount(
+	amount: u128,
+	reserve_in: u128,
+	reserve_out: u128,
+) -> Result<u128, ArithmeticError> {
+	checked_mul_div(amount, reserve_out, reserve_in, true)
+}
+
+/// Calculates the liquidity tokens minted for depositing `amount` to a pool holding `reserve`
+/// of the same asset. The amount is rounded down in favour of the pool.
+pub(crate) fn get_liquidity_minted(
+	amount: u128,
+	reserve: u128,
+	total_issuance: u128,
+) -> Result<u128, ArithmeticError> {
+	checked_mul_div(amount, total_issuance, reserve, false)
+}
+
+/// Calculates the amount of an asset held in `reserve` that is paid out for burning
+/// `liquidity` tokens. The amount is rounded down in favour of the pool.
+pub(crate) fn get_withdrawal_amount(
+	liquidity: u128,
+	reserve: u128,
+	total_issuance: u128,
+) -> Result<u128, ArithmeticError> {
+	checked_mul_div(liquidity, reserve, total_issuance, false)
+}
+
+/// Calculates the square root of the product of the reserves of a pool, rounded down.
+pub(crate) fn get_root_k(reserve_a: u128, reserve_b: u128) -> u128 {
+	// The square root of a product of two `u128` always fits into a `u128`
+	(U256::from(reserve_a) * U256::from(reserve_b)).integer_sqrt().low_u128()
+}
+
+/// Calculates the amount of liquidity tokens that have to be minted so that their holder owns
+/// `share` of the growth of the invariant since the last liquidity event. The invariant has to
+/// grow linearly with the liquidity of the pool, like `√k` for constant product pools.
+///
+/// `s = S * φ * (√k - √k_last) / ((1 - φ) * √k + φ * √k_last)`, see section 2.4 of the Uniswap v2
+/// whitepaper.
+pub(crate) fn get_protocol_fee_liquidity(
+	total_issuance: u128,
+	invariant: u128,
+	invariant_last: u128,
+	share: Permill,
+) -> Result<u128, ArithmeticError> {
+	if invariant <= invariant_last || share.is_zero() {
+		return Ok(0)
+	}
+
+	let share_parts = U256::from(share.deconstruct());
+	let remaining_parts = U256::from(Permill::ACCURACY - share.deconstruct());
+
+	// Can't overflow, each product has at most 128 + 20 bits
+	let numerator = U256::from(invariant - invariant_last) * share_parts;
+	let denominator =
+		U256::from(invariant) * remaining_parts + U256::from(invariant_last) * share_parts;
+	mul_div(U256::from(total_issuance), numerator, denominator, false)
+		.and_then(|liquidity| liquidity.try_into().ok())
+		.ok_or(ArithmeticError::Overflow)
+}
+
+/// The highest amplification coefficient a StableSwap pool can be created with.
+pub(crate) const MAX_AMPLIFICATION: u32 = 1_000_000;
+
+/// The maximum number of Newton iterations used to compute the StableSwap invariant and balances.
+const MAX_ITERATIONS: u8 = 255;
+
+/// Number of assets in a StableSwap pool.
+const N_COINS: u8 = 2;
+
+/// Calculates the StableSwap invariant `D` of a pool with two assets, as defined in the Curve
+/// whitepaper: `A * n^n * sum(x) + D = A * D * n^n + D^(n+1) / (n^n * prod(x))`.
+///
+/// Returns `None` if one of the reserves is empty or the calculation doesn't converge.
+pub(crate) fn get_stable_invariant(
+	reserve_a: u128,
+	reserve_b: u128,
+	amplification: u32,
+) -> Option<u128> {
+	if reserve_a == 0 && reserve_b == 0 {
+		return Some(0)
+	}
+	if reserve_a == 0 || reserve_b == 0 {
+		return None
+	}
+
+	let n = U256::from(N_COINS);
+	let (x_a, x_b) = (U256::from(reserve_a), U256::from(reserve_b));
+	let sum = x_a.checked_add(x_b)?;
+	let ann = U256::from(amplification).checked_mul(n)?.checked_mul(n)?;
+
+	let mut d = sum;
+	for _ in 0..MAX_ITERATIONS {
+		// d_p = D^(n+1) / (n^n * prod(x))
+		let d_p = d.checked_mul(d)?.checked_div(x_a.checked_mul(n)?)?;
+		let d_p = d_p.checked_mul(d)?.checked_div(x_b.checked_mul(n)?)?;
+
+		let d_prev = d;
+		let numerator = ann.checked_mul(sum)?.checked_add(d_p.checked_mul(n)?)?.checked_mul(d)?;
+		let denominator = ann
+			.checked_sub(U256::one())?
+			.checked_mul(d)?
+			.checked_add(n.checked_add(U256::one())?.checked_mul(d_p)?)?;
+		d = numerator.checked_div(denominator)?;
+
+		if d.max(d_prev) - d.min(d_prev) <= U256::one() {
+			return d.try_into().ok()
+		}
+	}
+	None
+}
+
+/// Calculates the reserve of one asset of a StableSwap pool given the reserve of the other asset
+/// and the invariant `D`.
+fn get_stable_reserve(other_reserve: U256, d: U256, amplification: u32) -> Option<U256> {
+	let n = U256::from(N_COINS);
+	let ann = U256::from(amplification).checked_mul(n)?.checked_mul(n)?;
+
+	// c = D^(n+1) / (n^n * prod(x) * ann), b = sum(x) + D / ann
+	let c = d
+		.checked_mul(d)?
+		.checked_div(other_reserve.checked_mul(n)?)?
+		.checked_mul(d)?
+		.checked_div(ann.checked_mul(n)?)?;
+	let b = other_reserve.checked_add(d.checked_div(ann)?)?;
+
+	let mut y = d;
+	for _ in 0..MAX_ITERATIONS {
+		let y_prev = y;
+		// y = (y^2 + c) / (2y + b - D)
+		let numerator = y.checked_mul(y)?.checked_add(c)?;
+		let denominator = y.checked_mul(n)?.checked_add(b)?.checked_sub(d)?;
+		y = numerator.checked_div(denominator)?;
+
+		if y.max(y_prev) - y.min(y_prev) <= U256::one() {
+			return Some(y)
+		}
+	}
+	None
+}
+
+/// Calculates the amount received when selling `amount_in` to a StableSwap pool. Like for
+/// constant product pools, the fee is deducted from the input amount.
+pub(crate) fn get_stable_amount_out<T: Config>(
+	amount_in: u128,
+	reserve_in: u128,
+	reserve_out: u128,
+	amplification: u32,
+	fee: Permill,
+) -> Result<u128, DispatchError> {
+	ensure!(amount_in > 0, Error::<T>::InsufficientInputAmount);
+	ensure!(reserve_in > 0 && reserve_out > 0, Error::<T>::InsufficientLiquidity);
+
+	let amount_in_with_fee = amount_in.saturating_sub(get_fee_amount(amount_in, fee));
+	let d = get_stable_invariant(reserve_in, reserve_out, amplification)
+		.ok_or(Error::<T>::InvariantCalculationFailed)?;
+	let new_reserve_in = U256::from(reserve_in)
+		.checked_add(amount_in_with_fee.into())
+		.ok_or(ArithmeticError::Overflow)?;
+	let new_reserve_out = get_stable_reserve(new_reserve_in, d.into(), amplification)
+		.ok_or(Error::<T>::InvariantCalculationFailed)?;
+
+	// Subtract one to make sure rounding errors are in favour of the pool
+	let amount_out = U256::from(reserve_out)
+		.checked_sub(new_reserve_out)
+		.and_then(|amount_out| amount_out.checked_sub(U256::one()))
+		.unwrap_or_default();
+	amount_out.try_into().map_err(|_| ArithmeticError::Overflow.into())
+}
+
+/// Calculates the amount that has to be paid to buy `amount_out` from a StableSwap pool,
+/// including the fee.
+pub(crate) fn get_stable_amount_in<T: Config>(
+	amount_out: u128,
+	reserve_in: u128,
+	reserve_out: u128,
+	amplification: u32,
+	fee: Permill,
+) -> Result<u128, DispatchError> {
+	ensure!(amount_out > 0, Error::<T>::InsufficientOutputAmount);
+	ensure!(reserve_in > 0 && reserve_out > 0, Error::<T>::InsufficientLiquidity);
+	ensure!(amount_out < reserve_out, Error::<T>::InsufficientLiquidity);
+
+	let d = get_stable_invariant(reserve_in, reserve_out, amplification)
+		.ok_or(Error::<T>::InvariantCalculationFailed)?;
+	let new_reserve_out = U256::from(reserve_out - amount_out);
+	let new_reserve_in = get_stable_reserve(new_reserve_out, d.into(), amplification)
+		.ok_or(Error::<T>::InvariantCalculationFailed)?;
+
+	// Add one to make sure rounding errors are in favour of the pool
+	let amount_in_with_fee: u128 = new_reserve_in
+		.checked_sub(U256::from(reserve_in))
+		.ok_or(Error::<T>::InvariantCalculationFailed)?
+		.saturating_add(U256::one())
+		.try_into()
+		.map_err(|_| ArithmeticError::Overflow)?;
+
+	add_fee(amount_in_with_fee, fee).ok_or_else(|| ArithmeticError::Overflow.into())
+}
+
+/// Adds the fee to `amount_in_with_fee`, rounding up. Deducting the fee from the result as in
+/// `get_fee_amount` leaves at least `amount_in_with_fee`.
+fn add_fee(amount_in_with_fee: u128, fee: Permill) -> Option<u128> {
+	let fee_complement = get_fee_complement(fee);
+	if fee_complement.is_zero() {
+		return None
+	}
+	mul_div(U256::from(amount_in_with_fee), U256::from(FEE_ACCURACY), fee_complement, true)?
+		.try_into()
+		.ok()
+}
+
+/// The lowest weight an asset of a weighted pool can have.
+pub(crate) const MIN_WEIGHT: Permill = Permill::from_percent(1);
+
+/// The approximation of fractional powers stops once the terms of the series get smaller than
+/// this value (`10^-10`).
+const POW_PRECISION: u128 = 100_000_000;
+
+/// The maximum number of terms used to approximate fractional powers.
+const MAX_POW_ITERATIONS: u128 = 100;
+
+/// `√2` as inner value of a `FixedU128`.
+const SQRT_2: u128 = 1_414_213_562_373_095_049;
+
+/// Calculates `base^exp` for a `base` in `(0, 2)`. The integer part of the exponent is
+/// calculated exactly, the fractional part is approximated with a binomial series. This is the
+/// `bpow` function of the Balancer contracts.
+fn pow(base: FixedU128, exp: FixedU128) -> Option<FixedU128> {
+	let two = FixedU128::saturating_from_integer(2u128);
+	if base.is_zero() || base >= two {
+		return None
+	}
+
+	let whole = exp.into_inner() / FixedU128::accuracy();
+	let remain = FixedU128::from_inner(exp.into_inner() % FixedU128::accuracy());
+	let whole_pow = base.saturating_pow(whole.try_into().ok()?);
+	if remain.is_zero() {
+		return Some(whole_pow)
+	}
+
+	let partial_pow = pow_approx(base, remain)?;
+	whole_pow.checked_mul(&partial_pow)
+}
+
+/// Approximates `base^exp` for an `exp` in `[0, 1)` with the binomial series of `(1 + x)^exp`
+/// where `x = base - 1`.
+fn pow_approx(base: FixedU128, exp: FixedU128) -> Option<FixedU128> {
+	let one = FixedU128::one();
+	let (x, x_negative) = if base >= one { (base - one, false) } else { (one - base, true) };
+
+	let mut term = one;
+	let mut sum = one;
+	let mut negative = false;
+	for i in 1..=MAX_POW_ITERATIONS {
+		if term.into_inner() < POW_PRECISION {
+			break
+		}
+
+		// term_i = term_(i-1) * (exp - (i - 1)) * x / i
+		let big_k = FixedU128::saturating_from_integer(i);
+		let k_minus_one = big_k - one;
+		let (c, c_negative) =
+			if exp >= k_minus_one { (exp - k_minus_one, false) } else { (k_minus_one - exp, true) };
+		term = term.checked_mul(&c.checked_mul(&x)?)?.checked_div(&big_k)?;
+		if term.is_zero() {
+			break
+		}
+
+		if x_negative {
+			negative = !negative;
+		}
+		if c_negative {
+			negative = !negative;
+		}
+		sum = if negative { sum.checked_sub(&term)? } else { sum.checked_add(&term)? };
+	}
+	Some(sum)
+}
+
+/// Calculates `base^exp` for any `base >= 1` and an `exp` in `[0, 1]`.
+///
+/// `base` is split into `2^m * y` with `y` in `[1, 2)`, so that
+/// `base^exp = y^exp * 2^(m * exp) = y^exp * 2^k * √2^(2 * f)`, where `k` and `f` are the integer
+/// and the fractional part of `m * exp`. All the bases are in the domain of `pow`.
+fn pow_unbounded(base: FixedU128, exp: FixedU128) -> Option<FixedU128> {
+	let integer_part = base.into_inner() / FixedU128::accuracy();
+	if integer_part == 0 {
+		return None
+	}
+	let m = 127 - integer_part.leading_zeros();
+	let y = FixedU128::from_inner(base.into_inner() >> m);
+
+	let m_exp = exp.checked_mul(&FixedU128::saturating_from_integer(m))?;
+	let k = m_exp.into_inner() / FixedU128::accuracy();
+	let f = FixedU128::from_inner(m_exp.into_inner() % FixedU128::accuracy());
+	let two_pow_k = FixedU128::checked_from_integer(1u128.checked_shl(k.try_into().ok()?)?)?;
+	let sqrt_2_pow = pow(
+		FixedU128::from_inner(SQRT_2),
+		f.checked_mul(&FixedU128::saturating_from_integer(2u128))?,
+	)?;
+
+	pow(y, exp)?.checked_mul(&two_pow_k)?.checked_mul(&sqrt_2_pow)
+}
+
+/// Calculates the invariant `V = reserve_a^weight_a * reserve_b^weight_b` of a weighted pool. The
+/// weights have to add up to one.
+pub(crate) fn get_weighted_invariant(
+	reserve_a: u128,
+	reserve_b: u128,
+	weight_a: Permill,
+	weight_b: Permill,
+) -> Option<u128> {
+	if reserve_a == 0 || reserve_b == 0 {
+		return Some(0)
+	}
+
+	// V = b * (a / b)^w_a = a * (b / a)^w_b, the base of the power has to be at least one
+	let (reserve_low, ratio, exp) = if reserve_a >= reserve_b {
+		(reserve_b, FixedU128::checked_from_rational(reserve_a, reserve_b)?, weight_a)
+	} else {
+		(reserve_a, FixedU128::checked_from_rational(reserve_b, reserve_a)?, weight_b)
+	};
+	let exp = FixedU128::saturating_from_rational(exp.deconstruct(), Permill::ACCURACY);
+	pow_unbounded(ratio, exp)?.checked_mul_int(reserve_low)
+}
+
+/// Calculates the amount received when selling `amount_in` to a weighted pool:
+/// `amount_out = reserve_out * (1 - (reserve_in / (reserve_in + amount_in))^(weight_in /
+/// weight_out))`. The fee is deducted from the input amount.
+pub(crate) fn get_weighted_amount_out<T: Config>(
+	amount_in: u128,
+	reserve_in: u128,
+	reserve_out: u128,
+	weight_in: Permill,
+	weight_out: Permill,
+	fee: Permill,
+) -> Result<u128, DispatchError> {
 	ensure!(amount_in > 0, Error::<T>::InsufficientInputAmount);
 	ensure!(reserve_in > 0 && reserve_out > 0, Error::<T>::InsufficientLiquidity);
+	// The approximation of the power is only precise enough for bounded trade sizes
+	ensure!(amount_in <= reserve_in / 2, Error::<T>::MaxInRatioExceeded);
+
+	let amount_in_with_fee = amount_in.saturating_sub(get_fee_amount(amount_in, fee));
+	let base =
+		FixedU128::checked_from_rational(reserve_in, reserve_in.saturating_add(amount_in_with_fee))
+			.ok_or(ArithmeticError::Overflow)?;
+	let exp = FixedU128::checked_from_rational(weight_in.deconstruct(), weight_out.deconstruct())
+		.ok_or(ArithmeticError::DivisionByZero)?;
+	let power = pow(base, exp).ok_or(ArithmeticError::Overflow)?;
+
+	Ok(FixedU128::one().saturating_sub(power).saturating_mul_int(reserve_out))
+}
+
+/// Calculates the amount that has to be paid to buy `amount_out` from a weighted pool, including
+/// the fee:
+/// `amount_in = reserve_in * ((reserve_out / (reserve_out - amount_out))^(weight_out / weight_in) -
+/// 1)`.
+pub(crate) fn get_weighted_amount_in<T: Config>(
+	amount_out: u128,
+	reserve_in: u128,
+	reserve_out: u128,
+	weight_in: Permill,
+	weight_out: Permill,
+	fee: Permill,
+) -> Result<u128, DispatchError> {
+	ensure!(amount_out > 0, Error::<T>::InsufficientOutputAmount);
+	ensure!(reserve_in > 0 && reserve_out > 0, Error::<T>::InsufficientLiquidity);
+	ensure!(amount_out <= reserve_out / 3, Error::<T>::MaxOutRatioExceeded);
+
+	let base = FixedU128::checked_from_rational(reserve_out, reserve_out - amount_out)
+		.ok_or(ArithmeticError::Overflow)?;
+	let exp = FixedU128::checked_from_rational(weight_out.deconstruct(), weight_in.deconstruct())
+		.ok_or(ArithmeticError::DivisionByZero)?;
+	let power = pow(base, exp).ok_or(ArithmeticError::Overflow)?;
+
+	// Add one to make sure rounding errors are in favour of the pool
+	let amount_in_with_fee = power
+		.saturating_sub(FixedU128::one())
+		.checked_mul_int(reserve_in)
+		.and_then(|amount| amount.checked_add(1))
+		.ok_or(ArithmeticError::Overflow)?;
+
+	add_fee(amount_in_with_fee, fee).ok_or_else(|| ArithmeticError::Overflow.into())
+}
+
+/// Calculates the invariant of a multi-asset pool, the geometric mean of its reserves
+/// `V = (x_1 * ... * x_n)^(1 / n)`. All assets of the pool have the same weight, so `V` equals
+/// the reserves if they are balanced.
+pub(crate) fn get_multi_asset_invariant(reserves: &[u128]) -> Option<u128> {
+	let reserve_low = *reserves.iter().min()?;
+	if reserve_low == 0 {
+		return Some(0)
+	}
+
+	// V = x_min * ((x_1 / x_min) * ... * (x_n / x_min))^(1 / n), every base is at least one
+	let exp = FixedU128::checked_from_rational(1u128, reserves.len() as u128)?;
+	let mut product = FixedU128::one();
+	for reserve in reserves {
+		let ratio = FixedU128::checked_from_rational(*reserve, reserve_low)?;
+		product = product.checked_mul(&pow_unbounded(ratio, exp)?)?;
+	}
+	product.checked_mul_int(reserve_low)
+}
+
+/// Calculates the amount of liquidity tokens minted for depositing only `amount_in` of one asset
+/// into a multi-asset pool with `n_assets` assets of the same weight `w = 1 / n_assets`:
+/// `minted = total_issuance * ((1 + amount_in * (1 - (1 - w) * fee) / reserve_in)^w - 1)`.
+/// The fee is only charged on the part of the deposit that is implicitly swapped into the other
+/// assets.
+pub(crate) fn get_single_asset_liquidity_out<T: Config>(
+	amount_in: u128,
+	reserve_in: u128,
+	total_issuance: u128,
+	n_assets: u32,
+	fee: Permill,
+) -> Result<u128, DispatchError> {
+	ensure!(amount_in > 0, Error::<T>::InsufficientInputAmount);
+	ensure!(reserve_in > 0 && total_issuance > 0, Error::<T>::InsufficientLiquidity);
+	ensure!(amount_in <= reserve_in / 2, Error::<T>::MaxInRatioExceeded);
+
+	let amount_in_with_fee =
+		amount_in.saturating_sub(get_swapped_share_fee(amount_in, n_assets, fee));
+	let base =
+		FixedU128::checked_from_rational(reserve_in.saturating_add(amount_in_with_fee), reserve_in)
+			.ok_or(ArithmeticError::Overflow)?;
+	let exp =
+		FixedU128::checked_from_rational(1u128, n_assets).ok_or(ArithmeticError::DivisionByZero)?;
+	let power = pow(base, exp).ok_or(ArithmeticError::Overflow)?;
+
+	Ok(power.saturating_sub(FixedU128::one()).saturating_mul_int(total_issuance))
+}
+
+/// Calculates the amount of one asset received for burning `liquidity` of a multi-asset pool
+/// with `n_assets` assets of the same weight `w = 1 / n_assets`:
+/// `amount_out = reserve_out * (1 - (1 - liquidity / total_issuance)^(1 / w))`.
+/// Like for deposits, the fee is charged on the share `1 - w` of the amount.
+pub(crate) fn get_single_asset_amount_out<T: Config>(
+	liquidity: u128,
+	reserve_out: u128,
+	total_issuance: u128,
+	n_assets: u32,
+	fee: Permill,
+) -> Result<u128, DispatchError> {
+	ensure!(liquidity > 0, Error::<T>::InvalidAmount);
+	ensure!(reserve_out > 0 && liquidity < total_issuance, Error::<T>::InsufficientLiquidity);
+
+	let base = FixedU128::checked_from_rational(total_issuance - liquidity, total_issuance)
+		.ok_or(ArithmeticError::Overflow)?;
+	let power =
+		pow(base, FixedU128::saturating_from_integer(n_assets)).ok_or(ArithmeticError::Overflow)?;
+	let amount_out_with_fee =
+		FixedU128::one().saturating_sub(power).saturating_mul_int(reserve_out);
+	ensure!(amount_out_with_fee <= reserve_out / 3, Error::<T>::MaxOutRatioExceeded);
+
+	Ok(amount_out_with_fee.saturating_sub(get_swapped_share_fee(
+		amount_out_with_fee,
+		n_assets,
+		fee,
+	)))
+}
+
+/// Calculates the fee on the share `1 - 1 / n_assets` of `amount` that is implicitly swapped
+/// into the other assets when liquidity is added or removed in a single asset.
+fn get_swapped_share_fee(amount: u128, n_assets: u32, fee: Permill) -> u128 {
+	let fee_amount = get_fee_amount(amount, fee);
+	fee_amount.saturating_sub(fee_amount / n_assets.max(1) as u128)
+}
+
+/// The lowest tick of a concentrated-liquidity pool. The price at a tick `i` is `1.0001^i`.
+pub(crate) const MIN_TICK: i32 = -200_000;
+
+/// The highest tick of a concentrated-liquidity pool.
+pub(crate) const MAX_TICK: i32 = 200_000;
+
+/// `√1.0001` as inner value of a `FixedU128`, the ratio of the square root prices of two
+/// neighbouring ticks.
+const SQRT_TICK_BASE: u128 = 1_000_049_998_750_062_496;
+
+/// Calculates the square root of the price at `tick`, `√1.0001^tick`.
+pub(crate) fn get_sqrt_price_at_tick(tick: i32) -> Option<FixedU128> {
+	if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+		return None
+	}
+
+	let sqrt_price =
+		FixedU128::from_inner(SQRT_TICK_BASE).saturating_pow(tick.unsigned_abs() as usize);
+	if tick >= 0 {
+		Some(sqrt_price)
+	} else {
+		FixedU128::one().checked_div(&sqrt_price)
+	}
+}
+
+/// Returns the highest tick whose square root price is at most `sqrt_price`.
+pub(crate) fn get_tick_at_sqrt_price(sqrt_price: FixedU128) -> Option<i32> {
+	if sqrt_price < get_sqrt_price_at_tick(MIN_TICK)? {
+		return None
+	}
 
-	let multiplier: u128 = 1000;
-	let fee_multiplier: u128 = 1000u128.saturating_sub(fee.mul_floor(1000));
+	let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+	while low < high {
+		// Round up so that the range shrinks in every iteration
+		let mid = low + (high - low + 1) / 2;
+		if get_sqrt_price_at_tick(mid)? <= sqrt_price {
+			low = mid;
+		} else {
+			high = mid - 1;
+		}
+	}
+	Some(low)
+}
+
+/// Calculates `a * b / c`, rounding up if `round_up` is set.
+fn mul_div(a: U256, b: U256, c: U256, round_up: bool) -> Option<U256> {
+	let product = a.checked_mul(b)?;
+	let result = product.checked_div(c)?;
+	if round_up && !(product % c).is_zero() {
+		result.checked_add(U256::one())
+	} else {
+		Some(result)
+	}
+}
+
+/// Calculates `a * b / c` with a 256-bit intermediate product, rounding up if `round_up` is set.
+pub(crate) fn checked_mul_div(
+	a: u128,
+	b: u128,
+	c: u128,
+	round_up: bool,
+) -> Result<u128, ArithmeticError> {
+	ensure!(c > 0, ArithmeticError::DivisionByZero);
+	// The product of two `u128` always fits into a `U256`
+	mul_div(U256::from(a), U256::from(b), U256::from(c), round_up)
+		.and_then(|result| result.try_into().ok())
+		.ok_or(ArithmeticError::Overflow)
+}
+
+/// Returns the inner values of two square root prices in ascending order.
+fn sorted_sqrt_prices(sqrt_price_0: FixedU128, sqrt_price_1: FixedU128) -> (U256, U256) {
+	let (lower, upper) = if sqrt_price_0 <= sqrt_price_1 {
+		(sqrt_price_0, sqrt_price_1)
+	} else {
+		(sqrt_price_1, sqrt_price_0)
+	};
+	(U256::from(lower.into_inner()), U256::from(upper.into_inner()))
+}
+
+/// Calculates the amount of token_a that `liquidity` holds between two square root prices:
+/// `Δa = L * (√P_upper - √P_lower) / (√P_upper * √P_lower)`.
+pub(crate) fn get_amount_a_delta(
+	sqrt_price_0: FixedU128,
+	sqrt_price_1: FixedU128,
+	liquidity: u128,
+	round_up: bool,
+) -> Option<u128> {
+	let (lower, upper) = sorted_sqrt_prices(sqrt_price_0, sqrt_price_1);
+	let accuracy = U256::from(FixedU128::accuracy());
+
+	let numerator = U256::from(liquidity).checked_mul(accuracy)?;
+	let amount = mul_div(numerator, upper - lower, upper, round_up)?;
+	mul_div(amount, U256::one(), lower, round_up)?.try_into().ok()
+}
+
+/// Calculates the amount of token_b that `liquidity` holds between two square root prices:
+/// `Δb = L * (√P_upper - √P_lower)`.
+pub(crate) fn get_amount_b_delta(
+	sqrt_price_0: FixedU128,
+	sqrt_price_1: FixedU128,
+	liquidity: u128,
+	round_up: bool,
+) -> Option<u128> {
+	let (lower, upper) = sorted_sqrt_prices(sqrt_price_0, sqrt_price_1);
+	let accuracy = U256::from(FixedU128::accuracy());
+
+	mul_div(U256::from(liquidity), upper - lower, accuracy, round_up)?
+		.try_into()
+		.ok()
+}
+
+/// Calculates the square root price after `amount` of token_a is added to or removed from the
+/// active liquidity: `√P' = L * √P / (L ± Δa * √P)`. The result is rounded up, so the price
+/// moves less if token_a is added and more if it is removed.
+fn get_next_sqrt_price_from_amount_a(
+	sqrt_price: FixedU128,
+	liquidity: u128,
+	amount: u128,
+	add: bool,
+) -> Option<FixedU128> {
+	if amount == 0 {
+		return Some(sqrt_price)
+	}
+
+	let sqrt_price = U256::from(sqrt_price.into_inner());
+	let numerator = U256::from(liquidity).checked_mul(U256::from(FixedU128::accuracy()))?;
+	let product = U256::from(amount).checked_mul(sqrt_price)?;
+	let denominator =
+		if add { numerator.checked_add(product)? } else { numerator.checked_sub(product)? };
+
+	let next = mul_div(numerator, sqrt_price, denominator, true)?;
+	next.try_into().ok().map(FixedU128::from_inner)
+}
+
+/// Calculates the square root price after `amount` of token_b is added to or removed from the
+/// active liquidity: `√P' = √P ± Δb / L`. The result is rounded down, so the price moves less
+/// if token_b is added and more if it is removed.
+fn get_next_sqrt_price_from_amount_b(
+	sqrt_price: FixedU128,
+	liquidity: u128,
+	amount: u128,
+	add: bool,
+) -> Option<FixedU128> {
+	let sqrt_price = U256::from(sqrt_price.into_inner());
+	let quotient = mul_div(
+		U256::from(amount),
+		U256::from(FixedU128::accuracy()),
+		U256::from(liquidity),
+		!add,
+	)?;
+
+	let next =
+		if add { sqrt_price.checked_add(quotient)? } else { sqrt_price.checked_sub(quotient)? };
+	if next.is_zero() {
+		return None
+	}
+	next.try_into().ok().map(FixedU128::from_inner)
+}
+
+/// The result of a swap within a price range of constant liquidity.
+pub(crate) struct SwapStep {
+	/// The square root price after the step.
+	pub sqrt_price_next: FixedU128,
+	/// The amount paid into the pool, without the fee.
+	pub amount_in: u128,
+	/// The amount received from the pool.
+	pub amount_out: u128,
+	/// The fee paid on top of `amount_in`.
+	pub fee_amount: u128,
+}
+
+/// Calculates a swap from `sqrt_price_current` towards `sqrt_price_target` with constant
+/// `liquidity`, following `SwapMath.computeSwapStep` of Uniswap v3. The swap stops at the target
+/// price or once `amount_remaining` is used up. `amount_remaining` is the amount paid including
+/// the fee if `exact_in` is set and the amount received otherwise.
+pub(crate) fn compute_swap_step(
+	sqrt_price_current: FixedU128,
+	sqrt_price_target: FixedU128,
+	liquidity: u128,
+	amount_remaining: u128,
+	fee: Permill,
+	exact_in: bool,
+) -> Option<SwapStep> {
+	// Selling token_a lowers the price of token_a
+	let a_for_b = sqrt_price_current >= sqrt_price_target;
+	let fee_parts = U256::from(fee.deconstruct());
+	let remaining_parts = U256::from(Permill::ACCURACY).checked_sub(fee_parts)?;
+
+	let sqrt_price_next = if exact_in {
+		let amount_remaining_less_fee: u128 = mul_div(
+			U256::from(amount_remaining),
+			remaining_parts,
+			U256::from(Permill::ACCURACY),
+			false,
+		)?
+		.try_into()
+		.ok()?;
+		let amount_in_max = if a_for_b {
+			get_amount_a_delta(sqrt_price_target, sqrt_price_current, liquidity, true)?
+		} else {
+			get_amount_b_delta(sqrt_price_current, sqrt_price_target, liquidity, true)?
+		};
+
+		if amount_remaining_less_fee >= amount_in_max {
+			sqrt_price_target
+		} else if a_for_b {
+			get_next_sqrt_price_from_amount_a(
+				sqrt_price_current,
+				liquidity,
+				amount_remaining_less_fee,
+				true,
+			)?
+		} else {
+			get_next_sqrt_price_from_amount_b(
+				sqrt_price_current,
+				liquidity,
+				amount_remaining_less_fee,
+				true,
+			)?
+		}
+	} else {
+		let amount_out_max = if a_for_b {
+			get_amount_b_delta(sqrt_price_target, sqrt_price_current, liquidity, false)?
+		} else {
+			get_amount_a_delta(sqrt_price_current, sqrt_price_target, liquidity, false)?
+		};
+
+		if amount_remaining >= amount_out_max {
+			sqrt_price_target
+		} else if a_for_b {
+			get_next_sqrt_price_from_amount_b(
+				sqrt_price_current,
+				liquidity,
+				amount_remaining,
+				false,
+			)?
+		} else {
+			get_next_sqrt_price_from_amount_a(
+				sqrt_price_current,
+				liquidity,
+				amount_remaining,
+				false,
+			)?
+		}
+	};
+	let reached_target = sqrt_price_next == sqrt_price_target;
+
+	let (amount_in, amount_out) = if a_for_b {
+		(
+			get_amount_a_delta(sqrt_price_next, sqrt_price_current, liquidity, true)?,
+			get_amount_b_delta(sqrt_price_next, sqrt_price_current, liquidity, false)?,
+		)
+	} else {
+		(
+			get_amount_b_delta(sqrt_price_current, sqrt_price_next, liquidity, true)?,
+			get_amount_a_delta(sqrt_price_current, sqrt_price_next, liquidity, false)?,
+		)
+	};
+
+	let amount_out = match (exact_in, reached_target) {
+		(true, _) => amount_out,
+		(false, true) => amount_out.min(amount_remaining),
+		// The price was derived from the remaining amount, so that amount is used up completely
+		(false, false) => amount_remaining,
+	};
+	let fee_amount = if exact_in && !reached_target {
+		// The price was derived from the remaining amount, the rest of it is the fee
+		amount_remaining.saturating_sub(amount_in)
+	} else {
+		mul_div(U256::from(amount_in), fee_parts, remaining_parts, true)?
+			.try_into()
+			.ok()?
+	};
+
+	Some(SwapStep { sqrt_price_next, amount_in, amount_out, fee_amount })
+}
+
+/// Calculates the liquidity that `amount_a` provides between two square root prices:
+/// `L = Δa * √P_lower * √P_upper / (√P_upper - √P_lower)`.
+fn get_liquidity_for_amount_a(
+	sqrt_price_0: FixedU128,
+	sqrt_price_1: FixedU128,
+	amount_a: u128,
+) -> Option<u128> {
+	let (lower, upper) = sorted_sqrt_prices(sqrt_price_0, sqrt_price_1);
+	let liquidity = mul_div(U256::from(amount_a), lower, upper - lower, false)?;
+	mul_div(liquidity, upper, U256::from(FixedU128::accuracy()), false)?
+		.try_into()
+		.ok()
+}
+
+/// Calculates the liquidity that `amount_b` provides between two square root prices:
+/// `L = Δb / (√P_upper - √P_lower)`.
+fn get_liquidity_for_amount_b(
+	sqrt_price_0: FixedU128,
+	sqrt_price_1: FixedU128,
+	amount_b: u128,
+) -> Option<u128> {
+	let (lower, upper) = sorted_sqrt_prices(sqrt_price_0, sqrt_price_1);
+	mul_div(U256::from(amount_b), U256::from(FixedU128::accuracy()), upper - lower, false)?
+		.try_into()
+		.ok()
+}
+
+/// Calculates the highest liquidity that can be provided between `sqrt_price_lower` and
+/// `sqrt_price_upper` with at most `amount_a` and `amount_b`, given the current `sqrt_price`.
+pub(crate) fn get_liquidity_for_amounts(
+	sqrt_price: FixedU128,
+	sqrt_price_lower: FixedU128,
+	sqrt_price_upper: FixedU128,
+	amount_a: u128,
+	amount_b: u128,
+) -> Option<u128> {
+	if sqrt_price <= sqrt_price_lower {
+		get_liquidity_for_amount_a(sqrt_price_lower, sqrt_price_upper, amount_a)
+	} else if sqrt_price < sqrt_price_upper {
+		let liquidity_a = get_liquidity_for_amount_a(sqrt_price, sqrt_price_upper, amount_a)?;
+		let liquidity_b = get_liquidity_for_amount_b(sqrt_price_lower, sqrt_price, amount_b)?;
+		Some(liquidity_a.min(liquidity_b))
+	} else {
+		get_liquidity_for_amount_b(sqrt_price_lower, sqrt_price_upper, amount_b)
+	}
+}
+
+/// Calculates the amounts of token_a and token_b that `liquidity` holds between
+/// `sqrt_price_lower` and `sqrt_price_upper`, given the current `sqrt_price`. Below the range the
+/// liquidity is held in token_a only, above it in token_b only.
+pub(crate) fn get_amounts_for_liquidity(
+	sqrt_price: FixedU128,
+	sqrt_price_lower: FixedU128,
+	sqrt_price_upper: FixedU128,
+	liquidity: u128,
+	round_up: bool,
+) -> Option<(u128, u128)> {
+	if sqrt_price <= sqrt_price_lower {
+		let amount_a = get_amount_a_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?;
+		Some((amount_a, 0))
+	} else if sqrt_price < sqrt_price_upper {
+		Some((
+			get_amount_a_delta(sqrt_price, sqrt_price_upper, liquidity, round_up)?,
+			get_amount_b_delta(sqrt_price_lower, sqrt_price, liquidity, round_up)?,
+		))
+	} else {
+		let amount_b = get_amount_b_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?;
+		Some((0, amount_b))
+	}
+}
+
+/// Applies a signed change to an amount of liquidity.
+pub(crate) fn add_liquidity_delta(liquidity: u128, liquidity_delta: i128) -> Option<u128> {
+	if liquidity_delta < 0 {
+		liquidity.checked_sub(liquidity_delta.unsigned_abs())
+	} else {
+		liquidity.checked_add(liquidity_delta.unsigned_abs())
+	}
+}
+
+/// Calculates the growth of the fees per unit of liquidity when `liquidity` earns `fee_amount`.
+/// The growth is the inner value of a `FixedU128` and wraps around on overflow, like the fee
+/// growth it is added to.
+pub(crate) fn get_fee_growth(fee_amount: u128, liquidity: u128) -> u128 {
+	mul_div(U256::from(fee_amount), U256::from(FixedU128::accuracy()), U256::from(liquidity), false)
+		.map(|growth| growth.low_u128())
+		.unwrap_or_default()
+}
 
-	// Subtract fee from amount_in
-	let amount_in_with_fee = amount_in.saturating_mul(fee_multiplier);
-	let numerator = amount_in_with_fee.saturating_mul(reserve_out);
-	let denominator = reserve_in.saturating_mul(multiplier).saturating_add(amount_in_with_fee);
-	let result = numerator.checked_div(denominator).unwrap_or(0);
-	Ok(result)
+/// Calculates the fees earned by `liquidity` while the fee growth per unit of liquidity grew by
+/// `fee_growth_delta`. Returns `None` if the fees don't fit into a `u128`.
+pub(crate) fn get_fees_earned(liquidity: u128, fee_growth_delta: u128) -> Option<u128> {
+	mul_div(
+		U256::from(liquidity),
+		U256::from(fee_growth_delta),
+		U256::from(FixedU128::accuracy()),
+		false,
+	)
+	.and_then(|fees| fees.try_into().ok())
 }
@@ -0,0 +1,611 @@
+use crate::{
+	calc,
+	traits::{ConcentratedState, OnSwap, PoolKind, PoolStatus, Position},
+	types::*,
+	ConcentratedStates, Config, Error, Event, InitializedTicks, Pallet, Positions, Ticks,
+};
+use frame_support::{dispatch::DispatchResult, ensure, BoundedVec};
+use orml_traits::MultiCurrency;
+use sp_runtime::{
+	traits::{Convert, Saturating, Zero},
+	ArithmeticError, DispatchError, FixedPointNumber, FixedU128,
+};
+use sp_std::{convert::TryInto, vec::Vec};
+
+/// The highest tick spacing a concentrated-liquidity pool can be created with.
+pub(crate) const MAX_TICK_SPACING: u32 = 10_000;
+
+/// The outcome of a swap on a concentrated-liquidity pool, calculated without changing the pool.
+pub(crate) struct ConcentratedSwap {
+	/// The amount paid into the pool, including the fee.
+	pub amount_in: u128,
+	pub amount_out: u128,
+	pub fee: u128,
+	/// The state of the pool after the swap.
+	pub state: ConcentratedState,
+	/// The ticks crossed by the swap together with the global fee growths of token_a and token_b
+	/// at the time of the crossing.
+	pub crossed_ticks: Vec<(i32, u128, u128)>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Returns the state of a concentrated-liquidity pool.
+	pub(crate) fn get_concentrated_state(
+		pool_id: PoolIdOf<T>,
+	) -> Result<ConcentratedState, DispatchError> {
+		ConcentratedStates::<T>::get(pool_id).ok_or_else(|| Error::<T>::PoolNotFound.into())
+	}
+
+	/// Returns the tick spacing of a concentrated-liquidity pool and fails for all other kinds of
+	/// pools.
+	fn concentrated_tick_spacing(pool: &PoolOf<T>) -> Result<u32, DispatchError> {
+		match pool.kind {
+			PoolKind::Concentrated { tick_spacing } => Ok(tick_spacing),
+			_ => Err(Error::<T>::UnsupportedPoolKind.into()),
+		}
+	}
+
+	/// Returns the initial state of a new concentrated-liquidity pool, which starts at the given
+	/// square root price without any liquidity. The price has to lie between the prices of
+	/// `MIN_TICK` and `MAX_TICK`.
+	pub(crate) fn initial_concentrated_state(
+		sqrt_price: Option<FixedU128>,
+	) -> Result<ConcentratedState, DispatchError> {
+		let sqrt_price = sqrt_price.ok_or(Error::<T>::InvalidInitialPrice)?;
+		let max_sqrt_price =
+			calc::get_sqrt_price_at_tick(calc::MAX_TICK).ok_or(Error::<T>::InvalidInitialPrice)?;
+		ensure!(sqrt_price <= max_sqrt_price, Error::<T>::InvalidInitialPrice);
+		// Fails for prices below the price of `MIN_TICK`
+		let tick =
+			calc::get_tick_at_sqrt_price(sqrt_price).ok_or(Error::<T>::InvalidInitialPrice)?;
+		Ok(ConcentratedState { sqrt_price, tick, ..Default::default() })
+	}
+
+	/// Returns the square root prices at both ends of a range, after ensuring that the range is
+	/// valid for the given tick spacing.
+	fn sqrt_price_range(
+		tick_lower: i32,
+		tick_upper: i32,
+		tick_spacing: u32,
+	) -> Result<(FixedU128, FixedU128), DispatchError> {
+		let tick_spacing: i32 =
+			tick_spacing.try_into().map_err(|_| Error::<T>::InvalidTickSpacing)?;
+		ensure!(
+			tick_lower < tick_upper &&
+				tick_lower % tick_spacing == 0 &&
+				tick_upper % tick_spacing == 0,
+			Error::<T>::InvalidTickRange
+		);
+
+		let sqrt_price_lower =
+			calc::get_sqrt_price_at_tick(tick_lower).ok_or(Error::<T>::InvalidTickRange)?;
+		let sqrt_price_upper =
+			calc::get_sqrt_price_at_tick(tick_upper).ok_or(Error::<T>::InvalidTickRange)?;
+		Ok((sqrt_price_lower, sqrt_price_upper))
+	}
+
+	/// Returns the spot prices of token_a in token_b and of token_b in token_a. There is no price
+	/// while the pool has no active liquidity.
+	pub(crate) fn concentrated_spot_prices(
+		pool_id: PoolIdOf<T>,
+	) -> Result<Option<(FixedU128, FixedU128)>, DispatchError> {
+		let state = Self::get_concentrated_state(pool_id)?;
+		if state.liquidity.is_zero() {
+			return Ok(None)
+		}
+
+		let price_a = state.sqrt_price.saturating_mul(state.sqrt_price);
+		let price_b = price_a.reciprocal().unwrap_or_default();
+		Ok(Some((price_a, price_b)))
+	}
+
+	/// Calculates the fee growth of token_a and token_b inside the range of a position.
+	fn fee_growth_inside(
+		pool_id: PoolIdOf<T>,
+		tick_lower: i32,
+		tick_upper: i32,
+		state: &ConcentratedState,
+	) -> (u128, u128) {
+		let lower = Ticks::<T>::get(pool_id, tick_lower).unwrap_or_default();
+		let upper = Ticks::<T>::get(pool_id, tick_upper).unwrap_or_default();
+
+		let (below_a, below_b) = if state.tick >= tick_lower {
+			(lower.fee_growth_outside_a, lower.fee_growth_outside_b)
+		} else {
+			(
+				state.fee_growth_global_a.wrapping_sub(lower.fee_growth_outside_a),
+				state.fee_growth_global_b.wrapping_sub(lower.fee_growth_outside_b),
+			)
+		};
+		let (above_a, above_b) = if state.tick < tick_upper {
+			(upper.fee_growth_outside_a, upper.fee_growth_outside_b)
+		} else {
+			(
+				state.fee_growth_global_a.wrapping_sub(upper.fee_growth_outside_a),
+				state.fee_growth_global_b.wrapping_sub(upper.fee_growth_outside_b),
+			)
+		};
+
+		(
+			state.fee_growth_global_a.wrapping_sub(below_a).wrapping_sub(above_a),
+			state.fee_growth_global_b.wrapping_sub(below_b).wrapping_sub(above_b),
+		)
+	}
+
+	/// Applies `liquidity_delta` to a tick at the lower or the upper end of a position. Returns
+	/// whether the tick was initialized or is no longer referenced by any position.
+	fn update_tick(
+		pool_id: PoolIdOf<T>,
+		tick: i32,
+		state: &ConcentratedState,
+		liquidity_delta: i128,
+		upper: bool,
+	) -> Result<bool, DispatchError> {
+		let mut info = Ticks::<T>::get(pool_id, tick).unwrap_or_default();
+		let liquidity_gross_before = info.liquidity_gross;
+		info.liquidity_gross = calc::add_liquidity_delta(liquidity_gross_before, liquidity_delta)
+			.ok_or(ArithmeticError::Overflow)?;
+		let flipped = liquidity_gross_before.is_zero() != info.liquidity_gross.is_zero();
+
+		if liquidity_gross_before.is_zero() {
+			// By convention, all fees so far were earned below the tick if it is at or below
+			// the current price
+			if tick <= state.tick {
+				info.fee_growth_outside_a = state.fee_growth_global_a;
+				info.fee_growth_outside_b = state.fee_growth_global_b;
+			}
+			InitializedTicks::<T>::try_mutate(pool_id, |ticks| -> DispatchResult {
+				if let Err(index) = ticks.binary_search(&tick) {
+					let mut new_ticks = ticks.clone().into_inner();
+					new_ticks.insert(index, tick);
+					*ticks =
+						BoundedVec::try_from(new_ticks).map_err(|_| Error::<T>::TooManyTicks)?;
+				}
+				Ok(())
+			})?;
+		}
+
+		// Crossing the lower tick upwards activates the liquidity, crossing the upper tick
+		// deactivates it
+		info.liquidity_net = if upper {
+			info.liquidity_net.checked_sub(liquidity_delta)
+		} else {
+			info.liquidity_net.checked_add(liquidity_delta)
+		}
+		.ok_or(ArithmeticError::Overflow)?;
+
+		Ticks::<T>::insert(pool_id, tick, info);
+		Ok(flipped)
+	}
+
+	/// Removes a tick that is no longer referenced by any position.
+	fn clear_tick(pool_id: PoolIdOf<T>, tick: i32) {
+		Ticks::<T>::remove(pool_id, tick);
+		InitializedTicks::<T>::mutate(pool_id, |ticks| {
+			if let Ok(index) = ticks.binary_search(&tick) {
+				ticks.remove(index);
+			}
+		});
+	}
+
+	/// Changes the liquidity of the position of `who` in the given range by `liquidity_delta`,
+	/// after crediting the fees the position earned so far. Returns the updated position, which
+	/// has to be stored by the caller.
+	fn modify_position(
+		who: &AccountIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		tick_lower: i32,
+		tick_upper: i32,
+		liquidity_delta: i128,
+	) -> Result<Position, DispatchError> {
+		let mut state = Self::get_concentrated_state(pool_id)?;
+		let (flipped_lower, flipped_upper) = if liquidity_delta == 0 {
+			(false, false)
+		} else {
+			(
+				Self::update_tick(pool_id, tick_lower, &state, liquidity_delta, false)?,
+				Self::update_tick(pool_id, tick_upper, &state, liquidity_delta, true)?,
+			)
+		};
+
+		let (fee_growth_inside_a, fee_growth_inside_b) =
+			Self::fee_growth_inside(pool_id, tick_lower, tick_upper, &state);
+		let mut position =
+			Positions::<T>::get(who, (pool_id, tick_lower, tick_upper)).unwrap_or_default();
+		let fees_earned_a = calc::get_fees_earned(
+			position.liquidity,
+			fee_growth_inside_a.wrapping_sub(position.fee_growth_inside_a_last),
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		let fees_earned_b = calc::get_fees_earned(
+			position.liquidity,
+			fee_growth_inside_b.wrapping_sub(position.fee_growth_inside_b_last),
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		position.fees_owed_a = position
+			.fees_owed_a
+			.checked_add(fees_earned_a)
+			.ok_or(ArithmeticError::Overflow)?;
+		position.fees_owed_b = position
+			.fees_owed_b
+			.checked_add(fees_earned_b)
+			.ok_or(ArithmeticError::Overflow)?;
+		position.fee_growth_inside_a_last = fee_growth_inside_a;
+		position.fee_growth_inside_b_last = fee_growth_inside_b;
+		position.liquidity = calc::add_liquidity_delta(position.liquidity, liquidity_delta)
+			.ok_or(Error::<T>::InsufficientLiquidityBalance)?;
+
+		if liquidity_delta < 0 {
+			if flipped_lower {
+				Self::clear_tick(pool_id, tick_lower);
+			}
+			if flipped_upper {
+				Self::clear_tick(pool_id, tick_upper);
+			}
+		}
+
+		// Only positions whose range contains the current price are active
+		if tick_lower <= state.tick && state.tick < tick_upper {
+			state.liquidity = calc::add_liquidity_delta(state.liquidity, liquidity_delta)
+				.ok_or(ArithmeticError::Overflow)?;
+			ConcentratedStates::<T>::insert(pool_id, state);
+		}
+		Ok(position)
+	}
+
+	/// Stores a position, or removes it once it has neither liquidity nor uncollected fees.
+	fn store_position(
+		who: &AccountIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		tick_lower: i32,
+		tick_upper: i32,
+		position: Position,
+	) {
+		let key = (pool_id, tick_lower, tick_upper);
+		if position.liquidity.is_zero() &&
+			position.fees_owed_a.is_zero() &&
+			position.fees_owed_b.is_zero()
+		{
+			Positions::<T>::remove(who, key);
+		} else {
+			Positions::<T>::insert(who, key, position);
+		}
+	}
+
+	/// Adds the highest liquidity that can be provided with at most `amount_a_desired` and
+	/// `amount_b_desired` to the position of `who` in the given range. Returns the added
+	/// liquidity.
+	pub(crate) fn do_mint_position(
+		who: &AccountIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		tick_lower: i32,
+		tick_upper: i32,
+		amount_a_desired: BalanceOf<T>,
+		amount_b_desired: BalanceOf<T>,
+	) -> Result<u128, DispatchError> {
+		let pool = Self::get_pool(pool_id)?;
+		let tick_spacing = Self::concentrated_tick_spacing(&pool)?;
+		ensure!(pool.status != PoolStatus::Paused, Error::<T>::PoolPaused);
+		let (sqrt_price_lower, sqrt_price_upper) =
+			Self::sqrt_price_range(tick_lower, tick_upper, tick_spacing)?;
+
+		// Convert to u128 for calculations
+		let (amount_a_desired, amount_b_desired): (u128, u128) =
+			(T::Convert::convert(amount_a_desired), T::Convert::convert(amount_b_desired));
+
+		let state = Self::get_concentrated_state(pool_id)?;
+		let liquidity = calc::get_liquidity_for_amounts(
+			state.sqrt_price,
+			sqrt_price_lower,
+			sqrt_price_upper,
+			amount_a_desired,
+			amount_b_desired,
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		ensure!(liquidity > 0, Error::<T>::InvalidAmount);
+		let liquidity_delta: i128 = liquidity.try_into().map_err(|_| ArithmeticError::Overflow)?;
+
+		// Round up so that the pool always holds enough to pay out the position
+		let (amount_a, amount_b) = calc::get_amounts_for_liquidity(
+			state.sqrt_price,
+			sqrt_price_lower,
+			sqrt_price_upper,
+			liquidity,
+			true,
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		ensure!(
+			amount_a <= amount_a_desired && amount_b <= amount_b_desired,
+			Error::<T>::InvalidAmount
+		);
+
+		// Convert back to balances
+		let (amount_a, amount_b) = (T::Convert::convert(amount_a), T::Convert::convert(amount_b));
+		ensure!(
+			T::Assets::free_balance(pool.pair.token_a, who) >= amount_a,
+			Error::<T>::InsufficientBalance
+		);
+		ensure!(
+			T::Assets::free_balance(pool.pair.token_b, who) >= amount_b,
+			Error::<T>::InsufficientBalance
+		);
+
+		Self::update_price_cumulatives(pool_id)?;
+		let position =
+			Self::modify_position(who, pool_id, tick_lower, tick_upper, liquidity_delta)?;
+		Self::store_position(who, pool_id, tick_lower, tick_upper, position);
+
+		let pool_account = Self::account_id(&pool_id);
+		T::Assets::transfer(pool.pair.token_a, who, &pool_account, amount_a)?;
+		T::Assets::transfer(pool.pair.token_b, who, &pool_account, amount_b)?;
+
+		Self::deposit_event(Event::<T>::PositionMinted {
+			who: who.clone(),
+			pool_id,
+			tick_lower,
+			tick_upper,
+			liquidity,
+			amount_a,
+			amount_b,
+		});
+		Ok(liquidity)
+	}
+
+	/// Removes `liquidity` from the position of `who` in the given range and pays out the
+	/// corresponding amounts. The fees earned by the position are kept until they are collected.
+	pub(crate) fn do_burn_position(
+		who: &AccountIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		tick_lower: i32,
+		tick_upper: i32,
+		liquidity: u128,
+	) -> Result<(BalanceOf<T>, BalanceOf<T>), DispatchError> {
+		let pool = Self::get_pool(pool_id)?;
+		let tick_spacing = Self::concentrated_tick_spacing(&pool)?;
+		let (sqrt_price_lower, sqrt_price_upper) =
+			Self::sqrt_price_range(tick_lower, tick_upper, tick_spacing)?;
+
+		let position = Positions::<T>::get(who, (pool_id, tick_lower, tick_upper))
+			.ok_or(Error::<T>::PositionNotFound)?;
+		ensure!(liquidity > 0, Error::<T>::InvalidAmount);
+		ensure!(liquidity <= position.liquidity, Error::<T>::InsufficientLiquidityBalance);
+		let liquidity_delta: i128 = liquidity.try_into().map_err(|_| ArithmeticError::Overflow)?;
+
+		// Round down so that the pool always holds enough to pay out the other positions
+		let state = Self::get_concentrated_state(pool_id)?;
+		let (amount_a, amount_b) = calc::get_amounts_for_liquidity(
+			state.sqrt_price,
+			sqrt_price_lower,
+			sqrt_price_upper,
+			liquidity,
+			false,
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+
+		Self::update_price_cumulatives(pool_id)?;
+		let position =
+			Self::modify_position(who, pool_id, tick_lower, tick_upper, -liquidity_delta)?;
+		Self::store_position(who, pool_id, tick_lower, tick_upper, position);
+
+		// Convert back to balances
+		let (amount_a, amount_b) = (T::Convert::convert(amount_a), T::Convert::convert(amount_b));
+
+		let pool_account = Self::account_id(&pool_id);
+		T::Assets::transfer(pool.pair.token_a, &pool_account, who, amount_a)?;
+		T::Assets::transfer(pool.pair.token_b, &pool_account, who, amount_b)?;
+
+		Self::deposit_event(Event::<T>::PositionBurned {
+			who: who.clone(),
+			pool_id,
+			tick_lower,
+			tick_upper,
+			liquidity,
+			amount_a,
+			amount_b,
+		});
+		Ok((amount_a, amount_b))
+	}
+
+	/// Pays out the fees earned by the position of `who` in the given range.
+	pub(crate) fn do_collect_fees(
+		who: &AccountIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		tick_lower: i32,
+		tick_upper: i32,
+	) -> Result<(BalanceOf<T>, BalanceOf<T>), DispatchError> {
+		let pool = Self::get_pool(pool_id)?;
+		Self::concentrated_tick_spacing(&pool)?;
+		ensure!(
+			Positions::<T>::contains_key(who, (pool_id, tick_lower, tick_upper)),
+			Error::<T>::PositionNotFound
+		);
+
+		let mut position = Self::modify_position(who, pool_id, tick_lower, tick_upper, 0)?;
+		let (amount_a, amount_b) = (
+			T::Convert::convert(sp_std::mem::take(&mut position.fees_owed_a)),
+			T::Convert::convert(sp_std::mem::take(&mut position.fees_owed_b)),
+		);
+		Self::store_position(who, pool_id, tick_lower, tick_upper, position);
+
+		let pool_account = Self::account_id(&pool_id);
+		T::Assets::transfer(pool.pair.token_a, &pool_account, who, amount_a)?;
+		T::Assets::transfer(pool.pair.token_b, &pool_account, who, amount_b)?;
+
+		Self::deposit_event(Event::<T>::FeesCollected {
+			who: who.clone(),
+			pool_id,
+			tick_lower,
+			tick_upper,
+			amount_a,
+			amount_b,
+		});
+		Ok((amount_a, amount_b))
+	}
+
+	/// Calculates a swap of `asset_in` on a concentrated-liquidity pool. `amount` is the amount
+	/// paid including the fee if `exact_in` is set and the amount received otherwise. The swap
+	/// moves through the ranges of constant liquidity between the initialized ticks, the fees
+	/// of each range are shared by the liquidity that is active in it.
+	pub(crate) fn compute_concentrated_swap(
+		pool_id: PoolIdOf<T>,
+		pool: &PoolOf<T>,
+		asset_in: AssetIdOf<T>,
+		amount: u128,
+		exact_in: bool,
+	) -> Result<ConcentratedSwap, DispatchError> {
+		ensure!(pool.pair.contains(asset_in), Error::<T>::InvalidAsset);
+		if exact_in {
+			ensure!(amount > 0, Error::<T>::InsufficientInputAmount);
+		} else {
+			ensure!(amount > 0, Error::<T>::InsufficientOutputAmount);
+		}
+
+		let mut state = Self::get_concentrated_state(pool_id)?;
+		let ticks = InitializedTicks::<T>::get(pool_id);
+		// Selling token_a lowers the price of token_a
+		let a_for_b = asset_in == pool.pair.token_a;
+		let limit_tick = if a_for_b { calc::MIN_TICK } else { calc::MAX_TICK };
+		let sqrt_price_limit =
+			calc::get_sqrt_price_at_tick(limit_tick).ok_or(ArithmeticError::Overflow)?;
+
+		let mut amount_remaining = amount;
+		let mut amount_calculated: u128 = 0;
+		let mut fee: u128 = 0;
+		let mut crossed_ticks = Vec::new();
+		while amount_remaining > 0 && state.sqrt_price != sqrt_price_limit {
+			let next_tick = if a_for_b {
+				ticks.iter().rev().find(|tick| **tick <= state.tick).copied()
+			} else {
+				ticks.iter().find(|tick| **tick > state.tick).copied()
+			};
+			let target_tick = next_tick.unwrap_or(limit_tick);
+			let sqrt_price_target =
+				calc::get_sqrt_price_at_tick(target_tick).ok_or(ArithmeticError::Overflow)?;
+
+			let step = calc::compute_swap_step(
+				state.sqrt_price,
+				sqrt_price_target,
+				state.liquidity,
+				amount_remaining,
+				pool.fee,
+				exact_in,
+			)
+			.ok_or(ArithmeticError::Overflow)?;
+			state.sqrt_price = step.sqrt_price_next;
+
+			let step_amount_in =
+				step.amount_in.checked_add(step.fee_amount).ok_or(ArithmeticError::Overflow)?;
+			let (step_remaining, step_calculated) = if exact_in {
+				(step_amount_in, step.amount_out)
+			} else {
+				(step.amount_out, step_amount_in)
+			};
+			amount_remaining = amount_remaining.saturating_sub(step_remaining);
+			amount_calculated = amount_calculated
+				.checked_add(step_calculated)
+				.ok_or(ArithmeticError::Overflow)?;
+			fee = fee.saturating_add(step.fee_amount);
+
+			if state.liquidity > 0 {
+				let fee_growth = calc::get_fee_growth(step.fee_amount, state.liquidity);
+				if a_for_b {
+					state.fee_growth_global_a = state.fee_growth_global_a.wrapping_add(fee_growth);
+				} else {
+					state.fee_growth_global_b = state.fee_growth_global_b.wrapping_add(fee_growth);
+				}
+			}
+
+			if state.sqrt_price == sqrt_price_target {
+				if let Some(tick) = next_tick {
+					let liquidity_net =
+						Ticks::<T>::get(pool_id, tick).unwrap_or_default().liquidity_net;
+					// Crossing a tick downwards reverses the change of the liquidity
+					let liquidity_delta = if a_for_b {
+						liquidity_net.checked_neg().ok_or(ArithmeticError::Overflow)?
+					} else {
+						liquidity_net
+					};
+					state.liquidity = calc::add_liquidity_delta(state.liquidity, liquidity_delta)
+						.ok_or(ArithmeticError::Overflow)?;
+					crossed_ticks.push((
+						tick,
+						state.fee_growth_global_a,
+						state.fee_growth_global_b,
+					));
+				}
+				state.tick = if a_for_b { target_tick - 1 } else { target_tick };
+			} else {
+				state.tick = calc::get_tick_at_sqrt_price(state.sqrt_price)
+					.ok_or(ArithmeticError::Overflow)?;
+			}
+		}
+		ensure!(amount_remaining.is_zero(), Error::<T>::InsufficientLiquidity);
+
+		let (amount_in, amount_out) =
+			if exact_in { (amount, amount_calculated) } else { (amount_calculated, amount) };
+		Ok(ConcentratedSwap { amount_in, amount_out, fee, state, crossed_ticks })
+	}
+
+	/// Swaps `amount` of `asset_in` on a concentrated-liquidity pool if `exact_in` is set, or
+	/// buys `amount` of the other asset otherwise. Returns the amount received.
+	pub(crate) fn concentrated_swap(
+		who: &AccountIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		pool: &PoolOf<T>,
+		asset_in: AssetIdOf<T>,
+		amount: BalanceOf<T>,
+		exact_in: bool,
+	) -> Result<BalanceOf<T>, DispatchError> {
+		match pool.status {
+			PoolStatus::Active => {},
+			PoolStatus::SwapsPaused => return Err(Error::<T>::SwapsPaused.into()),
+			PoolStatus::Paused => return Err(Error::<T>::PoolPaused.into()),
+		}
+		let pool_account = Self::account_id(&pool_id);
+		let asset_out =
+			if asset_in == pool.pair.token_a { pool.pair.token_b } else { pool.pair.token_a };
+
+		let swap = Self::compute_concentrated_swap(
+			pool_id,
+			pool,
+			asset_in,
+			T::Convert::convert(amount),
+			exact_in,
+		)?;
+		ensure!(swap.amount_out > 0, Error::<T>::InvalidAmount);
+
+		Self::update_price_cumulatives(pool_id)?;
+		for (tick, fee_growth_global_a, fee_growth_global_b) in swap.crossed_ticks {
+			Ticks::<T>::mutate(pool_id, tick, |info| {
+				if let Some(info) = info {
+					info.fee_growth_outside_a =
+						fee_growth_global_a.wrapping_sub(info.fee_growth_outside_a);
+					info.fee_growth_outside_b =
+						fee_growth_global_b.wrapping_sub(info.fee_growth_outside_b);
+				}
+			});
+		}
+		ConcentratedStates::<T>::insert(pool_id, swap.state);
+
+		// Convert back to balances
+		let amount_in = T::Convert::convert(swap.amount_in);
+		let amount_out = T::Convert::convert(swap.amount_out);
+		let fee = T::Convert::convert(swap.fee);
+
+		T::Assets::transfer(asset_in, who, &pool_account, amount_in)?;
+		T::Assets::transfer(asset_out, &pool_account, who, amount_out)?;
+
+		Self::deposit_event(Event::<T>::Swapped {
+			pool_id,
+			who: who.clone(),
+			token_a: asset_out,
+			token_b: asset_in,
+			amount_a: amount_out,
+			amount_b: amount_in,
+			fee,
+			estimated_protocol_fee: Zero::zero(),
+		});
+		T::OnSwap::on_swap(pool_id);
+		Ok(amount_out)
+	}
+}
@@ -1,30 +1,37 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use codec::{FullCodec};
-use frame_support::{transactional};
-use num_integer::{sqrt};
+use codec::{Decode, FullCodec};
+use frame_support::{traits::Contains, transactional};
 use orml_traits::{MultiCurrency};
 
 use sp_arithmetic::{PerThing, Permill};
+use sp_core::U256;
 use sp_runtime::{
 	traits::{
-		AccountIdConversion, AtLeast32BitUnsigned, CheckedAdd, CheckedDiv, CheckedMul,
-		Convert, One, Saturating, StaticLookup, Zero,
+		AccountIdConversion, AtLeast32BitUnsigned, CheckedAdd, CheckedDiv, CheckedMul, Convert,
+		One, SaturatedConversion, Saturating, StaticLookup, TrailingZeroInput, Zero,
 	},
-	ArithmeticError, FixedPointOperand,
-};
-use sp_std::{
-	convert::{TryInto},
-	fmt::Debug,
+	ArithmeticError, FixedPointNumber, FixedPointOperand, FixedU128,
 };
+use sp_std::{convert::TryInto, fmt::Debug, vec::Vec};
 
 pub use pallet::*;
+pub use weights::WeightInfo;
 
 use types::*;
 
+mod batch;
 mod calc;
-mod traits;
+mod concentrated;
+pub mod migrations;
+mod multi_asset;
+mod router;
+pub mod traits;
 mod types;
+pub mod weights;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 
 #[cfg(test)]
 mod mock;
@@ -37,7 +44,11 @@ pub mod pallet {
 	use frame_support::{pallet_prelude::*, PalletId};
 	use frame_system::pallet_prelude::*;
 
-	use crate::traits::{Amm, CurrencyPair, Pool};
+	use crate::traits::{
+		Amm, ConcentratedState, CurrencyPair, FlashSwapHandler, Observation, OnSwap, Oracle, Pool,
+		PoolKind, PoolStatus, Position, TickInfo,
+	};
+	use sp_std::vec;
 
 	use super::*;
 
@@ -54,6 +65,7 @@ pub mod pallet {
 			+ Copy
 			+ Default
 			+ TypeInfo
+			+ MaxEncodedLen
 			+ Debug;
 
 		/// The type of assets used by the Assets handler.
@@ -97,16 +109,85 @@ pub mod pallet {
 		/// Used to convert Balance to u128 in order to use it in the arithmetic.
 		type Convert: Convert<u128, BalanceOf<Self>> + Convert<BalanceOf<Self>, u128>;
 
-		/// This is used to derive the AssetId of the liquidity token.
-		/// The derivation is supposed to happen based on the two AssetIds of the assets in a pair.
-		type LiquidityTokenConversion: StaticLookup<
-			Source = AssetIdOf<Self>,
-			Target = (AssetIdOf<Self>, AssetIdOf<Self>),
+		/// Derives the AssetId of the liquidity token of a pool from the pool's id (`unlookup`)
+		/// and finds the pool of a liquidity token (`lookup`).
+		type LiquidityTokenConversion: StaticLookup<Source = AssetIdOf<Self>, Target = Self::PoolId>;
+
+		/// The amount of liquidity tokens that is locked forever on the first deposit of a pair
+		/// pool, see section 3.4 of the Uniswap v2 whitepaper.
+		#[pallet::constant]
+		type MinimumLiquidity: Get<BalanceOf<Self>>;
+
+		/// The maximum number of assets a multi-asset pool can hold.
+		#[pallet::constant]
+		type MaxAssetsInPool: Get<u32>;
+
+		/// The maximum number of initialized ticks of a concentrated-liquidity pool. Every
+		/// position initializes at most two ticks.
+		#[pallet::constant]
+		type MaxTicksPerPool: Get<u32>;
+
+		/// The maximum number of pools a routed swap can go through.
+		#[pallet::constant]
+		type MaxPathLength: Get<u32>;
+
+		/// The maximum number of pair pools with different fees that can exist for one pair of
+		/// assets. The best-price router quotes all of them, so this bounds its weight.
+		#[pallet::constant]
+		type MaxPoolsPerPair: Get<u32>;
+
+		/// The maximum number of swaps that can be queued for a pool in batch auction mode
+		/// within one block.
+		#[pallet::constant]
+		type MaxOrdersPerBatch: Get<u32>;
+
+		/// The share of the swap fees that is minted as liquidity tokens to the protocol fee
+		/// receiver. Setting it to zero switches the protocol fee off.
+		#[pallet::constant]
+		type ProtocolFeeShare: Get<Permill>;
+
+		/// The account receiving the protocol fee. If `None`, the fee accrues to the owner of
+		/// each pool.
+		type ProtocolFeeReceiver: Get<Option<Self::AccountId>>;
+
+		/// The origin that is allowed to administrate any pool and to create pools on behalf of
+		/// other accounts.
+		type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The number of price observations that are kept for each pool. At most one observation
+		/// is recorded per block, in the first block that changes the reserves, so this limits
+		/// the longest window of the time-weighted average price to at least as many blocks.
+		#[pallet::constant]
+		type MaxObservations: Get<u32>;
+
+		/// Notified after every swap, e.g. to settle limit orders against the new price.
+		type OnSwap: OnSwap<Self::PoolId>;
+
+		/// Called by `flash_swap` after the borrowed amounts were sent. Has to pay the pool back.
+		type FlashSwapHandler: FlashSwapHandler<
+			Self::AccountId,
+			Self::AssetId,
+			BalanceOf<Self>,
+			Self::PoolId,
 		>;
+
+		/// The assets pools can be created for, e.g. the active assets of an asset registry.
+		type RegisteredAssets: Contains<Self::AssetId>;
+
+		/// Weight information for the extrinsics of this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Registers the assets that are used by the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<Self::AssetId>;
 	}
 
+	/// The current storage version.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(4);
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub (super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	/// Keeps track of the number of pools in existence.
@@ -120,11 +201,139 @@ pub mod pallet {
 	#[pallet::getter(fn pools)]
 	pub type Pools<T: Config> = StorageMap<_, Blake2_128Concat, PoolIdOf<T>, PoolOf<T>>;
 
+	/// Map the pool id to the multi-asset pool. Pair pools and multi-asset pools share their ids.
+	#[pallet::storage]
+	#[pallet::getter(fn multi_asset_pools)]
+	pub type MultiAssetPools<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolIdOf<T>, MultiAssetPoolOf<T>>;
+
+	/// The price and the active liquidity of each concentrated-liquidity pool.
+	#[pallet::storage]
+	#[pallet::getter(fn concentrated_states)]
+	pub type ConcentratedStates<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolIdOf<T>, ConcentratedState>;
+
+	/// The liquidity of the positions starting or ending at each initialized tick of a
+	/// concentrated-liquidity pool.
+	#[pallet::storage]
+	#[pallet::getter(fn ticks)]
+	pub type Ticks<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, PoolIdOf<T>, Blake2_128Concat, i32, TickInfo>;
+
+	/// The initialized ticks of each concentrated-liquidity pool in ascending order.
+	#[pallet::storage]
+	pub type InitializedTicks<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		PoolIdOf<T>,
+		BoundedVec<i32, T::MaxTicksPerPool>,
+		ValueQuery,
+	>;
+
+	/// The concentrated-liquidity positions of each account, keyed by the pool and the lower and
+	/// upper tick of the position.
+	#[pallet::storage]
+	#[pallet::getter(fn positions)]
+	pub type Positions<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		(PoolIdOf<T>, i32, i32),
+		Position,
+	>;
+
+	/// Map the canonical (sorted) asset pair and the fee of a pair pool to the id of the pool.
+	/// There can be one pool per pair and fee.
+	#[pallet::storage]
+	#[pallet::getter(fn pool_id_by_pair)]
+	pub type PoolIdByPair<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		(AssetIdOf<T>, AssetIdOf<T>),
+		Blake2_128Concat,
+		Permill,
+		PoolIdOf<T>,
+	>;
+
 	/// Map the pool id to the account that holds the pool's funds.
 	#[pallet::storage]
 	#[pallet::getter(fn pool_accounts)]
 	pub type PoolAccounts<T: Config> = StorageMap<_, Blake2_128Concat, PoolIdOf<T>, AccountIdOf<T>>;
 
+	/// Map the pool id to the invariant of the pool right after the last liquidity event. Only
+	/// tracked while the protocol fee is switched on.
+	#[pallet::storage]
+	#[pallet::getter(fn invariant_last)]
+	pub type InvariantLast<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolIdOf<T>, BalanceOf<T>, ValueQuery>;
+
+	/// The latest cumulative prices of each pool.
+	#[pallet::storage]
+	#[pallet::getter(fn price_cumulatives)]
+	pub type PriceCumulatives<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolIdOf<T>, ObservationOf<T>, ValueQuery>;
+
+	/// Ring buffer of `MaxObservations` cumulative prices of each pool, keyed by the pool id and
+	/// the position in the buffer. An observation is recorded by the first change of the reserves
+	/// in a block, before the change is applied.
+	#[pallet::storage]
+	#[pallet::getter(fn observations)]
+	pub type Observations<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, PoolIdOf<T>, Twox64Concat, u32, ObservationOf<T>>;
+
+	/// The position of the newest observation of each pool in `Observations`, if any.
+	#[pallet::storage]
+	#[pallet::getter(fn observation_index)]
+	pub type ObservationIndex<T: Config> = StorageMap<_, Blake2_128Concat, PoolIdOf<T>, u32>;
+
+	/// Pools that are in the middle of a flash swap and can't be used until it is repaid.
+	#[pallet::storage]
+	pub type FlashSwapLocks<T: Config> = StorageMap<_, Blake2_128Concat, PoolIdOf<T>, ()>;
+
+	/// Pair pools whose swaps are queued and executed in a batch auction at the end of the block.
+	#[pallet::storage]
+	pub type BatchAuctionPools<T: Config> = StorageMap<_, Blake2_128Concat, PoolIdOf<T>, ()>;
+
+	/// The swaps that were queued for a pool in batch auction mode during the current block.
+	#[pallet::storage]
+	#[pallet::getter(fn batch_orders)]
+	pub type BatchOrders<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		PoolIdOf<T>,
+		BoundedVec<BatchOrderOf<T>, T::MaxOrdersPerBatch>,
+		ValueQuery,
+	>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		/// Pools that are created at genesis. The first deposit into a pool sets its price.
+		pub pools: Vec<GenesisPoolOf<T>>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> GenesisConfig<T> {
+			GenesisConfig { pools: vec![] }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			for (pool_params, deposits) in &self.pools {
+				let pool_id = Pallet::<T>::do_create_pool(pool_params.clone())
+					.expect("Genesis pool can't be created");
+				let pool = Pallet::<T>::get_pool(pool_id).expect("Genesis pool was just created");
+				for (who, amount_a, amount_b) in deposits {
+					Pallet::<T>::do_add_liquidity(who, pool_id, &pool, *amount_a, *amount_b)
+						.expect("Genesis liquidity can't be added");
+				}
+			}
+		}
+	}
+
 	// Pallets use events to inform users when important changes are made.
 	// https://docs.substrate.io/v3/runtime/events-and-errors
 	#[pallet::event]
@@ -132,6 +341,12 @@ pub mod pallet {
 	pub enum Event<T: Config> {
 		/// A new pol was created
 		PoolCreated { pool_id: T::PoolId, owner: T::AccountId, assets: CurrencyPair<AssetIdOf<T>> },
+		/// A new multi-asset pool was created
+		MultiAssetPoolCreated {
+			pool_id: PoolIdOf<T>,
+			owner: T::AccountId,
+			assets: BoundedVec<AssetIdOf<T>, T::MaxAssetsInPool>,
+		},
 		/// Liquidity was added to a pool
 		LiquidityAdded {
 			who: T::AccountId,
@@ -148,6 +363,51 @@ pub mod pallet {
 			amount_b: BalanceOf<T>,
 			total_issuance: BalanceOf<T>,
 		},
+		/// Liquidity was added to a multi-asset pool. The amounts are in the order of the pool's
+		/// assets.
+		MultiAssetLiquidityAdded {
+			who: T::AccountId,
+			pool_id: PoolIdOf<T>,
+			amounts: Vec<BalanceOf<T>>,
+			minted_lp: BalanceOf<T>,
+		},
+		/// Liquidity was removed from a multi-asset pool. The amounts are in the order of the
+		/// pool's assets.
+		MultiAssetLiquidityRemoved {
+			who: T::AccountId,
+			pool_id: PoolIdOf<T>,
+			amounts: Vec<BalanceOf<T>>,
+			total_issuance: BalanceOf<T>,
+		},
+		/// Liquidity was added to a concentrated-liquidity position
+		PositionMinted {
+			who: T::AccountId,
+			pool_id: PoolIdOf<T>,
+			tick_lower: i32,
+			tick_upper: i32,
+			liquidity: u128,
+			amount_a: BalanceOf<T>,
+			amount_b: BalanceOf<T>,
+		},
+		/// Liquidity was removed from a concentrated-liquidity position
+		PositionBurned {
+			who: T::AccountId,
+			pool_id: PoolIdOf<T>,
+			tick_lower: i32,
+			tick_upper: i32,
+			liquidity: u128,
+			amount_a: BalanceOf<T>,
+			amount_b: BalanceOf<T>,
+		},
+		/// The fees earned by a concentrated-liquidity position were collected
+		FeesCollected {
+			who: T::AccountId,
+			pool_id: PoolIdOf<T>,
+			tick_lower: i32,
+			tick_upper: i32,
+			amount_a: BalanceOf<T>,
+			amount_b: BalanceOf<T>,
+		},
 		/// Two assets were swapped in a pool
 		Swapped {
 			who: T::AccountId,
@@ -156,8 +416,86 @@ pub mod pallet {
 			token_b: AssetIdOf<T>,
 			amount_a: BalanceOf<T>,
 			amount_b: BalanceOf<T>,
-			/// Charged fees.
-			fee: Permill,
+			/// Charged fees, paid in `token_b`.
+			fee: BalanceOf<T>,
+			/// Estimate of the part of `fee` that accrues to the protocol fee receiver, i.e.
+			/// `ProtocolFeeShare` of it. The protocol fee is minted later as liquidity tokens from
+			/// the growth of the invariant, see `ProtocolFeeMinted` for the amount received.
+			estimated_protocol_fee: BalanceOf<T>,
+		},
+		/// Assets were borrowed from a pool and paid back within a flash swap
+		FlashSwapped {
+			who: T::AccountId,
+			pool_id: PoolIdOf<T>,
+			amount_a_out: BalanceOf<T>,
+			amount_b_out: BalanceOf<T>,
+			amount_a_in: BalanceOf<T>,
+			amount_b_in: BalanceOf<T>,
+		},
+		/// Assets were swapped along a path of pools
+		SwappedViaPath {
+			who: T::AccountId,
+			asset_in: AssetIdOf<T>,
+			asset_out: AssetIdOf<T>,
+			amount_in: BalanceOf<T>,
+			amount_out: BalanceOf<T>,
+		},
+		/// Assets were swapped on the pools of a pair that offered the best price
+		SwappedViaBestRoute {
+			who: T::AccountId,
+			asset_in: AssetIdOf<T>,
+			asset_out: AssetIdOf<T>,
+			amount_in: BalanceOf<T>,
+			amount_out: BalanceOf<T>,
+		},
+		/// The protocol's share of the swap fees was minted as liquidity tokens
+		ProtocolFeeMinted { pool_id: PoolIdOf<T>, receiver: T::AccountId, amount: BalanceOf<T> },
+		/// The fee of a pool was changed
+		PoolFeeUpdated { pool_id: PoolIdOf<T>, fee: Permill },
+		/// The status of a pool was changed
+		PoolStatusUpdated { pool_id: PoolIdOf<T>, status: PoolStatus },
+		/// The ownership of a pool was transferred to another account
+		PoolOwnershipTransferred {
+			pool_id: PoolIdOf<T>,
+			old_owner: T::AccountId,
+			new_owner: T::AccountId,
+		},
+		/// The batch auction mode of a pool was switched on or off
+		BatchAuctionModeUpdated { pool_id: PoolIdOf<T>, enabled: bool },
+		/// A swap was queued for the batch auction of a pool
+		BatchOrderSubmitted {
+			who: T::AccountId,
+			pool_id: PoolIdOf<T>,
+			asset_in: AssetIdOf<T>,
+			amount_in: BalanceOf<T>,
+			min_amount_out: BalanceOf<T>,
+		},
+		/// A queued swap was executed at the clearing price of the batch
+		BatchOrderExecuted {
+			who: T::AccountId,
+			pool_id: PoolIdOf<T>,
+			asset_in: AssetIdOf<T>,
+			amount_in: BalanceOf<T>,
+			amount_out: BalanceOf<T>,
+		},
+		/// A queued swap was refunded because it would have received less than its minimum or
+		/// the batch couldn't be cleared
+		BatchOrderRefunded {
+			who: T::AccountId,
+			pool_id: PoolIdOf<T>,
+			asset_in: AssetIdOf<T>,
+			amount_in: BalanceOf<T>,
+		},
+		/// The queued swaps of a pool were executed at a single price. The opposing amounts were
+		/// matched with each other, only the excess of one side was swapped with the pool.
+		BatchAuctionCleared {
+			pool_id: PoolIdOf<T>,
+			/// The price of token_a in token_b that all orders were executed at.
+			price: FixedU128,
+			/// The total amount of token_a sold by the orders.
+			sold_a: BalanceOf<T>,
+			/// The total amount of token_b sold by the orders.
+			sold_b: BalanceOf<T>,
 		},
 	}
 
@@ -175,154 +513,1626 @@ pub mod pallet {
 		InsufficientLiquidityBalance,
 		InvalidExchangeValue,
 		WithdrawWithoutSupply,
+		/// The path is empty, uses a pool more than once or the assets of consecutive pools don't
+		/// connect.
+		InvalidPath,
+		/// The amount received at the end of the path is lower than the requested minimum.
+		PathOutputTooLow,
+		/// The amount paid at the start of the path is higher than the allowed maximum.
+		PathInputTooHigh,
+		/// A pool for the given pair of assets and fee already exists.
+		PoolAlreadyExists,
+		/// The pair has reached the maximum number of pools.
+		TooManyPoolsForPair,
+		/// None of the pools of the pair can execute the trade.
+		NoRoute,
+		/// The deadline of the transaction has already passed.
+		DeadlinePassed,
+		/// The amount received from the swap is lower than the requested minimum.
+		AmountOutBelowMinimum,
+		/// The amount that has to be paid for the swap is higher than the allowed maximum.
+		AmountInAboveMaximum,
+		/// The amount of minted liquidity tokens is lower than the requested minimum.
+		LpMintedBelowMinimum,
+		/// The first deposit of a pool has to mint more than `MinimumLiquidity` liquidity tokens.
+		InsufficientInitialLiquidity,
+		/// The amount of token_a received for the liquidity is lower than the requested minimum.
+		AmountAOutBelowMinimum,
+		/// The amount of token_b received for the liquidity is lower than the requested minimum.
+		AmountBOutBelowMinimum,
+		/// The origin is neither the owner of the pool nor the admin origin.
+		NotPoolOwner,
+		/// The owner of a new pool has to be the signer, unless the pool is created by the admin
+		/// origin.
+		OwnerNotSigner,
+		/// The fee of a pool has to be lower than 100%.
+		InvalidFee,
+		/// Swaps are paused for this pool.
+		SwapsPaused,
+		/// The pool is paused, only withdrawals are allowed.
+		PoolPaused,
+		/// The window of a time-weighted average price has to be at least one block.
+		InvalidWindow,
+		/// There is no price observation that is old enough for the requested window.
+		InsufficientObservations,
+		/// The amplification coefficient of a StableSwap pool has to be between 1 and
+		/// `MAX_AMPLIFICATION`.
+		InvalidAmplification,
+		/// The invariant of a StableSwap pool could not be calculated.
+		InvariantCalculationFailed,
+		/// The weights of a weighted pool have to add up to 100% and each weight has to be at
+		/// least `MIN_WEIGHT`.
+		InvalidWeights,
+		/// Weighted pools only accept swaps of up to half of the input reserve.
+		MaxInRatioExceeded,
+		/// Weighted pools only allow buying up to a third of the output reserve.
+		MaxOutRatioExceeded,
+		/// A multi-asset pool has to hold at least three distinct assets.
+		InvalidPoolAssets,
+		/// The operation is not supported by the kind of the pool.
+		UnsupportedPoolKind,
+		/// The tick spacing of a concentrated-liquidity pool has to be between 1 and
+		/// `MAX_TICK_SPACING`.
+		InvalidTickSpacing,
+		/// The lower tick of a position has to be below the upper tick, both have to be multiples
+		/// of the tick spacing between `MIN_TICK` and `MAX_TICK`.
+		InvalidTickRange,
+		/// The pool has reached the maximum number of initialized ticks.
+		TooManyTicks,
+		/// The account has no position in the given range of the pool.
+		PositionNotFound,
+		/// The pool was not paid back enough during a flash swap.
+		FlashSwapNotRepaid,
+		/// The pool is in the middle of a flash swap.
+		PoolLocked,
+		/// Pools can only be created for registered assets.
+		AssetNotRegistered,
+		/// The swaps of the pool are executed in batch auctions. Only exact-in swaps can be
+		/// submitted to it.
+		BatchAuctionPool,
+		/// The batch of the pool already holds `MaxOrdersPerBatch` orders in this block.
+		TooManyBatchOrders,
+		/// A hop of the path would have paid a different amount than the previous hop received.
+		PathAmountMismatch,
+		/// The pool has no liquidity yet. The first deposit sets the price of the pool and has to
+		/// be made with `create_pool_with_liquidity` or `create_multi_asset_pool_with_liquidity`.
+		EmptyPool,
+		/// The initial price of a concentrated-liquidity pool is missing or outside of the prices
+		/// of `MIN_TICK` and `MAX_TICK`, or an initial price was given for another kind of pool.
+		InvalidInitialPrice,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: T::BlockNumber) -> Weight {
+			// The batches are drained in `on_finalize`, clearing them is paid by their orders
+			T::DbWeight::get().reads(1)
+		}
+
+		fn on_finalize(_n: T::BlockNumber) {
+			Self::clear_batches();
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			Self::do_try_state()
+		}
 	}
 
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
 		/// Create a new pool with the given params.
 		///
+		/// The owner of the pool has to be the signer, unless the call is made by the
+		/// `AdminOrigin`. Concentrated-liquidity pools start at the `initial_sqrt_price` of the
+		/// params.
+		///
 		/// Emits `PoolCreated` event when successful.
-		#[pallet::weight(10_000)]
+		#[pallet::weight(T::WeightInfo::create_pool())]
 		pub fn create_pool(
 			origin: OriginFor<T>,
 			pool_params: PoolCreationParamsOf<T>,
 		) -> DispatchResult {
-			let _ = ensure_signed(origin)?;
+			if T::AdminOrigin::ensure_origin(origin.clone()).is_err() {
+				let who = ensure_signed(origin)?;
+				ensure!(who == pool_params.owner, Error::<T>::OwnerNotSigner);
+			}
+
+			let pool_id = Self::do_create_pool(pool_params.clone())?;
+
+			Self::deposit_event(Event::<T>::PoolCreated {
+				owner: pool_params.owner,
+				pool_id,
+				assets: pool_params.pair,
+			});
+
+			Ok(())
+		}
+
+		/// Create a new pool with the given params and deposit `amount_a` of token_a and
+		/// `amount_b` of token_b, which sets the initial price of the pool. The signer makes the
+		/// deposit and has to be the owner of the pool.
+		///
+		/// `MinimumLiquidity` of the minted liquidity tokens are locked forever.
+		///
+		/// Emits `PoolCreated` and `LiquidityAdded` events when successful.
+		#[pallet::weight(T::WeightInfo::create_pool_with_liquidity())]
+		#[transactional]
+		pub fn create_pool_with_liquidity(
+			origin: OriginFor<T>,
+			pool_params: PoolCreationParamsOf<T>,
+			amount_a: BalanceOf<T>,
+			amount_b: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(who == pool_params.owner, Error::<T>::OwnerNotSigner);
+			// Concentrated liquidity is added with `mint_position`
+			ensure!(
+				!matches!(pool_params.kind, PoolKind::Concentrated { .. }),
+				Error::<T>::UnsupportedPoolKind
+			);
+
+			let pool_id = Self::do_create_pool(pool_params.clone())?;
+			Self::deposit_event(Event::<T>::PoolCreated {
+				owner: pool_params.owner,
+				pool_id,
+				assets: pool_params.pair,
+			});
+
+			let pool = Self::get_pool(pool_id)?;
+			Self::do_add_liquidity(&who, pool_id, &pool, amount_a, amount_b)?;
+			Ok(())
+		}
+
+		/// Create a new multi-asset pool holding the given `assets`. All assets have the same
+		/// weight. The owner of the pool has to be the signer, unless the call is made by the
+		/// `AdminOrigin`.
+		///
+		/// Emits `MultiAssetPoolCreated` event when successful.
+		#[pallet::weight(T::WeightInfo::create_multi_asset_pool(assets.len() as u32))]
+		pub fn create_multi_asset_pool(
+			origin: OriginFor<T>,
+			owner: T::AccountId,
+			assets: BoundedVec<AssetIdOf<T>, T::MaxAssetsInPool>,
+			fee: Permill,
+		) -> DispatchResult {
+			if T::AdminOrigin::ensure_origin(origin.clone()).is_err() {
+				let who = ensure_signed(origin)?;
+				ensure!(who == owner, Error::<T>::OwnerNotSigner);
+			}
+
+			let pool_id = Self::do_create_multi_asset_pool(owner.clone(), assets.clone(), fee)?;
+
+			Self::deposit_event(Event::<T>::MultiAssetPoolCreated { pool_id, owner, assets });
+			Ok(())
+		}
+
+		/// Create a new multi-asset pool holding the given `assets` and deposit the `amounts` of
+		/// them, in the same order, which sets the initial prices of the pool. The signer makes
+		/// the deposit and becomes the owner of the pool.
+		///
+		/// `MinimumLiquidity` of the minted liquidity tokens are locked forever.
+		///
+		/// Emits `MultiAssetPoolCreated` and `MultiAssetLiquidityAdded` events when successful.
+		#[pallet::weight(T::WeightInfo::create_multi_asset_pool_with_liquidity(assets.len() as u32))]
+		#[transactional]
+		pub fn create_multi_asset_pool_with_liquidity(
+			origin: OriginFor<T>,
+			assets: BoundedVec<AssetIdOf<T>, T::MaxAssetsInPool>,
+			fee: Permill,
+			amounts: BoundedVec<BalanceOf<T>, T::MaxAssetsInPool>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let pool_id = Self::do_create_multi_asset_pool(who.clone(), assets.clone(), fee)?;
+			Self::deposit_event(Event::<T>::MultiAssetPoolCreated {
+				pool_id,
+				owner: who.clone(),
+				assets,
+			});
+
+			Self::do_add_initial_multi_asset_liquidity(&who, pool_id, amounts.into_inner())?;
+			Ok(())
+		}
+
+		/// Add liquidity to a pair or multi-asset pool. The amounts of the other assets are
+		/// derived from the `amount` of `asset` such that the ratio of the reserves stays the same.
+		/// A pool without liquidity has to be funded with `create_pool_with_liquidity` or
+		/// `create_multi_asset_pool_with_liquidity`.
+		///
+		/// Fails if less than `min_lp_minted` liquidity tokens would be minted or if the
+		/// optional `deadline` block has already passed.
+		///
+		/// Emits `LiquidityAdded` event when successful.
+		#[pallet::weight(
+			T::WeightInfo::add_liquidity()
+				.max(T::WeightInfo::add_multi_asset_liquidity(T::MaxAssetsInPool::get()))
+		)]
+		#[transactional]
+		pub fn add_liquidity(
+			origin: OriginFor<T>,
+			pool_id: T::PoolId,
+			amount: BalanceOf<T>,
+			asset: AssetIdOf<T>,
+			min_lp_minted: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			let minted_lp = <Self as Amm>::add_liquidity(&sender, pool_id, amount, asset)?;
+			ensure!(minted_lp >= min_lp_minted, Error::<T>::LpMintedBelowMinimum);
+
+			Ok(())
+		}
+
+		/// Remove liquidity from a pool.
+		///
+		/// Fails if less than `min_amount_a`/`min_amount_b` of the pool's assets would be
+		/// received or if the optional `deadline` block has already passed.
+		///
+		/// Emits `LiquidityRemoved` event when successful.
+		#[pallet::weight(T::WeightInfo::remove_liquidity())]
+		#[transactional]
+		pub fn remove_liquidity(
+			origin: OriginFor<T>,
+			pool_id: T::PoolId,
+			amount: BalanceOf<T>,
+			min_amount_a: BalanceOf<T>,
+			min_amount_b: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			let (amount_a, amount_b) = <Self as Amm>::remove_liquidity(&sender, pool_id, amount)?;
+			ensure!(amount_a >= min_amount_a, Error::<T>::AmountAOutBelowMinimum);
+			ensure!(amount_b >= min_amount_b, Error::<T>::AmountBOutBelowMinimum);
+
+			Ok(())
+		}
+
+		/// Add liquidity to a multi-asset pool by depositing only `amount` of `asset`. Part of
+		/// the deposit is implicitly swapped into the other assets, so the pool fee is charged on
+		/// that part.
+		///
+		/// Fails if less than `min_lp_minted` liquidity tokens would be minted or if the
+		/// optional `deadline` block has already passed.
+		///
+		/// Emits `MultiAssetLiquidityAdded` event when successful.
+		#[pallet::weight(T::WeightInfo::add_single_asset_liquidity(T::MaxAssetsInPool::get()))]
+		#[transactional]
+		pub fn add_single_asset_liquidity(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			asset: AssetIdOf<T>,
+			amount: BalanceOf<T>,
+			min_lp_minted: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			let minted_lp = Self::do_add_single_asset_liquidity(&who, pool_id, asset, amount)?;
+			ensure!(minted_lp >= min_lp_minted, Error::<T>::LpMintedBelowMinimum);
+			Ok(())
+		}
+
+		/// Remove liquidity from a multi-asset pool proportionally to its reserves.
+		///
+		/// Fails if less than `min_amounts_out` of the pool's assets would be received or if the
+		/// optional `deadline` block has already passed. The minimum amounts are given in the
+		/// order of the pool's assets.
+		///
+		/// Emits `MultiAssetLiquidityRemoved` event when successful.
+		#[pallet::weight(
+			T::WeightInfo::remove_multi_asset_liquidity(min_amounts_out.len() as u32)
+		)]
+		#[transactional]
+		pub fn remove_multi_asset_liquidity(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			amount: BalanceOf<T>,
+			min_amounts_out: BoundedVec<BalanceOf<T>, T::MaxAssetsInPool>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			let amounts = Self::do_remove_multi_asset_liquidity(&who, pool_id, amount)?;
+			ensure!(amounts.len() == min_amounts_out.len(), Error::<T>::InvalidAmount);
+			for (amount_out, min_amount_out) in amounts.iter().zip(min_amounts_out.iter()) {
+				ensure!(amount_out >= min_amount_out, Error::<T>::AmountOutBelowMinimum);
+			}
+			Ok(())
+		}
+
+		/// Remove liquidity from a multi-asset pool by withdrawing only `asset`. Part of the
+		/// withdrawal is implicitly swapped from the other assets, so the pool fee is charged on
+		/// that part.
+		///
+		/// Fails if less than `min_amount_out` of `asset` would be received or if the optional
+		/// `deadline` block has already passed.
+		///
+		/// Emits `MultiAssetLiquidityRemoved` event when successful.
+		#[pallet::weight(T::WeightInfo::remove_single_asset_liquidity(T::MaxAssetsInPool::get()))]
+		#[transactional]
+		pub fn remove_single_asset_liquidity(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			asset: AssetIdOf<T>,
+			amount: BalanceOf<T>,
+			min_amount_out: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			let amount_out = Self::do_remove_single_asset_liquidity(&who, pool_id, asset, amount)?;
+			ensure!(amount_out >= min_amount_out, Error::<T>::AmountOutBelowMinimum);
+			Ok(())
+		}
+
+		/// Add liquidity to the price range between `tick_lower` and `tick_upper` of a
+		/// concentrated-liquidity pool, the price at a tick `i` is `1.0001^i`. The highest
+		/// liquidity that can be provided with at most `amount_a_desired` and `amount_b_desired`
+		/// is added to the sender's position in that range.
+		///
+		/// Fails if the optional `deadline` block has already passed.
+		///
+		/// Emits `PositionMinted` event when successful.
+		#[pallet::weight(T::WeightInfo::mint_position(T::MaxTicksPerPool::get() / 2))]
+		#[transactional]
+		pub fn mint_position(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			tick_lower: i32,
+			tick_upper: i32,
+			amount_a_desired: BalanceOf<T>,
+			amount_b_desired: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			Self::do_mint_position(
+				&who,
+				pool_id,
+				tick_lower,
+				tick_upper,
+				amount_a_desired,
+				amount_b_desired,
+			)?;
+			Ok(())
+		}
+
+		/// Remove `liquidity` from the sender's position in the given price range of a
+		/// concentrated-liquidity pool. The fees earned by the position are kept until they are
+		/// collected with `collect_fees`.
+		///
+		/// Fails if less than `min_amount_a`/`min_amount_b` of the pool's assets would be
+		/// received or if the optional `deadline` block has already passed.
+		///
+		/// Emits `PositionBurned` event when successful.
+		#[pallet::weight(T::WeightInfo::burn_position(T::MaxTicksPerPool::get() / 2))]
+		#[transactional]
+		pub fn burn_position(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			tick_lower: i32,
+			tick_upper: i32,
+			liquidity: u128,
+			min_amount_a: BalanceOf<T>,
+			min_amount_b: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			let (amount_a, amount_b) =
+				Self::do_burn_position(&who, pool_id, tick_lower, tick_upper, liquidity)?;
+			ensure!(amount_a >= min_amount_a, Error::<T>::AmountAOutBelowMinimum);
+			ensure!(amount_b >= min_amount_b, Error::<T>::AmountBOutBelowMinimum);
+			Ok(())
+		}
+
+		/// Transfer the fees earned by the sender's position in the given price range of a
+		/// concentrated-liquidity pool to the sender.
+		///
+		/// Emits `FeesCollected` event when successful.
+		#[pallet::weight(T::WeightInfo::collect_fees())]
+		#[transactional]
+		pub fn collect_fees(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			tick_lower: i32,
+			tick_upper: i32,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::do_collect_fees(&who, pool_id, tick_lower, tick_upper)?;
+			Ok(())
+		}
+
+		/// Execute a swap. The order of the tokens in the pair is important.
+		/// The user will send amount_b of pair.token_b to the pool to receive the corresponding
+		/// amount of pair.token_a.
+		///
+		/// Fails if less than `min_amount_out` of pair.token_a would be received or if the
+		/// optional `deadline` block has already passed. On a pool in batch auction mode the swap
+		/// is queued until the end of the block, see `set_batch_auction`.
+		///
+		/// Emits `Swapped` event when successful, or `BatchOrderSubmitted` when queued.
+		#[pallet::weight(Pallet::<T>::exact_in_swap_weight())]
+		#[transactional]
+		pub fn swap(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			pair: CurrencyPair<AssetIdOf<T>>,
+			amount_b: BalanceOf<T>,
+			min_amount_out: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			if Self::is_batch_auction_pool(pool_id) {
+				ensure!(pair == Self::get_pool(pool_id)?.pair, Error::<T>::PairMismatch);
+				return Self::submit_batch_order(
+					&who,
+					pool_id,
+					pair.token_b,
+					amount_b,
+					min_amount_out,
+				)
+			}
+
+			let amount_out = <Self as Amm>::swap(&who, pool_id, pair, amount_b)?;
+			ensure!(amount_out >= min_amount_out, Error::<T>::AmountOutBelowMinimum);
+			Ok(())
+		}
+
+		/// Borrow `amount_a_out` of token_a and `amount_b_out` of token_b from a constant product
+		/// pool. The amounts are sent to the caller before the `FlashSwapHandler` is called with
+		/// the given `data`. Before it returns, the handler has to pay the pool back in any of the
+		/// two assets such that the invariant of the pool, with the fee deducted from the paid
+		/// amounts, didn't decrease. Otherwise the whole call is reverted.
+		///
+		/// Emits `FlashSwapped` event when successful.
+		#[pallet::weight(T::WeightInfo::flash_swap(data.len() as u32))]
+		#[transactional]
+		pub fn flash_swap(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			amount_a_out: BalanceOf<T>,
+			amount_b_out: BalanceOf<T>,
+			data: Vec<u8>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::do_flash_swap(&who, pool_id, amount_a_out, amount_b_out, &data)?;
+			Ok(())
+		}
+
+		/// Buy a given amount of a given asset from the pool.
+		/// This is similar to `swap` but easier to use for users.
+		///
+		/// Fails if more than `max_amount_in` of the other asset would have to be paid or if the
+		/// optional `deadline` block has already passed.
+		///
+		/// Emits `Swapped` event when successful.
+		#[pallet::weight(Pallet::<T>::swap_weight())]
+		pub fn buy(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			asset_id: AssetIdOf<T>,
+			amount: BalanceOf<T>,
+			max_amount_in: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			let amount_in = <Self as Amm>::quote_buy(pool_id, asset_id, amount)?;
+			ensure!(amount_in <= max_amount_in, Error::<T>::AmountInAboveMaximum);
+
+			<Self as Amm>::buy(&who, pool_id, asset_id, amount)?;
+			Ok(())
+		}
+
+		/// Sell a given amount of a given asset to the pool.
+		/// This is similar to `swap` but easier to use for users.
+		///
+		/// Fails if less than `min_amount_out` of the other asset would be received or if the
+		/// optional `deadline` block has already passed. On a pool in batch auction mode the swap
+		/// is queued until the end of the block, see `set_batch_auction`.
+		///
+		/// Emits `Swapped` event when successful, or `BatchOrderSubmitted` when queued.
+		#[pallet::weight(Pallet::<T>::exact_in_swap_weight())]
+		#[transactional]
+		pub fn sell(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			asset_id: AssetIdOf<T>,
+			amount: BalanceOf<T>,
+			min_amount_out: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			if Self::is_batch_auction_pool(pool_id) {
+				return Self::submit_batch_order(&who, pool_id, asset_id, amount, min_amount_out)
+			}
+
+			let amount_out = <Self as Amm>::sell(&who, pool_id, asset_id, amount)?;
+			ensure!(amount_out >= min_amount_out, Error::<T>::AmountOutBelowMinimum);
+			Ok(())
+		}
+
+		/// Sell an exact amount of `asset_in` for `asset_out` on a pair or multi-asset pool.
+		///
+		/// Fails if less than `min_amount_out` of `asset_out` would be received or if the
+		/// optional `deadline` block has already passed. On a pool in batch auction mode the swap
+		/// is queued until the end of the block, see `set_batch_auction`.
+		///
+		/// Emits `Swapped` event when successful, or `BatchOrderSubmitted` when queued.
+		#[pallet::weight(Pallet::<T>::exact_in_swap_weight())]
+		#[transactional]
+		pub fn swap_exact_in(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			asset_in: AssetIdOf<T>,
+			asset_out: AssetIdOf<T>,
+			amount_in: BalanceOf<T>,
+			min_amount_out: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			if Self::is_batch_auction_pool(pool_id) {
+				Self::ensure_pair_assets(pool_id, asset_in, asset_out)?;
+				return Self::submit_batch_order(&who, pool_id, asset_in, amount_in, min_amount_out)
+			}
+
+			let amount_out =
+				<Self as Amm>::swap_exact_in(&who, pool_id, asset_in, asset_out, amount_in)?;
+			ensure!(amount_out >= min_amount_out, Error::<T>::AmountOutBelowMinimum);
+			Ok(())
+		}
+
+		/// Buy an exact amount of `asset_out` with `asset_in` on a pair or multi-asset pool.
+		///
+		/// Fails if more than `max_amount_in` of `asset_in` would have to be paid or if the
+		/// optional `deadline` block has already passed.
+		///
+		/// Emits `Swapped` event when successful.
+		#[pallet::weight(Pallet::<T>::swap_weight())]
+		#[transactional]
+		pub fn swap_exact_out(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			asset_in: AssetIdOf<T>,
+			asset_out: AssetIdOf<T>,
+			amount_out: BalanceOf<T>,
+			max_amount_in: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			let amount_in =
+				<Self as Amm>::quote_exact_out(pool_id, asset_in, asset_out, amount_out)?;
+			ensure!(amount_in <= max_amount_in, Error::<T>::AmountInAboveMaximum);
+
+			<Self as Amm>::swap_exact_out(&who, pool_id, asset_in, asset_out, amount_out)?;
+			Ok(())
+		}
+
+		/// Sell an exact amount of `asset_in` and route the trade through the given `path` of
+		/// pools. Each pool in the path has to contain the asset received from the previous pool.
+		/// The whole path is executed atomically and fails if less than `min_amount_out` of the
+		/// final asset is received or if the optional `deadline` block has already passed.
+		///
+		/// Emits `SwappedViaPath` event when successful.
+		#[pallet::weight(Pallet::<T>::swap_weight().saturating_mul(path.len() as Weight))]
+		#[transactional]
+		pub fn swap_exact_in_via_path(
+			origin: OriginFor<T>,
+			path: BoundedVec<PoolIdOf<T>, T::MaxPathLength>,
+			asset_in: AssetIdOf<T>,
+			amount_in: BalanceOf<T>,
+			min_amount_out: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			let assets = Self::assets_along_path(&path, asset_in)?;
+
+			let mut amount_out = amount_in;
+			for (pool_id, asset_id) in path.iter().zip(assets.iter()) {
+				amount_out = <Self as Amm>::sell(&who, *pool_id, *asset_id, amount_out)?;
+			}
+			ensure!(amount_out >= min_amount_out, Error::<T>::PathOutputTooLow);
+
+			Self::deposit_event(Event::<T>::SwappedViaPath {
+				who,
+				asset_in,
+				asset_out: *assets.last().ok_or(Error::<T>::InvalidPath)?,
+				amount_in,
+				amount_out,
+			});
+			Ok(())
+		}
+
+		/// Buy an exact amount of the final asset of the given `path` of pools by paying with
+		/// `asset_in`. The amounts that have to be paid at each hop are calculated backwards from
+		/// `amount_out`. The whole path is executed atomically and fails if more than
+		/// `max_amount_in` of `asset_in` would have to be paid or if the optional `deadline` block
+		/// has already passed.
+		///
+		/// Emits `SwappedViaPath` event when successful.
+		#[pallet::weight(Pallet::<T>::swap_weight().saturating_mul(path.len() as Weight))]
+		#[transactional]
+		pub fn swap_exact_out_via_path(
+			origin: OriginFor<T>,
+			path: BoundedVec<PoolIdOf<T>, T::MaxPathLength>,
+			asset_in: AssetIdOf<T>,
+			amount_out: BalanceOf<T>,
+			max_amount_in: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			let assets = Self::assets_along_path(&path, asset_in)?;
+
+			// `amounts[i]` is the amount of `assets[i]` that has to enter the path at hop `i`.
+			let mut amounts = Vec::with_capacity(assets.len());
+			amounts.push(amount_out);
+			for (pool_id, asset_id) in path.iter().zip(assets.iter().skip(1)).rev() {
+				let amount = *amounts.last().ok_or(Error::<T>::InvalidPath)?;
+				amounts.push(<Self as Amm>::quote_buy(*pool_id, *asset_id, amount)?);
+			}
+			amounts.reverse();
+
+			let amount_in = amounts[0];
+			ensure!(amount_in <= max_amount_in, Error::<T>::PathInputTooHigh);
+
+			// Every hop has to spend exactly what the previous hop received
+			for (i, pool_id) in path.iter().enumerate() {
+				let paid = <Self as Amm>::swap_exact_out(
+					&who,
+					*pool_id,
+					assets[i],
+					assets[i + 1],
+					amounts[i + 1],
+				)?;
+				ensure!(paid == amounts[i], Error::<T>::PathAmountMismatch);
+			}
+
+			Self::deposit_event(Event::<T>::SwappedViaPath {
+				who,
+				asset_in,
+				asset_out: *assets.last().ok_or(Error::<T>::InvalidPath)?,
+				amount_in,
+				amount_out,
+			});
+			Ok(())
+		}
+
+		/// Sell an exact amount of `asset_in` for `asset_out` on the pair pools of the two assets
+		/// that offer the best price. The amount is sold on the single best pool or split across
+		/// several pools with different fees if that yields more, see `quote_best_route`.
+		///
+		/// Fails if less than `min_amount_out` of `asset_out` would be received or if the
+		/// optional `deadline` block has already passed.
+		///
+		/// Emits `SwappedViaBestRoute` event when successful.
+		#[pallet::weight(Pallet::<T>::best_route_swap_weight())]
+		#[transactional]
+		pub fn swap_exact_in_best_route(
+			origin: OriginFor<T>,
+			asset_in: AssetIdOf<T>,
+			asset_out: AssetIdOf<T>,
+			amount_in: BalanceOf<T>,
+			min_amount_out: BalanceOf<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			let (route, _) = Self::quote_best_route(asset_in, asset_out, amount_in)?;
+			let mut amount_out = BalanceOf::<T>::zero();
+			for (pool_id, amount) in route {
+				let received =
+					<Self as Amm>::swap_exact_in(&who, pool_id, asset_in, asset_out, amount)?;
+				amount_out = amount_out.checked_add(&received).ok_or(ArithmeticError::Overflow)?;
+			}
+			ensure!(amount_out >= min_amount_out, Error::<T>::AmountOutBelowMinimum);
+
+			Self::deposit_event(Event::<T>::SwappedViaBestRoute {
+				who,
+				asset_in,
+				asset_out,
+				amount_in,
+				amount_out,
+			});
+			Ok(())
+		}
+
+		/// Change the fee of a pool. Can only be called by the pool owner or the `AdminOrigin`.
+		/// Fails if the pair of a pair pool already has a pool with the new fee.
+		///
+		/// Emits `PoolFeeUpdated` event when successful.
+		#[pallet::weight(T::WeightInfo::set_pool_fee())]
+		#[transactional]
+		pub fn set_pool_fee(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			fee: Permill,
+		) -> DispatchResult {
+			ensure!(fee < Permill::one(), Error::<T>::InvalidFee);
+			let old_fee = Self::mutate_pool_settings(origin, pool_id, |_, pool_fee, _| {
+				sp_std::mem::replace(pool_fee, fee)
+			})?;
+
+			// Pair pools are indexed by their fee
+			match Pools::<T>::get(pool_id) {
+				Some(pool) if old_fee != fee => {
+					let pair = pool.pair.canonical();
+					let pair_key = (pair.token_a, pair.token_b);
+					ensure!(
+						!PoolIdByPair::<T>::contains_key(pair_key, fee),
+						Error::<T>::PoolAlreadyExists
+					);
+					PoolIdByPair::<T>::remove(pair_key, old_fee);
+					PoolIdByPair::<T>::insert(pair_key, fee, pool_id);
+				},
+				_ => {},
+			}
+
+			Self::deposit_event(Event::<T>::PoolFeeUpdated { pool_id, fee });
+			Ok(())
+		}
+
+		/// Change the status of a pool to pause or resume swaps and deposits. Withdrawals are
+		/// always allowed. Can only be called by the pool owner or the `AdminOrigin`.
+		///
+		/// Emits `PoolStatusUpdated` event when successful.
+		#[pallet::weight(T::WeightInfo::set_pool_status())]
+		pub fn set_pool_status(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			status: PoolStatus,
+		) -> DispatchResult {
+			Self::mutate_pool_settings(origin, pool_id, |_, _, pool_status| *pool_status = status)?;
+
+			Self::deposit_event(Event::<T>::PoolStatusUpdated { pool_id, status });
+			Ok(())
+		}
+
+		/// Transfer the ownership of a pool to `new_owner`. Can only be called by the pool owner
+		/// or the `AdminOrigin`.
+		///
+		/// Emits `PoolOwnershipTransferred` event when successful.
+		#[pallet::weight(T::WeightInfo::transfer_pool_ownership())]
+		pub fn transfer_pool_ownership(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			new_owner: T::AccountId,
+		) -> DispatchResult {
+			let old_owner = Self::mutate_pool_settings(origin, pool_id, |owner, _, _| {
+				sp_std::mem::replace(owner, new_owner.clone())
+			})?;
+
+			Self::deposit_event(Event::<T>::PoolOwnershipTransferred {
+				pool_id,
+				old_owner,
+				new_owner,
+			});
+			Ok(())
+		}
+
+		/// Switch the batch auction mode of a pair pool on or off. In batch auction mode, the
+		/// exact-in swaps of a block (`swap`, `sell` and `swap_exact_in`) are queued and executed
+		/// together at a single price at the end of the block, so they can't be front-run within
+		/// the block. All other swaps and flash swaps on the pool fail. Orders that are already
+		/// queued are still executed when the mode is switched off. The mode can only be switched
+		/// on by the `AdminOrigin`, and switched off by the pool owner or the `AdminOrigin`.
+		///
+		/// Emits `BatchAuctionModeUpdated` event when successful.
+		#[pallet::weight(T::WeightInfo::set_pool_status())]
+		pub fn set_batch_auction(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			enabled: bool,
+		) -> DispatchResult {
+			let pool = Self::get_pool(pool_id)?;
+			if enabled {
+				T::AdminOrigin::ensure_origin(origin)?;
+				// The batches are cleared against the curve of the reserves
+				ensure!(
+					!matches!(pool.kind, PoolKind::Concentrated { .. }),
+					Error::<T>::UnsupportedPoolKind
+				);
+				BatchAuctionPools::<T>::insert(pool_id, ());
+			} else {
+				Self::ensure_pool_owner_or_admin(origin, &pool.owner)?;
+				BatchAuctionPools::<T>::remove(pool_id);
+			}
+
+			Self::deposit_event(Event::<T>::BatchAuctionModeUpdated { pool_id, enabled });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Returns the pool for a given id.
+		pub(crate) fn get_pool(pool_id: PoolIdOf<T>) -> Result<PoolOf<T>, DispatchError> {
+			Pools::<T>::get(pool_id).ok_or_else(|| Error::<T>::PoolNotFound.into())
+		}
+
+		/// Returns the id of the pool whose liquidity token is `asset_id`.
+		pub fn pool_of_lp_token(asset_id: AssetIdOf<T>) -> Option<PoolIdOf<T>> {
+			let pool_id = T::LiquidityTokenConversion::lookup(asset_id).ok()?;
+			if <Self as Amm>::pool_exists(pool_id) {
+				Some(pool_id)
+			} else {
+				None
+			}
+		}
+
+		/// Pools can be created for the registered assets and the liquidity tokens of existing
+		/// pools.
+		pub(crate) fn is_tradable(asset_id: &AssetIdOf<T>) -> bool {
+			T::RegisteredAssets::contains(asset_id) || Self::pool_of_lp_token(*asset_id).is_some()
+		}
+
+		/// Returns all pools together with their ids.
+		pub fn all_pools() -> Vec<(PoolIdOf<T>, PoolOf<T>)> {
+			Pools::<T>::iter().collect()
+		}
+
+		/// Ensures that the origin is either the `AdminOrigin` or signed by the `owner` of a pool.
+		pub(crate) fn ensure_pool_owner_or_admin(
+			origin: OriginFor<T>,
+			owner: &T::AccountId,
+		) -> DispatchResult {
+			if T::AdminOrigin::ensure_origin(origin.clone()).is_ok() {
+				return Ok(())
+			}
+			let who = ensure_signed(origin)?;
+			ensure!(who == *owner, Error::<T>::NotPoolOwner);
+			Ok(())
+		}
+
+		/// Applies `f` to the owner, the fee and the status of a pair or multi-asset pool, after
+		/// ensuring that the origin is allowed to administrate the pool.
+		fn mutate_pool_settings<R>(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			f: impl FnOnce(&mut T::AccountId, &mut Permill, &mut PoolStatus) -> R,
+		) -> Result<R, DispatchError> {
+			if let Some(mut pool) = Pools::<T>::get(pool_id) {
+				Self::ensure_pool_owner_or_admin(origin, &pool.owner)?;
+				let result = f(&mut pool.owner, &mut pool.fee, &mut pool.status);
+				Pools::<T>::insert(pool_id, pool);
+				return Ok(result)
+			}
+
+			let mut pool = Self::get_multi_asset_pool(pool_id)?;
+			Self::ensure_pool_owner_or_admin(origin, &pool.owner)?;
+			let result = f(&mut pool.owner, &mut pool.fee, &mut pool.status);
+			MultiAssetPools::<T>::insert(pool_id, pool);
+			Ok(result)
+		}
+
+		/// Deposits `amount_a` of token_a and `amount_b` of token_b into a pair pool and mints the
+		/// corresponding liquidity tokens to `who`. On the first deposit, `MinimumLiquidity` of
+		/// the minted tokens are locked forever, so that the value of a liquidity token can't be
+		/// inflated by donating to an almost empty pool.
+		fn do_add_liquidity(
+			who: &T::AccountId,
+			pool_id: PoolIdOf<T>,
+			pool: &PoolOf<T>,
+			amount_a: BalanceOf<T>,
+			amount_b: BalanceOf<T>,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			let pool_account = Self::account_id(&pool_id);
+			Self::ensure_not_locked(pool_id)?;
+
+			// Concentrated liquidity is added with `mint_position`
+			ensure!(
+				!matches!(pool.kind, PoolKind::Concentrated { .. }),
+				Error::<T>::UnsupportedPoolKind
+			);
+			ensure!(pool.status != PoolStatus::Paused, Error::<T>::PoolPaused);
+
+			let user_balance_a = T::Assets::free_balance(pool.pair.token_a, who);
+			ensure!(user_balance_a >= amount_a, Error::<T>::InsufficientBalance);
+
+			let user_balance_b = T::Assets::free_balance(pool.pair.token_b, who);
+			ensure!(user_balance_b >= amount_b, Error::<T>::InsufficientBalance);
+
+			Self::update_price_cumulatives(pool_id)?;
+			Self::mint_protocol_fee(pool_id, pool)?;
+
+			let (reserve_a, reserve_b) = Self::pool_reserves(pool_id)?;
+			// Convert to u128 for calculations
+			let (amount_a, amount_b) =
+				(T::Convert::convert(amount_a), T::Convert::convert(amount_b));
+
+			let lp_total_issuance = T::Convert::convert(T::Assets::total_issuance(pool.lp_token));
+			let amount_of_lp_token_to_mint =
+				if lp_total_issuance == 0 || (reserve_a.is_zero() && reserve_b.is_zero()) {
+					let liquidity = Self::invariant(pool.kind, amount_a, amount_b)?;
+					let minimum_liquidity = T::MinimumLiquidity::get();
+					ensure!(
+						liquidity > T::Convert::convert(minimum_liquidity),
+						Error::<T>::InsufficientInitialLiquidity
+					);
+					T::Assets::deposit(pool.lp_token, &Self::dead_account(), minimum_liquidity)?;
+					liquidity - T::Convert::convert(minimum_liquidity)
+				} else {
+					core::cmp::min(
+						calc::get_liquidity_minted(
+							amount_a,
+							T::Convert::convert(reserve_a),
+							lp_total_issuance,
+						)?,
+						calc::get_liquidity_minted(
+							amount_b,
+							T::Convert::convert(reserve_b),
+							lp_total_issuance,
+						)?,
+					)
+				};
+
+			// Convert back to balances
+			let (amount_a, amount_b) =
+				(T::Convert::convert(amount_a), T::Convert::convert(amount_b));
+			let amount_of_lp_token_to_mint = T::Convert::convert(amount_of_lp_token_to_mint);
+
+			T::Assets::transfer(pool.pair.token_a, who, &pool_account, amount_a)?;
+			T::Assets::transfer(pool.pair.token_b, who, &pool_account, amount_b)?;
+			T::Assets::deposit(pool.lp_token, who, amount_of_lp_token_to_mint)?;
+
+			Self::update_invariant_last(pool_id, pool)?;
+
+			Self::deposit_event(Event::<T>::LiquidityAdded {
+				who: who.clone(),
+				pool_id,
+				amount_a,
+				amount_b,
+				minted_lp: amount_of_lp_token_to_mint,
+			});
+			Ok(amount_of_lp_token_to_mint)
+		}
+
+		/// Checks the invariants of the pallet's storage:
+		/// - every pool has an account and its id is lower than `PoolCount`.
+		/// - the liquidity tokens of a pair pool or a multi-asset pool have no issuance exactly
+		///   when its reserves are empty. Concentrated-liquidity pools don't issue liquidity
+		///   tokens.
+		#[cfg(any(feature = "try-runtime", test))]
+		pub fn do_try_state() -> Result<(), &'static str> {
+			let pool_count = PoolCount::<T>::get();
+			let pool_ids = Pools::<T>::iter_keys().chain(MultiAssetPools::<T>::iter_keys());
+			for pool_id in pool_ids {
+				ensure!(PoolAccounts::<T>::contains_key(pool_id), "Pool has no account");
+				ensure!(pool_id < pool_count, "Pool id is not lower than the pool count");
+			}
+
+			for (pool_id, pool) in Pools::<T>::iter() {
+				let pair = pool.pair.canonical();
+				ensure!(
+					PoolIdByPair::<T>::get((pair.token_a, pair.token_b), pool.fee) == Some(pool_id),
+					"Pool isn't indexed by its pair and fee"
+				);
+			}
+			ensure!(
+				PoolIdByPair::<T>::iter_values().count() == Pools::<T>::iter_keys().count(),
+				"Pair index contains pools that don't exist"
+			);
+
+			for (pool_id, pool) in Pools::<T>::iter() {
+				if let PoolKind::Concentrated { .. } = pool.kind {
+					continue
+				}
+				let (reserve_a, reserve_b) =
+					Self::pool_reserves(pool_id).map_err(|_| "Pool reserves not found")?;
+				ensure!(
+					T::Assets::total_issuance(pool.lp_token).is_zero() ==
+						(reserve_a.is_zero() && reserve_b.is_zero()),
+					"Liquidity token issuance doesn't match the pool reserves"
+				);
+			}
+			for (pool_id, pool) in MultiAssetPools::<T>::iter() {
+				let reserves = Self::multi_asset_reserves(pool_id, &pool);
+				ensure!(
+					T::Assets::total_issuance(pool.lp_token).is_zero() ==
+						reserves.iter().all(|reserve| reserve.is_zero()),
+					"Liquidity token issuance doesn't match the pool reserves"
+				);
+			}
+
+			for (pool_id, index) in ObservationIndex::<T>::iter() {
+				ensure!(index < T::MaxObservations::get(), "Observation index is out of bounds");
+				ensure!(
+					Observations::<T>::get(pool_id, index) ==
+						Some(PriceCumulatives::<T>::get(pool_id)),
+					"Newest observation doesn't match the price cumulatives"
+				);
+			}
+			Ok(())
+		}
+
+		/// The weight of a swap on any kind of pool. A swap on a concentrated-liquidity pool can
+		/// cross the ranges of all positions, each of which initializes two ticks.
+		pub(crate) fn swap_weight() -> Weight {
+			T::WeightInfo::swap()
+				.max(T::WeightInfo::swap_concentrated(T::MaxTicksPerPool::get() / 2))
+				.max(T::WeightInfo::swap_multi_asset(T::MaxAssetsInPool::get()))
+		}
+
+		/// The weight of an exact-in swap, which queues an order on a pool in batch auction mode
+		/// and pays for clearing it, see `batch_order_weight`.
+		pub(crate) fn exact_in_swap_weight() -> Weight {
+			Self::swap_weight().max(Self::batch_order_weight())
+		}
+
+		/// Ensures that the pool is not in the middle of a flash swap.
+		fn ensure_not_locked(pool_id: PoolIdOf<T>) -> DispatchResult {
+			ensure!(!FlashSwapLocks::<T>::contains_key(pool_id), Error::<T>::PoolLocked);
+			Ok(())
+		}
+
+		/// Sends the borrowed amounts to `who`, calls the `FlashSwapHandler` and checks that the
+		/// pool was paid back. Other pallets can use this to borrow from a pool within their own
+		/// calls.
+		#[transactional]
+		pub fn do_flash_swap(
+			who: &T::AccountId,
+			pool_id: PoolIdOf<T>,
+			amount_a_out: BalanceOf<T>,
+			amount_b_out: BalanceOf<T>,
+			data: &[u8],
+		) -> DispatchResult {
+			let pool = Self::get_pool(pool_id)?;
+			ensure!(pool.kind == PoolKind::ConstantProduct, Error::<T>::UnsupportedPoolKind);
+			ensure!(!Self::is_batch_auction_pool(pool_id), Error::<T>::BatchAuctionPool);
+			match pool.status {
+				PoolStatus::Active => {},
+				PoolStatus::SwapsPaused => return Err(Error::<T>::SwapsPaused.into()),
+				PoolStatus::Paused => return Err(Error::<T>::PoolPaused.into()),
+			}
+			Self::ensure_not_locked(pool_id)?;
+			ensure!(!amount_a_out.is_zero() || !amount_b_out.is_zero(), Error::<T>::InvalidAmount);
+
+			let (reserve_a, reserve_b) = Self::pool_reserves(pool_id)?;
+			ensure!(
+				amount_a_out < reserve_a && amount_b_out < reserve_b,
+				Error::<T>::InsufficientLiquidity
+			);
+
+			Self::update_price_cumulatives(pool_id)?;
+			let pool_account = Self::account_id(&pool_id);
+			T::Assets::transfer(pool.pair.token_a, &pool_account, who, amount_a_out)?;
+			T::Assets::transfer(pool.pair.token_b, &pool_account, who, amount_b_out)?;
+
+			// The pool can't be used by the handler while its reserves are lent out
+			FlashSwapLocks::<T>::insert(pool_id, ());
+			T::FlashSwapHandler::on_flash_swap(
+				who,
+				pool_id,
+				pool.pair,
+				amount_a_out,
+				amount_b_out,
+				data,
+			)?;
+			FlashSwapLocks::<T>::remove(pool_id);
+
+			let (balance_a, balance_b) = Self::pool_reserves(pool_id)?;
+			let amount_a_in = balance_a.saturating_sub(reserve_a - amount_a_out);
+			let amount_b_in = balance_b.saturating_sub(reserve_b - amount_b_out);
+			ensure!(
+				calc::is_flash_swap_repaid(
+					(T::Convert::convert(reserve_a), T::Convert::convert(reserve_b)),
+					(T::Convert::convert(balance_a), T::Convert::convert(balance_b)),
+					(T::Convert::convert(amount_a_in), T::Convert::convert(amount_b_in)),
+					pool.fee,
+				),
+				Error::<T>::FlashSwapNotRepaid
+			);
+
+			Self::deposit_event(Event::<T>::FlashSwapped {
+				who: who.clone(),
+				pool_id,
+				amount_a_out,
+				amount_b_out,
+				amount_a_in,
+				amount_b_in,
+			});
+			T::OnSwap::on_swap(pool_id);
+			Ok(())
+		}
+
+		/// Ensures that the current block is not past the given `deadline`.
+		pub(crate) fn ensure_deadline(deadline: Option<T::BlockNumber>) -> DispatchResult {
+			if let Some(deadline) = deadline {
+				let now = frame_system::Pallet::<T>::block_number();
+				ensure!(now <= deadline, Error::<T>::DeadlinePassed);
+			}
+			Ok(())
+		}
+
+		/// Ensures that `asset_in` and `asset_out` are the two different assets of a pair pool.
+		fn ensure_pair_assets(
+			pool_id: PoolIdOf<T>,
+			asset_in: AssetIdOf<T>,
+			asset_out: AssetIdOf<T>,
+		) -> DispatchResult {
+			let pair = Self::get_pool(pool_id)?.pair;
+			ensure!(
+				asset_in != asset_out && pair.contains(asset_in) && pair.contains(asset_out),
+				Error::<T>::InvalidAsset
+			);
+			Ok(())
+		}
 
-			let pool_id = Self::do_create_pool(pool_params.clone())?;
+		/// Returns the assets that are traded along the given `path` when starting with `asset_in`.
+		/// The first element is `asset_in` and every following element is the asset received from
+		/// the pool at the same position in the path.
+		pub(crate) fn assets_along_path(
+			path: &[PoolIdOf<T>],
+			asset_in: AssetIdOf<T>,
+		) -> Result<Vec<AssetIdOf<T>>, DispatchError> {
+			ensure!(!path.is_empty(), Error::<T>::InvalidPath);
+
+			let mut assets = Vec::with_capacity(path.len() + 1);
+			assets.push(asset_in);
+			for (i, pool_id) in path.iter().enumerate() {
+				// A pool that is used twice would be quoted with reserves that an earlier hop
+				// already changed
+				ensure!(!path[..i].contains(pool_id), Error::<T>::InvalidPath);
+				let pair = Self::get_pool(*pool_id)?.pair;
+				let current = *assets.last().ok_or(Error::<T>::InvalidPath)?;
+				ensure!(pair.contains(current), Error::<T>::InvalidPath);
+
+				let next = if pair.token_a == current { pair.token_b } else { pair.token_a };
+				assets.push(next);
+			}
+			Ok(assets)
+		}
 
-			Self::deposit_event(Event::<T>::PoolCreated {
-				owner: pool_params.owner,
-				pool_id,
-				assets: pool_params.pair,
-			});
+		/// Derive a new pool id from the pallet ID.
+		pub(crate) fn account_id(pool_id: &PoolIdOf<T>) -> T::AccountId {
+			T::PalletId::get().into_sub_account_truncating(pool_id)
+		}
 
-			Ok(())
+		/// The account that holds the `MinimumLiquidity` of every pool. Nobody knows the key of
+		/// the all-zero account, so the liquidity tokens can never be withdrawn.
+		pub(crate) fn dead_account() -> T::AccountId {
+			T::AccountId::decode(&mut TrailingZeroInput::zeroes())
+				.expect("infinite length input; no invalid inputs for type; qed")
 		}
 
-		/// Add liquidity to a pool.
-		///
-		/// Emits `LiquidityAdded` event when successful.
-		#[pallet::weight(10_000)]
-		pub fn add_liquidity(
-			origin: OriginFor<T>,
-			pool_id: T::PoolId,
-			amount: BalanceOf<T>,
-			asset: AssetIdOf<T>,
-		) -> DispatchResult {
-			let sender = ensure_signed(origin)?;
+		/// Whether part of the swap fees is minted to the protocol fee receiver.
+		fn protocol_fee_on() -> bool {
+			!T::ProtocolFeeShare::get().is_zero()
+		}
 
-			<Self as Amm>::add_liquidity(&sender, pool_id, amount, asset)?;
+		/// Mints the protocol's share of the fees accrued since the last liquidity event as
+		/// liquidity tokens. Has to be called before the liquidity of the pool changes.
+		fn mint_protocol_fee(pool_id: PoolIdOf<T>, pool: &PoolOf<T>) -> DispatchResult {
+			let invariant_last = InvariantLast::<T>::get(pool_id);
+			if !Self::protocol_fee_on() {
+				// Stop tracking the invariant so no fees accrue while the protocol fee is off
+				if !invariant_last.is_zero() {
+					InvariantLast::<T>::remove(pool_id);
+				}
+				return Ok(())
+			}
+			if invariant_last.is_zero() {
+				return Ok(())
+			}
+
+			let invariant = Self::pool_invariant(pool_id, pool)?;
+			let total_issuance = T::Convert::convert(T::Assets::total_issuance(pool.lp_token));
+
+			let liquidity = calc::get_protocol_fee_liquidity(
+				total_issuance,
+				invariant,
+				T::Convert::convert(invariant_last),
+				T::ProtocolFeeShare::get(),
+			)?;
+			if liquidity > 0 {
+				let receiver = T::ProtocolFeeReceiver::get().unwrap_or_else(|| pool.owner.clone());
+				let amount = T::Convert::convert(liquidity);
+				T::Assets::deposit(pool.lp_token, &receiver, amount)?;
+
+				Self::deposit_event(Event::<T>::ProtocolFeeMinted { pool_id, receiver, amount });
+			}
+			Ok(())
+		}
 
+		/// Stores the invariant of the pool after a liquidity event, if the protocol fee is
+		/// switched on.
+		fn update_invariant_last(pool_id: PoolIdOf<T>, pool: &PoolOf<T>) -> DispatchResult {
+			if Self::protocol_fee_on() {
+				let invariant = Self::pool_invariant(pool_id, pool)?;
+				InvariantLast::<T>::insert(pool_id, T::Convert::convert(invariant));
+			}
 			Ok(())
 		}
 
-		/// Remove liquidity from a pool.
-		///
-		/// Emits `LiquidityRemoved` event when successful.
-		#[pallet::weight(10_000)]
-		pub fn remove_liquidity(
-			origin: OriginFor<T>,
-			pool_id: T::PoolId,
-			amount: BalanceOf<T>,
-		) -> DispatchResult {
-			let sender = ensure_signed(origin)?;
+		/// Returns the invariant of the pool with its current reserves.
+		fn pool_invariant(pool_id: PoolIdOf<T>, pool: &PoolOf<T>) -> Result<u128, DispatchError> {
+			let (reserve_a, reserve_b) = Self::pool_reserves(pool_id)?;
+			Self::invariant(
+				pool.kind,
+				T::Convert::convert(reserve_a),
+				T::Convert::convert(reserve_b),
+			)
+		}
 
-			<Self as Amm>::remove_liquidity(&sender, pool_id, amount)?;
+		/// Calculates the invariant of a pool of the given kind. The invariant grows linearly with
+		/// the liquidity of the pool: `√k` for constant product pools, `D` for StableSwap pools
+		/// and `V` for weighted pools.
+		pub(crate) fn invariant(
+			kind: PoolKind,
+			reserve_a: u128,
+			reserve_b: u128,
+		) -> Result<u128, DispatchError> {
+			match kind {
+				PoolKind::ConstantProduct => Ok(calc::get_root_k(reserve_a, reserve_b)),
+				PoolKind::StableSwap { amplification } =>
+					calc::get_stable_invariant(reserve_a, reserve_b, amplification)
+						.ok_or_else(|| Error::<T>::InvariantCalculationFailed.into()),
+				PoolKind::Weighted { weight_a, weight_b } =>
+					calc::get_weighted_invariant(reserve_a, reserve_b, weight_a, weight_b)
+						.ok_or_else(|| Error::<T>::InvariantCalculationFailed.into()),
+				// The liquidity of concentrated-liquidity pools is not fungible
+				PoolKind::Concentrated { .. } => Err(Error::<T>::UnsupportedPoolKind.into()),
+			}
+		}
 
-			Ok(())
+		/// Returns the weights of the input and the output asset of a swap on a weighted pool.
+		fn swap_weights(
+			pool: &PoolOf<T>,
+			asset_in: AssetIdOf<T>,
+			weight_a: Permill,
+			weight_b: Permill,
+		) -> (Permill, Permill) {
+			if asset_in == pool.pair.token_a {
+				(weight_a, weight_b)
+			} else {
+				(weight_b, weight_a)
+			}
 		}
 
-		/// Execute a swap. The order of the tokens in the pair is important.
-		/// The user will send amount_b of pair.token_b to the pool to receive the corresponding
-		/// amount of pair.token_a.
-		///
-		/// Emits `Swapped` event when successful.
-		#[pallet::weight(10_000)]
-		pub fn swap(
-			origin: OriginFor<T>,
-			pool_id: PoolIdOf<T>,
-			pair: CurrencyPair<AssetIdOf<T>>,
-			amount_b: BalanceOf<T>,
-		) -> DispatchResult {
-			let who = ensure_signed(origin)?;
+		/// Calculates the amount received when selling `amount_in` of `asset_in` to the pool, based
+		/// on the kind of the pool.
+		pub(crate) fn amount_out(
+			pool: &PoolOf<T>,
+			asset_in: AssetIdOf<T>,
+			amount_in: u128,
+			reserve_in: u128,
+			reserve_out: u128,
+		) -> Result<u128, DispatchError> {
+			match pool.kind {
+				PoolKind::ConstantProduct =>
+					Ok(calc::get_amount_out::<T>(amount_in, reserve_in, reserve_out, pool.fee)?),
+				PoolKind::StableSwap { amplification } => calc::get_stable_amount_out::<T>(
+					amount_in,
+					reserve_in,
+					reserve_out,
+					amplification,
+					pool.fee,
+				),
+				PoolKind::Weighted { weight_a, weight_b } => {
+					let (weight_in, weight_out) =
+						Self::swap_weights(pool, asset_in, weight_a, weight_b);
+					calc::get_weighted_amount_out::<T>(
+						amount_in,
+						reserve_in,
+						reserve_out,
+						weight_in,
+						weight_out,
+						pool.fee,
+					)
+				},
+				// Swaps on concentrated-liquidity pools depend on the ticks, not on the reserves
+				PoolKind::Concentrated { .. } => Err(Error::<T>::UnsupportedPoolKind.into()),
+			}
+		}
 
-			<Self as Amm>::swap(&who, pool_id, pair, amount_b)?;
-			Ok(())
+		/// Calculates the amount of `asset_in` that has to be paid to buy `amount_out` from the
+		/// pool, based on the kind of the pool.
+		fn amount_in(
+			pool: &PoolOf<T>,
+			asset_in: AssetIdOf<T>,
+			amount_out: u128,
+			reserve_in: u128,
+			reserve_out: u128,
+		) -> Result<u128, DispatchError> {
+			match pool.kind {
+				PoolKind::ConstantProduct =>
+					Ok(calc::get_amount_in::<T>(amount_out, reserve_in, reserve_out, pool.fee)?),
+				PoolKind::StableSwap { amplification } => calc::get_stable_amount_in::<T>(
+					amount_out,
+					reserve_in,
+					reserve_out,
+					amplification,
+					pool.fee,
+				),
+				PoolKind::Weighted { weight_a, weight_b } => {
+					let (weight_in, weight_out) =
+						Self::swap_weights(pool, asset_in, weight_a, weight_b);
+					calc::get_weighted_amount_in::<T>(
+						amount_out,
+						reserve_in,
+						reserve_out,
+						weight_in,
+						weight_out,
+						pool.fee,
+					)
+				},
+				// Swaps on concentrated-liquidity pools depend on the ticks, not on the reserves
+				PoolKind::Concentrated { .. } => Err(Error::<T>::UnsupportedPoolKind.into()),
+			}
 		}
 
-		/// Buy a given amount of a given asset from the pool.
-		/// This is similar to `swap` but easier to use for users.
-		///
-		/// Emits `Swapped` event when successful.
-		#[pallet::weight(10_000)]
-		pub fn buy(
-			origin: OriginFor<T>,
+		/// Returns the cumulative prices of the pool, accumulated up to the current block with the
+		/// current spot prices.
+		fn current_price_cumulatives(
 			pool_id: PoolIdOf<T>,
-			asset_id: AssetIdOf<T>,
-			amount: BalanceOf<T>,
-		) -> DispatchResult {
-			let who = ensure_signed(origin)?;
+		) -> Result<ObservationOf<T>, DispatchError> {
+			Self::price_cumulatives_until(pool_id, frame_system::Pallet::<T>::block_number())
+		}
 
-			<Self as Amm>::buy(&who, pool_id, asset_id, amount)?;
-			Ok(())
+		/// Returns the cumulative prices of the pool, accumulated up to block `until` with the
+		/// current spot prices. Only meaningful if the reserves don't change before `until`.
+		fn price_cumulatives_until(
+			pool_id: PoolIdOf<T>,
+			until: T::BlockNumber,
+		) -> Result<ObservationOf<T>, DispatchError> {
+			let mut cumulatives = PriceCumulatives::<T>::get(pool_id);
+			if cumulatives.block_number >= until {
+				return Ok(cumulatives)
+			}
+
+			if let Some((price_a, price_b)) = Self::spot_prices(pool_id)? {
+				let elapsed =
+					until.saturating_sub(cumulatives.block_number).saturated_into::<u128>();
+				cumulatives.price_a_cumulative = cumulatives
+					.price_a_cumulative
+					.wrapping_add(price_a.into_inner().wrapping_mul(elapsed));
+				cumulatives.price_b_cumulative = cumulatives
+					.price_b_cumulative
+					.wrapping_add(price_b.into_inner().wrapping_mul(elapsed));
+			}
+			cumulatives.block_number = until;
+			Ok(cumulatives)
 		}
 
-		/// Sell a given amount of a given asset to the pool.
-		/// This is similar to `swap` but easier to use for users.
-		///
-		/// Emits `Swapped` event when successful.
-		#[pallet::weight(10_000)]
-		pub fn sell(
-			origin: OriginFor<T>,
+		/// Returns the spot prices of token_a in token_b and of token_b in token_a, or `None` if
+		/// the pool has no liquidity.
+		fn spot_prices(
 			pool_id: PoolIdOf<T>,
-			asset_id: AssetIdOf<T>,
-			amount: BalanceOf<T>,
-		) -> DispatchResult {
-			let who = ensure_signed(origin)?;
+		) -> Result<Option<(FixedU128, FixedU128)>, DispatchError> {
+			let pool = Self::get_pool(pool_id)?;
+			if let PoolKind::Concentrated { .. } = pool.kind {
+				return Self::concentrated_spot_prices(pool_id)
+			}
 
-			<Self as Amm>::sell(&who, pool_id, asset_id, amount)?;
+			let (reserve_a, reserve_b) = Self::pool_reserves(pool_id)?;
+			if reserve_a.is_zero() || reserve_b.is_zero() {
+				return Ok(None)
+			}
+
+			// The spot price of a weighted pool is the ratio of the reserves divided by the ratio
+			// of the weights
+			let (weight_a, weight_b) = match pool.kind {
+				PoolKind::Weighted { weight_a, weight_b } =>
+					(weight_a.deconstruct() as u128, weight_b.deconstruct() as u128),
+				_ => (1, 1),
+			};
+			let (reserve_a, reserve_b): (u128, u128) =
+				(T::Convert::convert(reserve_a), T::Convert::convert(reserve_b));
+			let price_a = FixedU128::saturating_from_rational(
+				reserve_b.saturating_mul(weight_a),
+				reserve_a.saturating_mul(weight_b),
+			);
+			let price_b = FixedU128::saturating_from_rational(
+				reserve_a.saturating_mul(weight_b),
+				reserve_b.saturating_mul(weight_a),
+			);
+			Ok(Some((price_a, price_b)))
+		}
+
+		/// Accumulates the prices of the pool up to the current block and records them as an
+		/// observation if this is the first change of the reserves in the block. Has to be called
+		/// before the reserves of the pool change.
+		pub(crate) fn update_price_cumulatives(pool_id: PoolIdOf<T>) -> DispatchResult {
+			let last_update = PriceCumulatives::<T>::get(pool_id).block_number;
+			let cumulatives = Self::current_price_cumulatives(pool_id)?;
+			if cumulatives.block_number > last_update ||
+				ObservationIndex::<T>::get(pool_id).is_none()
+			{
+				PriceCumulatives::<T>::insert(pool_id, cumulatives);
+				Self::record_observation(pool_id, cumulatives);
+			}
 			Ok(())
 		}
-	}
 
-	impl<T: Config> Pallet<T> {
-		/// Returns the pool for a given id.
-		pub(crate) fn get_pool(pool_id: PoolIdOf<T>) -> Result<PoolOf<T>, DispatchError> {
-			Pools::<T>::get(pool_id).ok_or_else(|| Error::<T>::PoolNotFound.into())
+		/// Stores the observation in the ring buffer of the pool, overwriting the oldest one once
+		/// the buffer is full.
+		fn record_observation(pool_id: PoolIdOf<T>, observation: ObservationOf<T>) {
+			let index = match ObservationIndex::<T>::get(pool_id) {
+				Some(newest) =>
+					newest.saturating_add(1).checked_rem(T::MaxObservations::get()).unwrap_or(0),
+				None => 0,
+			};
+			Observations::<T>::insert(pool_id, index, observation);
+			ObservationIndex::<T>::insert(pool_id, index);
 		}
 
-		/// Derive a new pool id from the pallet ID.
-		pub(crate) fn account_id(pool_id: &PoolIdOf<T>) -> T::AccountId {
-			T::PalletId::get().into_sub_account_truncating(pool_id)
+		/// Returns the cumulative prices of the pool at the block `target`. The prices only change
+		/// with the reserves, so they are interpolated linearly between the observations around
+		/// `target`, or extrapolated with the spot prices after the newest observation.
+		fn price_cumulatives_at(
+			pool_id: PoolIdOf<T>,
+			target: T::BlockNumber,
+		) -> Result<ObservationOf<T>, DispatchError> {
+			let newest_index =
+				ObservationIndex::<T>::get(pool_id).ok_or(Error::<T>::InsufficientObservations)?;
+			let max_observations = T::MaxObservations::get();
+			let observation = |i: u32| {
+				Observations::<T>::get(pool_id, i).ok_or(Error::<T>::InsufficientObservations)
+			};
+
+			let newest = observation(newest_index)?;
+			if newest.block_number <= target {
+				// The newest observation is taken right before the last change of the reserves
+				return Self::price_cumulatives_until(pool_id, target)
+			}
+
+			// Once the buffer is full, the position after the newest observation holds the oldest
+			let next_index =
+				newest_index.saturating_add(1).checked_rem(max_observations).unwrap_or(0);
+			let (oldest_index, len) = if next_index != newest_index &&
+				Observations::<T>::contains_key(pool_id, next_index)
+			{
+				(next_index, max_observations)
+			} else {
+				(0, newest_index.saturating_add(1))
+			};
+			let nth = |n: u32| {
+				observation(
+					oldest_index.saturating_add(n).checked_rem(max_observations).unwrap_or(0),
+				)
+			};
+			ensure!(nth(0)?.block_number <= target, Error::<T>::InsufficientObservations);
+
+			// Binary search for the newest observation at or before `target`, the observations
+			// are ordered by their block numbers
+			let (mut low, mut high) = (0, len.saturating_sub(1));
+			while high - low > 1 {
+				let mid = low + (high - low) / 2;
+				if nth(mid)?.block_number <= target {
+					low = mid;
+				} else {
+					high = mid;
+				}
+			}
+
+			let (before, after) = (nth(low)?, nth(high)?);
+			let elapsed = target.saturating_sub(before.block_number).saturated_into::<u128>();
+			let interval =
+				after.block_number.saturating_sub(before.block_number).saturated_into::<u128>();
+			// The interpolated difference is at most the difference between the observations, so
+			// it fits into 128 bits
+			let interpolate = |before: u128, after: u128| {
+				let difference = U256::from(after.wrapping_sub(before)) * U256::from(elapsed) /
+					U256::from(interval.max(1));
+				before.wrapping_add(difference.low_u128())
+			};
+			Ok(Observation {
+				block_number: target,
+				price_a_cumulative: interpolate(
+					before.price_a_cumulative,
+					after.price_a_cumulative,
+				),
+				price_b_cumulative: interpolate(
+					before.price_b_cumulative,
+					after.price_b_cumulative,
+				),
+			})
 		}
 
 		/// Creates a new pool in storage with the given params.
-		/// The lp token is derived from the assets in the air. The derivation function is defined
-		/// in the pallet's config.
+		/// The lp token is derived from the pool id. The derivation function is defined in the
+		/// pallet's config. Only one pool can exist for each pair of assets and fee.
 		fn do_create_pool(
 			pool_params: PoolCreationParamsOf<T>,
 		) -> Result<PoolIdOf<T>, DispatchError> {
-			// Derive the lp token based on the pair
-			let pair = pool_params.pair;
-			let lp_token: AssetIdOf<T> =
-				T::LiquidityTokenConversion::unlookup((pair.token_a, pair.token_b));
-
-			let pool: PoolOf<T> = Pool {
-				lp_token,
-				pair: pool_params.pair,
-				owner: pool_params.owner,
-				fee: pool_params.fee,
+			ensure!(pool_params.fee < Permill::one(), Error::<T>::InvalidFee);
+			if let PoolKind::StableSwap { amplification } = pool_params.kind {
+				ensure!(
+					amplification > 0 && amplification <= calc::MAX_AMPLIFICATION,
+					Error::<T>::InvalidAmplification
+				);
+			}
+			if let PoolKind::Weighted { weight_a, weight_b } = pool_params.kind {
+				ensure!(
+					weight_a.deconstruct().saturating_add(weight_b.deconstruct()) ==
+						Permill::ACCURACY && weight_a >= calc::MIN_WEIGHT &&
+						weight_b >= calc::MIN_WEIGHT,
+					Error::<T>::InvalidWeights
+				);
+			}
+			if let PoolKind::Concentrated { tick_spacing } = pool_params.kind {
+				ensure!(
+					tick_spacing > 0 && tick_spacing <= concentrated::MAX_TICK_SPACING,
+					Error::<T>::InvalidTickSpacing
+				);
+			}
+			let concentrated_state = match pool_params.kind {
+				PoolKind::Concentrated { .. } =>
+					Some(Self::initial_concentrated_state(pool_params.initial_sqrt_price)?),
+				_ => {
+					ensure!(
+						pool_params.initial_sqrt_price.is_none(),
+						Error::<T>::InvalidInitialPrice
+					);
+					None
+				},
 			};
 
+			ensure!(
+				Self::is_tradable(&pool_params.pair.token_a) &&
+					Self::is_tradable(&pool_params.pair.token_b),
+				Error::<T>::AssetNotRegistered
+			);
+
+			// Both orderings of a pair refer to the same pools
+			let pair = pool_params.pair.canonical();
+			let pair_key = (pair.token_a, pair.token_b);
+			ensure!(
+				!PoolIdByPair::<T>::contains_key(pair_key, pool_params.fee),
+				Error::<T>::PoolAlreadyExists
+			);
+			ensure!(
+				(PoolIdByPair::<T>::iter_prefix_values(pair_key).count() as u32) <
+					T::MaxPoolsPerPair::get(),
+				Error::<T>::TooManyPoolsForPair
+			);
+
 			let pool_id =
 				PoolCount::<T>::try_mutate(|pool_count| -> Result<T::PoolId, DispatchError> {
 					let pool_id = *pool_count;
+					let pool: PoolOf<T> = Pool {
+						// Every pool has its own lp token, derived from the pool id
+						lp_token: T::LiquidityTokenConversion::unlookup(pool_id),
+						pair: pool_params.pair,
+						owner: pool_params.owner,
+						fee: pool_params.fee,
+						status: PoolStatus::Active,
+						kind: pool_params.kind,
+					};
 					// Add the pool to the storage
-					Pools::<T>::insert(pool_id, pool.clone());
+					Pools::<T>::insert(pool_id, pool);
 					// Add the pools account to the storage
 					let pool_account = Self::account_id(&pool_id);
 					PoolAccounts::<T>::insert(pool_id, pool_account);
+					PoolIdByPair::<T>::insert(pair_key, pool_params.fee, pool_id);
+					if let Some(state) = concentrated_state {
+						ConcentratedStates::<T>::insert(pool_id, state);
+					}
 
 					*pool_count = pool_id
 						.checked_add(&T::PoolId::one())
@@ -340,7 +2150,17 @@ pub mod pallet {
 		type PoolId = T::PoolId;
 
 		fn pool_exists(pool_id: Self::PoolId) -> bool {
-			Pools::<T>::contains_key(pool_id)
+			Pools::<T>::contains_key(pool_id) || MultiAssetPools::<T>::contains_key(pool_id)
+		}
+
+		fn pool_for_pair(pair: CurrencyPair<Self::AssetId>, fee: Permill) -> Option<Self::PoolId> {
+			let pair = pair.canonical();
+			PoolIdByPair::<T>::get((pair.token_a, pair.token_b), fee)
+		}
+
+		fn pools_for_pair(pair: CurrencyPair<Self::AssetId>) -> Vec<Self::PoolId> {
+			let pair = pair.canonical();
+			PoolIdByPair::<T>::iter_prefix_values((pair.token_a, pair.token_b)).collect()
 		}
 
 		fn currency_pair(
@@ -351,8 +2171,10 @@ pub mod pallet {
 		}
 
 		fn lp_token(pool_id: Self::PoolId) -> Result<Self::AssetId, DispatchError> {
-			let pool = Self::get_pool(pool_id)?;
-			Ok(pool.lp_token)
+			match Pools::<T>::get(pool_id) {
+				Some(pool) => Ok(pool.lp_token),
+				None => Ok(Self::get_multi_asset_pool(pool_id)?.lp_token),
+			}
 		}
 
 		/// Return the balances of the assets in the pool.
@@ -381,6 +2203,14 @@ pub mod pallet {
 
 			ensure!(asset_id == pair.token_a || asset_id == pair.token_b, Error::<T>::InvalidAsset);
 
+			// The reserves of concentrated-liquidity pools don't reflect their price
+			if let PoolKind::Concentrated { .. } = pool.kind {
+				let (price_a, price_b) = Self::concentrated_spot_prices(pool_id)?
+					.ok_or(Error::<T>::InsufficientLiquidity)?;
+				let price = if pair.token_a == asset_id { price_a } else { price_b };
+				return price.checked_mul_int(amount).ok_or(Error::<T>::InvalidAmount.into())
+			}
+
 			let quote = if pair.token_a == asset_id {
 				amount.checked_mul(&reserve_b).and_then(|x| x.checked_div(&reserve_a))
 			} else {
@@ -389,6 +2219,88 @@ pub mod pallet {
 			quote.ok_or(Error::<T>::InvalidAmount.into())
 		}
 
+		/// Calculate the amount of the other asset in the pair that has to be paid in order to buy
+		/// `amount` of `asset_id` from the pool. The pool fee is included in the returned amount.
+		fn quote_buy(
+			pool_id: Self::PoolId,
+			asset_id: Self::AssetId,
+			amount: Self::Balance,
+		) -> Result<Self::Balance, DispatchError> {
+			let pool = Self::get_pool(pool_id)?;
+			ensure!(pool.pair.contains(asset_id), Error::<T>::InvalidAsset);
+
+			if let PoolKind::Concentrated { .. } = pool.kind {
+				let asset_in = if asset_id == pool.pair.token_a {
+					pool.pair.token_b
+				} else {
+					pool.pair.token_a
+				};
+				let swap = Self::compute_concentrated_swap(
+					pool_id,
+					&pool,
+					asset_in,
+					T::Convert::convert(amount),
+					false,
+				)?;
+				return Ok(T::Convert::convert(swap.amount_in))
+			}
+
+			let (reserve_a, reserve_b) = Self::pool_reserves(pool_id)?;
+			// The asset that is bought leaves the pool, so its reserve is the output reserve.
+			let (asset_in, reserve_in, reserve_out) = if asset_id == pool.pair.token_a {
+				(pool.pair.token_b, reserve_b, reserve_a)
+			} else {
+				(pool.pair.token_a, reserve_a, reserve_b)
+			};
+
+			// Convert to u128 for calculations
+			let (reserve_in, reserve_out) =
+				(T::Convert::convert(reserve_in), T::Convert::convert(reserve_out));
+			let amount = T::Convert::convert(amount);
+
+			let amount_in = Self::amount_in(&pool, asset_in, amount, reserve_in, reserve_out)?;
+			Ok(T::Convert::convert(amount_in))
+		}
+
+		/// Calculate the amount of the other asset in the pair that is received when selling
+		/// `amount` of `asset_id` to the pool. The pool fee is already deducted from the returned
+		/// amount.
+		fn quote_sell(
+			pool_id: Self::PoolId,
+			asset_id: Self::AssetId,
+			amount: Self::Balance,
+		) -> Result<Self::Balance, DispatchError> {
+			let pool = Self::get_pool(pool_id)?;
+			ensure!(pool.pair.contains(asset_id), Error::<T>::InvalidAsset);
+
+			if let PoolKind::Concentrated { .. } = pool.kind {
+				let swap = Self::compute_concentrated_swap(
+					pool_id,
+					&pool,
+					asset_id,
+					T::Convert::convert(amount),
+					true,
+				)?;
+				return Ok(T::Convert::convert(swap.amount_out))
+			}
+
+			let (reserve_a, reserve_b) = Self::pool_reserves(pool_id)?;
+			// The asset that is sold enters the pool, so its reserve is the input reserve.
+			let (reserve_in, reserve_out) = if asset_id == pool.pair.token_a {
+				(reserve_a, reserve_b)
+			} else {
+				(reserve_b, reserve_a)
+			};
+
+			// Convert to u128 for calculations
+			let (reserve_in, reserve_out) =
+				(T::Convert::convert(reserve_in), T::Convert::convert(reserve_out));
+			let amount = T::Convert::convert(amount);
+
+			let amount_out = Self::amount_out(&pool, asset_id, amount, reserve_in, reserve_out)?;
+			Ok(T::Convert::convert(amount_out))
+		}
+
 		/// Since the `swap` function always assumes the user is selling the asset in the pool,
 		/// the buy function calculates the `sell_amount` first and hands it to the `swap` function.
 		#[transactional]
@@ -406,14 +2318,13 @@ pub mod pallet {
 
 			let pair = if asset_id == pool.pair.token_a { pool.pair } else { pool.pair.swap() };
 
-			// Compute how much user has to pay to buy the given amount of the given asset.
-			let (reserve_a, reserve_b) = Self::pool_reserves(pool_id)?;
-			let (reserve_a, reserve_b) =
-				(T::Convert::convert(reserve_a), T::Convert::convert(reserve_b));
-			let amount = T::Convert::convert(amount);
+			// Concentrated-liquidity pools can swap an exact output directly
+			if let PoolKind::Concentrated { .. } = pool.kind {
+				return Self::concentrated_swap(who, pool_id, &pool, pair.token_b, amount, false)
+			}
 
-			let sell_amount = calc::get_amount_in::<T>(amount, reserve_a, reserve_b, pool.fee)?;
-			let sell_amount = T::Convert::convert(sell_amount);
+			// Compute how much user has to pay to buy the given amount of the given asset.
+			let sell_amount = <Self as Amm>::quote_buy(pool_id, asset_id, amount)?;
 			<Self as Amm>::swap(who, pool_id, pair, sell_amount)
 		}
 
@@ -437,81 +2348,42 @@ pub mod pallet {
 		}
 
 		/// Adds liquidity to the given pool. Only one `amount` and `asset` are given because the
-		/// required amount of the other asset will be calculated automatically such that the ratio
-		/// of assets in the pool remains the same.
+		/// required amounts of the other assets will be calculated automatically such that the
+		/// ratio of assets in the pool remains the same.
 		#[transactional]
 		fn add_liquidity(
 			who: &Self::AccountId,
 			pool_id: Self::PoolId,
 			amount: Self::Balance,
 			asset: Self::AssetId,
-		) -> Result<(), DispatchError> {
-			let pool = Self::get_pool(pool_id)?;
-			let pool_account = Self::account_id(&pool_id);
+		) -> Result<Self::Balance, DispatchError> {
+			if MultiAssetPools::<T>::contains_key(pool_id) {
+				return Self::do_add_multi_asset_liquidity(who, pool_id, amount, asset)
+			}
 
+			let pool = Self::get_pool(pool_id)?;
 			ensure!(
 				asset == pool.pair.token_a || asset == pool.pair.token_b,
 				Error::<T>::InvalidAsset
 			);
 
 			let (reserve_a, reserve_b) = Self::pool_reserves(pool_id)?;
-			let (amount_a, amount_b) = if reserve_a.is_zero() && reserve_b.is_zero() {
-				(amount, amount)
-			} else if pool.pair.token_a == asset {
+			let (reserve_a, reserve_b) =
+				(T::Convert::convert(reserve_a), T::Convert::convert(reserve_b));
+			// A single amount can't set the price of an empty pool
+			ensure!(!reserve_a.is_zero() || !reserve_b.is_zero(), Error::<T>::EmptyPool);
+			// The amount of the other asset is rounded up in favour of the pool
+			let (amount_a, amount_b) = if pool.pair.token_a == asset {
 				let other_amount =
-					<Self as Amm>::get_exchange_value(pool_id, pool.pair.token_b, amount)?;
-				(amount, other_amount)
+					calc::get_deposit_amount(T::Convert::convert(amount), reserve_a, reserve_b)?;
+				(amount, T::Convert::convert(other_amount))
 			} else {
 				let other_amount =
-					<Self as Amm>::get_exchange_value(pool_id, pool.pair.token_a, amount)?;
-				(other_amount, amount)
+					calc::get_deposit_amount(T::Convert::convert(amount), reserve_b, reserve_a)?;
+				(T::Convert::convert(other_amount), amount)
 			};
 
-			let user_balance_a = T::Assets::free_balance(pool.pair.token_a, who);
-			ensure!(user_balance_a >= amount_a, Error::<T>::InsufficientBalance);
-
-			let user_balance_b = T::Assets::free_balance(pool.pair.token_b, who);
-			ensure!(user_balance_b >= amount_b, Error::<T>::InsufficientBalance);
-
-			// Convert to u128 for calculations
-			let (amount_a, amount_b) =
-				(T::Convert::convert(amount_a), T::Convert::convert(amount_b));
-
-			let lp_total_issuance = T::Convert::convert(T::Assets::total_issuance(pool.lp_token));
-			let amount_of_lp_token_to_mint =
-				if lp_total_issuance == 0 || (reserve_a.is_zero() && reserve_b.is_zero()) {
-					let product = amount_a.saturating_mul(amount_b);
-					sqrt(product)
-				} else {
-					core::cmp::min(
-						amount_a
-							.saturating_mul(lp_total_issuance)
-							.checked_div(T::Convert::convert(reserve_a))
-							.ok_or(ArithmeticError::DivisionByZero)?,
-						amount_b
-							.saturating_mul(lp_total_issuance)
-							.checked_div(T::Convert::convert(reserve_b))
-							.ok_or(ArithmeticError::DivisionByZero)?,
-					)
-				};
-
-			// Convert back to balances
-			let (amount_a, amount_b) =
-				(T::Convert::convert(amount_a), T::Convert::convert(amount_b));
-			let amount_of_lp_token_to_mint = T::Convert::convert(amount_of_lp_token_to_mint);
-
-			T::Assets::transfer(pool.pair.token_a, who, &pool_account, amount_a)?;
-			T::Assets::transfer(pool.pair.token_b, who, &pool_account, amount_b)?;
-			T::Assets::deposit(pool.lp_token, who, amount_of_lp_token_to_mint)?;
-
-			Self::deposit_event(Event::<T>::LiquidityAdded {
-				who: who.clone(),
-				pool_id,
-				amount_a,
-				amount_b,
-				minted_lp: amount_of_lp_token_to_mint,
-			});
-			Ok(())
+			Self::do_add_liquidity(who, pool_id, &pool, amount_a, amount_b)
 		}
 
 		/// Removes liquidity from the given pool. The `amount` refers to the liquidity tokens that
@@ -521,15 +2393,23 @@ pub mod pallet {
 			who: &Self::AccountId,
 			pool_id: Self::PoolId,
 			amount: Self::Balance,
-		) -> Result<(), DispatchError> {
+		) -> Result<(Self::Balance, Self::Balance), DispatchError> {
 			let pool = Self::get_pool(pool_id)?;
 			let pool_account = Self::account_id(&pool_id);
-			let total_issuance = T::Assets::total_issuance(pool.lp_token);
-			ensure!(!total_issuance.is_zero(), Error::<T>::WithdrawWithoutSupply);
+			Self::ensure_not_locked(pool_id)?;
+			ensure!(
+				!T::Assets::total_issuance(pool.lp_token).is_zero(),
+				Error::<T>::WithdrawWithoutSupply
+			);
 
 			let user_lp_balance = T::Assets::free_balance(pool.lp_token, who);
 			ensure!(user_lp_balance >= amount, Error::<T>::InsufficientLiquidityBalance);
 
+			Self::update_price_cumulatives(pool_id)?;
+			// The fee is minted first so that it is not paid out to the withdrawing user
+			Self::mint_protocol_fee(pool_id, &pool)?;
+			let total_issuance = T::Assets::total_issuance(pool.lp_token);
+
 			let (reserve_a, reserve_b) = Self::pool_reserves(pool_id)?;
 			// Convert to u128 for calculations
 			let amount = T::Convert::convert(amount);
@@ -537,18 +2417,13 @@ pub mod pallet {
 			let (reserve_a, reserve_b) =
 				(T::Convert::convert(reserve_a), T::Convert::convert(reserve_b));
 
-			/// Calculate the amounts of tokens the user will receive for removing liquidity
-			let amount_a = amount.checked_mul(reserve_a).and_then(|x| x.checked_div(total_issuance));
-			let amount_b =
-				amount.checked_mul(reserve_b).and_then(|x| x.checked_div(total_issuance));
-
-			ensure!(amount_a.is_some() && amount_b.is_some(), Error::<T>::InvalidAmount);
+			// Calculate the amounts of tokens the user will receive for removing liquidity
+			let amount_a = calc::get_withdrawal_amount(amount, reserve_a, total_issuance)?;
+			let amount_b = calc::get_withdrawal_amount(amount, reserve_b, total_issuance)?;
 
-			// Unwrap and convert to Balance
-			let (amount_a, amount_b) = (
-				T::Convert::convert(amount_a.expect("Checked to be Some; qed")),
-				T::Convert::convert(amount_b.expect("Checked to be Some; qed")),
-			);
+			// Convert to Balance
+			let (amount_a, amount_b) =
+				(T::Convert::convert(amount_a), T::Convert::convert(amount_b));
 			let amount = T::Convert::convert(amount);
 
 			ensure!(!amount_a.is_zero() && !amount_b.is_zero(), Error::<T>::InvalidAmount);
@@ -557,6 +2432,8 @@ pub mod pallet {
 			T::Assets::transfer(pool.pair.token_b, &pool_account, who, amount_b)?;
 			T::Assets::withdraw(pool.lp_token, who, amount)?;
 
+			Self::update_invariant_last(pool_id, &pool)?;
+
 			let total_issuance = T::Assets::total_issuance(pool.lp_token);
 
 			Self::deposit_event(Event::<T>::LiquidityRemoved {
@@ -567,7 +2444,7 @@ pub mod pallet {
 				total_issuance,
 			});
 
-			Ok(())
+			Ok((amount_a, amount_b))
 		}
 
 		/// Execute a swap and return the amount of tokens received by executing the swap
@@ -584,21 +2461,42 @@ pub mod pallet {
 			let pool_account = Self::account_id(&pool_id);
 
 			ensure!(pair == pool.pair, Error::<T>::PairMismatch);
-
-			let (reserve_a, reserve_b) = Self::pool_reserves(pool_id)?;
+			ensure!(!Self::is_batch_auction_pool(pool_id), Error::<T>::BatchAuctionPool);
+			Self::ensure_not_locked(pool_id)?;
+			match pool.status {
+				PoolStatus::Active => {},
+				PoolStatus::SwapsPaused => return Err(Error::<T>::SwapsPaused.into()),
+				PoolStatus::Paused => return Err(Error::<T>::PoolPaused.into()),
+			}
+
+			if let PoolKind::Concentrated { .. } = pool.kind {
+				return Self::concentrated_swap(who, pool_id, &pool, pair.token_b, amount_b_in, true)
+			}
+
+			// The reserves are returned in the order of the pool's pair, not the given one.
+			let (reserve_a, reserve_b) = if pair.token_a == pool.pair.token_a {
+				Self::pool_reserves(pool_id)?
+			} else {
+				let (reserve_b, reserve_a) = Self::pool_reserves(pool_id)?;
+				(reserve_a, reserve_b)
+			};
 
 			// Convert to u128 for calculations
 			let amount_b = T::Convert::convert(amount_b_in);
 			let (reserve_a, reserve_b) =
 				(T::Convert::convert(reserve_a), T::Convert::convert(reserve_b));
 
-			let amount_a = calc::get_amount_out::<T>(amount_b, reserve_b, reserve_a, pool.fee)?;
+			let amount_a = Self::amount_out(&pool, pair.token_b, amount_b, reserve_b, reserve_a)?;
 			ensure!(amount_a > 0, Error::<T>::InvalidAmount);
+			let fee = calc::get_fee_amount(amount_b, pool.fee);
 
 			// Convert back to balances
 			let amount_a = T::Convert::convert(amount_a);
 			let amount_b = T::Convert::convert(amount_b);
+			let fee: BalanceOf<T> = T::Convert::convert(fee);
+			let estimated_protocol_fee = T::ProtocolFeeShare::get().mul_floor(fee);
 
+			Self::update_price_cumulatives(pool_id)?;
 			T::Assets::transfer(pair.token_b, who, &pool_account, amount_b)?;
 			T::Assets::transfer(pair.token_a, &pool_account, who, amount_a)?;
 
@@ -609,9 +2507,121 @@ pub mod pallet {
 				token_b: pair.token_b,
 				amount_a,
 				amount_b,
-				fee: pool.fee,
+				fee,
+				estimated_protocol_fee,
 			});
+			T::OnSwap::on_swap(pool_id);
 			Ok(amount_a)
 		}
+
+		fn pool_assets(pool_id: Self::PoolId) -> Result<Vec<Self::AssetId>, DispatchError> {
+			match Pools::<T>::get(pool_id) {
+				Some(pool) => Ok(vec![pool.pair.token_a, pool.pair.token_b]),
+				None => Ok(Self::get_multi_asset_pool(pool_id)?.assets.into_inner()),
+			}
+		}
+
+		fn quote_exact_in(
+			pool_id: Self::PoolId,
+			asset_in: Self::AssetId,
+			asset_out: Self::AssetId,
+			amount_in: Self::Balance,
+		) -> Result<Self::Balance, DispatchError> {
+			if MultiAssetPools::<T>::contains_key(pool_id) {
+				return Self::quote_multi_asset_exact_in(pool_id, asset_in, asset_out, amount_in)
+			}
+
+			Self::ensure_pair_assets(pool_id, asset_in, asset_out)?;
+			<Self as Amm>::quote_sell(pool_id, asset_in, amount_in)
+		}
+
+		fn quote_exact_out(
+			pool_id: Self::PoolId,
+			asset_in: Self::AssetId,
+			asset_out: Self::AssetId,
+			amount_out: Self::Balance,
+		) -> Result<Self::Balance, DispatchError> {
+			if MultiAssetPools::<T>::contains_key(pool_id) {
+				return Self::quote_multi_asset_exact_out(pool_id, asset_in, asset_out, amount_out)
+			}
+
+			Self::ensure_pair_assets(pool_id, asset_in, asset_out)?;
+			<Self as Amm>::quote_buy(pool_id, asset_out, amount_out)
+		}
+
+		#[transactional]
+		fn swap_exact_in(
+			who: &Self::AccountId,
+			pool_id: Self::PoolId,
+			asset_in: Self::AssetId,
+			asset_out: Self::AssetId,
+			amount_in: Self::Balance,
+		) -> Result<Self::Balance, DispatchError> {
+			if MultiAssetPools::<T>::contains_key(pool_id) {
+				return Self::multi_asset_swap_exact_in(who, pool_id, asset_in, asset_out, amount_in)
+			}
+
+			Self::ensure_pair_assets(pool_id, asset_in, asset_out)?;
+			<Self as Amm>::sell(who, pool_id, asset_in, amount_in)
+		}
+
+		/// The amount that has to be paid is calculated first. Multi-asset pools sell it to the
+		/// pool, pair pools execute the swap with `buy`.
+		#[transactional]
+		fn swap_exact_out(
+			who: &Self::AccountId,
+			pool_id: Self::PoolId,
+			asset_in: Self::AssetId,
+			asset_out: Self::AssetId,
+			amount_out: Self::Balance,
+		) -> Result<Self::Balance, DispatchError> {
+			let amount_in =
+				<Self as Amm>::quote_exact_out(pool_id, asset_in, asset_out, amount_out)?;
+			if MultiAssetPools::<T>::contains_key(pool_id) {
+				Self::multi_asset_swap_exact_in(who, pool_id, asset_in, asset_out, amount_in)?;
+			} else {
+				<Self as Amm>::buy(who, pool_id, asset_out, amount_out)?;
+			}
+			Ok(amount_in)
+		}
+
+		fn is_batch_auction_pool(pool_id: Self::PoolId) -> bool {
+			Self::is_batch_auction_pool(pool_id)
+		}
+	}
+
+	impl<T: Config> Oracle for Pallet<T> {
+		type AssetId = T::AssetId;
+		type PoolId = T::PoolId;
+		type BlockNumber = T::BlockNumber;
+
+		/// Calculates the average price from the difference of the current cumulative price and
+		/// the cumulative price `window` blocks ago.
+		fn twap(
+			pool_id: Self::PoolId,
+			asset_id: Self::AssetId,
+			window: Self::BlockNumber,
+		) -> Result<FixedU128, DispatchError> {
+			let pool = Self::get_pool(pool_id)?;
+			ensure!(pool.pair.contains(asset_id), Error::<T>::InvalidAsset);
+			ensure!(!window.is_zero(), Error::<T>::InvalidWindow);
+
+			let current = Self::current_price_cumulatives(pool_id)?;
+			let target = current.block_number.saturating_sub(window);
+			let past = Self::price_cumulatives_at(pool_id, target)?;
+
+			let (current_cumulative, past_cumulative) = if asset_id == pool.pair.token_a {
+				(current.price_a_cumulative, past.price_a_cumulative)
+			} else {
+				(current.price_b_cumulative, past.price_b_cumulative)
+			};
+			let elapsed =
+				current.block_number.saturating_sub(past.block_number).saturated_into::<u128>();
+			let average = current_cumulative
+				.wrapping_sub(past_cumulative)
+				.checked_div(elapsed)
+				.ok_or(Error::<T>::InsufficientObservations)?;
+			Ok(FixedU128::from_inner(average))
+		}
 	}
 }
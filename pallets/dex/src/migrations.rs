@@ -0,0 +1,405 @@
+//! Storage migrations of the dex pallet.
+
+use super::*;
+
+pub mod v1 {
+	use super::*;
+	use crate::traits::{CurrencyPair, Pool, PoolKind, PoolStatus};
+	use codec::Encode;
+	use frame_support::{
+		ensure,
+		traits::{Get, GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+		weights::Weight,
+	};
+	use sp_runtime::RuntimeDebug;
+	use sp_std::marker::PhantomData;
+
+	/// The layout of a pool in storage version 0, before pools had a status and a kind.
+	#[derive(RuntimeDebug, Encode, Decode, Clone, PartialEq, Eq)]
+	pub struct PoolV0<AccountId, AssetId> {
+		pub owner: AccountId,
+		pub pair: CurrencyPair<AssetId>,
+		pub lp_token: AssetId,
+		pub fee: Permill,
+	}
+
+	/// Migrates the pools to storage version 1:
+	/// - every pool becomes an active constant product pool, the only kind that existed in v0.
+	/// - the canonical pair and the fee of every pool are indexed in `PoolIdByPair`.
+	pub struct MigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() != 0 {
+				return T::DbWeight::get().reads(1)
+			}
+
+			let mut migrated: Weight = 0;
+			Pools::<T>::translate::<PoolV0<AccountIdOf<T>, AssetIdOf<T>>, _>(|pool_id, pool| {
+				let pair = pool.pair.canonical();
+				PoolIdByPair::<T>::insert((pair.token_a, pair.token_b), pool.fee, pool_id);
+				migrated += 1;
+				Some(Pool {
+					owner: pool.owner,
+					pair: pool.pair,
+					lp_token: pool.lp_token,
+					fee: pool.fee,
+					status: PoolStatus::Active,
+					kind: PoolKind::ConstantProduct,
+				})
+			});
+			StorageVersion::new(1).put::<Pallet<T>>();
+
+			T::DbWeight::get().reads_writes(migrated + 1, migrated * 2 + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<(), &'static str> {
+			use frame_support::traits::OnRuntimeUpgradeHelpersExt;
+
+			// Chains that were already migrated skip the migration and its checks
+			let migrating = Pallet::<T>::on_chain_storage_version() == 0;
+			Self::set_temp_storage(migrating, "dex_migrating_to_v1");
+			if migrating {
+				Self::set_temp_storage(Pools::<T>::iter_keys().count() as u32, "pool_count");
+			}
+			Ok(())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			use frame_support::traits::OnRuntimeUpgradeHelpersExt;
+
+			if !Self::get_temp_storage::<bool>("dex_migrating_to_v1").unwrap_or(false) {
+				return Ok(())
+			}
+			ensure!(
+				Pallet::<T>::on_chain_storage_version() >= 1,
+				"The dex pallet storage version wasn't updated to v1"
+			);
+			let pool_count: u32 =
+				Self::get_temp_storage("pool_count").ok_or("Pool count of v0 not found")?;
+			// Fails if any pool couldn't be decoded in the new layout
+			let pools = Pools::<T>::iter().collect::<Vec<_>>();
+			ensure!(pools.len() as u32 == pool_count, "Pools were lost during the migration");
+			for (pool_id, pool) in pools {
+				let pair = pool.pair.canonical();
+				ensure!(
+					PoolIdByPair::<T>::get((pair.token_a, pair.token_b), pool.fee) == Some(pool_id),
+					"Pool pair isn't indexed"
+				);
+			}
+			Pallet::<T>::do_try_state()
+		}
+	}
+}
+
+pub mod v2 {
+	use super::*;
+	use frame_support::{
+		ensure,
+		traits::{Get, GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+		weights::Weight,
+	};
+	use sp_std::marker::PhantomData;
+
+	/// Migrates the pair index to storage version 2, in which `PoolIdByPair` is keyed by the
+	/// canonical pair and the fee of a pool. The keys of v1 don't contain the fee, so the index
+	/// is cleared and rebuilt from the pair pools.
+	pub struct MigrateToV2<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV2<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() != 1 {
+				return T::DbWeight::get().reads(1)
+			}
+
+			let removed = PoolIdByPair::<T>::clear(u32::MAX, None).unique as Weight;
+			let mut migrated: Weight = 0;
+			for (pool_id, pool) in Pools::<T>::iter() {
+				let pair = pool.pair.canonical();
+				PoolIdByPair::<T>::insert((pair.token_a, pair.token_b), pool.fee, pool_id);
+				migrated += 1;
+			}
+			StorageVersion::new(2).put::<Pallet<T>>();
+
+			T::DbWeight::get().reads_writes(migrated + 1, removed + migrated + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<(), &'static str> {
+			use frame_support::traits::OnRuntimeUpgradeHelpersExt;
+
+			// The older migrations might run right before this one in the same upgrade. Chains
+			// that were already migrated skip the migration and its checks.
+			let migrating = Pallet::<T>::on_chain_storage_version() < 2;
+			Self::set_temp_storage(migrating, "dex_migrating_to_v2");
+			Ok(())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			use frame_support::traits::OnRuntimeUpgradeHelpersExt;
+
+			if !Self::get_temp_storage::<bool>("dex_migrating_to_v2").unwrap_or(false) {
+				return Ok(())
+			}
+			// The newer migrations might have run after this one in the same upgrade
+			ensure!(
+				Pallet::<T>::on_chain_storage_version() >= 2,
+				"The dex pallet storage version wasn't updated to v2"
+			);
+			// Checks that every pair pool and nothing else is indexed
+			Pallet::<T>::do_try_state()
+		}
+	}
+}
+
+pub mod v3 {
+	use super::*;
+	use frame_support::{
+		ensure,
+		storage::migration::storage_key_iter,
+		traits::{Get, GetStorageVersion, OnRuntimeUpgrade, PalletInfoAccess, StorageVersion},
+		weights::Weight,
+		Blake2_128Concat,
+	};
+	use sp_std::marker::PhantomData;
+
+	/// Migrates the price observations to storage version 3, in which `Observations` is keyed by
+	/// the pool id and the position in the ring buffer instead of storing the whole buffer of a
+	/// pool in one value. The observations are written in chronological order, so the newest one
+	/// is at `ObservationIndex`. Since observations are only recorded when the reserves change,
+	/// the latest price cumulatives are appended if they are newer than the newest observation.
+	pub struct MigrateToV3<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV3<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() != 2 {
+				return T::DbWeight::get().reads(1)
+			}
+
+			// The old and the new keys share the prefix, so the old buffers are taken out first
+			let pallet_name = <Pallet<T> as PalletInfoAccess>::name().as_bytes();
+			let buffers = storage_key_iter::<PoolIdOf<T>, Vec<ObservationOf<T>>, Blake2_128Concat>(
+				pallet_name,
+				b"Observations",
+			)
+			.drain()
+			.collect::<Vec<_>>();
+
+			let max_observations = T::MaxObservations::get() as usize;
+			let mut writes: Weight = 0;
+			for (pool_id, buffer) in &buffers {
+				// Once the buffer was full, the old index pointed at the oldest observation
+				let oldest =
+					(ObservationIndex::<T>::take(pool_id).unwrap_or(0) as usize).min(buffer.len());
+				let mut observations = buffer[oldest..].to_vec();
+				observations.extend_from_slice(&buffer[..oldest]);
+
+				let latest = PriceCumulatives::<T>::get(pool_id);
+				if observations
+					.last()
+					.map_or(true, |newest| newest.block_number < latest.block_number)
+				{
+					observations.push(latest);
+				}
+				let skipped = observations.len().saturating_sub(max_observations);
+				let mut index = 0;
+				for observation in observations.into_iter().skip(skipped) {
+					Observations::<T>::insert(pool_id, index, observation);
+					index += 1;
+				}
+				ObservationIndex::<T>::insert(pool_id, index.saturating_sub(1));
+				writes += index as Weight + 1;
+			}
+			StorageVersion::new(3).put::<Pallet<T>>();
+
+			let migrated = buffers.len() as Weight;
+			T::DbWeight::get().reads_writes(migrated * 3 + 1, migrated * 2 + writes + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<(), &'static str> {
+			use frame_support::traits::OnRuntimeUpgradeHelpersExt;
+
+			// The older migrations might run right before this one in the same upgrade. Chains
+			// that were already migrated skip the migration and its checks.
+			let migrating = Pallet::<T>::on_chain_storage_version() < 3;
+			Self::set_temp_storage(migrating, "dex_migrating_to_v3");
+			Ok(())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			use frame_support::traits::OnRuntimeUpgradeHelpersExt;
+
+			if !Self::get_temp_storage::<bool>("dex_migrating_to_v3").unwrap_or(false) {
+				return Ok(())
+			}
+			// The newer migrations might have run after this one in the same upgrade
+			ensure!(
+				Pallet::<T>::on_chain_storage_version() >= 3,
+				"The dex pallet storage version wasn't updated to v3"
+			);
+			// Checks that the newest observation of every pool matches its price cumulatives
+			Pallet::<T>::do_try_state()
+		}
+	}
+}
+
+pub mod v4 {
+	use super::*;
+	use frame_support::{
+		ensure,
+		traits::{Get, GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+		weights::Weight,
+	};
+	use sp_std::marker::PhantomData;
+
+	/// Moves the liquidity tokens of the existing pools to storage version 4, in which every pool
+	/// has its own liquidity token `LiquidityTokenConversion::unlookup(pool_id)`. The balances,
+	/// locks, reserves and the total issuance of the old token are re-keyed in `orml_tokens`.
+	///
+	/// The old liquidity token of a pool can only be moved if no other pool uses it and it isn't
+	/// one of the pool's own assets. Pools of the native asset used `Native` itself as their
+	/// liquidity token, whose balances can't be told apart from real native balances. Such pools
+	/// are left unchanged and make `pre_upgrade` fail.
+	pub struct MigrateToV4<T>(PhantomData<T>);
+
+	/// The id, the old and the new liquidity token of a pool.
+	type LpTokenMigration<T> = (PoolIdOf<T>, AssetIdOf<T>, AssetIdOf<T>);
+
+	impl<T> MigrateToV4<T>
+	where
+		T: Config + orml_tokens::Config<CurrencyId = AssetIdOf<T>>,
+	{
+		/// Returns the pools whose liquidity token isn't derived from their id yet, together with
+		/// whether their old liquidity token can be moved.
+		fn lp_tokens_to_migrate() -> Vec<(LpTokenMigration<T>, bool)> {
+			let pools = Pools::<T>::iter()
+				.map(|(pool_id, pool)| {
+					(pool_id, pool.lp_token, sp_std::vec![pool.pair.token_a, pool.pair.token_b])
+				})
+				.chain(
+					MultiAssetPools::<T>::iter()
+						.map(|(pool_id, pool)| (pool_id, pool.lp_token, pool.assets.into_inner())),
+				)
+				.collect::<Vec<_>>();
+
+			pools
+				.iter()
+				.filter_map(|(pool_id, lp_token, assets)| {
+					let new_lp_token = T::LiquidityTokenConversion::unlookup(*pool_id);
+					if *lp_token == new_lp_token {
+						return None
+					}
+					let shared = pools.iter().filter(|(_, other, _)| other == lp_token).count() > 1;
+					let movable = !shared && !assets.contains(lp_token);
+					Some(((*pool_id, *lp_token, new_lp_token), movable))
+				})
+				.collect()
+		}
+	}
+
+	impl<T> OnRuntimeUpgrade for MigrateToV4<T>
+	where
+		T: Config + orml_tokens::Config<CurrencyId = AssetIdOf<T>>,
+	{
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() != 3 {
+				return T::DbWeight::get().reads(1)
+			}
+
+			let migrations = Self::lp_tokens_to_migrate()
+				.into_iter()
+				.filter_map(|(migration, movable)| movable.then(|| migration))
+				.collect::<Vec<_>>();
+			let new_lp_token = |currency_id: &AssetIdOf<T>| {
+				migrations
+					.iter()
+					.find(|(_, old_lp_token, _)| old_lp_token == currency_id)
+					.map(|(_, _, new_lp_token)| *new_lp_token)
+			};
+
+			// The accounts are collected first, the map can't be changed while iterating it
+			let mut reads: Weight = 0;
+			let accounts = orml_tokens::Accounts::<T>::iter()
+				.filter_map(|(who, currency_id, data)| {
+					reads += 1;
+					new_lp_token(&currency_id).map(|new| (who, currency_id, new, data))
+				})
+				.collect::<Vec<_>>();
+			for (who, old_lp_token, new_lp_token, data) in &accounts {
+				orml_tokens::Accounts::<T>::remove(who, old_lp_token);
+				orml_tokens::Accounts::<T>::insert(who, new_lp_token, data);
+				let locks = orml_tokens::Locks::<T>::take(who, old_lp_token);
+				if !locks.is_empty() {
+					orml_tokens::Locks::<T>::insert(who, new_lp_token, locks);
+				}
+				let reserves = orml_tokens::Reserves::<T>::take(who, old_lp_token);
+				if !reserves.is_empty() {
+					orml_tokens::Reserves::<T>::insert(who, new_lp_token, reserves);
+				}
+			}
+
+			for (pool_id, old_lp_token, new_lp_token) in &migrations {
+				let issuance = orml_tokens::TotalIssuance::<T>::take(old_lp_token);
+				orml_tokens::TotalIssuance::<T>::insert(new_lp_token, issuance);
+				Pools::<T>::mutate(pool_id, |pool| {
+					if let Some(pool) = pool {
+						pool.lp_token = *new_lp_token;
+					}
+				});
+				MultiAssetPools::<T>::mutate(pool_id, |pool| {
+					if let Some(pool) = pool {
+						pool.lp_token = *new_lp_token;
+					}
+				});
+			}
+			StorageVersion::new(4).put::<Pallet<T>>();
+
+			let (accounts, pools) = (accounts.len() as Weight, migrations.len() as Weight);
+			T::DbWeight::get()
+				.reads_writes(reads + accounts * 2 + pools * 3 + 1, accounts * 6 + pools * 4 + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<(), &'static str> {
+			use frame_support::traits::OnRuntimeUpgradeHelpersExt;
+
+			// The older migrations might run right before this one in the same upgrade. Chains
+			// that were already migrated skip the migration and its checks.
+			let migrating = Pallet::<T>::on_chain_storage_version() < 4;
+			Self::set_temp_storage(migrating, "dex_migrating_to_v4");
+			if !migrating {
+				return Ok(())
+			}
+			ensure!(
+				Self::lp_tokens_to_migrate().into_iter().all(|(_, movable)| movable),
+				"A pool uses one of its own assets or the liquidity token of another pool as its \
+				 liquidity token, its balances can't be moved"
+			);
+			Ok(())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			use frame_support::traits::OnRuntimeUpgradeHelpersExt;
+
+			if !Self::get_temp_storage::<bool>("dex_migrating_to_v4").unwrap_or(false) {
+				return Ok(())
+			}
+			// The newer migrations might have run after this one in the same upgrade
+			ensure!(
+				Pallet::<T>::on_chain_storage_version() >= 4,
+				"The dex pallet storage version wasn't updated to v4"
+			);
+			ensure!(
+				Self::lp_tokens_to_migrate().is_empty(),
+				"A liquidity token isn't derived from its pool id"
+			);
+			Pallet::<T>::do_try_state()
+		}
+	}
+}
@@ -1,10 +1,11 @@
+use codec::{Decode, Encode};
 use frame_support::{
 	parameter_types,
-	traits::{ConstU16, ConstU32, ConstU64, Everything, GenesisBuild},
+	traits::{ConstU16, ConstU32, ConstU64, Contains, Everything, GenesisBuild, Hooks},
 	PalletId,
 };
 use frame_system as system;
-use orml_traits::parameter_type_with_key;
+use orml_traits::{parameter_type_with_key, MultiCurrency};
 use primitives::{CurrencyId, TokenSymbol};
 pub use primitives::{CurrencyId::Token, TokenSymbol::*, UnsignedInner};
 
@@ -12,9 +13,11 @@ use sp_core::H256;
 use sp_runtime::{
 	testing::Header,
 	traits::{BlakeTwo256, ConvertInto, IdentityLookup, Zero},
+	DispatchError, DispatchResult, Permill,
 };
 
 use crate as pallet_dex;
+use crate::traits::{Amm, CurrencyPair, FlashSwapHandler};
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -29,7 +32,7 @@ frame_support::construct_runtime!(
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
 		Tokens: orml_tokens::{Pallet, Storage, Config<T>, Event<T>},
 
-		Dex: pallet_dex::{Pallet, Call, Storage, Event<T>},
+		Dex: pallet_dex::{Pallet, Call, Config<T>, Storage, Event<T>},
 	}
 );
 
@@ -72,6 +75,9 @@ parameter_types! {
 	pub const GetNativeCurrencyId: AssetId = CurrencyId::Native;
 	pub const MaxLocks: u32 = 50;
 	pub const DexPalletId: PalletId = PalletId(*b"dex_pall");
+	pub static ProtocolFeeShare: Permill = Permill::zero();
+	pub static ProtocolFeeReceiver: Option<AccountId> = None;
+	pub static MinimumLiquidity: Balance = 0;
 }
 
 impl pallet_dex::Config for Test {
@@ -82,7 +88,67 @@ impl pallet_dex::Config for Test {
 	type PalletId = DexPalletId;
 	type Assets = Tokens;
 	type Convert = ConvertInto;
-	type LiquidityTokenConversion = primitives::token_conversion::CurrencyConversion;
+	type LiquidityTokenConversion = primitives::token_conversion::LiquidityTokenConversion;
+	type MinimumLiquidity = MinimumLiquidity;
+	type MaxAssetsInPool = ConstU32<4>;
+	type MaxTicksPerPool = ConstU32<16>;
+	type MaxPathLength = ConstU32<4>;
+	type MaxPoolsPerPair = ConstU32<3>;
+	type MaxOrdersPerBatch = ConstU32<4>;
+	type ProtocolFeeShare = ProtocolFeeShare;
+	type ProtocolFeeReceiver = ProtocolFeeReceiver;
+	type AdminOrigin = system::EnsureRoot<AccountId>;
+	type MaxObservations = ConstU32<16>;
+	type OnSwap = ();
+	type FlashSwapHandler = MockFlashSwapHandler;
+	type RegisteredAssets = RegisteredAssets;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+/// All tokens except `UNREGISTERED_ASSET` are registered.
+pub struct RegisteredAssets;
+
+impl Contains<AssetId> for RegisteredAssets {
+	fn contains(asset_id: &AssetId) -> bool {
+		matches!(asset_id, CurrencyId::Token(_)) && *asset_id != UNREGISTERED_ASSET
+	}
+}
+
+/// What the flash swap handler of the mock does with the borrowed amounts. Passed to
+/// `flash_swap` as encoded `data`.
+#[derive(Encode, Decode)]
+pub enum FlashSwapAction {
+	/// Pay the given amounts of token_a and token_b to the pool.
+	Repay(Balance, Balance),
+	/// Sell the given amount of token_a on the same pool.
+	Sell(Balance),
+}
+
+pub struct MockFlashSwapHandler;
+
+impl FlashSwapHandler<AccountId, AssetId, Balance, PoolId> for MockFlashSwapHandler {
+	fn on_flash_swap(
+		who: &AccountId,
+		pool_id: PoolId,
+		pair: CurrencyPair<AssetId>,
+		_amount_a_out: Balance,
+		_amount_b_out: Balance,
+		data: &[u8],
+	) -> DispatchResult {
+		let action = FlashSwapAction::decode(&mut &data[..])
+			.map_err(|_| DispatchError::Other("Invalid flash swap data"))?;
+		match action {
+			FlashSwapAction::Repay(amount_a, amount_b) => {
+				let pool_account = Dex::account_id(&pool_id);
+				Tokens::transfer(pair.token_a, who, &pool_account, amount_a)?;
+				Tokens::transfer(pair.token_b, who, &pool_account, amount_b)
+			},
+			FlashSwapAction::Sell(amount) =>
+				<Dex as Amm>::sell(who, pool_id, pair.token_a, amount).map(|_| ()),
+		}
+	}
 }
 
 parameter_type_with_key! {
@@ -114,10 +180,17 @@ pub const DARWIN: AccountId = 4;
 
 pub const ASSET_1: AssetId = CurrencyId::Token(TokenSymbol::Short([0; 4]));
 pub const ASSET_2: AssetId = CurrencyId::Token(TokenSymbol::Short([1; 4]));
+pub const ASSET_3: AssetId = CurrencyId::Token(TokenSymbol::Short([2; 4]));
+pub const UNREGISTERED_ASSET: AssetId = CurrencyId::Token(TokenSymbol::Short([9; 4]));
 // pub const ASSET_1: AssetId = CurrencyId::Token(TokenSymbol::Short([0x00, 0x01, 0x02, 0x03]));
 
 
 pub fn new_test_ext_multi_currency() -> sp_io::TestExternalities {
+	new_test_ext_with_pools(vec![])
+}
+
+/// Builds the test externalities with the given pools created at genesis.
+pub fn new_test_ext_with_pools(pools: Vec<crate::GenesisPoolOf<Test>>) -> sp_io::TestExternalities {
 	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into();
 
 	let base_balance = 1_000_000;
@@ -125,6 +198,7 @@ pub fn new_test_ext_multi_currency() -> sp_io::TestExternalities {
 	let balances: Vec<(AccountId, AssetId, Balance)> = vec![
 		(ALICE, ASSET_1, base_balance),
 		(ALICE, ASSET_2, base_balance),
+		(ALICE, ASSET_3, base_balance),
 		(BOB, ASSET_1, base_balance),
 	];
 
@@ -132,6 +206,8 @@ pub fn new_test_ext_multi_currency() -> sp_io::TestExternalities {
 		.assimilate_storage(&mut t)
 		.unwrap();
 
+	pallet_dex::GenesisConfig::<Test> { pools }.assimilate_storage(&mut t).unwrap();
+
 	let mut ext = sp_io::TestExternalities::new(t);
 	// set block number to 1 to make sure that events are populated
 	ext.execute_with(|| System::set_block_number(1));
@@ -144,5 +220,14 @@ where
 {
 	new_test_ext_multi_currency().execute_with(|| {
 		test();
+		// Every test has to leave the storage in a consistent state
+		Dex::do_try_state().unwrap();
 	});
 }
+
+pub fn run_to_block(n: BlockNumber) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		Dex::on_initialize(System::block_number());
+	}
+}
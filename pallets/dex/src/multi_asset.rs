@@ -0,0 +1,417 @@
+use crate::{
+	calc,
+	traits::{MultiAssetPool, OnSwap, PoolStatus},
+	types::*,
+	Config, Error, Event, MultiAssetPools, Pallet, PoolAccounts, PoolCount,
+};
+use frame_support::{dispatch::DispatchResult, ensure, traits::Get, BoundedVec};
+use orml_traits::MultiCurrency;
+use sp_arithmetic::{helpers_128bit::multiply_by_rational, Permill};
+use sp_runtime::{
+	traits::{CheckedAdd, Convert, One, StaticLookup, Zero},
+	ArithmeticError, DispatchError,
+};
+use sp_std::{vec, vec::Vec};
+
+/// The lowest number of assets a multi-asset pool can hold. Pools with two assets are pair pools.
+pub(crate) const MIN_MULTI_ASSET_POOL_ASSETS: usize = 3;
+
+impl<T: Config> Pallet<T> {
+	/// Returns the multi-asset pool for a given id.
+	pub(crate) fn get_multi_asset_pool(
+		pool_id: PoolIdOf<T>,
+	) -> Result<MultiAssetPoolOf<T>, DispatchError> {
+		MultiAssetPools::<T>::get(pool_id).ok_or_else(|| Error::<T>::PoolNotFound.into())
+	}
+
+	/// Returns the balances of the assets in a multi-asset pool, in the order of its assets.
+	pub(crate) fn multi_asset_reserves(
+		pool_id: PoolIdOf<T>,
+		pool: &MultiAssetPoolOf<T>,
+	) -> Vec<BalanceOf<T>> {
+		let pool_account = Self::account_id(&pool_id);
+		pool.assets
+			.iter()
+			.map(|asset| T::Assets::free_balance(*asset, &pool_account))
+			.collect()
+	}
+
+	/// Returns the position of `asset` in the assets of the pool.
+	fn multi_asset_index(
+		pool: &MultiAssetPoolOf<T>,
+		asset: AssetIdOf<T>,
+	) -> Result<usize, DispatchError> {
+		pool.assets
+			.iter()
+			.position(|pool_asset| *pool_asset == asset)
+			.ok_or_else(|| Error::<T>::InvalidAsset.into())
+	}
+
+	/// Creates a new multi-asset pool in storage. The pool shares its id space and account
+	/// derivation with the pair pools. The lp token is derived from the pool id.
+	pub(crate) fn do_create_multi_asset_pool(
+		owner: AccountIdOf<T>,
+		assets: BoundedVec<AssetIdOf<T>, T::MaxAssetsInPool>,
+		fee: Permill,
+	) -> Result<PoolIdOf<T>, DispatchError> {
+		ensure!(fee < Permill::one(), Error::<T>::InvalidFee);
+
+		let mut sorted_assets = assets.clone().into_inner();
+		sorted_assets.sort();
+		sorted_assets.dedup();
+		ensure!(
+			sorted_assets.len() == assets.len() && assets.len() >= MIN_MULTI_ASSET_POOL_ASSETS,
+			Error::<T>::InvalidPoolAssets
+		);
+		ensure!(assets.iter().all(Self::is_tradable), Error::<T>::AssetNotRegistered);
+
+		PoolCount::<T>::try_mutate(|pool_count| -> Result<T::PoolId, DispatchError> {
+			let pool_id = *pool_count;
+			let pool: MultiAssetPoolOf<T> = MultiAssetPool {
+				owner,
+				assets,
+				lp_token: T::LiquidityTokenConversion::unlookup(pool_id),
+				fee,
+				status: PoolStatus::Active,
+			};
+			MultiAssetPools::<T>::insert(pool_id, pool);
+			PoolAccounts::<T>::insert(pool_id, Self::account_id(&pool_id));
+
+			*pool_count = pool_id
+				.checked_add(&T::PoolId::one())
+				.ok_or(Error::<T>::MaximumPoolCountReached)?;
+			Ok(pool_id)
+		})
+	}
+
+	/// Makes the first deposit of `amounts` into an empty multi-asset pool, in the order of its
+	/// assets, which sets the initial prices of the pool. `MinimumLiquidity` of the minted
+	/// liquidity tokens are locked in the dead account, like the first deposit of a pair pool.
+	pub(crate) fn do_add_initial_multi_asset_liquidity(
+		who: &AccountIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		amounts: Vec<BalanceOf<T>>,
+	) -> Result<BalanceOf<T>, DispatchError> {
+		let pool = Self::get_multi_asset_pool(pool_id)?;
+		let pool_account = Self::account_id(&pool_id);
+		ensure!(pool.status != PoolStatus::Paused, Error::<T>::PoolPaused);
+		ensure!(amounts.len() == pool.assets.len(), Error::<T>::InvalidAmount);
+
+		let deposits: Vec<u128> =
+			amounts.iter().map(|amount| T::Convert::convert(*amount)).collect();
+		let liquidity = calc::get_multi_asset_invariant(&deposits)
+			.ok_or(Error::<T>::InvariantCalculationFailed)?;
+		let minimum_liquidity = T::MinimumLiquidity::get();
+		ensure!(
+			liquidity > T::Convert::convert(minimum_liquidity),
+			Error::<T>::InsufficientInitialLiquidity
+		);
+		let minted_lp: BalanceOf<T> =
+			T::Convert::convert(liquidity - T::Convert::convert(minimum_liquidity));
+
+		for (asset, amount) in pool.assets.iter().zip(amounts.iter()) {
+			ensure!(
+				T::Assets::free_balance(*asset, who) >= *amount,
+				Error::<T>::InsufficientBalance
+			);
+			T::Assets::transfer(*asset, who, &pool_account, *amount)?;
+		}
+		T::Assets::deposit(pool.lp_token, &Self::dead_account(), minimum_liquidity)?;
+		T::Assets::deposit(pool.lp_token, who, minted_lp)?;
+
+		Self::deposit_event(Event::<T>::MultiAssetLiquidityAdded {
+			who: who.clone(),
+			pool_id,
+			amounts,
+			minted_lp,
+		});
+		Ok(minted_lp)
+	}
+
+	/// Adds liquidity to a multi-asset pool proportionally to its reserves. The amounts of the
+	/// other assets are derived from the `amount` of `asset`. The pool must already hold
+	/// liquidity, the first deposit is made with `do_add_initial_multi_asset_liquidity`.
+	pub(crate) fn do_add_multi_asset_liquidity(
+		who: &AccountIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		amount: BalanceOf<T>,
+		asset: AssetIdOf<T>,
+	) -> Result<BalanceOf<T>, DispatchError> {
+		let pool = Self::get_multi_asset_pool(pool_id)?;
+		let pool_account = Self::account_id(&pool_id);
+		ensure!(pool.status != PoolStatus::Paused, Error::<T>::PoolPaused);
+		let index = Self::multi_asset_index(&pool, asset)?;
+
+		// Convert to u128 for calculations
+		let amount: u128 = T::Convert::convert(amount);
+		let reserves: Vec<u128> = Self::multi_asset_reserves(pool_id, &pool)
+			.into_iter()
+			.map(T::Convert::convert)
+			.collect();
+		let total_issuance: u128 = T::Convert::convert(T::Assets::total_issuance(pool.lp_token));
+
+		ensure!(total_issuance > 0 && reserves.iter().any(|r| *r > 0), Error::<T>::EmptyPool);
+		let reserve_in = reserves[index];
+		ensure!(reserve_in > 0, Error::<T>::InsufficientLiquidity);
+
+		let mut amounts = Vec::with_capacity(reserves.len());
+		let mut minted_lp = u128::MAX;
+		for reserve in reserves.iter() {
+			let deposit = multiply_by_rational(amount, *reserve, reserve_in)
+				.map_err(|_| ArithmeticError::Overflow)?;
+			let liquidity = multiply_by_rational(deposit, total_issuance, *reserve)
+				.map_err(|_| ArithmeticError::Overflow)?;
+			minted_lp = minted_lp.min(liquidity);
+			amounts.push(deposit);
+		}
+		ensure!(minted_lp > 0, Error::<T>::InvalidAmount);
+
+		// Convert back to balances
+		let amounts: Vec<BalanceOf<T>> = amounts.into_iter().map(T::Convert::convert).collect();
+		let minted_lp = T::Convert::convert(minted_lp);
+
+		for (asset, amount) in pool.assets.iter().zip(amounts.iter()) {
+			ensure!(
+				T::Assets::free_balance(*asset, who) >= *amount,
+				Error::<T>::InsufficientBalance
+			);
+			T::Assets::transfer(*asset, who, &pool_account, *amount)?;
+		}
+		T::Assets::deposit(pool.lp_token, who, minted_lp)?;
+
+		Self::deposit_event(Event::<T>::MultiAssetLiquidityAdded {
+			who: who.clone(),
+			pool_id,
+			amounts,
+			minted_lp,
+		});
+		Ok(minted_lp)
+	}
+
+	/// Adds liquidity to a multi-asset pool by depositing only `amount` of `asset`. The fee is
+	/// charged on the part of the deposit that is implicitly swapped into the other assets.
+	pub(crate) fn do_add_single_asset_liquidity(
+		who: &AccountIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		asset: AssetIdOf<T>,
+		amount: BalanceOf<T>,
+	) -> Result<BalanceOf<T>, DispatchError> {
+		let pool = Self::get_multi_asset_pool(pool_id)?;
+		Self::ensure_multi_asset_swaps_allowed(&pool)?;
+		let index = Self::multi_asset_index(&pool, asset)?;
+		let pool_account = Self::account_id(&pool_id);
+
+		let reserve_in = T::Assets::free_balance(asset, &pool_account);
+		let total_issuance = T::Assets::total_issuance(pool.lp_token);
+		let minted_lp = calc::get_single_asset_liquidity_out::<T>(
+			T::Convert::convert(amount),
+			T::Convert::convert(reserve_in),
+			T::Convert::convert(total_issuance),
+			pool.assets.len() as u32,
+			pool.fee,
+		)?;
+		ensure!(minted_lp > 0, Error::<T>::InvalidAmount);
+		let minted_lp = T::Convert::convert(minted_lp);
+
+		T::Assets::transfer(asset, who, &pool_account, amount)?;
+		T::Assets::deposit(pool.lp_token, who, minted_lp)?;
+
+		let mut amounts = vec![BalanceOf::<T>::zero(); pool.assets.len()];
+		amounts[index] = amount;
+		Self::deposit_event(Event::<T>::MultiAssetLiquidityAdded {
+			who: who.clone(),
+			pool_id,
+			amounts,
+			minted_lp,
+		});
+		Ok(minted_lp)
+	}
+
+	/// Removes liquidity from a multi-asset pool proportionally to its reserves. The `amount`
+	/// refers to the liquidity tokens that are burned.
+	pub(crate) fn do_remove_multi_asset_liquidity(
+		who: &AccountIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		amount: BalanceOf<T>,
+	) -> Result<Vec<BalanceOf<T>>, DispatchError> {
+		let pool = Self::get_multi_asset_pool(pool_id)?;
+		let pool_account = Self::account_id(&pool_id);
+		let total_issuance = T::Assets::total_issuance(pool.lp_token);
+		ensure!(!total_issuance.is_zero(), Error::<T>::WithdrawWithoutSupply);
+		ensure!(
+			T::Assets::free_balance(pool.lp_token, who) >= amount,
+			Error::<T>::InsufficientLiquidityBalance
+		);
+
+		// Convert to u128 for calculations
+		let liquidity: u128 = T::Convert::convert(amount);
+		let total_issuance: u128 = T::Convert::convert(total_issuance);
+
+		let mut amounts = Vec::with_capacity(pool.assets.len());
+		for reserve in Self::multi_asset_reserves(pool_id, &pool) {
+			let amount_out =
+				multiply_by_rational(liquidity, T::Convert::convert(reserve), total_issuance)
+					.map_err(|_| ArithmeticError::Overflow)?;
+			ensure!(amount_out > 0, Error::<T>::InvalidAmount);
+			amounts.push(T::Convert::convert(amount_out));
+		}
+
+		for (asset, amount_out) in pool.assets.iter().zip(amounts.iter()) {
+			T::Assets::transfer(*asset, &pool_account, who, *amount_out)?;
+		}
+		T::Assets::withdraw(pool.lp_token, who, amount)?;
+
+		Self::deposit_event(Event::<T>::MultiAssetLiquidityRemoved {
+			who: who.clone(),
+			pool_id,
+			amounts: amounts.clone(),
+			total_issuance: T::Assets::total_issuance(pool.lp_token),
+		});
+		Ok(amounts)
+	}
+
+	/// Removes liquidity from a multi-asset pool by withdrawing only `asset`. The fee is charged
+	/// on the part of the withdrawal that is implicitly swapped from the other assets.
+	pub(crate) fn do_remove_single_asset_liquidity(
+		who: &AccountIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		asset: AssetIdOf<T>,
+		amount: BalanceOf<T>,
+	) -> Result<BalanceOf<T>, DispatchError> {
+		let pool = Self::get_multi_asset_pool(pool_id)?;
+		Self::ensure_multi_asset_swaps_allowed(&pool)?;
+		let index = Self::multi_asset_index(&pool, asset)?;
+		let pool_account = Self::account_id(&pool_id);
+		ensure!(
+			T::Assets::free_balance(pool.lp_token, who) >= amount,
+			Error::<T>::InsufficientLiquidityBalance
+		);
+
+		let reserve_out = T::Assets::free_balance(asset, &pool_account);
+		let amount_out = calc::get_single_asset_amount_out::<T>(
+			T::Convert::convert(amount),
+			T::Convert::convert(reserve_out),
+			T::Convert::convert(T::Assets::total_issuance(pool.lp_token)),
+			pool.assets.len() as u32,
+			pool.fee,
+		)?;
+		ensure!(amount_out > 0, Error::<T>::InvalidAmount);
+		let amount_out = T::Convert::convert(amount_out);
+
+		T::Assets::transfer(asset, &pool_account, who, amount_out)?;
+		T::Assets::withdraw(pool.lp_token, who, amount)?;
+
+		let mut amounts = vec![BalanceOf::<T>::zero(); pool.assets.len()];
+		amounts[index] = amount_out;
+		Self::deposit_event(Event::<T>::MultiAssetLiquidityRemoved {
+			who: who.clone(),
+			pool_id,
+			amounts,
+			total_issuance: T::Assets::total_issuance(pool.lp_token),
+		});
+		Ok(amount_out)
+	}
+
+	/// Returns the reserves of `asset_in` and `asset_out` in a multi-asset pool.
+	fn multi_asset_swap_reserves(
+		pool_id: PoolIdOf<T>,
+		pool: &MultiAssetPoolOf<T>,
+		asset_in: AssetIdOf<T>,
+		asset_out: AssetIdOf<T>,
+	) -> Result<(u128, u128), DispatchError> {
+		ensure!(asset_in != asset_out, Error::<T>::InvalidAsset);
+		Self::multi_asset_index(pool, asset_in)?;
+		Self::multi_asset_index(pool, asset_out)?;
+
+		let pool_account = Self::account_id(&pool_id);
+		Ok((
+			T::Convert::convert(T::Assets::free_balance(asset_in, &pool_account)),
+			T::Convert::convert(T::Assets::free_balance(asset_out, &pool_account)),
+		))
+	}
+
+	/// Calculates the amount of `asset_out` received when selling `amount_in` of `asset_in` to
+	/// a multi-asset pool. All assets have the same weight, so only the reserves of the two
+	/// assets matter.
+	pub(crate) fn quote_multi_asset_exact_in(
+		pool_id: PoolIdOf<T>,
+		asset_in: AssetIdOf<T>,
+		asset_out: AssetIdOf<T>,
+		amount_in: BalanceOf<T>,
+	) -> Result<BalanceOf<T>, DispatchError> {
+		let pool = Self::get_multi_asset_pool(pool_id)?;
+		let (reserve_in, reserve_out) =
+			Self::multi_asset_swap_reserves(pool_id, &pool, asset_in, asset_out)?;
+
+		let amount_out = calc::get_amount_out::<T>(
+			T::Convert::convert(amount_in),
+			reserve_in,
+			reserve_out,
+			pool.fee,
+		)?;
+		Ok(T::Convert::convert(amount_out))
+	}
+
+	/// Calculates the amount of `asset_in` that has to be paid to buy `amount_out` of
+	/// `asset_out` from a multi-asset pool.
+	pub(crate) fn quote_multi_asset_exact_out(
+		pool_id: PoolIdOf<T>,
+		asset_in: AssetIdOf<T>,
+		asset_out: AssetIdOf<T>,
+		amount_out: BalanceOf<T>,
+	) -> Result<BalanceOf<T>, DispatchError> {
+		let pool = Self::get_multi_asset_pool(pool_id)?;
+		let (reserve_in, reserve_out) =
+			Self::multi_asset_swap_reserves(pool_id, &pool, asset_in, asset_out)?;
+
+		let amount_out = T::Convert::convert(amount_out);
+		ensure!(amount_out < reserve_out, Error::<T>::InsufficientLiquidity);
+		let amount_in = calc::get_amount_in::<T>(amount_out, reserve_in, reserve_out, pool.fee)?;
+		Ok(T::Convert::convert(amount_in))
+	}
+
+	/// Sells `amount_in` of `asset_in` to a multi-asset pool and returns the amount of
+	/// `asset_out` received.
+	pub(crate) fn multi_asset_swap_exact_in(
+		who: &AccountIdOf<T>,
+		pool_id: PoolIdOf<T>,
+		asset_in: AssetIdOf<T>,
+		asset_out: AssetIdOf<T>,
+		amount_in: BalanceOf<T>,
+	) -> Result<BalanceOf<T>, DispatchError> {
+		let pool = Self::get_multi_asset_pool(pool_id)?;
+		Self::ensure_multi_asset_swaps_allowed(&pool)?;
+		let pool_account = Self::account_id(&pool_id);
+
+		let amount_out = Self::quote_multi_asset_exact_in(pool_id, asset_in, asset_out, amount_in)?;
+		ensure!(!amount_out.is_zero(), Error::<T>::InvalidAmount);
+		let fee: BalanceOf<T> =
+			T::Convert::convert(calc::get_fee_amount(T::Convert::convert(amount_in), pool.fee));
+
+		T::Assets::transfer(asset_in, who, &pool_account, amount_in)?;
+		T::Assets::transfer(asset_out, &pool_account, who, amount_out)?;
+
+		Self::deposit_event(Event::<T>::Swapped {
+			pool_id,
+			who: who.clone(),
+			token_a: asset_out,
+			token_b: asset_in,
+			amount_a: amount_out,
+			amount_b: amount_in,
+			fee,
+			estimated_protocol_fee: Zero::zero(),
+		});
+		T::OnSwap::on_swap(pool_id);
+		Ok(amount_out)
+	}
+
+	/// Ensures that the status of a multi-asset pool allows swaps. Adding or removing liquidity
+	/// in a single asset is an implicit swap and has to pass this check as well.
+	fn ensure_multi_asset_swaps_allowed(pool: &MultiAssetPoolOf<T>) -> DispatchResult {
+		match pool.status {
+			PoolStatus::Active => Ok(()),
+			PoolStatus::SwapsPaused => Err(Error::<T>::SwapsPaused.into()),
+			PoolStatus::Paused => Err(Error::<T>::PoolPaused.into()),
+		}
+	}
+}
@@ -0,0 +1,120 @@
+use crate::{
+	traits::{Amm, CurrencyPair, PoolStatus},
+	types::*,
+	Config, Error, Pallet, Pools,
+};
+use frame_support::{ensure, traits::Get, weights::Weight};
+use sp_runtime::{
+	traits::{Saturating, Zero},
+	DispatchError,
+};
+use sp_std::vec::Vec;
+
+/// The number of parts the amount of a routed swap is split into. Each part is sold on the pool
+/// that offers the most for it, so a higher number gets closer to the best split but needs more
+/// quotes.
+pub(crate) const ROUTER_SPLIT_PARTS: u32 = 10;
+
+impl<T: Config> Pallet<T> {
+	/// The weight of a swap with the best-price router. Every pool of the pair is quoted once for
+	/// the whole amount and once for each part, and at most every pool executes a swap.
+	pub(crate) fn best_route_swap_weight() -> Weight {
+		let max_pools = T::MaxPoolsPerPair::get() as Weight;
+		let quotes = max_pools.saturating_mul(ROUTER_SPLIT_PARTS as Weight + 1);
+		Self::swap_weight()
+			.saturating_mul(max_pools)
+			.saturating_add(T::DbWeight::get().reads(quotes.saturating_mul(3)))
+	}
+
+	/// Finds the pool or the split of `amount_in` across the pair pools of `asset_in` and
+	/// `asset_out` that yields the most `asset_out`. Returns the amount sold on each pool of the
+	/// route and the total amount that is received, without executing the trade.
+	///
+	/// The amount is split into `ROUTER_SPLIT_PARTS` parts and each part is sold on the pool
+	/// whose output increases the most, given the parts that were already sold on it. The split
+	/// is only used if it yields more than selling the whole amount on the best single pool.
+	/// Pools whose swaps are paused, that are in batch auction mode or that can't take the amount
+	/// are left out.
+	pub fn quote_best_route(
+		asset_in: AssetIdOf<T>,
+		asset_out: AssetIdOf<T>,
+		amount_in: BalanceOf<T>,
+	) -> Result<(Vec<(PoolIdOf<T>, BalanceOf<T>)>, BalanceOf<T>), DispatchError> {
+		ensure!(!amount_in.is_zero(), Error::<T>::InvalidAmount);
+		ensure!(asset_in != asset_out, Error::<T>::InvalidAsset);
+
+		let pools: Vec<PoolIdOf<T>> =
+			<Self as Amm>::pools_for_pair(CurrencyPair { token_a: asset_in, token_b: asset_out })
+				.into_iter()
+				.filter(|pool_id| {
+					!Self::is_batch_auction_pool(*pool_id) &&
+						Pools::<T>::get(pool_id)
+							.map_or(false, |pool| pool.status == PoolStatus::Active)
+				})
+				.collect();
+		let quote = |pool_id: PoolIdOf<T>, amount: BalanceOf<T>| -> Option<BalanceOf<T>> {
+			<Self as Amm>::quote_exact_in(pool_id, asset_in, asset_out, amount).ok()
+		};
+
+		let best_single = pools
+			.iter()
+			.filter_map(|pool_id| {
+				quote(*pool_id, amount_in).map(|amount_out| (*pool_id, amount_out))
+			})
+			.max_by_key(|(_, amount_out)| *amount_out);
+
+		// `(pool_id, amount_in, amount_out)` of every pool in the split
+		let mut split: Vec<(PoolIdOf<T>, BalanceOf<T>, BalanceOf<T>)> =
+			pools.iter().map(|pool_id| (*pool_id, Zero::zero(), Zero::zero())).collect();
+		let part = amount_in / BalanceOf::<T>::from(ROUTER_SPLIT_PARTS);
+		let mut remaining = amount_in;
+		let mut split_complete = true;
+		for i in 0..ROUTER_SPLIT_PARTS {
+			// The last part includes the remainder of the division
+			let size = if i == ROUTER_SPLIT_PARTS - 1 { remaining } else { part };
+			if size.is_zero() {
+				continue
+			}
+
+			let mut best: Option<(usize, BalanceOf<T>, BalanceOf<T>)> = None;
+			for (index, (pool_id, pool_in, pool_out)) in split.iter().enumerate() {
+				let amount_out = match quote(*pool_id, pool_in.saturating_add(size)) {
+					Some(amount_out) => amount_out,
+					None => continue,
+				};
+				let gain = amount_out.saturating_sub(*pool_out);
+				if best.map_or(true, |(_, best_gain, _)| gain > best_gain) {
+					best = Some((index, gain, amount_out));
+				}
+			}
+			match best {
+				Some((index, _, amount_out)) => {
+					split[index].1 = split[index].1.saturating_add(size);
+					split[index].2 = amount_out;
+					remaining = remaining.saturating_sub(size);
+				},
+				None => {
+					split_complete = false;
+					break
+				},
+			}
+		}
+
+		let split_out = split.iter().fold(BalanceOf::<T>::zero(), |total, (_, _, amount_out)| {
+			total.saturating_add(*amount_out)
+		});
+		match best_single {
+			Some((pool_id, amount_out)) if !split_complete || amount_out >= split_out =>
+				Ok((sp_std::vec![(pool_id, amount_in)], amount_out)),
+			_ if split_complete => {
+				let route = split
+					.into_iter()
+					.filter(|(_, pool_in, _)| !pool_in.is_zero())
+					.map(|(pool_id, pool_in, _)| (pool_id, pool_in))
+					.collect();
+				Ok((route, split_out))
+			},
+			_ => Err(Error::<T>::NoRoute.into()),
+		}
+	}
+}
@@ -1,17 +1,35 @@
-use frame_support::{assert_noop, assert_ok};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::GetDispatchInfo,
+	storage::{unhashed, StoragePrefixedMap},
+	traits::{GetStorageVersion, Hooks, OnRuntimeUpgrade, StorageVersion},
+	Blake2_128Concat, BoundedVec, StorageHasher,
+};
 use frame_system::{Config, EventRecord};
 use orml_traits::MultiCurrency;
 
 use sp_core::H256;
-use sp_runtime::{Permill};
+use sp_runtime::{
+	traits::One, ArithmeticError, DispatchError, FixedPointNumber, FixedU128, Permill,
+};
 
 use primitives::{CurrencyId, TokenSymbol};
 
 use crate::{
+	batch::MAX_BATCH_SEARCH_STEPS,
+	calc,
+	migrations::{
+		v1::{MigrateToV1, PoolV0},
+		v2::MigrateToV2,
+		v3::MigrateToV3,
+		v4::MigrateToV4,
+	},
 	mock,
 	mock::*,
-	traits::{CurrencyPair, PoolCreationParams},
-	AssetIdOf, Error, PoolCreationParamsOf, PoolOf,
+	traits::{Amm, CurrencyPair, Observation, Oracle, PoolCreationParams, PoolKind, PoolStatus},
+	AssetIdOf, Error, ObservationIndex, Observations, PoolAccounts, PoolCreationParamsOf,
+	PoolIdByPair, PoolIdOf, PoolOf, Pools, PriceCumulatives, WeightInfo,
 };
 
 pub fn assert_has_event<T, F>(matcher: F)
@@ -40,7 +58,13 @@ fn create_default_pool_params() -> PoolCreationParamsOf<Test> {
 
 	let fee = Permill::from_percent(3);
 
-	PoolCreationParams { owner, pair, fee }
+	PoolCreationParams {
+		owner,
+		pair,
+		fee,
+		kind: PoolKind::ConstantProduct,
+		initial_sqrt_price: None,
+	}
 }
 
 /// Default value for deviation of computation error
@@ -78,15 +102,307 @@ fn create_pool_should_work() {
 	});
 }
 
+#[test]
+fn create_pool_should_fail_for_existing_pair() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool_params));
+
+		assert_noop!(
+			Dex::create_pool(Origin::signed(ALICE), pool_params),
+			Error::<Test>::PoolAlreadyExists
+		);
+
+		// The reversed pair refers to the same pool
+		let mut reversed_params = pool_params;
+		reversed_params.owner = BOB;
+		reversed_params.pair = pool_params.pair.swap();
+		assert_noop!(
+			Dex::create_pool(Origin::signed(BOB), reversed_params),
+			Error::<Test>::PoolAlreadyExists
+		);
+
+		assert_eq!(Dex::pool_count(), 1);
+	});
+}
+
+#[test]
+fn create_pool_should_fail_for_unregistered_asset() {
+	run_test(|| {
+		let mut pool_params = create_default_pool_params();
+		pool_params.pair.token_b = UNREGISTERED_ASSET;
+		assert_noop!(
+			Dex::create_pool(Origin::signed(ALICE), pool_params),
+			Error::<Test>::AssetNotRegistered
+		);
+
+		pool_params.pair = pool_params.pair.swap();
+		assert_noop!(
+			Dex::create_pool(Origin::signed(ALICE), pool_params),
+			Error::<Test>::AssetNotRegistered
+		);
+	});
+}
+
+#[test]
+fn create_pool_should_require_owner_to_be_signer() {
+	run_test(|| {
+		let mut pool_params = create_default_pool_params();
+		pool_params.owner = BOB;
+
+		assert_noop!(
+			Dex::create_pool(Origin::signed(ALICE), pool_params),
+			Error::<Test>::OwnerNotSigner
+		);
+
+		// The admin origin can create pools on behalf of other accounts
+		assert_ok!(Dex::create_pool(Origin::root(), pool_params));
+		assert_eq!(Dex::pools(0).unwrap().owner, BOB);
+	});
+}
+
+#[test]
+fn set_pool_fee_should_work() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool_params));
+		let pool_id = 0;
+
+		let fee = Permill::from_percent(1);
+		assert_noop!(
+			Dex::set_pool_fee(Origin::signed(BOB), pool_id, fee),
+			Error::<Test>::NotPoolOwner
+		);
+		assert_noop!(
+			Dex::set_pool_fee(Origin::signed(ALICE), pool_id, Permill::one()),
+			Error::<Test>::InvalidFee
+		);
+
+		assert_ok!(Dex::set_pool_fee(Origin::signed(ALICE), pool_id, fee));
+		assert_eq!(Dex::pools(pool_id).unwrap().fee, fee);
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::PoolFeeUpdated { pool_id, fee: new_fee })
+            if pool_id == 0 && new_fee == fee)
+		});
+
+		let fee = Permill::from_percent(5);
+		assert_ok!(Dex::set_pool_fee(Origin::root(), pool_id, fee));
+		assert_eq!(Dex::pools(pool_id).unwrap().fee, fee);
+	});
+}
+
+#[test]
+fn set_pool_status_should_restrict_operations() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			10_000,
+			10_000
+		));
+		let pool_id = 0;
+
+		assert_noop!(
+			Dex::set_pool_status(Origin::signed(BOB), pool_id, PoolStatus::Paused),
+			Error::<Test>::NotPoolOwner
+		);
+
+		// Only swaps are paused
+		assert_ok!(Dex::set_pool_status(Origin::signed(ALICE), pool_id, PoolStatus::SwapsPaused));
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::PoolStatusUpdated { pool_id, status })
+            if pool_id == 0 && status == PoolStatus::SwapsPaused)
+		});
+		assert_noop!(
+			Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, 100, 0, None),
+			Error::<Test>::SwapsPaused
+		);
+		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), pool_id, 100, ASSET_1, 0, None));
+
+		// Only withdrawals are allowed
+		assert_ok!(Dex::set_pool_status(Origin::root(), pool_id, PoolStatus::Paused));
+		assert_noop!(
+			Dex::buy(Origin::signed(ALICE), pool_id, ASSET_1, 100, Balance::MAX, None),
+			Error::<Test>::PoolPaused
+		);
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(ALICE), pool_id, 100, ASSET_1, 0, None),
+			Error::<Test>::PoolPaused
+		);
+		assert_ok!(Dex::remove_liquidity(Origin::signed(ALICE), pool_id, 100, 0, 0, None));
+
+		assert_ok!(Dex::set_pool_status(Origin::signed(ALICE), pool_id, PoolStatus::Active));
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, 100, 0, None));
+	});
+}
+
+#[test]
+fn transfer_pool_ownership_should_work() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool_params));
+		let pool_id = 0;
+
+		assert_noop!(
+			Dex::transfer_pool_ownership(Origin::signed(BOB), pool_id, BOB),
+			Error::<Test>::NotPoolOwner
+		);
+
+		assert_ok!(Dex::transfer_pool_ownership(Origin::signed(ALICE), pool_id, BOB));
+		assert_eq!(Dex::pools(pool_id).unwrap().owner, BOB);
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::PoolOwnershipTransferred { pool_id, old_owner, new_owner })
+            if pool_id == 0 && old_owner == ALICE && new_owner == BOB)
+		});
+
+		// The previous owner can no longer administrate the pool
+		assert_noop!(
+			Dex::set_pool_fee(Origin::signed(ALICE), pool_id, Permill::from_percent(1)),
+			Error::<Test>::NotPoolOwner
+		);
+		assert_ok!(Dex::set_pool_fee(Origin::signed(BOB), pool_id, Permill::from_percent(1)));
+	});
+}
+
+#[test]
+fn pool_for_pair_should_work() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		let fee = pool_params.fee;
+		assert_eq!(Dex::pool_for_pair(pool_params.pair, fee), None);
+
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool_params));
+
+		assert_eq!(Dex::pool_for_pair(pool_params.pair, fee), Some(0));
+		assert_eq!(Dex::pool_for_pair(pool_params.pair.swap(), fee), Some(0));
+		assert_eq!(Dex::pool_for_pair(pool_params.pair, Permill::from_percent(1)), None);
+		assert_eq!(
+			Dex::pool_for_pair(CurrencyPair { token_a: ASSET_1, token_b: ASSET_3 }, fee),
+			None
+		);
+	});
+}
+
+/// Creates a constant product pool for ASSET_1/ASSET_2 with the given fee.
+fn create_pool_with_fee(fee: Permill) -> PoolIdOf<Test> {
+	let pool_params = PoolCreationParams { fee, ..create_default_pool_params() };
+	let pool_id = Dex::pool_count();
+	assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool_params));
+	pool_id
+}
+
+/// Creates a default pool with the given fee and reserves of `amount` of each asset.
+fn create_funded_pool_with_fee(fee: Permill, amount: Balance) -> PoolIdOf<Test> {
+	let pool_params = PoolCreationParams { fee, ..create_default_pool_params() };
+	let pool_id = Dex::pool_count();
+	assert_ok!(Dex::create_pool_with_liquidity(Origin::signed(ALICE), pool_params, amount, amount));
+	pool_id
+}
+
+#[test]
+fn create_pool_should_work_for_different_fees() {
+	run_test(|| {
+		let pair = create_default_pool_params().pair;
+		let low = create_pool_with_fee(Permill::from_parts(500));
+		let medium = create_pool_with_fee(Permill::from_parts(3_000));
+		let high = create_pool_with_fee(Permill::from_percent(1));
+
+		assert_eq!(Dex::pool_for_pair(pair, Permill::from_parts(500)), Some(low));
+		assert_eq!(Dex::pool_for_pair(pair.swap(), Permill::from_parts(3_000)), Some(medium));
+		assert_eq!(Dex::pool_for_pair(pair, Permill::from_percent(1)), Some(high));
+		let mut pools = Dex::pools_for_pair(pair.swap());
+		pools.sort();
+		assert_eq!(pools, vec![low, medium, high]);
+
+		// Every pool has its own liquidity token
+		assert_ne!(Dex::pools(low).unwrap().lp_token, Dex::pools(medium).unwrap().lp_token);
+
+		let pool_params =
+			PoolCreationParams { fee: Permill::from_percent(2), ..create_default_pool_params() };
+		assert_noop!(
+			Dex::create_pool(Origin::signed(ALICE), pool_params),
+			Error::<Test>::TooManyPoolsForPair
+		);
+	});
+}
+
+#[test]
+fn set_pool_fee_should_update_pair_index() {
+	run_test(|| {
+		let pair = create_default_pool_params().pair;
+		let pool_id = create_pool_with_fee(Permill::from_percent(3));
+		let other_pool_id = create_pool_with_fee(Permill::from_percent(1));
+
+		assert_noop!(
+			Dex::set_pool_fee(Origin::signed(ALICE), pool_id, Permill::from_percent(1)),
+			Error::<Test>::PoolAlreadyExists
+		);
+
+		assert_ok!(Dex::set_pool_fee(Origin::signed(ALICE), pool_id, Permill::from_percent(2)));
+		assert_eq!(Dex::pool_for_pair(pair, Permill::from_percent(2)), Some(pool_id));
+		assert_eq!(Dex::pool_for_pair(pair, Permill::from_percent(3)), None);
+		assert_eq!(Dex::pool_for_pair(pair, Permill::from_percent(1)), Some(other_pool_id));
+
+		// Keeping the fee doesn't change the index
+		assert_ok!(Dex::set_pool_fee(Origin::signed(ALICE), pool_id, Permill::from_percent(2)));
+		assert_eq!(Dex::pool_for_pair(pair, Permill::from_percent(2)), Some(pool_id));
+	});
+}
+
+#[test]
+fn lp_token_should_be_derived_from_pool_id() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		let mut reversed_params = pool_params;
+		reversed_params.pair = CurrencyPair { token_a: ASSET_3, token_b: ASSET_1 };
+
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool_params));
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), reversed_params));
+
+		assert_eq!(Dex::lp_token(0), Ok(CurrencyId::LpToken(0)));
+		assert_eq!(Dex::lp_token(1), Ok(CurrencyId::LpToken(1)));
+		assert_eq!(Dex::pool_of_lp_token(CurrencyId::LpToken(1)), Some(1));
+		assert_eq!(Dex::pool_of_lp_token(CurrencyId::LpToken(2)), None);
+		assert_eq!(Dex::pool_of_lp_token(ASSET_1), None);
+		// The pool keeps the pair in the order it was created with
+		assert_eq!(Dex::pools(1).unwrap().pair.token_a, ASSET_3);
+	});
+}
+
+#[test]
+fn create_pool_should_work_for_lp_tokens() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool_params));
+
+		// Liquidity tokens aren't registered, only those of existing pools can be traded
+		let mut lp_params = pool_params;
+		lp_params.pair = CurrencyPair { token_a: CurrencyId::LpToken(1), token_b: ASSET_1 };
+		assert_noop!(
+			Dex::create_pool(Origin::signed(ALICE), lp_params),
+			Error::<Test>::AssetNotRegistered
+		);
+
+		lp_params.pair.token_a = CurrencyId::LpToken(0);
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), lp_params));
+		assert_eq!(Dex::lp_token(1), Ok(CurrencyId::LpToken(1)));
+	});
+}
+
 #[test]
 fn add_liquidity_should_work() {
 	run_test(|| {
 		let pool = create_default_pool_params();
 
-		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool));
+		assert_ok!(Dex::create_pool_with_liquidity(Origin::signed(ALICE), pool, 1_000, 1_000));
 
 		let pool_id = 0;
 		let pool = Dex::pools(pool_id).unwrap();
+		let user_lp_pre_deposit = Tokens::free_balance(pool.lp_token, &ALICE);
 
 		let user_balance_1_pre_deposit = Tokens::free_balance(ASSET_1, &ALICE);
 		let user_balance_2_pre_deposit = Tokens::free_balance(ASSET_2, &ALICE);
@@ -98,7 +414,7 @@ fn add_liquidity_should_work() {
 		let amount = 100;
 		let asset = ASSET_1;
 		// Add liquidity to pool
-		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), pool_id, amount, asset));
+		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), pool_id, amount, asset, 0, None));
 
 		// Expect user balance to be reduced by amount
 		assert_eq!(user_balance_1_pre_deposit - amount, Tokens::free_balance(ASSET_1, &ALICE));
@@ -113,10 +429,13 @@ fn add_liquidity_should_work() {
 			Tokens::free_balance(ASSET_2, &pool_account)
 		);
 
-		// LP of initial deposit will be sqrt(amount_a*amount_b)
+		// LP is minted in proportion to the share of the reserves that was added
 		let expected_minted_lp = 100u128;
-		// Expect Alice to now have 100 LP tokens
-		assert_eq!(Tokens::free_balance(pool.lp_token, &ALICE), expected_minted_lp);
+		// Expect Alice to now have 100 more LP tokens
+		assert_eq!(
+			Tokens::free_balance(pool.lp_token, &ALICE),
+			user_lp_pre_deposit + expected_minted_lp
+		);
 
 		assert_last_event::<Test, _>(|e| {
 			matches!(e.event,
@@ -136,7 +455,7 @@ fn add_liquidity_should_fail_with_invalid_asset() {
 		let amount = 100;
 		let invalid_asset: AssetIdOf<Test> = CurrencyId::Token(TokenSymbol::Short([u8::MAX; 4]));
 		assert_noop!(
-			Dex::add_liquidity(Origin::signed(ALICE), pool_id, amount, invalid_asset),
+			Dex::add_liquidity(Origin::signed(ALICE), pool_id, amount, invalid_asset, 0, None),
 			Error::<Test>::InvalidAsset
 		);
 	})
@@ -151,31 +470,172 @@ fn add_liquidity_should_fail_without_balance() {
 			CurrencyId::Token(TokenSymbol::Short([u8::MAX; 4]));
 		pool_params.pair.token_a = asset_without_balance;
 
-		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool_params));
-		let pool_id = 0;
 		let amount = 100;
 		assert_noop!(
-			Dex::add_liquidity(Origin::signed(ALICE), pool_id, amount, asset_without_balance),
+			Dex::create_pool_with_liquidity(Origin::signed(ALICE), pool_params, amount, amount),
 			Error::<Test>::InsufficientBalance
 		);
 	})
 }
 
+#[test]
+fn add_liquidity_should_fail_for_empty_pool() {
+	run_test(|| {
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), create_default_pool_params()));
+		let pool_id = 0;
+
+		// The price of the pool is set by the first deposit, which needs both amounts
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(ALICE), pool_id, 1_000, ASSET_1, 0, None),
+			Error::<Test>::EmptyPool
+		);
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(ALICE), pool_id, 1_000, ASSET_2, 0, None),
+			Error::<Test>::EmptyPool
+		);
+	})
+}
+
+#[test]
+fn create_pool_with_liquidity_should_work() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			1_000,
+			4_000
+		));
+
+		let pool_id = 0;
+		let pool = Dex::pools(pool_id).unwrap();
+		let pool_account = Dex::pool_accounts(pool_id).unwrap();
+		assert_eq!(Tokens::free_balance(ASSET_1, &pool_account), 1_000);
+		assert_eq!(Tokens::free_balance(ASSET_2, &pool_account), 4_000);
+		// sqrt(1_000 * 4_000)
+		assert_eq!(Tokens::free_balance(pool.lp_token, &ALICE), 2_000);
+		// The deposit sets the initial price of the pool
+		assert_eq!(Dex::get_exchange_value(pool_id, ASSET_1, 100), Ok(400));
+
+		assert_has_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::PoolCreated { owner, pool_id, .. })
+            if owner == ALICE && pool_id == 0)
+		});
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::LiquidityAdded {who, pool_id, amount_a, amount_b, minted_lp})
+            if who == ALICE && pool_id == 0 && amount_a == 1_000 && amount_b == 4_000 && minted_lp == 2_000)
+		});
+	});
+}
+
+#[test]
+fn create_pool_with_liquidity_should_fail() {
+	run_test(|| {
+		let mut pool_params = create_default_pool_params();
+		pool_params.owner = BOB;
+		assert_noop!(
+			Dex::create_pool_with_liquidity(Origin::signed(ALICE), pool_params, 1_000, 1_000),
+			Error::<Test>::OwnerNotSigner
+		);
+
+		// Nothing is created if the deposit fails
+		let pool_params = create_default_pool_params();
+		assert_noop!(
+			Dex::create_pool_with_liquidity(Origin::signed(ALICE), pool_params, 1_000, 2_000_000),
+			Error::<Test>::InsufficientBalance
+		);
+
+		let pool_params = create_concentrated_pool_params(10);
+		assert_noop!(
+			Dex::create_pool_with_liquidity(Origin::signed(ALICE), pool_params, 1_000, 1_000),
+			Error::<Test>::UnsupportedPoolKind
+		);
+	});
+}
+
+#[test]
+fn first_deposit_should_lock_minimum_liquidity() {
+	run_test(|| {
+		MinimumLiquidity::set(1_000);
+		let pool_params = create_default_pool_params();
+		assert_noop!(
+			Dex::create_pool_with_liquidity(
+				Origin::signed(ALICE),
+				pool_params.clone(),
+				1_000,
+				1_000
+			),
+			Error::<Test>::InsufficientInitialLiquidity
+		);
+
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			10_000,
+			10_000
+		));
+		let pool_id = 0;
+		let pool = Dex::pools(pool_id).unwrap();
+		assert_eq!(Tokens::free_balance(pool.lp_token, &Dex::dead_account()), 1_000);
+		assert_eq!(Tokens::free_balance(pool.lp_token, &ALICE), 9_000);
+		assert_eq!(Tokens::total_issuance(pool.lp_token), 10_000);
+
+		// The locked liquidity stays in the pool when everything else is withdrawn
+		assert_ok!(Dex::remove_liquidity(Origin::signed(ALICE), pool_id, 9_000, 0, 0, None));
+		let pool_account = Dex::pool_accounts(pool_id).unwrap();
+		assert_eq!(Tokens::free_balance(ASSET_1, &pool_account), 1_000);
+		assert_eq!(Tokens::free_balance(ASSET_2, &pool_account), 1_000);
+
+		// Later deposits don't lock anything
+		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), pool_id, 1_000, ASSET_1, 0, None));
+		assert_eq!(Tokens::free_balance(pool.lp_token, &Dex::dead_account()), 1_000);
+		assert_eq!(Tokens::free_balance(pool.lp_token, &ALICE), 1_000);
+	});
+}
+
+#[test]
+fn add_liquidity_should_round_in_favour_of_pool() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			3_000,
+			10_000
+		));
+		let pool_id = 0;
+
+		// 10 * 10_000 / 3_000 = 33.3 is rounded up
+		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), pool_id, 10, ASSET_1, 0, None));
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::LiquidityAdded {amount_a, amount_b, .. })
+            if amount_a == 10 && amount_b == 34)
+		});
+
+		// 10 * 3_010 / 10_034 = 2.99 is rounded up
+		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), pool_id, 10, ASSET_2, 0, None));
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::LiquidityAdded {amount_a, amount_b, .. })
+            if amount_a == 3 && amount_b == 10)
+		});
+	});
+}
+
 #[test]
 fn remove_liquidity_with_too_high_amount_should_fail() {
 	run_test(|| {
 		let pool = create_default_pool_params();
 
-		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool));
-		let pool_id = 0;
-		let amount = 100;
-		let asset = ASSET_1;
-		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), pool_id, amount, asset));
+		assert_ok!(Dex::create_pool_with_liquidity(Origin::signed(ALICE), pool, 100, 100));
 
 		let pool_id = 0;
 		let amount = 10_000;
 		assert_noop!(
-			Dex::remove_liquidity(Origin::signed(ALICE), pool_id, amount),
+			Dex::remove_liquidity(Origin::signed(ALICE), pool_id, amount, 0, 0, None),
 			Error::<Test>::InsufficientLiquidityBalance
 		);
 	});
@@ -190,7 +650,7 @@ fn remove_liquidity_without_supply_should_fail() {
 		let pool_id = 0;
 		let amount = 100;
 		assert_noop!(
-			Dex::remove_liquidity(Origin::signed(ALICE), pool_id, amount),
+			Dex::remove_liquidity(Origin::signed(ALICE), pool_id, amount, 0, 0, None),
 			Error::<Test>::WithdrawWithoutSupply
 		);
 	});
@@ -201,11 +661,17 @@ fn add_and_remove_liquidity_should_work() {
 	run_test(|| {
 		let pool_params = create_default_pool_params();
 
-		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool_params));
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			1_000,
+			1_000
+		));
 		// pool_id will be 0
 		let pool_id = 0;
 
 		let pool: PoolOf<Test> = Dex::pools(pool_id).unwrap();
+		let user_lp_pre_deposit = Tokens::free_balance(pool.lp_token, &ALICE);
 
 		let user_balance_1_pre_deposit = Tokens::free_balance(ASSET_1, &ALICE);
 		let user_balance_2_pre_deposit = Tokens::free_balance(ASSET_2, &ALICE);
@@ -216,7 +682,7 @@ fn add_and_remove_liquidity_should_work() {
 
 		let amount = 100;
 		let asset = ASSET_1;
-		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), pool_id, amount, asset));
+		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), pool_id, amount, asset, 0, None));
 
 		// Expect user balance to be reduced by amount
 		assert_eq!(user_balance_1_pre_deposit - amount, Tokens::free_balance(ASSET_1, &ALICE));
@@ -231,10 +697,13 @@ fn add_and_remove_liquidity_should_work() {
 			Tokens::free_balance(ASSET_2, &pool_account)
 		);
 
-		// LP of initial deposit will be sqrt(amount_a*amount_b) -> sqrt(100*100) = 100
+		// LP is minted in proportion to the added share -> 100 * 1_000 / 1_000 = 100
 		let expected_minted_lp = 100u128;
-		// Expect Alice to now have 100 LP tokens
-		assert_eq!(Tokens::free_balance(pool.lp_token, &ALICE), expected_minted_lp);
+		// Expect Alice to now have 100 more LP tokens
+		assert_eq!(
+			Tokens::free_balance(pool.lp_token, &ALICE),
+			user_lp_pre_deposit + expected_minted_lp
+		);
 
 		assert_last_event::<Test, _>(|e| {
 			matches!(e.event,
@@ -242,7 +711,7 @@ fn add_and_remove_liquidity_should_work() {
             if who == ALICE && pool_id == pool_id && amount_a == amount && amount_b == amount && minted_lp == expected_minted_lp)
 		});
 
-		// Withdraw all LP
+		// Withdraw the minted LP
 		let user_balance_1_pre_withdraw = Tokens::free_balance(ASSET_1, &ALICE);
 		let user_balance_2_pre_withdraw = Tokens::free_balance(ASSET_2, &ALICE);
 
@@ -250,7 +719,7 @@ fn add_and_remove_liquidity_should_work() {
 		let pool_balance_2_pre_withdraw = Tokens::free_balance(ASSET_2, &pool_account);
 
 		let amount = expected_minted_lp;
-		assert_ok!(Dex::remove_liquidity(Origin::signed(ALICE), pool_id, amount));
+		assert_ok!(Dex::remove_liquidity(Origin::signed(ALICE), pool_id, amount, 0, 0, None));
 
 		// The received amount of the other tokens happens to be the same as the minted LP
 		let expected_amount_a = amount;
@@ -276,7 +745,7 @@ fn add_and_remove_liquidity_should_work() {
 		assert_last_event::<Test, _>(|e| {
 			matches!(e.event,
             mock::Event::Dex(crate::Event::LiquidityRemoved {who, pool_id, amount_a, amount_b, total_issuance})
-            if who == ALICE && pool_id == pool_id && amount_a == expected_amount_a && amount_b == expected_amount_b && total_issuance == 0)
+            if who == ALICE && pool_id == pool_id && amount_a == expected_amount_a && amount_b == expected_amount_b && total_issuance == user_lp_pre_deposit)
 		});
 	});
 }
@@ -285,20 +754,21 @@ fn add_and_remove_liquidity_should_work() {
 fn sell_should_work() {
 	run_test(|| {
 		let pool_params = create_default_pool_params();
-		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool_params));
-
 		// Add liquidity to pool
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			100_000,
+			100_000
+		));
 		let pool_id = 0;
-		let amount = 100_000;
-		let asset = ASSET_1;
-		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), pool_id, amount, asset));
 
 		let balance_1_pre_swap = Tokens::free_balance(ASSET_1, &ALICE);
 		let balance_2_pre_swap = Tokens::free_balance(ASSET_2, &ALICE);
 
 		let asset = ASSET_1;
 		let amount_to_sell = 100;
-		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, asset, amount_to_sell));
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, asset, amount_to_sell, 0, None));
 
 		// Expect to spend `amount_to_sell` tokens of token_a
 		assert_eq!(balance_1_pre_swap - amount_to_sell, Tokens::free_balance(ASSET_1, &ALICE));
@@ -320,9 +790,10 @@ fn sell_should_work() {
 
 		assert_last_event::<Test, _>(|e| {
 			matches!(e.event,
-            mock::Event::Dex(crate::Event::Swapped {who, pool_id, amount_a: _, amount_b, token_a, token_b, fee})
+            mock::Event::Dex(crate::Event::Swapped {who, pool_id, amount_a: _, amount_b, token_a, token_b, fee, estimated_protocol_fee})
             if who == ALICE && pool_id == pool_id && amount_b == amount_to_sell &&
-			token_a == ASSET_2 && token_b == ASSET_1 && fee == pool_params.fee)
+			token_a == ASSET_2 && token_b == ASSET_1 && fee == pool_params.fee.mul_floor(amount_to_sell) &&
+			estimated_protocol_fee == 0)
 		});
 	});
 }
@@ -331,20 +802,28 @@ fn sell_should_work() {
 fn buy_should_work() {
 	run_test(|| {
 		let pool_params = create_default_pool_params();
-		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool_params));
-
 		// Add liquidity to pool
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			100_000,
+			100_000
+		));
 		let pool_id = 0;
-		let amount = 100_000;
-		let asset = ASSET_1;
-		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), pool_id, amount, asset));
 
 		let balance_1_pre_swap = Tokens::free_balance(ASSET_1, &ALICE);
 		let balance_2_pre_swap = Tokens::free_balance(ASSET_2, &ALICE);
 
 		let asset = ASSET_1;
 		let amount_to_receive = 100;
-		assert_ok!(Dex::buy(Origin::signed(ALICE), pool_id, asset, amount_to_receive));
+		assert_ok!(Dex::buy(
+			Origin::signed(ALICE),
+			pool_id,
+			asset,
+			amount_to_receive,
+			Balance::MAX,
+			None
+		));
 
 		// Expect to receive `amount_to_receive` tokens of token_a
 		assert_eq!(balance_1_pre_swap + amount_to_receive, Tokens::free_balance(ASSET_1, &ALICE));
@@ -366,9 +845,2346 @@ fn buy_should_work() {
 
 		assert_last_event::<Test, _>(|e| {
 			matches!(e.event,
-            mock::Event::Dex(crate::Event::Swapped {who, pool_id, amount_a, amount_b: _, token_a, token_b, fee})
+            mock::Event::Dex(crate::Event::Swapped {who, pool_id, amount_a, amount_b, token_a, token_b, fee, estimated_protocol_fee})
             if who == ALICE && pool_id == pool_id && amount_a == amount_to_receive &&
-			token_a == ASSET_1 && token_b == ASSET_2 && fee == pool_params.fee)
+			token_a == ASSET_1 && token_b == ASSET_2 && fee == pool_params.fee.mul_floor(amount_b) &&
+			estimated_protocol_fee == 0)
 		});
 	});
 }
+
+/// Creates the default pool with 100_000 of each asset and trades back and forth so that fees
+/// accrue in the pool.
+fn create_pool_with_accrued_fees() -> PoolIdOf<Test> {
+	let pool_params = create_default_pool_params();
+	assert_ok!(Dex::create_pool_with_liquidity(
+		Origin::signed(ALICE),
+		pool_params,
+		100_000,
+		100_000
+	));
+
+	let pool_id = 0;
+	assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, 10_000, 0, None));
+	assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_2, 10_000, 0, None));
+	pool_id
+}
+
+#[test]
+fn protocol_fee_should_be_minted_to_receiver() {
+	run_test(|| {
+		let share = Permill::from_parts(166_667);
+		ProtocolFeeShare::set(share);
+		ProtocolFeeReceiver::set(Some(DARWIN));
+
+		let pool_id = create_pool_with_accrued_fees();
+		let lp_token = Dex::lp_token(pool_id).unwrap();
+		let invariant_last = Dex::invariant_last(pool_id);
+		assert_eq!(invariant_last, 100_000);
+
+		// The swap event reports an estimate of the part of the fee that accrues to the protocol
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::Swapped {fee, estimated_protocol_fee, ..})
+            if fee == 300 && estimated_protocol_fee == share.mul_floor(fee))
+		});
+
+		let (reserve_a, reserve_b) = Dex::pool_reserves(pool_id).unwrap();
+		let expected_fee = calc::get_protocol_fee_liquidity(
+			Tokens::total_issuance(lp_token),
+			calc::get_root_k(reserve_a, reserve_b),
+			invariant_last,
+			share,
+		)
+		.unwrap();
+		assert!(expected_fee > 0);
+
+		// The fee is minted on the next liquidity event
+		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), pool_id, 100, ASSET_1, 0, None));
+		assert_eq!(Tokens::free_balance(lp_token, &DARWIN), expected_fee);
+		assert_has_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::ProtocolFeeMinted {pool_id, receiver, amount})
+            if pool_id == 0 && receiver == DARWIN && amount == expected_fee)
+		});
+
+		// `√k` is tracked again from the new reserves
+		let (reserve_a, reserve_b) = Dex::pool_reserves(pool_id).unwrap();
+		assert_eq!(Dex::invariant_last(pool_id), calc::get_root_k(reserve_a, reserve_b));
+	});
+}
+
+#[test]
+fn protocol_fee_should_default_to_pool_owner() {
+	run_test(|| {
+		ProtocolFeeShare::set(Permill::from_parts(166_667));
+
+		let pool_id = create_pool_with_accrued_fees();
+		let lp_token = Dex::lp_token(pool_id).unwrap();
+		let lp_balance_pre_withdrawal = Tokens::free_balance(lp_token, &ALICE);
+
+		assert_ok!(Dex::remove_liquidity(Origin::signed(ALICE), pool_id, 1_000, 0, 0, None));
+
+		let minted_fee = System::events()
+			.into_iter()
+			.find_map(|e| match e.event {
+				mock::Event::Dex(crate::Event::ProtocolFeeMinted { receiver, amount, .. })
+					if receiver == ALICE =>
+					Some(amount),
+				_ => None,
+			})
+			.expect("protocol fee should be minted to the pool owner");
+		assert!(minted_fee > 0);
+		assert_eq!(
+			Tokens::free_balance(lp_token, &ALICE),
+			lp_balance_pre_withdrawal + minted_fee - 1_000
+		);
+	});
+}
+
+#[test]
+fn protocol_fee_should_not_be_minted_when_switched_off() {
+	run_test(|| {
+		let pool_id = create_pool_with_accrued_fees();
+		let lp_token = Dex::lp_token(pool_id).unwrap();
+		assert_eq!(Dex::invariant_last(pool_id), 0);
+
+		let total_issuance = Tokens::total_issuance(lp_token);
+		assert_ok!(Dex::remove_liquidity(Origin::signed(ALICE), pool_id, 1_000, 0, 0, None));
+		assert_eq!(Tokens::total_issuance(lp_token), total_issuance - 1_000);
+
+		assert!(!System::events()
+			.iter()
+			.any(|e| matches!(e.event, mock::Event::Dex(crate::Event::ProtocolFeeMinted { .. }))));
+	});
+}
+
+#[test]
+fn price_cumulatives_should_accumulate_on_reserve_changes() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			100_000,
+			100_000
+		));
+		let pool_id = 0;
+		// The first deposit records the first observation
+		assert_eq!(Dex::price_cumulatives(pool_id).block_number, 1);
+		assert_eq!(Dex::observation_index(pool_id), Some(0));
+
+		// Nothing is recorded in blocks that don't change the reserves
+		run_to_block(3);
+		assert_eq!(Dex::price_cumulatives(pool_id).block_number, 1);
+		assert_eq!(Dex::observation_index(pool_id), Some(0));
+
+		// The price of both assets was 1 for two blocks
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, 100, 0, None));
+		let cumulatives = Dex::price_cumulatives(pool_id);
+		assert_eq!(cumulatives.block_number, 3);
+		assert_eq!(cumulatives.price_a_cumulative, 2 * FixedU128::accuracy());
+		assert_eq!(cumulatives.price_b_cumulative, 2 * FixedU128::accuracy());
+		assert_eq!(Dex::observation_index(pool_id), Some(1));
+		assert_eq!(Dex::observations(pool_id, 1), Some(cumulatives));
+
+		// Later changes in the same block don't record another observation
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, 100, 0, None));
+		assert_eq!(Dex::price_cumulatives(pool_id), cumulatives);
+		assert_eq!(Dex::observation_index(pool_id), Some(1));
+	});
+}
+
+#[test]
+fn twap_should_average_prices_over_window() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			100_000,
+			100_000
+		));
+		let pool_id = 0;
+
+		run_to_block(11);
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, 10_000, 0, None));
+		let (reserve_a, reserve_b) = Dex::pool_reserves(pool_id).unwrap();
+		let price_a = FixedU128::saturating_from_rational(reserve_b, reserve_a);
+		let price_b = FixedU128::saturating_from_rational(reserve_a, reserve_b);
+
+		// The swap doesn't affect the average price within the same block
+		assert_eq!(Dex::twap(pool_id, ASSET_1, 5), Ok(FixedU128::one()));
+
+		run_to_block(21);
+
+		// Only the new price was valid during the last 10 blocks
+		assert_eq!(Dex::twap(pool_id, ASSET_1, 10), Ok(price_a));
+		assert_eq!(Dex::twap(pool_id, ASSET_2, 10), Ok(price_b));
+
+		// 5 blocks at the old price and 10 blocks at the new price, the start of the window lies
+		// between the observations of blocks 1 and 11
+		let expected =
+			FixedU128::from_inner((5 * FixedU128::accuracy() + 10 * price_a.into_inner()) / 15);
+		assert_eq!(Dex::twap(pool_id, ASSET_1, 15), Ok(expected));
+
+		// The window can't start before the first observation
+		let expected =
+			FixedU128::from_inner((10 * FixedU128::accuracy() + 10 * price_a.into_inner()) / 20);
+		assert_eq!(Dex::twap(pool_id, ASSET_1, 20), Ok(expected));
+		assert_noop!(Dex::twap(pool_id, ASSET_1, 21), Error::<Test>::InsufficientObservations);
+	});
+}
+
+#[test]
+fn twap_should_fail_for_overwritten_observations() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			100_000,
+			100_000
+		));
+		let pool_id = 0;
+
+		// Every block with a swap records an observation, only the last 16 are kept
+		for n in 2..=17 {
+			run_to_block(n);
+			assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, 100, 0, None));
+		}
+		assert_eq!(Dex::observation_index(pool_id), Some(0));
+		assert_eq!(Dex::observations(pool_id, 1).unwrap().block_number, 2);
+
+		run_to_block(20);
+		assert_ok!(Dex::twap(pool_id, ASSET_1, 18));
+		assert_noop!(Dex::twap(pool_id, ASSET_1, 19), Error::<Test>::InsufficientObservations);
+	});
+}
+
+#[test]
+fn twap_should_fail_with_invalid_params() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			100_000,
+			100_000
+		));
+		let pool_id = 0;
+		run_to_block(5);
+
+		assert_noop!(Dex::twap(pool_id, ASSET_1, 0), Error::<Test>::InvalidWindow);
+		assert_noop!(Dex::twap(pool_id, ASSET_3, 2), Error::<Test>::InvalidAsset);
+		assert_noop!(Dex::twap(1, ASSET_1, 2), Error::<Test>::PoolNotFound);
+		// The pool was created in block 1
+		assert_noop!(Dex::twap(pool_id, ASSET_1, 5), Error::<Test>::InsufficientObservations);
+	});
+}
+
+fn create_stable_pool_params(amplification: u32) -> PoolCreationParamsOf<Test> {
+	let mut pool_params = create_default_pool_params();
+	pool_params.kind = PoolKind::StableSwap { amplification };
+	pool_params
+}
+
+#[test]
+fn create_stable_pool_should_fail_with_invalid_amplification() {
+	run_test(|| {
+		assert_noop!(
+			Dex::create_pool(Origin::signed(ALICE), create_stable_pool_params(0)),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_noop!(
+			Dex::create_pool(
+				Origin::signed(ALICE),
+				create_stable_pool_params(calc::MAX_AMPLIFICATION + 1)
+			),
+			Error::<Test>::InvalidAmplification
+		);
+
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), create_stable_pool_params(100)));
+		assert_eq!(Dex::pools(0).unwrap().kind, PoolKind::StableSwap { amplification: 100 });
+	});
+}
+
+#[test]
+fn stable_pool_should_have_less_slippage() {
+	run_test(|| {
+		// Constant product pool for ASSET_1/ASSET_2
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			create_default_pool_params(),
+			100_000,
+			100_000
+		));
+		// StableSwap pool for ASSET_1/ASSET_3 with the same fee
+		let mut stable_params = create_stable_pool_params(100);
+		stable_params.pair = CurrencyPair { token_a: ASSET_1, token_b: ASSET_3 };
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			stable_params,
+			100_000,
+			100_000
+		));
+
+		let (constant_product_pool, stable_pool) = (0, 1);
+
+		let amount = 10_000;
+		let constant_product_out = Dex::quote_sell(constant_product_pool, ASSET_1, amount).unwrap();
+		let stable_out = Dex::quote_sell(stable_pool, ASSET_1, amount).unwrap();
+		assert_eq!(constant_product_out, 8842);
+		// The StableSwap curve is almost flat close to the peg, so only the fee is lost
+		assert_ok!(assert_with_computation_error(9695, stable_out, DEFAULT_EPSILON));
+		assert!(stable_out > constant_product_out);
+		assert!(stable_out < amount - stable_params.fee.mul_floor(amount));
+	});
+}
+
+#[test]
+fn stable_pool_should_work() {
+	run_test(|| {
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			create_stable_pool_params(100),
+			100_000,
+			100_000
+		));
+		let pool_id = 0;
+		let lp_token = Dex::lp_token(pool_id).unwrap();
+
+		// The initial liquidity is the invariant `D`, which is the sum of balanced reserves
+		assert_eq!(Tokens::free_balance(lp_token, &ALICE), 200_000);
+
+		// Selling pays out the quoted amount
+		let balance_2_pre_swap = Tokens::free_balance(ASSET_2, &ALICE);
+		let expected_amount = Dex::quote_sell(pool_id, ASSET_1, 1_000).unwrap();
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, 1_000, 0, None));
+		assert_eq!(Tokens::free_balance(ASSET_2, &ALICE), balance_2_pre_swap + expected_amount);
+
+		// Buying pays out at least the requested amount for the quoted price
+		let balance_1_pre_swap = Tokens::free_balance(ASSET_1, &ALICE);
+		let balance_2_pre_swap = Tokens::free_balance(ASSET_2, &ALICE);
+		let expected_price = Dex::quote_buy(pool_id, ASSET_1, 1_000).unwrap();
+		assert_ok!(Dex::buy(Origin::signed(ALICE), pool_id, ASSET_1, 1_000, expected_price, None));
+		assert_eq!(Tokens::free_balance(ASSET_2, &ALICE), balance_2_pre_swap - expected_price);
+		assert_ok!(assert_with_computation_error(
+			balance_1_pre_swap + 1_000,
+			Tokens::free_balance(ASSET_1, &ALICE),
+			DEFAULT_EPSILON
+		));
+		assert!(Tokens::free_balance(ASSET_1, &ALICE) >= balance_1_pre_swap + 1_000);
+
+		// Withdrawals are proportional to the reserves
+		let (reserve_a, reserve_b) = Dex::pool_reserves(pool_id).unwrap();
+		assert_ok!(Dex::remove_liquidity(Origin::signed(ALICE), pool_id, 100_000, 0, 0, None));
+		assert_eq!(
+			Dex::pool_reserves(pool_id).unwrap(),
+			(reserve_a - reserve_a / 2, reserve_b - reserve_b / 2)
+		);
+	});
+}
+
+fn create_weighted_pool_params(weight_a: u32, weight_b: u32) -> PoolCreationParamsOf<Test> {
+	let mut pool_params = create_default_pool_params();
+	pool_params.kind = PoolKind::Weighted {
+		weight_a: Permill::from_percent(weight_a),
+		weight_b: Permill::from_percent(weight_b),
+	};
+	pool_params
+}
+
+#[test]
+fn create_weighted_pool_should_fail_with_invalid_weights() {
+	run_test(|| {
+		for (weight_a, weight_b) in [(50, 40), (60, 50), (100, 0), (0, 100)] {
+			assert_noop!(
+				Dex::create_pool(
+					Origin::signed(ALICE),
+					create_weighted_pool_params(weight_a, weight_b)
+				),
+				Error::<Test>::InvalidWeights
+			);
+		}
+
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), create_weighted_pool_params(80, 20)));
+	});
+}
+
+#[test]
+fn weighted_invariant_should_work() {
+	let half = Permill::from_percent(50);
+	assert_eq!(calc::get_weighted_invariant(1_600, 100, half, half), Some(400));
+	assert_eq!(calc::get_weighted_invariant(100_000, 100_000, half, half), Some(100_000));
+
+	let (heavy, light) = (Permill::from_percent(80), Permill::from_percent(20));
+	assert_ok!(assert_with_computation_error(
+		918,
+		calc::get_weighted_invariant(1_600, 100, heavy, light).unwrap(),
+		1
+	));
+	assert_eq!(
+		calc::get_weighted_invariant(1_600, 100, heavy, light),
+		calc::get_weighted_invariant(100, 1_600, light, heavy)
+	);
+	assert_eq!(calc::get_weighted_invariant(0, 100, heavy, light), Some(0));
+}
+
+#[test]
+fn amm_math_should_keep_full_fee_precision() {
+	// Fees are not rounded to per-mille, so a fee of 0.05% is still charged
+	let fee = Permill::from_parts(500);
+	let reserve = 1_000_000_000;
+	assert_eq!(
+		calc::get_amount_out::<Test>(1_000_000, reserve, reserve, Permill::from_percent(0)),
+		Ok(999_000)
+	);
+	assert_eq!(calc::get_amount_out::<Test>(1_000_000, reserve, reserve, fee), Ok(998_501));
+	assert_eq!(calc::get_fee_amount(1_000_000, fee), 500);
+}
+
+#[test]
+fn amm_math_should_round_in_favour_of_pool() {
+	let fee = Permill::from_percent(0);
+	assert_eq!(calc::get_amount_out::<Test>(1_000, 1_000, 1_000, fee), Ok(500));
+	assert_eq!(calc::get_amount_out::<Test>(999, 1_000, 1_000, fee), Ok(499));
+	// The amount in is only rounded up if the division isn't exact
+	assert_eq!(calc::get_amount_in::<Test>(500, 1_000, 1_000, fee), Ok(1_000));
+	assert_eq!(calc::get_amount_in::<Test>(499, 1_000, 1_000, fee), Ok(997));
+
+	assert_eq!(calc::get_deposit_amount(100, 300, 100), Ok(34));
+	assert_eq!(calc::get_liquidity_minted(100, 300, 100), Ok(33));
+	assert_eq!(calc::get_withdrawal_amount(100, 100, 300), Ok(33));
+}
+
+#[test]
+fn amm_math_should_not_saturate() {
+	let fee = Permill::from_percent(0);
+	let reserve = u128::MAX / 2;
+	assert_eq!(calc::get_amount_out::<Test>(reserve, reserve, reserve, fee), Ok(reserve / 2));
+	assert_eq!(calc::get_amount_in::<Test>(reserve / 2, reserve, reserve, fee), Ok(reserve - 1));
+	assert_eq!(
+		calc::get_amount_in::<Test>(reserve, reserve, reserve, fee),
+		Err(Error::<Test>::InsufficientLiquidity.into())
+	);
+	assert_eq!(calc::get_root_k(u128::MAX, u128::MAX), u128::MAX);
+
+	assert_eq!(calc::checked_mul_div(u128::MAX, u128::MAX, u128::MAX, false), Ok(u128::MAX));
+	assert_eq!(calc::checked_mul_div(u128::MAX, 2, 1, false), Err(ArithmeticError::Overflow));
+	assert_eq!(calc::checked_mul_div(1, 1, 0, false), Err(ArithmeticError::DivisionByZero));
+}
+
+#[test]
+fn weighted_pool_should_work() {
+	run_test(|| {
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			create_weighted_pool_params(80, 20),
+			100_000,
+			100_000
+		));
+		let pool_id = 0;
+		let lp_token = Dex::lp_token(pool_id).unwrap();
+
+		// The initial liquidity is the invariant, which equals the reserves if they are balanced
+		assert_ok!(assert_with_computation_error(
+			100_000,
+			Tokens::free_balance(lp_token, &ALICE),
+			DEFAULT_EPSILON
+		));
+
+		// ASSET_1 holds 80% of the value of the pool, so it is worth four times as much as ASSET_2
+		let amount_out = Dex::quote_sell(pool_id, ASSET_1, 1_000).unwrap();
+		assert_ok!(assert_with_computation_error(3787, amount_out, DEFAULT_EPSILON));
+
+		let balance_2_pre_swap = Tokens::free_balance(ASSET_2, &ALICE);
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, 1_000, 0, None));
+		assert_eq!(Tokens::free_balance(ASSET_2, &ALICE), balance_2_pre_swap + amount_out);
+
+		// Buying pays out at least the requested amount for the quoted price
+		let balance_1_pre_swap = Tokens::free_balance(ASSET_1, &ALICE);
+		let balance_2_pre_swap = Tokens::free_balance(ASSET_2, &ALICE);
+		let expected_price = Dex::quote_buy(pool_id, ASSET_2, 1_000).unwrap();
+		assert!(expected_price < 1_000);
+		assert_ok!(Dex::buy(Origin::signed(ALICE), pool_id, ASSET_2, 1_000, expected_price, None));
+		assert_eq!(Tokens::free_balance(ASSET_1, &ALICE), balance_1_pre_swap - expected_price);
+		assert!(Tokens::free_balance(ASSET_2, &ALICE) >= balance_2_pre_swap + 1_000);
+	});
+}
+
+#[test]
+fn weighted_pool_should_limit_trade_size() {
+	run_test(|| {
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			create_weighted_pool_params(50, 50),
+			100_000,
+			100_000
+		));
+		let pool_id = 0;
+
+		assert_noop!(Dex::quote_sell(pool_id, ASSET_1, 50_001), Error::<Test>::MaxInRatioExceeded);
+		assert_noop!(Dex::quote_buy(pool_id, ASSET_1, 33_334), Error::<Test>::MaxOutRatioExceeded);
+
+		// Equal weights price like a constant product pool
+		assert_ok!(assert_with_computation_error(
+			960,
+			Dex::quote_sell(pool_id, ASSET_1, 1_000).unwrap(),
+			DEFAULT_EPSILON
+		));
+	});
+}
+
+fn create_multi_asset_pool() -> PoolIdOf<Test> {
+	let assets = BoundedVec::truncate_from(vec![ASSET_1, ASSET_2, ASSET_3]);
+	assert_ok!(Dex::create_multi_asset_pool(
+		Origin::signed(ALICE),
+		ALICE,
+		assets,
+		Permill::from_percent(3)
+	));
+	Dex::pool_count() - 1
+}
+
+/// Creates an ASSET_1/ASSET_2/ASSET_3 pool that holds 100_000 of every asset.
+fn create_funded_multi_asset_pool() -> PoolIdOf<Test> {
+	let assets = BoundedVec::truncate_from(vec![ASSET_1, ASSET_2, ASSET_3]);
+	assert_ok!(Dex::create_multi_asset_pool_with_liquidity(
+		Origin::signed(ALICE),
+		assets,
+		Permill::from_percent(3),
+		BoundedVec::truncate_from(vec![100_000; 3])
+	));
+	Dex::pool_count() - 1
+}
+
+#[test]
+fn create_multi_asset_pool_should_work() {
+	run_test(|| {
+		// Pair pools and multi-asset pools share their ids
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), create_default_pool_params()));
+		let pool_id = create_multi_asset_pool();
+		assert_eq!(pool_id, 1);
+
+		let pool = Dex::multi_asset_pools(pool_id).unwrap();
+		assert_eq!(pool.assets.to_vec(), vec![ASSET_1, ASSET_2, ASSET_3]);
+		assert_eq!(pool.status, PoolStatus::Active);
+		assert_eq!(Dex::pool_accounts(pool_id), Some(Dex::account_id(&pool_id)));
+		assert_eq!(<Dex as Amm>::pool_assets(pool_id).unwrap(), vec![ASSET_1, ASSET_2, ASSET_3]);
+		assert!(<Dex as Amm>::pool_exists(pool_id));
+		assert_ne!(Dex::lp_token(pool_id).unwrap(), Dex::lp_token(0).unwrap());
+		assert_last_event::<Test, _>(|e| {
+			matches!(&e.event,
+            mock::Event::Dex(crate::Event::MultiAssetPoolCreated { owner, pool_id, assets })
+            if *owner == ALICE && *pool_id == 1 && *assets == pool.assets)
+		});
+	});
+}
+
+#[test]
+fn create_multi_asset_pool_should_fail_with_invalid_assets() {
+	run_test(|| {
+		let fee = Permill::from_percent(3);
+		for assets in [
+			vec![ASSET_1, ASSET_2],
+			vec![ASSET_1, ASSET_2, ASSET_1],
+			vec![ASSET_1, ASSET_1, ASSET_1],
+		] {
+			assert_noop!(
+				Dex::create_multi_asset_pool(
+					Origin::signed(ALICE),
+					ALICE,
+					BoundedVec::truncate_from(assets),
+					fee
+				),
+				Error::<Test>::InvalidPoolAssets
+			);
+		}
+
+		let assets = BoundedVec::truncate_from(vec![ASSET_1, ASSET_2, ASSET_3]);
+		assert_noop!(
+			Dex::create_multi_asset_pool(Origin::signed(BOB), ALICE, assets.clone(), fee),
+			Error::<Test>::OwnerNotSigner
+		);
+		assert_noop!(
+			Dex::create_multi_asset_pool(Origin::signed(ALICE), ALICE, assets, Permill::one()),
+			Error::<Test>::InvalidFee
+		);
+
+		let assets = BoundedVec::truncate_from(vec![ASSET_1, ASSET_2, UNREGISTERED_ASSET]);
+		assert_noop!(
+			Dex::create_multi_asset_pool(Origin::signed(ALICE), ALICE, assets, fee),
+			Error::<Test>::AssetNotRegistered
+		);
+	});
+}
+
+#[test]
+fn create_multi_asset_pool_with_liquidity_should_work() {
+	run_test(|| {
+		MinimumLiquidity::set(1_000);
+		let assets = BoundedVec::truncate_from(vec![ASSET_1, ASSET_2, ASSET_3]);
+		let fee = Permill::from_percent(3);
+
+		// The first deposit sets the prices of the pool
+		assert_ok!(Dex::create_multi_asset_pool_with_liquidity(
+			Origin::signed(ALICE),
+			assets.clone(),
+			fee,
+			BoundedVec::truncate_from(vec![100_000, 200_000, 400_000])
+		));
+		let pool_id = Dex::pool_count() - 1;
+		let lp_token = Dex::lp_token(pool_id).unwrap();
+		let pool_account = Dex::account_id(&pool_id);
+		assert_eq!(Dex::multi_asset_pools(pool_id).unwrap().owner, ALICE);
+		assert_eq!(Tokens::free_balance(ASSET_1, &pool_account), 100_000);
+		assert_eq!(Tokens::free_balance(ASSET_2, &pool_account), 200_000);
+		assert_eq!(Tokens::free_balance(ASSET_3, &pool_account), 400_000);
+		assert!(<Dex as Amm>::quote_exact_in(pool_id, ASSET_1, ASSET_3, 1_000).unwrap() > 3_000);
+
+		// The geometric mean of the deposit is minted, `MinimumLiquidity` of it is locked
+		assert_eq!(Tokens::free_balance(lp_token, &Dex::dead_account()), 1_000);
+		assert_ok!(assert_with_computation_error(
+			199_000,
+			Tokens::free_balance(lp_token, &ALICE),
+			DEFAULT_EPSILON
+		));
+		assert_last_event::<Test, _>(|e| {
+			matches!(&e.event,
+            mock::Event::Dex(crate::Event::MultiAssetLiquidityAdded { who, amounts, .. })
+            if *who == ALICE && *amounts == vec![100_000, 200_000, 400_000])
+		});
+
+		assert_noop!(
+			Dex::create_multi_asset_pool_with_liquidity(
+				Origin::signed(ALICE),
+				assets.clone(),
+				fee,
+				BoundedVec::truncate_from(vec![100_000; 2])
+			),
+			Error::<Test>::InvalidAmount
+		);
+		for amounts in [vec![100_000, 0, 100_000], vec![1_000; 3]] {
+			assert_noop!(
+				Dex::create_multi_asset_pool_with_liquidity(
+					Origin::signed(ALICE),
+					assets.clone(),
+					fee,
+					BoundedVec::truncate_from(amounts)
+				),
+				Error::<Test>::InsufficientInitialLiquidity
+			);
+		}
+	});
+}
+
+#[test]
+fn multi_asset_liquidity_should_work() {
+	run_test(|| {
+		// The first deposit has to be made with `create_multi_asset_pool_with_liquidity`
+		let pool_id = create_multi_asset_pool();
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(ALICE), pool_id, 100_000, ASSET_1, 0, None),
+			Error::<Test>::EmptyPool
+		);
+
+		let pool_id = create_funded_multi_asset_pool();
+		let lp_token = Dex::lp_token(pool_id).unwrap();
+		let pool_account = Dex::account_id(&pool_id);
+		assert_eq!(Tokens::free_balance(lp_token, &ALICE), 100_000);
+		for asset in [ASSET_1, ASSET_2, ASSET_3] {
+			assert_eq!(Tokens::free_balance(asset, &pool_account), 100_000);
+		}
+
+		// Later deposits are proportional to the reserves
+		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), pool_id, 1_000, ASSET_2, 0, None));
+		assert_eq!(Tokens::free_balance(lp_token, &ALICE), 101_000);
+		for asset in [ASSET_1, ASSET_2, ASSET_3] {
+			assert_eq!(Tokens::free_balance(asset, &pool_account), 101_000);
+		}
+		assert_last_event::<Test, _>(|e| {
+			matches!(&e.event,
+            mock::Event::Dex(crate::Event::MultiAssetLiquidityAdded { who, amounts, minted_lp, .. })
+            if *who == ALICE && *amounts == vec![1_000; 3] && *minted_lp == 1_000)
+		});
+
+		assert_noop!(
+			Dex::remove_multi_asset_liquidity(
+				Origin::signed(ALICE),
+				pool_id,
+				50_500,
+				BoundedVec::truncate_from(vec![0, 50_501, 0]),
+				None
+			),
+			Error::<Test>::AmountOutBelowMinimum
+		);
+		assert_noop!(
+			Dex::remove_multi_asset_liquidity(
+				Origin::signed(ALICE),
+				pool_id,
+				50_500,
+				BoundedVec::truncate_from(vec![0, 0]),
+				None
+			),
+			Error::<Test>::InvalidAmount
+		);
+		assert_ok!(Dex::remove_multi_asset_liquidity(
+			Origin::signed(ALICE),
+			pool_id,
+			50_500,
+			BoundedVec::truncate_from(vec![50_500; 3]),
+			None
+		));
+		assert_eq!(Tokens::free_balance(lp_token, &ALICE), 50_500);
+		for asset in [ASSET_1, ASSET_2, ASSET_3] {
+			assert_eq!(Tokens::free_balance(asset, &pool_account), 50_500);
+		}
+	});
+}
+
+#[test]
+fn single_asset_liquidity_should_work() {
+	run_test(|| {
+		let pool_id = create_funded_multi_asset_pool();
+		let lp_token = Dex::lp_token(pool_id).unwrap();
+
+		// A third of the deposit stays ASSET_1, the rest is implicitly swapped and pays the fee
+		assert_noop!(
+			Dex::add_single_asset_liquidity(
+				Origin::signed(ALICE),
+				pool_id,
+				ASSET_1,
+				1_000,
+				326,
+				None
+			),
+			Error::<Test>::LpMintedBelowMinimum
+		);
+		assert_ok!(Dex::add_single_asset_liquidity(
+			Origin::signed(ALICE),
+			pool_id,
+			ASSET_1,
+			1_000,
+			325,
+			None
+		));
+		assert_eq!(Tokens::free_balance(lp_token, &ALICE), 100_325);
+
+		// Withdrawing the liquidity again in the same asset returns less because of the fee
+		let balance_1_pre_removal = Tokens::free_balance(ASSET_1, &ALICE);
+		assert_ok!(Dex::remove_single_asset_liquidity(
+			Origin::signed(ALICE),
+			pool_id,
+			ASSET_1,
+			325,
+			0,
+			None
+		));
+		assert_eq!(Tokens::free_balance(lp_token, &ALICE), 100_000);
+		assert_ok!(assert_with_computation_error(
+			balance_1_pre_removal + 958,
+			Tokens::free_balance(ASSET_1, &ALICE),
+			DEFAULT_EPSILON
+		));
+		assert!(Tokens::free_balance(ASSET_1, &ALICE) < balance_1_pre_removal + 1_000);
+
+		assert_noop!(
+			Dex::add_single_asset_liquidity(
+				Origin::signed(ALICE),
+				pool_id,
+				ASSET_1,
+				60_000,
+				0,
+				None
+			),
+			Error::<Test>::MaxInRatioExceeded
+		);
+		assert_noop!(
+			Dex::remove_single_asset_liquidity(
+				Origin::signed(ALICE),
+				pool_id,
+				ASSET_1,
+				20_000,
+				0,
+				None
+			),
+			Error::<Test>::MaxOutRatioExceeded
+		);
+	});
+}
+
+#[test]
+fn multi_asset_swap_should_work() {
+	run_test(|| {
+		let pool_id = create_funded_multi_asset_pool();
+
+		// Only the reserves of the two traded assets matter
+		let quote = <Dex as Amm>::quote_exact_in(pool_id, ASSET_1, ASSET_3, 1_000).unwrap();
+		assert_eq!(quote, 960);
+
+		let balance_3_pre_swap = Tokens::free_balance(ASSET_3, &ALICE);
+		assert_ok!(Dex::swap_exact_in(
+			Origin::signed(ALICE),
+			pool_id,
+			ASSET_1,
+			ASSET_3,
+			1_000,
+			quote,
+			None
+		));
+		assert_eq!(Tokens::free_balance(ASSET_3, &ALICE), balance_3_pre_swap + quote);
+		assert_eq!(Tokens::free_balance(ASSET_2, &Dex::account_id(&pool_id)), 100_000);
+
+		let price = <Dex as Amm>::quote_exact_out(pool_id, ASSET_2, ASSET_1, 1_000).unwrap();
+		let balance_1_pre_swap = Tokens::free_balance(ASSET_1, &ALICE);
+		let balance_2_pre_swap = Tokens::free_balance(ASSET_2, &ALICE);
+		assert_noop!(
+			Dex::swap_exact_out(
+				Origin::signed(ALICE),
+				pool_id,
+				ASSET_2,
+				ASSET_1,
+				1_000,
+				price - 1,
+				None
+			),
+			Error::<Test>::AmountInAboveMaximum
+		);
+		assert_ok!(Dex::swap_exact_out(
+			Origin::signed(ALICE),
+			pool_id,
+			ASSET_2,
+			ASSET_1,
+			1_000,
+			price,
+			None
+		));
+		assert_eq!(Tokens::free_balance(ASSET_2, &ALICE), balance_2_pre_swap - price);
+		assert!(Tokens::free_balance(ASSET_1, &ALICE) >= balance_1_pre_swap + 1_000);
+
+		assert_noop!(
+			Dex::swap_exact_in(Origin::signed(ALICE), pool_id, ASSET_1, ASSET_1, 1_000, 0, None),
+			Error::<Test>::InvalidAsset
+		);
+		let invalid_asset: AssetIdOf<Test> = CurrencyId::Token(TokenSymbol::Short([u8::MAX; 4]));
+		assert_noop!(
+			Dex::swap_exact_in(
+				Origin::signed(ALICE),
+				pool_id,
+				ASSET_1,
+				invalid_asset,
+				1_000,
+				0,
+				None
+			),
+			Error::<Test>::InvalidAsset
+		);
+
+		// Multi-asset pools are administrated like pair pools
+		assert_ok!(Dex::set_pool_status(Origin::signed(ALICE), pool_id, PoolStatus::SwapsPaused));
+		assert_noop!(
+			Dex::swap_exact_in(Origin::signed(ALICE), pool_id, ASSET_1, ASSET_3, 1_000, 0, None),
+			Error::<Test>::SwapsPaused
+		);
+	});
+}
+
+#[test]
+fn swap_exact_in_should_work_for_pair_pools() {
+	run_test(|| {
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			create_default_pool_params(),
+			100_000,
+			100_000
+		));
+		let pool_id = 0;
+
+		let quote = <Dex as Amm>::quote_exact_in(pool_id, ASSET_2, ASSET_1, 1_000).unwrap();
+		assert_eq!(quote, Dex::quote_sell(pool_id, ASSET_2, 1_000).unwrap());
+		assert_eq!(
+			<Dex as Amm>::quote_exact_out(pool_id, ASSET_2, ASSET_1, 1_000).unwrap(),
+			Dex::quote_buy(pool_id, ASSET_1, 1_000).unwrap()
+		);
+
+		let balance_1_pre_swap = Tokens::free_balance(ASSET_1, &ALICE);
+		assert_ok!(Dex::swap_exact_in(
+			Origin::signed(ALICE),
+			pool_id,
+			ASSET_2,
+			ASSET_1,
+			1_000,
+			quote,
+			None
+		));
+		assert_eq!(Tokens::free_balance(ASSET_1, &ALICE), balance_1_pre_swap + quote);
+
+		assert_noop!(
+			<Dex as Amm>::quote_exact_in(pool_id, ASSET_2, ASSET_3, 1_000),
+			Error::<Test>::InvalidAsset
+		);
+	});
+}
+
+#[test]
+fn flash_loan_should_work() {
+	run_test(|| {
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			create_default_pool_params(),
+			100_000,
+			100_000
+		));
+		let pool_id = 0;
+		let pool_account = Dex::account_id(&pool_id);
+		let balance_1_pre_loan = Tokens::free_balance(ASSET_1, &ALICE);
+
+		// The borrowed amount has to be paid back plus the fee of 3% on the repaid amount
+		assert_noop!(
+			Dex::flash_swap(
+				Origin::signed(ALICE),
+				pool_id,
+				1_000,
+				0,
+				FlashSwapAction::Repay(1_030, 0).encode()
+			),
+			Error::<Test>::FlashSwapNotRepaid
+		);
+		assert_ok!(Dex::flash_swap(
+			Origin::signed(ALICE),
+			pool_id,
+			1_000,
+			0,
+			FlashSwapAction::Repay(1_031, 0).encode()
+		));
+
+		assert_eq!(Tokens::free_balance(ASSET_1, &ALICE), balance_1_pre_loan - 31);
+		assert_eq!(Tokens::free_balance(ASSET_1, &pool_account), 100_031);
+		assert_eq!(Tokens::free_balance(ASSET_2, &pool_account), 100_000);
+		assert!(!crate::FlashSwapLocks::<Test>::contains_key(pool_id));
+
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::FlashSwapped { who, amount_a_out, amount_b_out, amount_a_in, amount_b_in, .. })
+            if who == ALICE && amount_a_out == 1_000 && amount_b_out == 0 && amount_a_in == 1_031 && amount_b_in == 0)
+		});
+	});
+}
+
+#[test]
+fn flash_swap_should_work() {
+	run_test(|| {
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			create_default_pool_params(),
+			100_000,
+			100_000
+		));
+		let pool_id = 0;
+		let pool_account = Dex::account_id(&pool_id);
+
+		// Borrow ASSET_2 and pay back in ASSET_1, which has to keep the product of the reserves
+		assert_noop!(
+			Dex::flash_swap(
+				Origin::signed(ALICE),
+				pool_id,
+				0,
+				1_000,
+				FlashSwapAction::Repay(1_042, 0).encode()
+			),
+			Error::<Test>::FlashSwapNotRepaid
+		);
+		assert_ok!(Dex::flash_swap(
+			Origin::signed(ALICE),
+			pool_id,
+			0,
+			1_000,
+			FlashSwapAction::Repay(1_043, 0).encode()
+		));
+
+		assert_eq!(Tokens::free_balance(ASSET_1, &pool_account), 101_043);
+		assert_eq!(Tokens::free_balance(ASSET_2, &pool_account), 99_000);
+	});
+}
+
+#[test]
+fn flash_swap_should_fail() {
+	run_test(|| {
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			create_default_pool_params(),
+			100_000,
+			100_000
+		));
+		let pool_id = 0;
+		let data = FlashSwapAction::Repay(2_000, 0).encode();
+
+		assert_noop!(
+			Dex::flash_swap(Origin::signed(ALICE), pool_id, 0, 0, data.clone()),
+			Error::<Test>::InvalidAmount
+		);
+		assert_noop!(
+			Dex::flash_swap(Origin::signed(ALICE), pool_id, 100_000, 0, data.clone()),
+			Error::<Test>::InsufficientLiquidity
+		);
+
+		// The pool can't be used while its reserves are lent out
+		assert_noop!(
+			Dex::flash_swap(
+				Origin::signed(ALICE),
+				pool_id,
+				1_000,
+				0,
+				FlashSwapAction::Sell(100).encode()
+			),
+			Error::<Test>::PoolLocked
+		);
+
+		assert_ok!(Dex::set_pool_status(Origin::signed(ALICE), pool_id, PoolStatus::SwapsPaused));
+		assert_noop!(
+			Dex::flash_swap(Origin::signed(ALICE), pool_id, 1_000, 0, data.clone()),
+			Error::<Test>::SwapsPaused
+		);
+
+		let mut pool_params = create_default_pool_params();
+		pool_params.pair = CurrencyPair { token_a: ASSET_1, token_b: ASSET_3 };
+		pool_params.kind = PoolKind::StableSwap { amplification: 100 };
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			100_000,
+			100_000
+		));
+		assert_noop!(
+			Dex::flash_swap(Origin::signed(ALICE), 1, 1_000, 0, data),
+			Error::<Test>::UnsupportedPoolKind
+		);
+	});
+}
+
+fn create_concentrated_pool_params(tick_spacing: u32) -> PoolCreationParamsOf<Test> {
+	let mut pool_params = create_default_pool_params();
+	pool_params.fee = Permill::from_percent(1);
+	pool_params.kind = PoolKind::Concentrated { tick_spacing };
+	pool_params.initial_sqrt_price = Some(FixedU128::one());
+	pool_params
+}
+
+#[test]
+fn create_concentrated_pool_should_work() {
+	run_test(|| {
+		for tick_spacing in [0, 10_001] {
+			assert_noop!(
+				Dex::create_pool(
+					Origin::signed(ALICE),
+					create_concentrated_pool_params(tick_spacing)
+				),
+				Error::<Test>::InvalidTickSpacing
+			);
+		}
+
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), create_concentrated_pool_params(10)));
+		let state = Dex::concentrated_states(0).unwrap();
+		assert_eq!(state.sqrt_price, FixedU128::one());
+		assert_eq!(state.tick, 0);
+		assert_eq!(state.liquidity, 0);
+	});
+}
+
+#[test]
+fn create_concentrated_pool_should_start_at_initial_price() {
+	run_test(|| {
+		let min_sqrt_price = calc::get_sqrt_price_at_tick(calc::MIN_TICK).unwrap();
+		let max_sqrt_price = calc::get_sqrt_price_at_tick(calc::MAX_TICK).unwrap();
+		for initial_sqrt_price in [
+			None,
+			Some(FixedU128::from_inner(min_sqrt_price.into_inner() - 1)),
+			Some(FixedU128::from_inner(max_sqrt_price.into_inner() + 1)),
+		] {
+			let mut pool_params = create_concentrated_pool_params(10);
+			pool_params.initial_sqrt_price = initial_sqrt_price;
+			assert_noop!(
+				Dex::create_pool(Origin::signed(ALICE), pool_params),
+				Error::<Test>::InvalidInitialPrice
+			);
+		}
+
+		// Only concentrated-liquidity pools have an initial price
+		let mut pool_params = create_default_pool_params();
+		pool_params.initial_sqrt_price = Some(FixedU128::one());
+		assert_noop!(
+			Dex::create_pool(Origin::signed(ALICE), pool_params),
+			Error::<Test>::InvalidInitialPrice
+		);
+
+		// The price lies between tick 1_000 and tick 1_001
+		let sqrt_price_at_tick = calc::get_sqrt_price_at_tick(1_000).unwrap();
+		let sqrt_price = FixedU128::from_inner(sqrt_price_at_tick.into_inner() + 1);
+		let mut pool_params = create_concentrated_pool_params(10);
+		pool_params.initial_sqrt_price = Some(sqrt_price);
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool_params));
+		let state = Dex::concentrated_states(0).unwrap();
+		assert_eq!(state.sqrt_price, sqrt_price);
+		assert_eq!(state.tick, 1_000);
+
+		// A range below the initial price only holds ASSET_2 and is not active
+		assert_ok!(Dex::mint_position(Origin::signed(ALICE), 0, 0, 500, 5_000, 5_000, None));
+		assert_eq!(Tokens::free_balance(ASSET_1, &ALICE), 1_000_000);
+		assert_eq!(Tokens::free_balance(ASSET_2, &ALICE), 995_000);
+		assert_eq!(Dex::concentrated_states(0).unwrap().liquidity, 0);
+	});
+}
+
+#[test]
+fn tick_math_should_work() {
+	assert_eq!(calc::get_sqrt_price_at_tick(0), Some(FixedU128::one()));
+	assert!(calc::get_sqrt_price_at_tick(calc::MAX_TICK + 1).is_none());
+	assert!(calc::get_sqrt_price_at_tick(calc::MIN_TICK - 1).is_none());
+
+	for tick in [calc::MIN_TICK, -1_000, -1, 0, 1, 1_000, calc::MAX_TICK] {
+		let sqrt_price = calc::get_sqrt_price_at_tick(tick).unwrap();
+		assert_eq!(calc::get_tick_at_sqrt_price(sqrt_price), Some(tick));
+		if tick > calc::MIN_TICK {
+			let below = FixedU128::from_inner(sqrt_price.into_inner() - 1);
+			assert_eq!(calc::get_tick_at_sqrt_price(below), Some(tick - 1));
+		}
+	}
+
+	// √1.0001^1000 = 1.05126846...
+	let sqrt_price = calc::get_sqrt_price_at_tick(1_000).unwrap();
+	assert_eq!(sqrt_price.into_inner() / 1_000_000_000, 1_051_268_468);
+}
+
+#[test]
+fn fee_math_should_work() {
+	// 1_000 units of liquidity that earned 3 per unit
+	let fee_growth = calc::get_fee_growth(3_000, 1_000);
+	assert_eq!(fee_growth, 3 * FixedU128::accuracy());
+	assert_eq!(calc::get_fees_earned(1_000, fee_growth), Some(3_000));
+	assert_eq!(calc::get_fees_earned(500, fee_growth), Some(1_500));
+	assert_eq!(calc::get_fees_earned(0, fee_growth), Some(0));
+
+	// Fees that don't fit into a u128 are an error instead of being capped
+	assert_eq!(calc::get_fees_earned(u128::MAX, fee_growth), None);
+}
+
+#[test]
+fn mint_position_should_work() {
+	run_test(|| {
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), create_concentrated_pool_params(10)));
+		let pool_id = 0;
+
+		// The range is symmetric around the current price, so both assets are needed equally
+		assert_ok!(Dex::mint_position(
+			Origin::signed(ALICE),
+			pool_id,
+			-1_000,
+			1_000,
+			100_000,
+			100_000,
+			None
+		));
+		let liquidity = 2_050_515;
+		assert_eq!(Tokens::free_balance(ASSET_1, &ALICE), 900_000);
+		assert_eq!(Tokens::free_balance(ASSET_2, &ALICE), 900_000);
+		assert_eq!(Dex::positions(ALICE, (pool_id, -1_000, 1_000)).unwrap().liquidity, liquidity);
+		assert_eq!(Dex::concentrated_states(pool_id).unwrap().liquidity, liquidity);
+		assert_eq!(Dex::ticks(pool_id, -1_000).unwrap().liquidity_net, liquidity as i128);
+		assert_eq!(Dex::ticks(pool_id, 1_000).unwrap().liquidity_net, -(liquidity as i128));
+		assert_eq!(crate::InitializedTicks::<Test>::get(pool_id).into_inner(), vec![-1_000, 1_000]);
+
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::PositionMinted { who, amount_a, amount_b, .. })
+            if who == ALICE && amount_a == 100_000 && amount_b == 100_000)
+		});
+
+		// A range above the current price only holds ASSET_1 and is not active
+		assert_ok!(Dex::mint_position(
+			Origin::signed(ALICE),
+			pool_id,
+			100,
+			200,
+			5_000,
+			5_000,
+			None
+		));
+		assert_eq!(Tokens::free_balance(ASSET_1, &ALICE), 895_000);
+		assert_eq!(Tokens::free_balance(ASSET_2, &ALICE), 900_000);
+		assert_eq!(Dex::concentrated_states(pool_id).unwrap().liquidity, liquidity);
+
+		for (tick_lower, tick_upper) in [(5, 100), (100, 100), (200, 100), (-200_010, 0)] {
+			assert_noop!(
+				Dex::mint_position(
+					Origin::signed(ALICE),
+					pool_id,
+					tick_lower,
+					tick_upper,
+					5_000,
+					5_000,
+					None
+				),
+				Error::<Test>::InvalidTickRange
+			);
+		}
+
+		// Positions are not represented by liquidity tokens
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(ALICE), pool_id, 1_000, ASSET_1, 0, None),
+			Error::<Test>::UnsupportedPoolKind
+		);
+	});
+}
+
+#[test]
+fn concentrated_swap_should_cross_ticks() {
+	run_test(|| {
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), create_concentrated_pool_params(10)));
+		let pool_id = 0;
+		assert_ok!(Dex::mint_position(
+			Origin::signed(ALICE),
+			pool_id,
+			-1_000,
+			1_000,
+			100_000,
+			100_000,
+			None
+		));
+		assert_ok!(Dex::mint_position(
+			Origin::signed(ALICE),
+			pool_id,
+			-100,
+			100,
+			10_000,
+			10_000,
+			None
+		));
+		assert_eq!(Dex::concentrated_states(pool_id).unwrap().liquidity, 2_050_515 + 2_005_103);
+
+		// Selling ASSET_1 lowers its price below the narrow range, which is then inactive
+		let quote = Dex::quote_sell(pool_id, ASSET_1, 30_000).unwrap();
+		assert_eq!(quote, 29_461);
+		assert_ok!(Dex::sell(Origin::signed(BOB), pool_id, ASSET_1, 30_000, quote, None));
+		assert_eq!(Tokens::free_balance(ASSET_2, &BOB), quote);
+
+		let state = Dex::concentrated_states(pool_id).unwrap();
+		assert_eq!(state.liquidity, 2_050_515);
+		assert!(state.tick < -100);
+
+		// Exact outputs are swapped exactly
+		let price = Dex::quote_buy(pool_id, ASSET_1, 1_000).unwrap();
+		assert_eq!(price, 992);
+		let balance_1_pre_swap = Tokens::free_balance(ASSET_1, &BOB);
+		assert_ok!(Dex::buy(Origin::signed(BOB), pool_id, ASSET_1, 1_000, price, None));
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), balance_1_pre_swap + 1_000);
+		assert_eq!(Tokens::free_balance(ASSET_2, &BOB), quote - price);
+
+		// The liquidity of the pool is limited to the ranges of the positions
+		assert_noop!(
+			Dex::sell(Origin::signed(BOB), pool_id, ASSET_1, 500_000, 0, None),
+			Error::<Test>::InsufficientLiquidity
+		);
+	});
+}
+
+#[test]
+fn positions_should_earn_fees_while_active() {
+	run_test(|| {
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), create_concentrated_pool_params(10)));
+		let pool_id = 0;
+		for (tick_lower, tick_upper, amount) in
+			[(-1_000, 1_000, 100_000), (-100, 100, 10_000), (100, 200, 5_000)]
+		{
+			assert_ok!(Dex::mint_position(
+				Origin::signed(ALICE),
+				pool_id,
+				tick_lower,
+				tick_upper,
+				amount,
+				amount,
+				None
+			));
+		}
+
+		// The fee of 100 is shared by the active positions according to their liquidity
+		assert_ok!(Dex::sell(Origin::signed(BOB), pool_id, ASSET_1, 10_000, 0, None));
+		assert_has_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::Swapped { fee, .. })
+            if fee == 100)
+		});
+
+		for (tick_lower, tick_upper, fees) in [(-1_000, 1_000, 50), (-100, 100, 49), (100, 200, 0)]
+		{
+			let balance_1_pre_collect = Tokens::free_balance(ASSET_1, &ALICE);
+			let balance_2_pre_collect = Tokens::free_balance(ASSET_2, &ALICE);
+			assert_ok!(Dex::collect_fees(Origin::signed(ALICE), pool_id, tick_lower, tick_upper));
+			assert_eq!(Tokens::free_balance(ASSET_1, &ALICE), balance_1_pre_collect + fees);
+			assert_eq!(Tokens::free_balance(ASSET_2, &ALICE), balance_2_pre_collect);
+		}
+
+		// The fees can only be collected once
+		let balance_1_pre_collect = Tokens::free_balance(ASSET_1, &ALICE);
+		assert_ok!(Dex::collect_fees(Origin::signed(ALICE), pool_id, -1_000, 1_000));
+		assert_eq!(Tokens::free_balance(ASSET_1, &ALICE), balance_1_pre_collect);
+
+		assert_noop!(
+			Dex::collect_fees(Origin::signed(BOB), pool_id, -1_000, 1_000),
+			Error::<Test>::PositionNotFound
+		);
+	});
+}
+
+#[test]
+fn burn_position_should_work() {
+	run_test(|| {
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), create_concentrated_pool_params(10)));
+		let pool_id = 0;
+		assert_ok!(Dex::mint_position(
+			Origin::signed(ALICE),
+			pool_id,
+			-1_000,
+			1_000,
+			100_000,
+			100_000,
+			None
+		));
+		let liquidity = Dex::positions(ALICE, (pool_id, -1_000, 1_000)).unwrap().liquidity;
+		assert_ok!(Dex::sell(Origin::signed(BOB), pool_id, ASSET_1, 10_000, 0, None));
+
+		assert_noop!(
+			Dex::burn_position(
+				Origin::signed(ALICE),
+				pool_id,
+				-1_000,
+				1_000,
+				liquidity + 1,
+				0,
+				0,
+				None
+			),
+			Error::<Test>::InsufficientLiquidityBalance
+		);
+		assert_noop!(
+			Dex::burn_position(Origin::signed(ALICE), pool_id, -100, 100, liquidity, 0, 0, None),
+			Error::<Test>::PositionNotFound
+		);
+		assert_noop!(
+			Dex::burn_position(
+				Origin::signed(ALICE),
+				pool_id,
+				-1_000,
+				1_000,
+				liquidity,
+				109_900,
+				0,
+				None
+			),
+			Error::<Test>::AmountAOutBelowMinimum
+		);
+
+		// The withdrawn amounts don't include the fees
+		assert_ok!(Dex::burn_position(
+			Origin::signed(ALICE),
+			pool_id,
+			-1_000,
+			1_000,
+			liquidity,
+			0,
+			0,
+			None
+		));
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::PositionBurned { amount_a, amount_b, .. })
+            if amount_a == 109_899 && amount_b == 90_147)
+		});
+		assert_eq!(Dex::concentrated_states(pool_id).unwrap().liquidity, 0);
+		assert!(Dex::ticks(pool_id, -1_000).is_none());
+		assert!(crate::InitializedTicks::<Test>::get(pool_id).is_empty());
+
+		// The position is kept until its fees are collected
+		assert_eq!(Dex::positions(ALICE, (pool_id, -1_000, 1_000)).unwrap().fees_owed_a, 99);
+		assert_ok!(Dex::collect_fees(Origin::signed(ALICE), pool_id, -1_000, 1_000));
+		assert!(Dex::positions(ALICE, (pool_id, -1_000, 1_000)).is_none());
+
+		// The pool never pays out more than it holds
+		let pool_account = Dex::account_id(&pool_id);
+		assert!(Tokens::free_balance(ASSET_1, &pool_account) <= 2);
+		assert!(Tokens::free_balance(ASSET_2, &pool_account) <= 2);
+	});
+}
+
+#[test]
+fn quote_sell_should_match_sell() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			100_000,
+			100_000
+		));
+
+		let pool_id = 0;
+		// Skew the reserves so that the direction of the quote matters
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, 10_000, 0, None));
+
+		let amount_to_sell = 1_000;
+		let quote = <Dex as Amm>::quote_sell(pool_id, ASSET_2, amount_to_sell).unwrap();
+
+		let balance_1_pre_swap = Tokens::free_balance(ASSET_1, &ALICE);
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_2, amount_to_sell, 0, None));
+		assert_eq!(balance_1_pre_swap + quote, Tokens::free_balance(ASSET_1, &ALICE));
+	});
+}
+
+#[test]
+fn quote_buy_should_match_buy() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			100_000,
+			100_000
+		));
+
+		let pool_id = 0;
+		// Skew the reserves so that the direction of the quote matters
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, 10_000, 0, None));
+
+		let amount_to_buy = 1_000;
+		let quote = <Dex as Amm>::quote_buy(pool_id, ASSET_1, amount_to_buy).unwrap();
+
+		let balance_2_pre_swap = Tokens::free_balance(ASSET_2, &ALICE);
+		assert_ok!(Dex::buy(
+			Origin::signed(ALICE),
+			pool_id,
+			ASSET_1,
+			amount_to_buy,
+			Balance::MAX,
+			None
+		));
+		assert_eq!(balance_2_pre_swap - quote, Tokens::free_balance(ASSET_2, &ALICE));
+	});
+}
+
+#[test]
+fn quote_should_fail_with_invalid_asset() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			100_000,
+			100_000
+		));
+
+		let pool_id = 0;
+
+		let invalid_asset: AssetIdOf<Test> = CurrencyId::Token(TokenSymbol::Short([u8::MAX; 4]));
+		assert_noop!(
+			<Dex as Amm>::quote_sell(pool_id, invalid_asset, 100),
+			Error::<Test>::InvalidAsset
+		);
+		assert_noop!(
+			<Dex as Amm>::quote_buy(pool_id, invalid_asset, 100),
+			Error::<Test>::InvalidAsset
+		);
+	});
+}
+
+/// Creates two pools (ASSET_1/ASSET_2 and ASSET_2/ASSET_3) with liquidity and returns their ids.
+fn create_pools_for_path() -> (PoolIdOf<Test>, PoolIdOf<Test>) {
+	let fee = Permill::from_percent(3);
+	let first_pair = CurrencyPair { token_a: ASSET_1, token_b: ASSET_2 };
+	let second_pair = CurrencyPair { token_a: ASSET_2, token_b: ASSET_3 };
+
+	for pair in [first_pair, second_pair] {
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			PoolCreationParams {
+				owner: ALICE,
+				pair,
+				fee,
+				kind: PoolKind::ConstantProduct,
+				initial_sqrt_price: None,
+			},
+			100_000,
+			100_000
+		));
+	}
+	(0, 1)
+}
+
+#[test]
+fn swap_exact_in_via_path_should_work() {
+	run_test(|| {
+		let (first_pool, second_pool) = create_pools_for_path();
+		let path: BoundedVec<_, _> = vec![first_pool, second_pool].try_into().unwrap();
+
+		let amount_in = 1_000;
+		let intermediate = <Dex as Amm>::quote_sell(first_pool, ASSET_1, amount_in).unwrap();
+		let expected_out = <Dex as Amm>::quote_sell(second_pool, ASSET_2, intermediate).unwrap();
+
+		let balance_1_pre_swap = Tokens::free_balance(ASSET_1, &ALICE);
+		let balance_2_pre_swap = Tokens::free_balance(ASSET_2, &ALICE);
+		let balance_3_pre_swap = Tokens::free_balance(ASSET_3, &ALICE);
+
+		assert_ok!(Dex::swap_exact_in_via_path(
+			Origin::signed(ALICE),
+			path,
+			ASSET_1,
+			amount_in,
+			expected_out,
+			None
+		));
+
+		assert_eq!(balance_1_pre_swap - amount_in, Tokens::free_balance(ASSET_1, &ALICE));
+		// The intermediate asset is passed through completely
+		assert_eq!(balance_2_pre_swap, Tokens::free_balance(ASSET_2, &ALICE));
+		assert_eq!(balance_3_pre_swap + expected_out, Tokens::free_balance(ASSET_3, &ALICE));
+
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::SwappedViaPath {who, asset_in, asset_out, amount_in: paid, amount_out})
+            if who == ALICE && asset_in == ASSET_1 && asset_out == ASSET_3 && paid == amount_in && amount_out == expected_out)
+		});
+	});
+}
+
+#[test]
+fn swap_exact_in_via_path_should_fail_below_minimum_output() {
+	run_test(|| {
+		let (first_pool, second_pool) = create_pools_for_path();
+		let path: BoundedVec<_, _> = vec![first_pool, second_pool].try_into().unwrap();
+
+		let amount_in = 1_000;
+		let intermediate = <Dex as Amm>::quote_sell(first_pool, ASSET_1, amount_in).unwrap();
+		let expected_out = <Dex as Amm>::quote_sell(second_pool, ASSET_2, intermediate).unwrap();
+
+		assert_noop!(
+			Dex::swap_exact_in_via_path(
+				Origin::signed(ALICE),
+				path,
+				ASSET_1,
+				amount_in,
+				expected_out + 1,
+				None
+			),
+			Error::<Test>::PathOutputTooLow
+		);
+	});
+}
+
+/// Creates two ASSET_1/ASSET_2 pools with a fee of 0.05% and 1% and the same reserves.
+fn create_pools_for_router() -> (PoolIdOf<Test>, PoolIdOf<Test>) {
+	let low_fee_pool = create_funded_pool_with_fee(Permill::from_parts(500), 200_000);
+	let high_fee_pool = create_funded_pool_with_fee(Permill::from_percent(1), 200_000);
+	(low_fee_pool, high_fee_pool)
+}
+
+#[test]
+fn quote_best_route_should_use_best_pool_for_small_amounts() {
+	run_test(|| {
+		let (low_fee_pool, _) = create_pools_for_router();
+
+		let amount_in = 100;
+		let expected_out = <Dex as Amm>::quote_sell(low_fee_pool, ASSET_1, amount_in).unwrap();
+		assert_eq!(
+			Dex::quote_best_route(ASSET_1, ASSET_2, amount_in),
+			Ok((vec![(low_fee_pool, amount_in)], expected_out))
+		);
+	});
+}
+
+#[test]
+fn quote_best_route_should_split_large_amounts() {
+	run_test(|| {
+		let (low_fee_pool, high_fee_pool) = create_pools_for_router();
+
+		let amount_in = 100_000;
+		let single_out = <Dex as Amm>::quote_sell(low_fee_pool, ASSET_1, amount_in).unwrap();
+		let (route, amount_out) = Dex::quote_best_route(ASSET_1, ASSET_2, amount_in).unwrap();
+
+		assert!(amount_out > single_out);
+		assert_eq!(route.len(), 2);
+		assert_eq!(route.iter().map(|(_, amount)| amount).sum::<Balance>(), amount_in);
+		let expected_out: Balance = route
+			.iter()
+			.map(|(pool_id, amount)| <Dex as Amm>::quote_sell(*pool_id, ASSET_1, *amount).unwrap())
+			.sum();
+		assert_eq!(amount_out, expected_out);
+		// The cheaper pool takes the larger part
+		let amount_of = |pool_id| route.iter().find(|(id, _)| *id == pool_id).unwrap().1;
+		assert!(amount_of(low_fee_pool) > amount_of(high_fee_pool));
+	});
+}
+
+#[test]
+fn quote_best_route_should_skip_paused_pools() {
+	run_test(|| {
+		let (low_fee_pool, high_fee_pool) = create_pools_for_router();
+		assert_ok!(Dex::set_pool_status(Origin::root(), low_fee_pool, PoolStatus::SwapsPaused));
+
+		let amount_in = 100_000;
+		let expected_out = <Dex as Amm>::quote_sell(high_fee_pool, ASSET_1, amount_in).unwrap();
+		assert_eq!(
+			Dex::quote_best_route(ASSET_1, ASSET_2, amount_in),
+			Ok((vec![(high_fee_pool, amount_in)], expected_out))
+		);
+
+		assert_ok!(Dex::set_pool_status(Origin::root(), high_fee_pool, PoolStatus::Paused));
+		assert_noop!(Dex::quote_best_route(ASSET_1, ASSET_2, amount_in), Error::<Test>::NoRoute);
+		assert_noop!(Dex::quote_best_route(ASSET_1, ASSET_3, amount_in), Error::<Test>::NoRoute);
+		assert_noop!(Dex::quote_best_route(ASSET_1, ASSET_2, 0), Error::<Test>::InvalidAmount);
+	});
+}
+
+#[test]
+fn swap_exact_in_best_route_should_work() {
+	run_test(|| {
+		create_pools_for_router();
+
+		let amount_in = 100_000;
+		let (_, expected_out) = Dex::quote_best_route(ASSET_1, ASSET_2, amount_in).unwrap();
+
+		assert_noop!(
+			Dex::swap_exact_in_best_route(
+				Origin::signed(BOB),
+				ASSET_1,
+				ASSET_2,
+				amount_in,
+				expected_out + 1,
+				None
+			),
+			Error::<Test>::AmountOutBelowMinimum
+		);
+
+		assert_ok!(Dex::swap_exact_in_best_route(
+			Origin::signed(BOB),
+			ASSET_1,
+			ASSET_2,
+			amount_in,
+			expected_out,
+			None
+		));
+
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), 1_000_000 - amount_in);
+		assert_eq!(Tokens::free_balance(ASSET_2, &BOB), expected_out);
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::SwappedViaBestRoute {who, asset_in, asset_out, amount_in: paid, amount_out})
+            if who == BOB && asset_in == ASSET_1 && asset_out == ASSET_2 && paid == amount_in && amount_out == expected_out)
+		});
+	});
+}
+
+#[test]
+fn swap_exact_out_via_path_should_work() {
+	run_test(|| {
+		let (first_pool, second_pool) = create_pools_for_path();
+		let path: BoundedVec<_, _> = vec![first_pool, second_pool].try_into().unwrap();
+
+		let amount_out = 1_000;
+		let intermediate = <Dex as Amm>::quote_buy(second_pool, ASSET_3, amount_out).unwrap();
+		let expected_in = <Dex as Amm>::quote_buy(first_pool, ASSET_2, intermediate).unwrap();
+
+		let balance_1_pre_swap = Tokens::free_balance(ASSET_1, &ALICE);
+		let balance_2_pre_swap = Tokens::free_balance(ASSET_2, &ALICE);
+		let balance_3_pre_swap = Tokens::free_balance(ASSET_3, &ALICE);
+
+		// Paying more than the expected amount is not allowed
+		assert_noop!(
+			Dex::swap_exact_out_via_path(
+				Origin::signed(ALICE),
+				path.clone(),
+				ASSET_1,
+				amount_out,
+				expected_in - 1,
+				None
+			),
+			Error::<Test>::PathInputTooHigh
+		);
+
+		assert_ok!(Dex::swap_exact_out_via_path(
+			Origin::signed(ALICE),
+			path,
+			ASSET_1,
+			amount_out,
+			expected_in,
+			None
+		));
+
+		assert_eq!(balance_1_pre_swap - expected_in, Tokens::free_balance(ASSET_1, &ALICE));
+		// Rounding in favour of the pool can leave a tiny remainder of the intermediate asset
+		assert_ok!(assert_with_computation_error(
+			balance_2_pre_swap,
+			Tokens::free_balance(ASSET_2, &ALICE),
+			DEFAULT_EPSILON
+		));
+		assert_ok!(assert_with_computation_error(
+			balance_3_pre_swap + amount_out,
+			Tokens::free_balance(ASSET_3, &ALICE),
+			DEFAULT_EPSILON
+		));
+	});
+}
+
+#[test]
+fn swap_via_path_should_fail_with_disconnected_path() {
+	run_test(|| {
+		let (first_pool, second_pool) = create_pools_for_path();
+
+		// ASSET_1 is not part of the second pool
+		let path: BoundedVec<_, _> = vec![second_pool, first_pool].try_into().unwrap();
+		assert_noop!(
+			Dex::swap_exact_in_via_path(Origin::signed(ALICE), path, ASSET_1, 1_000, 0, None),
+			Error::<Test>::InvalidPath
+		);
+
+		let empty_path: BoundedVec<_, _> = vec![].try_into().unwrap();
+		assert_noop!(
+			Dex::swap_exact_out_via_path(
+				Origin::signed(ALICE),
+				empty_path,
+				ASSET_1,
+				1_000,
+				0,
+				None
+			),
+			Error::<Test>::InvalidPath
+		);
+	});
+}
+
+#[test]
+fn swap_via_path_should_fail_with_repeated_pool() {
+	run_test(|| {
+		let (first_pool, second_pool) = create_pools_for_path();
+		let balance_2_pre_swap = Tokens::free_balance(ASSET_2, &ALICE);
+
+		// The second hop would be quoted against the reserves before the first one, so ALICE
+		// would pay the difference with her ASSET_2
+		let path: BoundedVec<_, _> = vec![first_pool, first_pool].try_into().unwrap();
+		assert_noop!(
+			Dex::swap_exact_out_via_path(
+				Origin::signed(ALICE),
+				path.clone(),
+				ASSET_1,
+				1_000,
+				10_000,
+				None
+			),
+			Error::<Test>::InvalidPath
+		);
+		assert_noop!(
+			Dex::swap_exact_in_via_path(Origin::signed(ALICE), path, ASSET_1, 1_000, 0, None),
+			Error::<Test>::InvalidPath
+		);
+
+		// Revisiting a pool later in the path is rejected as well
+		let path: BoundedVec<_, _> = vec![first_pool, second_pool, second_pool].try_into().unwrap();
+		assert_noop!(
+			Dex::swap_exact_out_via_path(Origin::signed(ALICE), path, ASSET_1, 100, 10_000, None),
+			Error::<Test>::InvalidPath
+		);
+		assert_eq!(Tokens::free_balance(ASSET_2, &ALICE), balance_2_pre_swap);
+	});
+}
+
+#[test]
+fn trading_should_fail_after_deadline() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			100_000,
+			100_000
+		));
+
+		let pool_id = 0;
+
+		System::set_block_number(10);
+		let deadline = Some(9);
+
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(ALICE), pool_id, 100, ASSET_1, 0, deadline),
+			Error::<Test>::DeadlinePassed
+		);
+		assert_noop!(
+			Dex::remove_liquidity(Origin::signed(ALICE), pool_id, 100, 0, 0, deadline),
+			Error::<Test>::DeadlinePassed
+		);
+		assert_noop!(
+			Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, 100, 0, deadline),
+			Error::<Test>::DeadlinePassed
+		);
+		assert_noop!(
+			Dex::buy(Origin::signed(ALICE), pool_id, ASSET_1, 100, Balance::MAX, deadline),
+			Error::<Test>::DeadlinePassed
+		);
+		assert_noop!(
+			Dex::swap(Origin::signed(ALICE), pool_id, pool_params.pair, 100, 0, deadline),
+			Error::<Test>::DeadlinePassed
+		);
+
+		// The deadline block itself is still valid
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, 100, 0, Some(10)));
+	});
+}
+
+#[test]
+fn swaps_should_respect_slippage_limits() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			100_000,
+			100_000
+		));
+
+		let pool_id = 0;
+
+		let amount = 1_000;
+		let amount_out = <Dex as Amm>::quote_sell(pool_id, ASSET_1, amount).unwrap();
+		assert_noop!(
+			Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, amount, amount_out + 1, None),
+			Error::<Test>::AmountOutBelowMinimum
+		);
+		// `swap` sells token_b of the given pair
+		assert_noop!(
+			Dex::swap(
+				Origin::signed(ALICE),
+				pool_id,
+				pool_params.pair.swap(),
+				amount,
+				amount_out + 1,
+				None
+			),
+			Error::<Test>::AmountOutBelowMinimum
+		);
+
+		let amount_in = <Dex as Amm>::quote_buy(pool_id, ASSET_1, amount).unwrap();
+		assert_noop!(
+			Dex::buy(Origin::signed(ALICE), pool_id, ASSET_1, amount, amount_in - 1, None),
+			Error::<Test>::AmountInAboveMaximum
+		);
+
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_1, amount, amount_out, None));
+		assert_ok!(Dex::buy(Origin::signed(ALICE), pool_id, ASSET_2, amount, Balance::MAX, None));
+	});
+}
+
+#[test]
+fn liquidity_changes_should_respect_slippage_limits() {
+	run_test(|| {
+		let pool_params = create_default_pool_params();
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			pool_params,
+			1_000,
+			1_000
+		));
+
+		let pool_id = 0;
+		let amount = 100;
+		// A tenth of the reserves mints a tenth of the liquidity tokens
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(ALICE), pool_id, amount, ASSET_1, amount + 1, None),
+			Error::<Test>::LpMintedBelowMinimum
+		);
+		assert_ok!(Dex::add_liquidity(
+			Origin::signed(ALICE),
+			pool_id,
+			amount,
+			ASSET_1,
+			amount,
+			None
+		));
+
+		assert_noop!(
+			Dex::remove_liquidity(Origin::signed(ALICE), pool_id, amount, amount + 1, 0, None),
+			Error::<Test>::AmountAOutBelowMinimum
+		);
+		assert_noop!(
+			Dex::remove_liquidity(Origin::signed(ALICE), pool_id, amount, 0, amount + 1, None),
+			Error::<Test>::AmountBOutBelowMinimum
+		);
+		assert_ok!(Dex::remove_liquidity(
+			Origin::signed(ALICE),
+			pool_id,
+			amount,
+			amount,
+			amount,
+			None
+		));
+	});
+}
+
+#[test]
+fn try_state_should_detect_inconsistent_pools() {
+	new_test_ext_multi_currency().execute_with(|| {
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), create_default_pool_params()));
+		let pool_id = 0;
+		assert_ok!(Dex::do_try_state());
+
+		// Reserves without any issued liquidity tokens
+		let pool_account = Dex::pool_accounts(pool_id).unwrap();
+		assert_ok!(Tokens::deposit(ASSET_1, &pool_account, 100));
+		assert_eq!(
+			Dex::do_try_state(),
+			Err("Liquidity token issuance doesn't match the pool reserves")
+		);
+		assert_ok!(Tokens::withdraw(ASSET_1, &pool_account, 100));
+		assert_ok!(Dex::do_try_state());
+
+		PoolAccounts::<Test>::remove(pool_id);
+		assert_eq!(Dex::do_try_state(), Err("Pool has no account"));
+	});
+}
+
+#[test]
+fn try_state_should_detect_inconsistent_multi_asset_pools() {
+	run_test(|| {
+		create_funded_multi_asset_pool();
+		let pool_id = create_multi_asset_pool();
+		assert_ok!(Dex::do_try_state());
+
+		// Reserves of a single asset without any issued liquidity tokens
+		let pool_account = Dex::pool_accounts(pool_id).unwrap();
+		assert_ok!(Tokens::deposit(ASSET_3, &pool_account, 100));
+		assert_eq!(
+			Dex::do_try_state(),
+			Err("Liquidity token issuance doesn't match the pool reserves")
+		);
+		assert_ok!(Tokens::withdraw(ASSET_3, &pool_account, 100));
+		assert_ok!(Dex::do_try_state());
+	});
+}
+
+/// Creates the default ASSET_1/ASSET_2 pool with reserves of 100_000 each in batch auction mode.
+fn create_batch_auction_pool() -> PoolIdOf<Test> {
+	let pool_id = create_funded_pool_with_fee(Permill::from_percent(3), 100_000);
+	assert_ok!(Dex::set_batch_auction(Origin::root(), pool_id, true));
+	pool_id
+}
+
+#[test]
+fn set_batch_auction_should_work() {
+	run_test(|| {
+		let pool_id = create_pool_with_fee(Permill::from_percent(3));
+
+		// Only the admin can switch the mode on, even for the owner's own pool
+		assert_noop!(
+			Dex::set_batch_auction(Origin::signed(ALICE), pool_id, true),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Dex::set_batch_auction(Origin::root(), pool_id, true));
+		assert!(Dex::is_batch_auction_pool(pool_id));
+		assert!(<Dex as Amm>::is_batch_auction_pool(pool_id));
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::BatchAuctionModeUpdated { pool_id: id, enabled })
+            if id == pool_id && enabled)
+		});
+
+		// The owner can switch it off again
+		assert_noop!(
+			Dex::set_batch_auction(Origin::signed(BOB), pool_id, false),
+			Error::<Test>::NotPoolOwner
+		);
+		assert_ok!(Dex::set_batch_auction(Origin::signed(ALICE), pool_id, false));
+		assert!(!Dex::is_batch_auction_pool(pool_id));
+
+		// Concentrated-liquidity pools have no curve to clear the batches against
+		let pool_params = create_concentrated_pool_params(10);
+		let concentrated_pool = Dex::pool_count();
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), pool_params));
+		assert_noop!(
+			Dex::set_batch_auction(Origin::root(), concentrated_pool, true),
+			Error::<Test>::UnsupportedPoolKind
+		);
+		assert_noop!(Dex::set_batch_auction(Origin::root(), 42, true), Error::<Test>::PoolNotFound);
+	});
+}
+
+#[test]
+fn batch_auction_should_queue_swaps_until_end_of_block() {
+	run_test(|| {
+		let pool_id = create_batch_auction_pool();
+		let expected_out = <Dex as Amm>::quote_sell(pool_id, ASSET_1, 1_000).unwrap();
+
+		assert_ok!(Dex::sell(Origin::signed(BOB), pool_id, ASSET_1, 1_000, 0, None));
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), 1_000_000 - 1_000);
+		assert_eq!(Tokens::free_balance(ASSET_1, &Dex::batch_account()), 1_000);
+		assert_eq!(Dex::batch_orders(pool_id).len(), 1);
+		assert_eq!(Dex::pool_reserves(pool_id).unwrap(), (100_000, 100_000));
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::BatchOrderSubmitted { who, pool_id: id, asset_in, amount_in, .. })
+            if who == BOB && id == pool_id && asset_in == ASSET_1 && amount_in == 1_000)
+		});
+
+		// Without opposing orders the batch is a single swap with the pool
+		Dex::on_finalize(System::block_number());
+		assert_eq!(Tokens::free_balance(ASSET_2, &BOB), expected_out);
+		assert_eq!(Dex::pool_reserves(pool_id).unwrap(), (101_000, 100_000 - expected_out));
+		assert_eq!(Tokens::free_balance(ASSET_1, &Dex::batch_account()), 0);
+		assert!(Dex::batch_orders(pool_id).is_empty());
+		assert_has_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::BatchOrderExecuted { who, asset_in, amount_in, amount_out, .. })
+            if who == BOB && asset_in == ASSET_1 && amount_in == 1_000 && amount_out == expected_out)
+		});
+	});
+}
+
+#[test]
+fn batch_auction_should_match_opposing_swaps_at_uniform_price() {
+	run_test(|| {
+		let pool_id = create_batch_auction_pool();
+		let alice_asset_1 = Tokens::free_balance(ASSET_1, &ALICE);
+		let bob_alone_out = <Dex as Amm>::quote_sell(pool_id, ASSET_1, 3_000).unwrap();
+		let pair = CurrencyPair { token_a: ASSET_1, token_b: ASSET_2 };
+		assert_ok!(Dex::swap(Origin::signed(ALICE), pool_id, pair, 1_000, 0, None));
+		assert_ok!(Dex::swap_exact_in(
+			Origin::signed(BOB),
+			pool_id,
+			ASSET_1,
+			ASSET_2,
+			3_000,
+			0,
+			None
+		));
+
+		Dex::on_finalize(System::block_number());
+		let alice_out = Tokens::free_balance(ASSET_1, &ALICE) - alice_asset_1;
+		let bob_out = Tokens::free_balance(ASSET_2, &BOB);
+
+		// Only the excess of ASSET_1 that isn't matched with ALICE's order was sold to the pool
+		let amount_in = 3_000 - alice_out;
+		let amount_out = bob_out - 1_000;
+		let fee = Permill::from_percent(3);
+		assert_eq!(calc::get_amount_out::<Test>(amount_in, 100_000, 100_000, fee), Ok(amount_out));
+		assert_eq!(
+			Dex::pool_reserves(pool_id).unwrap(),
+			(100_000 + amount_in, 100_000 - amount_out)
+		);
+
+		// Both orders got the same price up to rounding, and BOB got more than by swapping alone
+		let price = FixedU128::checked_from_rational(bob_out, 3_000).unwrap();
+		let alice_price = FixedU128::checked_from_rational(1_000, alice_out).unwrap();
+		assert!(alice_price <= price && price - alice_price < FixedU128::from_rational(1, 1_000));
+		assert!(bob_out > bob_alone_out);
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::BatchAuctionCleared { pool_id: id, price: p, sold_a, sold_b })
+            if id == pool_id && p == price && sold_a == 3_000 && sold_b == 1_000)
+		});
+	});
+}
+
+#[test]
+fn batch_auction_should_not_trade_with_pool_when_orders_match() {
+	run_test(|| {
+		let pool_id = create_batch_auction_pool();
+		let alice_asset_1 = Tokens::free_balance(ASSET_1, &ALICE);
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_2, 1_000, 0, None));
+		assert_ok!(Dex::sell(Origin::signed(BOB), pool_id, ASSET_1, 1_000, 0, None));
+
+		// At the spot price the orders are worth the same, a swap with the pool would only
+		// add its fee
+		Dex::on_finalize(System::block_number());
+		assert_eq!(Tokens::free_balance(ASSET_1, &ALICE), alice_asset_1 + 1_000);
+		assert_eq!(Tokens::free_balance(ASSET_2, &BOB), 1_000);
+		assert_eq!(Dex::pool_reserves(pool_id).unwrap(), (100_000, 100_000));
+	});
+}
+
+#[test]
+fn batch_auction_should_refund_orders_below_minimum() {
+	run_test(|| {
+		let pool_id = create_batch_auction_pool();
+		let expected_out = <Dex as Amm>::quote_sell(pool_id, ASSET_2, 1_000).unwrap();
+		assert_ok!(Dex::sell(Origin::signed(BOB), pool_id, ASSET_1, 1_000, 1_001, None));
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_2, 1_000, 0, None));
+
+		// Matched with ALICE's order, BOB would only get 1_000
+		Dex::on_finalize(System::block_number());
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), 1_000_000);
+		assert_eq!(Tokens::free_balance(ASSET_2, &BOB), 0);
+		assert_has_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Dex(crate::Event::BatchOrderRefunded { who, asset_in, amount_in, .. })
+            if who == BOB && asset_in == ASSET_1 && amount_in == 1_000)
+		});
+		// ALICE's order is executed alone
+		assert_eq!(Dex::pool_reserves(pool_id).unwrap(), (100_000 - expected_out, 101_000));
+		assert_eq!(Tokens::free_balance(ASSET_1, &Dex::batch_account()), 0);
+		assert_eq!(Tokens::free_balance(ASSET_2, &Dex::batch_account()), 0);
+	});
+}
+
+#[test]
+fn batch_auction_should_refund_orders_of_paused_pool() {
+	run_test(|| {
+		let pool_id = create_batch_auction_pool();
+		assert_ok!(Dex::sell(Origin::signed(BOB), pool_id, ASSET_1, 1_000, 0, None));
+		assert_ok!(Dex::set_pool_status(Origin::signed(ALICE), pool_id, PoolStatus::Paused));
+
+		Dex::on_finalize(System::block_number());
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), 1_000_000);
+		assert_eq!(Dex::pool_reserves(pool_id).unwrap(), (100_000, 100_000));
+		assert!(Dex::batch_orders(pool_id).is_empty());
+
+		assert_noop!(
+			Dex::sell(Origin::signed(BOB), pool_id, ASSET_1, 1_000, 0, None),
+			Error::<Test>::PoolPaused
+		);
+	});
+}
+
+#[test]
+fn batch_auction_pool_should_reject_other_swaps() {
+	run_test(|| {
+		let pool_id = create_batch_auction_pool();
+
+		assert_noop!(
+			Dex::buy(Origin::signed(BOB), pool_id, ASSET_2, 100, 1_000, None),
+			Error::<Test>::BatchAuctionPool
+		);
+		assert_noop!(
+			Dex::swap_exact_out(Origin::signed(BOB), pool_id, ASSET_1, ASSET_2, 100, 1_000, None),
+			Error::<Test>::BatchAuctionPool
+		);
+		assert_noop!(
+			Dex::flash_swap(Origin::signed(ALICE), pool_id, 1_000, 0, vec![]),
+			Error::<Test>::BatchAuctionPool
+		);
+		assert_noop!(
+			<Dex as Amm>::sell(&BOB, pool_id, ASSET_1, 100),
+			Error::<Test>::BatchAuctionPool
+		);
+		assert_noop!(Dex::quote_best_route(ASSET_1, ASSET_2, 100), Error::<Test>::NoRoute);
+
+		assert_noop!(
+			Dex::swap_exact_in(Origin::signed(BOB), pool_id, ASSET_1, ASSET_3, 100, 0, None),
+			Error::<Test>::InvalidAsset
+		);
+		for _ in 0..4 {
+			assert_ok!(Dex::sell(Origin::signed(BOB), pool_id, ASSET_1, 100, 0, None));
+		}
+		assert_noop!(
+			Dex::sell(Origin::signed(BOB), pool_id, ASSET_1, 100, 0, None),
+			Error::<Test>::TooManyBatchOrders
+		);
+		Dex::on_finalize(System::block_number());
+	});
+}
+
+#[test]
+fn batch_orders_should_pay_for_their_clearing() {
+	run_test(|| {
+		// Nothing is reserved for the pools in batch auction mode
+		let pool_id = create_batch_auction_pool();
+		assert_eq!(Dex::on_initialize(System::block_number()), 0);
+
+		// The clearing price can be searched for again after every refunded order
+		let search_weight =
+			<() as WeightInfo>::batch_search_step() * (MAX_BATCH_SEARCH_STEPS as u64 + 1);
+		assert!(Dex::batch_order_weight() >= search_weight);
+		let pair = CurrencyPair { token_a: ASSET_1, token_b: ASSET_2 };
+		let calls = [
+			mock::Call::Dex(crate::Call::swap {
+				pool_id,
+				pair,
+				amount_b: 1_000,
+				min_amount_out: 0,
+				deadline: None,
+			}),
+			mock::Call::Dex(crate::Call::sell {
+				pool_id,
+				asset_id: ASSET_1,
+				amount: 1_000,
+				min_amount_out: 0,
+				deadline: None,
+			}),
+			mock::Call::Dex(crate::Call::swap_exact_in {
+				pool_id,
+				asset_in: ASSET_1,
+				asset_out: ASSET_2,
+				amount_in: 1_000,
+				min_amount_out: 0,
+				deadline: None,
+			}),
+		];
+		for call in calls {
+			assert!(call.get_dispatch_info().weight >= Dex::batch_order_weight());
+		}
+	});
+}
+
+#[test]
+fn migration_to_v1_should_translate_pools() {
+	run_test(|| {
+		let params = create_default_pool_params();
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), params));
+		let pool_id = 0;
+		let pool = Dex::pools(pool_id).unwrap();
+
+		// Store the pool in the v0 layout
+		let old_pool =
+			PoolV0 { owner: pool.owner, pair: pool.pair, lp_token: pool.lp_token, fee: pool.fee };
+		unhashed::put(&Pools::<Test>::hashed_key_for(pool_id), &old_pool);
+		PoolIdByPair::<Test>::remove((ASSET_1, ASSET_2), params.fee);
+		StorageVersion::new(0).put::<Dex>();
+		assert_eq!(Dex::pools(pool_id), None);
+
+		MigrateToV1::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Dex::on_chain_storage_version(), 1);
+		assert_eq!(
+			Dex::pools(pool_id),
+			Some(PoolOf::<Test> {
+				status: PoolStatus::Active,
+				kind: PoolKind::ConstantProduct,
+				..pool
+			})
+		);
+		assert_eq!(Dex::pool_for_pair(params.pair.swap(), params.fee), Some(pool_id));
+
+		// Running the migration again doesn't touch the storage
+		PoolIdByPair::<Test>::remove((ASSET_1, ASSET_2), params.fee);
+		MigrateToV1::<Test>::on_runtime_upgrade();
+		assert_eq!(Dex::pool_for_pair(params.pair, params.fee), None);
+		PoolIdByPair::<Test>::insert((ASSET_1, ASSET_2), params.fee, pool_id);
+	});
+}
+
+#[test]
+fn migration_to_v2_should_rebuild_pair_index() {
+	run_test(|| {
+		let params = create_default_pool_params();
+		let pool_id = create_pool_with_fee(params.fee);
+
+		// Index the pool in the v1 layout, which is keyed by the pair only
+		PoolIdByPair::<Test>::remove((ASSET_1, ASSET_2), params.fee);
+		let mut old_key = PoolIdByPair::<Test>::final_prefix().to_vec();
+		old_key.extend(Blake2_128Concat::hash(&(ASSET_1, ASSET_2).encode()));
+		unhashed::put(&old_key, &pool_id);
+		StorageVersion::new(1).put::<Dex>();
+
+		MigrateToV2::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Dex::on_chain_storage_version(), 2);
+		assert_eq!(unhashed::get_raw(&old_key), None);
+		assert_eq!(Dex::pool_for_pair(params.pair, params.fee), Some(pool_id));
+		assert_eq!(Dex::pools_for_pair(params.pair), vec![pool_id]);
+	});
+}
+
+#[test]
+fn migration_to_v3_should_move_observations() {
+	run_test(|| {
+		let pool_id = create_funded_pool_with_fee(Permill::from_percent(3), 100_000);
+		Observations::<Test>::remove(pool_id, 0);
+		let observation = |block_number: BlockNumber| Observation {
+			block_number,
+			price_a_cumulative: block_number as u128 * FixedU128::accuracy(),
+			price_b_cumulative: block_number as u128 * FixedU128::accuracy(),
+		};
+
+		// A full v2 ring buffer of the blocks 4 to 19 whose oldest observation is at position 3
+		let buffer: Vec<_> = (17..=19).chain(4..=16).map(observation).collect();
+		let mut old_key = Observations::<Test>::final_prefix().to_vec();
+		old_key.extend(Blake2_128Concat::hash(&pool_id.encode()));
+		unhashed::put(&old_key, &buffer);
+		ObservationIndex::<Test>::insert(pool_id, 3);
+		// The reserves changed after the last observation
+		PriceCumulatives::<Test>::insert(pool_id, observation(21));
+		StorageVersion::new(2).put::<Dex>();
+
+		MigrateToV3::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Dex::on_chain_storage_version(), 3);
+		assert_eq!(unhashed::get_raw(&old_key), None);
+		// The observations are ordered by block, the latest cumulatives replace the oldest one
+		assert_eq!(Dex::observation_index(pool_id), Some(15));
+		for index in 0..15 {
+			assert_eq!(Dex::observations(pool_id, index), Some(observation(index as u64 + 5)));
+		}
+		assert_eq!(Dex::observations(pool_id, 15), Some(observation(21)));
+	});
+}
+
+#[test]
+fn migration_to_v4_should_move_liquidity_tokens() {
+	run_test(|| {
+		let pool_id = create_funded_pool_with_fee(Permill::from_percent(3), 100_000);
+		let lp_token = Dex::lp_token(pool_id).unwrap();
+		assert_ok!(Tokens::transfer(Origin::signed(ALICE), BOB, lp_token, 40_000));
+
+		// Move the liquidity tokens back to the long symbol of the pair, as derived before v4
+		let old_lp_token = CurrencyId::Token(TokenSymbol::Long(*b"AAAABBBB"));
+		for (who, amount) in [(ALICE, 60_000), (BOB, 40_000)] {
+			assert_ok!(Tokens::withdraw(lp_token, &who, amount));
+			assert_ok!(Tokens::deposit(old_lp_token, &who, amount));
+		}
+		Pools::<Test>::mutate(pool_id, |pool| pool.as_mut().unwrap().lp_token = old_lp_token);
+
+		// A pool whose liquidity token is one of its own assets can't be migrated
+		let mut other_params = create_default_pool_params();
+		other_params.pair = CurrencyPair { token_a: ASSET_1, token_b: ASSET_3 };
+		assert_ok!(Dex::create_pool_with_liquidity(
+			Origin::signed(ALICE),
+			other_params,
+			1_000,
+			1_000
+		));
+		let other_pool_id = 1;
+		Pools::<Test>::mutate(other_pool_id, |pool| pool.as_mut().unwrap().lp_token = ASSET_1);
+		StorageVersion::new(3).put::<Dex>();
+
+		MigrateToV4::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Dex::on_chain_storage_version(), 4);
+		assert_eq!(Dex::lp_token(pool_id), Ok(lp_token));
+		assert_eq!(Tokens::free_balance(lp_token, &ALICE), 60_000);
+		assert_eq!(Tokens::free_balance(lp_token, &BOB), 40_000);
+		assert_eq!(Tokens::total_issuance(lp_token), 100_000);
+		assert_eq!(Tokens::free_balance(old_lp_token, &ALICE), 0);
+		assert_eq!(Tokens::total_issuance(old_lp_token), 0);
+		assert_eq!(Dex::lp_token(other_pool_id), Ok(ASSET_1));
+	});
+}
+
+#[test]
+fn genesis_config_should_create_pools_with_liquidity() {
+	let pool_params = create_default_pool_params();
+	let mut other_pool_params = create_default_pool_params();
+	other_pool_params.pair = CurrencyPair { token_a: ASSET_1, token_b: ASSET_3 };
+
+	new_test_ext_with_pools(vec![
+		(pool_params, vec![(ALICE, 100, 400), (ALICE, 50, 200)]),
+		(other_pool_params, vec![]),
+	])
+	.execute_with(|| {
+		assert_eq!(Dex::pool_count(), 2);
+		assert_eq!(Dex::pool_for_pair(pool_params.pair, pool_params.fee), Some(0));
+		assert_eq!(Dex::pool_for_pair(other_pool_params.pair, other_pool_params.fee), Some(1));
+
+		// The first deposit sets the price, the second one keeps it
+		assert_eq!(Dex::pool_reserves(0).unwrap(), (150, 600));
+		assert_eq!(Dex::pool_reserves(1).unwrap(), (0, 0));
+		let lp_token = Dex::pools(0).unwrap().lp_token;
+		assert_eq!(Tokens::free_balance(lp_token, &ALICE), 300);
+		assert_eq!(Tokens::free_balance(ASSET_2, &ALICE), 1_000_000 - 600);
+
+		assert_ok!(Dex::do_try_state());
+	});
+}
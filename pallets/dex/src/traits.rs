@@ -1,10 +1,15 @@
+use crate::{AccountIdOf, AssetIdOf, Config};
 use codec::{Decode, Encode, MaxEncodedLen};
-use frame_support::RuntimeDebug;
+use frame_support::{BoundedVec, RuntimeDebug};
 use scale_info::TypeInfo;
-use sp_runtime::{DispatchError, Permill};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{DispatchError, DispatchResult, FixedU128, Permill};
+use sp_std::vec::Vec;
 
 
 #[derive(RuntimeDebug, Encode, Decode, MaxEncodedLen, Copy, Clone, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct CurrencyPair<AssetId> {
 	pub token_a: AssetId,
 	pub token_b: AssetId,
@@ -20,6 +25,18 @@ impl<AssetId: Copy + PartialEq> CurrencyPair<AssetId> {
 	}
 }
 
+impl<AssetId: Copy + Ord> CurrencyPair<AssetId> {
+	/// Returns the pair with its assets in canonical (ascending) order. Two pairs that are equal
+	/// always have the same canonical form.
+	pub fn canonical(&self) -> Self {
+		if self.token_a <= self.token_b {
+			*self
+		} else {
+			self.swap()
+		}
+	}
+}
+
 impl<AssetId: PartialEq> PartialEq for CurrencyPair<AssetId> {
 	fn eq(&self, other: &Self) -> bool {
 		(self.token_a == other.token_a && self.token_b == other.token_b) ||
@@ -27,19 +44,154 @@ impl<AssetId: PartialEq> PartialEq for CurrencyPair<AssetId> {
 	}
 }
 
+/// Defines the curve that is used to price the assets of a pool.
+#[derive(RuntimeDebug, Encode, Decode, MaxEncodedLen, Copy, Clone, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum PoolKind {
+	/// Uniswap v2 style pool following `x * y = k`.
+	ConstantProduct,
+	/// Curve StableSwap pool for assets that are pegged to each other. The higher the
+	/// `amplification` coefficient, the lower the slippage close to the peg.
+	StableSwap { amplification: u32 },
+	/// Balancer style pool where the value of each asset is a fixed share of the pool's value.
+	/// The weights have to add up to 100%.
+	Weighted { weight_a: Permill, weight_b: Permill },
+	/// Uniswap v3 style pool where liquidity is provided to chosen price ranges. The ranges of
+	/// the positions start and end at ticks that are multiples of `tick_spacing`.
+	Concentrated { tick_spacing: u32 },
+}
+
+impl Default for PoolKind {
+	fn default() -> Self {
+		PoolKind::ConstantProduct
+	}
+}
+
 #[derive(RuntimeDebug, Encode, Decode, MaxEncodedLen, Copy, Clone, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct PoolCreationParams<AccountId, AssetId: Ord> {
 	pub owner: AccountId,
 	pub pair: CurrencyPair<AssetId>,
 	pub fee: Permill,
+	pub kind: PoolKind,
+	/// The square root of the price of token_a in token_b a concentrated-liquidity pool starts
+	/// at. Has to be set for concentrated-liquidity pools and left empty for all other kinds.
+	pub initial_sqrt_price: Option<FixedU128>,
+}
+
+/// Defines which operations are allowed on a pool. Liquidity can always be withdrawn.
+#[derive(RuntimeDebug, Encode, Decode, MaxEncodedLen, Copy, Clone, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum PoolStatus {
+	/// All operations are allowed.
+	Active,
+	/// Swaps are paused, liquidity can still be added and removed.
+	SwapsPaused,
+	/// Swaps and deposits are paused, liquidity can only be removed.
+	Paused,
+}
+
+impl Default for PoolStatus {
+	fn default() -> Self {
+		PoolStatus::Active
+	}
 }
 
 #[derive(RuntimeDebug, Encode, Decode, MaxEncodedLen, Copy, Clone, PartialEq, Eq, TypeInfo)]
-pub struct Pool<AccountId, AssetId: Ord> {
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct Pool<AccountId, AssetId> {
 	pub owner: AccountId,
 	pub pair: CurrencyPair<AssetId>,
 	pub lp_token: AssetId,
 	pub fee: Permill,
+	pub status: PoolStatus,
+	pub kind: PoolKind,
+}
+
+/// A pool holding a basket of three or more assets. All assets have the same weight, so the
+/// price between any two of them follows `x * y = k`.
+#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(T))]
+pub struct MultiAssetPool<T: Config> {
+	pub owner: AccountIdOf<T>,
+	pub assets: BoundedVec<AssetIdOf<T>, T::MaxAssetsInPool>,
+	pub lp_token: AssetIdOf<T>,
+	pub fee: Permill,
+	pub status: PoolStatus,
+}
+
+/// The price and the active liquidity of a concentrated-liquidity pool.
+#[derive(
+	RuntimeDebug, Encode, Decode, MaxEncodedLen, Copy, Clone, Default, PartialEq, Eq, TypeInfo,
+)]
+pub struct ConcentratedState {
+	/// Square root of the price of token_a in token_b.
+	pub sqrt_price: FixedU128,
+	/// The highest tick whose price is at most the current price.
+	pub tick: i32,
+	/// The liquidity of all positions whose range contains the current price.
+	pub liquidity: u128,
+	/// The fees in token_a earned per unit of liquidity over the lifetime of the pool. Stored as
+	/// the inner value of a `FixedU128` that wraps around on overflow.
+	pub fee_growth_global_a: u128,
+	/// The fees in token_b earned per unit of liquidity, see `fee_growth_global_a`.
+	pub fee_growth_global_b: u128,
+}
+
+/// The liquidity of the concentrated-liquidity positions that start or end at a tick.
+#[derive(
+	RuntimeDebug, Encode, Decode, MaxEncodedLen, Copy, Clone, Default, PartialEq, Eq, TypeInfo,
+)]
+pub struct TickInfo {
+	/// The total liquidity of the positions referencing the tick.
+	pub liquidity_gross: u128,
+	/// The change of the active liquidity when the price crosses the tick upwards.
+	pub liquidity_net: i128,
+	/// The fee growth of token_a on the other side of the tick than the current price.
+	pub fee_growth_outside_a: u128,
+	/// The fee growth of token_b on the other side of the tick than the current price.
+	pub fee_growth_outside_b: u128,
+}
+
+/// The liquidity an account provides to a price range of a concentrated-liquidity pool.
+#[derive(
+	RuntimeDebug, Encode, Decode, MaxEncodedLen, Copy, Clone, Default, PartialEq, Eq, TypeInfo,
+)]
+pub struct Position {
+	pub liquidity: u128,
+	/// The fee growth of token_a inside the range when the fees of the position were last
+	/// updated.
+	pub fee_growth_inside_a_last: u128,
+	/// The fee growth of token_b inside the range, see `fee_growth_inside_a_last`.
+	pub fee_growth_inside_b_last: u128,
+	/// The fees in token_a earned by the position that have not been collected yet.
+	pub fees_owed_a: u128,
+	/// The fees in token_b earned by the position that have not been collected yet.
+	pub fees_owed_b: u128,
+}
+
+/// Snapshot of the cumulative prices of a pool at a given block.
+#[derive(
+	RuntimeDebug, Encode, Decode, MaxEncodedLen, Copy, Clone, Default, PartialEq, Eq, TypeInfo,
+)]
+pub struct Observation<BlockNumber> {
+	pub block_number: BlockNumber,
+	/// Sum of the prices of token_a in token_b, each weighted by the number of blocks it was
+	/// valid for. Stored as the inner value of a `FixedU128` that wraps around on overflow.
+	pub price_a_cumulative: u128,
+	/// Sum of the prices of token_b in token_a, see `price_a_cumulative`.
+	pub price_b_cumulative: u128,
+}
+
+/// A swap that was queued for the batch auction of a pool. The `amount_in` is held by the pallet
+/// until the batch is cleared at the end of the block.
+#[derive(RuntimeDebug, Encode, Decode, MaxEncodedLen, Clone, PartialEq, Eq, TypeInfo)]
+pub struct BatchOrder<AccountId, AssetId, Balance> {
+	pub who: AccountId,
+	pub asset_in: AssetId,
+	pub amount_in: Balance,
+	/// The order is refunded if it would receive less than this at the clearing price.
+	pub min_amount_out: Balance,
 }
 
 pub trait Amm {
@@ -50,6 +202,12 @@ pub trait Amm {
 
 	fn pool_exists(pool_id: Self::PoolId) -> bool;
 
+	/// The pair pool of the pair with the given fee.
+	fn pool_for_pair(pair: CurrencyPair<Self::AssetId>, fee: Permill) -> Option<Self::PoolId>;
+
+	/// All pair pools of the pair, one for each fee.
+	fn pools_for_pair(pair: CurrencyPair<Self::AssetId>) -> Vec<Self::PoolId>;
+
 	fn currency_pair(pool_id: Self::PoolId) -> Result<CurrencyPair<Self::AssetId>, DispatchError>;
 
 	fn lp_token(pool_id: Self::PoolId) -> Result<Self::AssetId, DispatchError>;
@@ -64,6 +222,18 @@ pub trait Amm {
 		amount: Self::Balance,
 	) -> Result<Self::Balance, DispatchError>;
 
+	fn quote_buy(
+		pool_id: Self::PoolId,
+		asset_id: Self::AssetId,
+		amount: Self::Balance,
+	) -> Result<Self::Balance, DispatchError>;
+
+	fn quote_sell(
+		pool_id: Self::PoolId,
+		asset_id: Self::AssetId,
+		amount: Self::Balance,
+	) -> Result<Self::Balance, DispatchError>;
+
 	fn buy(
 		who: &Self::AccountId,
 		pool_id: Self::PoolId,
@@ -83,13 +253,13 @@ pub trait Amm {
 		pool_id: Self::PoolId,
 		amount: Self::Balance,
 		asset: Self::AssetId,
-	) -> Result<(), DispatchError>;
+	) -> Result<Self::Balance, DispatchError>;
 
 	fn remove_liquidity(
 		who: &Self::AccountId,
 		pool_id: Self::PoolId,
 		lp_amount: Self::Balance,
-	) -> Result<(), DispatchError>;
+	) -> Result<(Self::Balance, Self::Balance), DispatchError>;
 
 	fn swap(
 		who: &Self::AccountId,
@@ -97,4 +267,115 @@ pub trait Amm {
 		pair: CurrencyPair<Self::AssetId>,
 		amount_b: Self::Balance,
 	) -> Result<Self::Balance, DispatchError>;
+
+	/// Returns the assets of a pair or multi-asset pool.
+	fn pool_assets(pool_id: Self::PoolId) -> Result<Vec<Self::AssetId>, DispatchError>;
+
+	/// Calculates the amount of `asset_out` received when selling `amount_in` of `asset_in`.
+	fn quote_exact_in(
+		pool_id: Self::PoolId,
+		asset_in: Self::AssetId,
+		asset_out: Self::AssetId,
+		amount_in: Self::Balance,
+	) -> Result<Self::Balance, DispatchError>;
+
+	/// Calculates the amount of `asset_in` that has to be paid to buy `amount_out` of
+	/// `asset_out`.
+	fn quote_exact_out(
+		pool_id: Self::PoolId,
+		asset_in: Self::AssetId,
+		asset_out: Self::AssetId,
+		amount_out: Self::Balance,
+	) -> Result<Self::Balance, DispatchError>;
+
+	/// Sells `amount_in` of `asset_in` for `asset_out` and returns the amount received.
+	fn swap_exact_in(
+		who: &Self::AccountId,
+		pool_id: Self::PoolId,
+		asset_in: Self::AssetId,
+		asset_out: Self::AssetId,
+		amount_in: Self::Balance,
+	) -> Result<Self::Balance, DispatchError>;
+
+	/// Buys `amount_out` of `asset_out` with `asset_in` and returns the amount paid.
+	fn swap_exact_out(
+		who: &Self::AccountId,
+		pool_id: Self::PoolId,
+		asset_in: Self::AssetId,
+		asset_out: Self::AssetId,
+		amount_out: Self::Balance,
+	) -> Result<Self::Balance, DispatchError>;
+
+	/// Returns whether the swaps of the pool are executed in batch auctions. Such pools can't be
+	/// swapped on through this trait.
+	fn is_batch_auction_pool(pool_id: Self::PoolId) -> bool;
+}
+
+/// Provides prices that are resistant to manipulation within a single block.
+///
+/// The observations behind the prices are recorded lazily on the first change of the reserves
+/// of a pool in a block, not in `on_initialize`, because updating every pool there would be
+/// unbounded in the number of pools. A pool without trades therefore has no observation for
+/// most blocks, and the prices in between are interpolated.
+pub trait Oracle {
+	type AssetId;
+	type PoolId;
+	type BlockNumber;
+
+	/// Returns the time-weighted average price of `asset_id` in terms of the other asset of the
+	/// pool. The average is taken over at least the last `window` blocks.
+	fn twap(
+		pool_id: Self::PoolId,
+		asset_id: Self::AssetId,
+		window: Self::BlockNumber,
+	) -> Result<FixedU128, DispatchError>;
+}
+
+/// Hook that is called after a swap changed the price of a pool.
+pub trait OnSwap<PoolId> {
+	fn on_swap(pool_id: PoolId);
+}
+
+impl<PoolId> OnSwap<PoolId> for () {
+	fn on_swap(_pool_id: PoolId) {}
+}
+
+/// Callback of a flash swap. It is called after the borrowed amounts were sent to `who` and has
+/// to pay the pool back, plus the fee, before it returns.
+pub trait FlashSwapHandler<AccountId, AssetId, Balance, PoolId> {
+	fn on_flash_swap(
+		who: &AccountId,
+		pool_id: PoolId,
+		pair: CurrencyPair<AssetId>,
+		amount_a_out: Balance,
+		amount_b_out: Balance,
+		data: &[u8],
+	) -> DispatchResult;
+}
+
+impl<AccountId, AssetId, Balance, PoolId> FlashSwapHandler<AccountId, AssetId, Balance, PoolId>
+	for ()
+{
+	fn on_flash_swap(
+		_who: &AccountId,
+		_pool_id: PoolId,
+		_pair: CurrencyPair<AssetId>,
+		_amount_a_out: Balance,
+		_amount_b_out: Balance,
+		_data: &[u8],
+	) -> DispatchResult {
+		Ok(())
+	}
+}
+
+/// Prepares the state the benchmarks depend on.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AssetId> {
+	/// Makes sure that pools can be created for the asset, e.g. by registering it.
+	fn register_asset(asset_id: AssetId);
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl<AssetId> BenchmarkHelper<AssetId> for () {
+	fn register_asset(_asset_id: AssetId) {}
 }
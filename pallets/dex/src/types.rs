@@ -2,9 +2,10 @@
 
 
 use crate::{
-	traits::{Pool, PoolCreationParams},
+	traits::{BatchOrder, MultiAssetPool, Observation, Pool, PoolCreationParams},
 	Config,
 };
+use sp_std::vec::Vec;
 
 pub(crate) type BalanceOf<T> = <T as Config>::Balance;
 pub(crate) type AssetIdOf<T> = <T as Config>::AssetId;
@@ -12,3 +13,9 @@ pub(crate) type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
 pub(crate) type PoolOf<T> = Pool<AccountIdOf<T>, AssetIdOf<T>>;
 pub(crate) type PoolCreationParamsOf<T> = PoolCreationParams<AccountIdOf<T>, AssetIdOf<T>>;
 pub(crate) type PoolIdOf<T> = <T as Config>::PoolId;
+pub(crate) type ObservationOf<T> = Observation<<T as frame_system::Config>::BlockNumber>;
+pub(crate) type MultiAssetPoolOf<T> = MultiAssetPool<T>;
+pub(crate) type BatchOrderOf<T> = BatchOrder<AccountIdOf<T>, AssetIdOf<T>, BalanceOf<T>>;
+/// A pool created at genesis and the `(provider, amount_a, amount_b)` deposits made into it.
+pub(crate) type GenesisPoolOf<T> =
+	(PoolCreationParamsOf<T>, Vec<(AccountIdOf<T>, BalanceOf<T>, BalanceOf<T>)>);
@@ -0,0 +1,443 @@
+//! Placeholder weights for pallet_dex
+//!
+//! The values were estimated by hand and have not been measured. The times are rounded guesses.
+//! The storage annotations list the accesses of the most expensive path through each call with
+//! the configuration of the runtime in this repository, every key is counted once and a component
+//! like `n` stands for the value of the component of the call. Replace this file with the output
+//! of the benchmarks in `benchmarking.rs` on the reference hardware before relying on the
+//! weights, e.g. with
+//! `./target/release/node-template benchmark pallet --chain=dev --steps=50 --repeat=20
+//! --pallet=pallet_dex --extrinsic=* --execution=wasm --wasm-execution=compiled
+//! --output=pallets/dex/src/weights.rs`.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_dex.
+pub trait WeightInfo {
+	fn create_pool() -> Weight;
+	fn create_pool_with_liquidity() -> Weight;
+	fn create_multi_asset_pool(n: u32, ) -> Weight;
+	fn create_multi_asset_pool_with_liquidity(n: u32, ) -> Weight;
+	fn add_liquidity() -> Weight;
+	fn add_multi_asset_liquidity(n: u32, ) -> Weight;
+	fn remove_liquidity() -> Weight;
+	fn add_single_asset_liquidity(n: u32, ) -> Weight;
+	fn remove_multi_asset_liquidity(n: u32, ) -> Weight;
+	fn remove_single_asset_liquidity(n: u32, ) -> Weight;
+	fn mint_position(t: u32, ) -> Weight;
+	fn burn_position(t: u32, ) -> Weight;
+	fn collect_fees() -> Weight;
+	fn swap() -> Weight;
+	fn swap_concentrated(t: u32, ) -> Weight;
+	fn swap_multi_asset(n: u32, ) -> Weight;
+	fn flash_swap(l: u32, ) -> Weight;
+	fn set_pool_fee() -> Weight;
+	fn set_pool_status() -> Weight;
+	fn transfer_pool_ownership() -> Weight;
+	fn batch_search_step() -> Weight;
+}
+
+/// Placeholder weights for pallet_dex, see the module documentation.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: AssetRegistry Assets (r:2 w:0)
+	// Storage: Dex Pools (r:2 w:1)
+	// Storage: Dex MultiAssetPools (r:2 w:0)
+	// Storage: Dex PoolIdByPair (r:4 w:1)
+	// Storage: Dex PoolCount (r:1 w:1)
+	// Storage: Dex PoolAccounts (r:0 w:1)
+	// Storage: Dex ConcentratedStates (r:0 w:1)
+	fn create_pool() -> Weight {
+		(25_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(11 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	// Storage: AssetRegistry Assets (r:3 w:0)
+	// Storage: Dex Pools (r:2 w:1)
+	// Storage: Dex MultiAssetPools (r:2 w:0)
+	// Storage: Dex PoolIdByPair (r:4 w:1)
+	// Storage: Dex PoolCount (r:1 w:1)
+	// Storage: Dex PoolAccounts (r:0 w:1)
+	// Storage: Dex FlashSwapLocks (r:1 w:0)
+	// Storage: Tokens Accounts (r:6 w:6)
+	// Storage: Dex PriceCumulatives (r:1 w:1)
+	// Storage: Dex ObservationIndex (r:1 w:1)
+	// Storage: Dex Observations (r:0 w:1)
+	// Storage: Dex InvariantLast (r:1 w:1)
+	// Storage: Tokens TotalIssuance (r:1 w:1)
+	// Storage: System Account (r:3 w:3)
+	fn create_pool_with_liquidity() -> Weight {
+		(100_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(26 as Weight))
+			.saturating_add(T::DbWeight::get().writes(18 as Weight))
+	}
+	// Storage: AssetRegistry Assets (r:n w:0)
+	// Storage: Dex Pools (r:n w:0)
+	// Storage: Dex MultiAssetPools (r:n w:1)
+	// Storage: Dex PoolCount (r:1 w:1)
+	// Storage: Dex PoolAccounts (r:0 w:1)
+	fn create_multi_asset_pool(n: u32, ) -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add((350_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	// Storage: AssetRegistry Assets (r:1+n w:0)
+	// Storage: Dex Pools (r:n w:0)
+	// Storage: Dex MultiAssetPools (r:n w:1)
+	// Storage: Dex PoolCount (r:1 w:1)
+	// Storage: Dex PoolAccounts (r:0 w:1)
+	// Storage: Tokens Accounts (r:2+2n w:2+2n)
+	// Storage: Tokens TotalIssuance (r:1 w:1)
+	// Storage: System Account (r:3 w:3)
+	fn create_multi_asset_pool_with_liquidity(n: u32, ) -> Weight {
+		(60_000_000 as Weight)
+			.saturating_add((25_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(8 as Weight))
+			.saturating_add(T::DbWeight::get().reads((5 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(9 as Weight))
+			.saturating_add(T::DbWeight::get().writes((2 as Weight).saturating_mul(n as Weight)))
+	}
+	// Storage: Dex MultiAssetPools (r:1 w:0)
+	// Storage: Dex Pools (r:1 w:0)
+	// Storage: Dex FlashSwapLocks (r:1 w:0)
+	// Storage: AssetRegistry Assets (r:3 w:0)
+	// Storage: Tokens Accounts (r:6 w:6)
+	// Storage: Dex PriceCumulatives (r:1 w:1)
+	// Storage: Dex ObservationIndex (r:1 w:1)
+	// Storage: Dex Observations (r:0 w:1)
+	// Storage: Dex InvariantLast (r:1 w:1)
+	// Storage: Tokens TotalIssuance (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn add_liquidity() -> Weight {
+		(85_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(17 as Weight))
+			.saturating_add(T::DbWeight::get().writes(12 as Weight))
+	}
+	// Storage: Dex MultiAssetPools (r:1 w:0)
+	// Storage: AssetRegistry Assets (r:1+n w:0)
+	// Storage: Tokens Accounts (r:1+2n w:1+2n)
+	// Storage: Tokens TotalIssuance (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn add_multi_asset_liquidity(n: u32, ) -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add((25_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes((2 as Weight).saturating_mul(n as Weight)))
+	}
+	// Storage: Dex Pools (r:1 w:0)
+	// Storage: Dex FlashSwapLocks (r:1 w:0)
+	// Storage: AssetRegistry Assets (r:3 w:0)
+	// Storage: Tokens Accounts (r:6 w:6)
+	// Storage: Tokens TotalIssuance (r:1 w:1)
+	// Storage: Dex PriceCumulatives (r:1 w:1)
+	// Storage: Dex ObservationIndex (r:1 w:1)
+	// Storage: Dex Observations (r:0 w:1)
+	// Storage: Dex InvariantLast (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn remove_liquidity() -> Weight {
+		(85_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(16 as Weight))
+			.saturating_add(T::DbWeight::get().writes(12 as Weight))
+	}
+	// Storage: Dex MultiAssetPools (r:1 w:0)
+	// Storage: AssetRegistry Assets (r:2 w:0)
+	// Storage: Tokens Accounts (r:3 w:3)
+	// Storage: Tokens TotalIssuance (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn add_single_asset_liquidity(n: u32, ) -> Weight {
+		(45_000_000 as Weight)
+			.saturating_add((4_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(8 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	// Storage: Dex MultiAssetPools (r:1 w:0)
+	// Storage: AssetRegistry Assets (r:1+n w:0)
+	// Storage: Tokens Accounts (r:1+2n w:1+2n)
+	// Storage: Tokens TotalIssuance (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn remove_multi_asset_liquidity(n: u32, ) -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add((25_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes((2 as Weight).saturating_mul(n as Weight)))
+	}
+	// Storage: Dex MultiAssetPools (r:1 w:0)
+	// Storage: AssetRegistry Assets (r:2 w:0)
+	// Storage: Tokens Accounts (r:3 w:3)
+	// Storage: Tokens TotalIssuance (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn remove_single_asset_liquidity(n: u32, ) -> Weight {
+		(45_000_000 as Weight)
+			.saturating_add((4_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(8 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	// Storage: Dex Pools (r:1 w:0)
+	// Storage: Dex ConcentratedStates (r:1 w:1)
+	// Storage: Dex Ticks (r:2 w:2)
+	// Storage: Dex InitializedTicks (r:1 w:1)
+	// Storage: Dex Positions (r:1 w:1)
+	// Storage: AssetRegistry Assets (r:2 w:0)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: Dex PriceCumulatives (r:1 w:1)
+	// Storage: Dex ObservationIndex (r:1 w:1)
+	// Storage: Dex Observations (r:0 w:1)
+	fn mint_position(t: u32, ) -> Weight {
+		(70_000_000 as Weight)
+			.saturating_add((120_000 as Weight).saturating_mul(t as Weight))
+			.saturating_add(T::DbWeight::get().reads(14 as Weight))
+			.saturating_add(T::DbWeight::get().writes(12 as Weight))
+	}
+	// Storage: Dex Pools (r:1 w:0)
+	// Storage: Dex Positions (r:1 w:1)
+	// Storage: Dex ConcentratedStates (r:1 w:1)
+	// Storage: Dex Ticks (r:2 w:2)
+	// Storage: Dex InitializedTicks (r:1 w:1)
+	// Storage: AssetRegistry Assets (r:2 w:0)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: Dex PriceCumulatives (r:1 w:1)
+	// Storage: Dex ObservationIndex (r:1 w:1)
+	// Storage: Dex Observations (r:0 w:1)
+	fn burn_position(t: u32, ) -> Weight {
+		(70_000_000 as Weight)
+			.saturating_add((120_000 as Weight).saturating_mul(t as Weight))
+			.saturating_add(T::DbWeight::get().reads(14 as Weight))
+			.saturating_add(T::DbWeight::get().writes(12 as Weight))
+	}
+	// Storage: Dex Pools (r:1 w:0)
+	// Storage: Dex Positions (r:1 w:1)
+	// Storage: Dex ConcentratedStates (r:1 w:1)
+	// Storage: Dex Ticks (r:2 w:0)
+	// Storage: AssetRegistry Assets (r:2 w:0)
+	// Storage: Tokens Accounts (r:4 w:4)
+	fn collect_fees() -> Weight {
+		(50_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(11 as Weight))
+			.saturating_add(T::DbWeight::get().writes(6 as Weight))
+	}
+	// Storage: Dex MultiAssetPools (r:1 w:0)
+	// Storage: Dex Pools (r:1 w:0)
+	// Storage: Dex BatchAuctionPools (r:1 w:0)
+	// Storage: Dex FlashSwapLocks (r:1 w:0)
+	// Storage: AssetRegistry Assets (r:2 w:0)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: Dex PriceCumulatives (r:1 w:1)
+	// Storage: Dex ObservationIndex (r:1 w:1)
+	// Storage: Dex Observations (r:0 w:1)
+	// Storage: Orderbook OrdersByPool (r:1 w:0)
+	// Storage: Orderbook PoolsToMatch (r:0 w:1)
+	fn swap() -> Weight {
+		(100_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(13 as Weight))
+			.saturating_add(T::DbWeight::get().writes(8 as Weight))
+	}
+	// Storage: Dex MultiAssetPools (r:1 w:0)
+	// Storage: Dex Pools (r:1 w:0)
+	// Storage: Dex ConcentratedStates (r:1 w:1)
+	// Storage: Dex InitializedTicks (r:1 w:0)
+	// Storage: Dex Ticks (r:t w:t)
+	// Storage: AssetRegistry Assets (r:2 w:0)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: Dex PriceCumulatives (r:1 w:1)
+	// Storage: Dex ObservationIndex (r:1 w:1)
+	// Storage: Dex Observations (r:0 w:1)
+	// Storage: Orderbook OrdersByPool (r:1 w:0)
+	// Storage: Orderbook PoolsToMatch (r:0 w:1)
+	fn swap_concentrated(t: u32, ) -> Weight {
+		(60_000_000 as Weight)
+			.saturating_add((9_000_000 as Weight).saturating_mul(t as Weight))
+			.saturating_add(T::DbWeight::get().reads(13 as Weight))
+			.saturating_add(T::DbWeight::get().reads((1 as Weight).saturating_mul(t as Weight)))
+			.saturating_add(T::DbWeight::get().writes(9 as Weight))
+			.saturating_add(T::DbWeight::get().writes((1 as Weight).saturating_mul(t as Weight)))
+	}
+	// Storage: Dex MultiAssetPools (r:1 w:0)
+	// Storage: AssetRegistry Assets (r:2 w:0)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: Orderbook OrdersByPool (r:1 w:0)
+	// Storage: Orderbook PoolsToMatch (r:0 w:1)
+	fn swap_multi_asset(n: u32, ) -> Weight {
+		(50_000_000 as Weight)
+			.saturating_add((4_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(8 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	// Storage: Dex Pools (r:1 w:0)
+	// Storage: Dex BatchAuctionPools (r:1 w:0)
+	// Storage: Dex FlashSwapLocks (r:1 w:1)
+	// Storage: AssetRegistry Assets (r:2 w:0)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: Dex PriceCumulatives (r:1 w:1)
+	// Storage: Dex ObservationIndex (r:1 w:1)
+	// Storage: Dex Observations (r:0 w:1)
+	// Storage: Orderbook OrdersByPool (r:1 w:0)
+	// Storage: Orderbook PoolsToMatch (r:0 w:1)
+	fn flash_swap(l: u32, ) -> Weight {
+		(90_000_000 as Weight)
+			.saturating_add((1_000 as Weight).saturating_mul(l as Weight))
+			.saturating_add(T::DbWeight::get().reads(12 as Weight))
+			.saturating_add(T::DbWeight::get().writes(9 as Weight))
+	}
+	// Storage: Dex Pools (r:1 w:1)
+	// Storage: Dex PoolIdByPair (r:1 w:2)
+	fn set_pool_fee() -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	// Storage: Dex Pools (r:1 w:0)
+	// Storage: Dex MultiAssetPools (r:1 w:1)
+	fn set_pool_status() -> Weight {
+		(17_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: Dex Pools (r:1 w:0)
+	// Storage: Dex MultiAssetPools (r:1 w:1)
+	fn transfer_pool_ownership() -> Weight {
+		(18_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn batch_search_step() -> Weight {
+		(15_000_000 as Weight)
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn create_pool() -> Weight {
+		(25_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(11 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+	fn create_pool_with_liquidity() -> Weight {
+		(100_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(26 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(18 as Weight))
+	}
+	fn create_multi_asset_pool(n: u32, ) -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add((350_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	fn create_multi_asset_pool_with_liquidity(n: u32, ) -> Weight {
+		(60_000_000 as Weight)
+			.saturating_add((25_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(8 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((5 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(9 as Weight))
+			.saturating_add(RocksDbWeight::get().writes((2 as Weight).saturating_mul(n as Weight)))
+	}
+	fn add_liquidity() -> Weight {
+		(85_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(17 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(12 as Weight))
+	}
+	fn add_multi_asset_liquidity(n: u32, ) -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add((25_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes((2 as Weight).saturating_mul(n as Weight)))
+	}
+	fn remove_liquidity() -> Weight {
+		(85_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(16 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(12 as Weight))
+	}
+	fn add_single_asset_liquidity(n: u32, ) -> Weight {
+		(45_000_000 as Weight)
+			.saturating_add((4_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(8 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+	fn remove_multi_asset_liquidity(n: u32, ) -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add((25_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes((2 as Weight).saturating_mul(n as Weight)))
+	}
+	fn remove_single_asset_liquidity(n: u32, ) -> Weight {
+		(45_000_000 as Weight)
+			.saturating_add((4_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(8 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+	fn mint_position(t: u32, ) -> Weight {
+		(70_000_000 as Weight)
+			.saturating_add((120_000 as Weight).saturating_mul(t as Weight))
+			.saturating_add(RocksDbWeight::get().reads(14 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(12 as Weight))
+	}
+	fn burn_position(t: u32, ) -> Weight {
+		(70_000_000 as Weight)
+			.saturating_add((120_000 as Weight).saturating_mul(t as Weight))
+			.saturating_add(RocksDbWeight::get().reads(14 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(12 as Weight))
+	}
+	fn collect_fees() -> Weight {
+		(50_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(11 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(6 as Weight))
+	}
+	fn swap() -> Weight {
+		(100_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(13 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(8 as Weight))
+	}
+	fn swap_concentrated(t: u32, ) -> Weight {
+		(60_000_000 as Weight)
+			.saturating_add((9_000_000 as Weight).saturating_mul(t as Weight))
+			.saturating_add(RocksDbWeight::get().reads(13 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((1 as Weight).saturating_mul(t as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(9 as Weight))
+			.saturating_add(RocksDbWeight::get().writes((1 as Weight).saturating_mul(t as Weight)))
+	}
+	fn swap_multi_asset(n: u32, ) -> Weight {
+		(50_000_000 as Weight)
+			.saturating_add((4_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(8 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+	fn flash_swap(l: u32, ) -> Weight {
+		(90_000_000 as Weight)
+			.saturating_add((1_000 as Weight).saturating_mul(l as Weight))
+			.saturating_add(RocksDbWeight::get().reads(12 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(9 as Weight))
+	}
+	fn set_pool_fee() -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	fn set_pool_status() -> Weight {
+		(17_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn transfer_pool_ownership() -> Weight {
+		(18_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn batch_search_step() -> Weight {
+		(15_000_000 as Weight)
+	}
+}
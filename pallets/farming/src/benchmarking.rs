@@ -0,0 +1,107 @@
+//! Benchmarking setup for pallet-farming
+
+use super::*;
+
+use crate::Pallet as Farming;
+use frame_benchmarking::{benchmarks, whitelisted_caller};
+use frame_support::traits::{EnsureOrigin, Get};
+use frame_system::RawOrigin;
+
+/// The amount of every reward asset the farm account is funded with.
+const FUNDS: u32 = 1_000_000_000;
+/// The amount of every reward asset emitted per block.
+const REWARD_PER_BLOCK: u32 = 1_000;
+/// The amount of LP tokens that is staked, withdrawn or added to a stake.
+const AMOUNT: u32 = 1_000;
+
+/// The schedules of the first `n` reward assets.
+fn rewards<T: Config>(n: u32) -> Vec<(AssetIdOf<T>, BalanceOf<T>)> {
+	(0..n)
+		.map(|i| (T::BenchmarkHelper::reward_asset(i), REWARD_PER_BLOCK.into()))
+		.collect()
+}
+
+/// Creates a funded farm with `n` reward assets for a new pool whose LP tokens are held by
+/// `owner`.
+fn create_farm<T: Config>(owner: &T::AccountId, n: u32) -> PoolIdOf<T> {
+	let pool_id = T::BenchmarkHelper::create_pool(owner);
+	Farming::<T>::create_farm(T::AdminOrigin::successful_origin(), pool_id, rewards::<T>(n))
+		.unwrap();
+	for i in 0..n {
+		let asset_id = T::BenchmarkHelper::reward_asset(i);
+		T::Assets::deposit(asset_id, &Farming::<T>::farm_account(pool_id), FUNDS.into()).unwrap();
+	}
+	pool_id
+}
+
+/// Stakes `AMOUNT` LP tokens of `who` and moves ten blocks ahead, so the stake earned rewards.
+fn stake<T: Config>(who: &T::AccountId, pool_id: PoolIdOf<T>) {
+	Farming::<T>::deposit(RawOrigin::Signed(who.clone()).into(), pool_id, AMOUNT.into()).unwrap();
+	let n = frame_system::Pallet::<T>::block_number();
+	frame_system::Pallet::<T>::set_block_number(n + 10u32.into());
+}
+
+benchmarks! {
+	create_farm {
+		let n in 1 .. T::MaxRewardsPerFarm::get();
+		let caller: T::AccountId = whitelisted_caller();
+		let pool_id = T::BenchmarkHelper::create_pool(&caller);
+		let origin = T::AdminOrigin::successful_origin();
+	}: _<T::Origin>(origin, pool_id, rewards::<T>(n))
+	verify {
+		assert_eq!(Farms::<T>::get(pool_id).unwrap().rewards.len() as u32, n);
+	}
+
+	// A new reward asset is added to a farm with staked LP tokens
+	set_reward_schedule {
+		let max_rewards = T::MaxRewardsPerFarm::get();
+		let caller: T::AccountId = whitelisted_caller();
+		let pool_id = create_farm::<T>(&caller, max_rewards - 1);
+		stake::<T>(&caller, pool_id);
+		let asset_id = T::BenchmarkHelper::reward_asset(max_rewards - 1);
+		let origin = T::AdminOrigin::successful_origin();
+	}: _<T::Origin>(origin, pool_id, asset_id, REWARD_PER_BLOCK.into())
+	verify {
+		assert_eq!(Farms::<T>::get(pool_id).unwrap().rewards.len() as u32, max_rewards);
+	}
+
+	// The existing stake earned rewards in all reward assets
+	deposit {
+		let caller: T::AccountId = whitelisted_caller();
+		let pool_id = create_farm::<T>(&caller, T::MaxRewardsPerFarm::get());
+		stake::<T>(&caller, pool_id);
+	}: _(RawOrigin::Signed(caller.clone()), pool_id, AMOUNT.into())
+	verify {
+		let staked: BalanceOf<T> = (2 * AMOUNT).into();
+		assert_eq!(Stakes::<T>::get(pool_id, &caller).unwrap().amount, staked);
+	}
+
+	// Part of the stake is withdrawn, so the lock is updated instead of removed
+	withdraw {
+		let caller: T::AccountId = whitelisted_caller();
+		let pool_id = create_farm::<T>(&caller, T::MaxRewardsPerFarm::get());
+		stake::<T>(&caller, pool_id);
+		stake::<T>(&caller, pool_id);
+	}: _(RawOrigin::Signed(caller.clone()), pool_id, AMOUNT.into())
+	verify {
+		let staked: BalanceOf<T> = AMOUNT.into();
+		assert_eq!(Stakes::<T>::get(pool_id, &caller).unwrap().amount, staked);
+	}
+
+	claim {
+		let n in 1 .. T::MaxRewardsPerFarm::get();
+		let caller: T::AccountId = whitelisted_caller();
+		let pool_id = create_farm::<T>(&caller, n);
+		stake::<T>(&caller, pool_id);
+	}: _(RawOrigin::Signed(caller.clone()), pool_id)
+	verify {
+		let pending = Farming::<T>::pending_rewards(pool_id, &caller);
+		assert!(pending.iter().all(|(_, amount)| amount.is_zero()));
+	}
+
+	impl_benchmark_test_suite!(
+		Farming,
+		crate::mock::new_test_ext_multi_currency(),
+		crate::mock::Test
+	);
+}
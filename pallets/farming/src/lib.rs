@@ -0,0 +1,477 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::FullCodec;
+use frame_support::{
+	dispatch::DispatchResult, ensure, traits::LockIdentifier, transactional, PalletId,
+};
+use orml_traits::{MultiCurrency, MultiLockableCurrency};
+use pallet_dex::traits::Amm;
+use sp_runtime::{
+	traits::{
+		AccountIdConversion, AtLeast32BitUnsigned, CheckedAdd, Saturating, UniqueSaturatedInto,
+		Zero,
+	},
+	ArithmeticError, FixedPointNumber, FixedPointOperand, FixedU128,
+};
+use sp_std::{fmt::Debug, vec::Vec};
+
+pub use pallet::*;
+pub use types::{Farm, RewardSchedule, Stake, StakeReward};
+pub use weights::WeightInfo;
+
+use types::*;
+
+mod types;
+pub mod weights;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// The identifier of the locks on the staked LP tokens. An account can stake the LP token of a
+/// pool in a single farm only, so one lock per LP token is enough.
+pub const FARMING_LOCK_ID: LockIdentifier = *b"farming ";
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	use super::*;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		type Balance: AtLeast32BitUnsigned
+			+ FixedPointOperand
+			+ MaybeSerializeDeserialize
+			+ FullCodec
+			+ Copy
+			+ Default
+			+ TypeInfo
+			+ MaxEncodedLen
+			+ Debug;
+
+		/// The type of assets used by the Assets handler.
+		type AssetId: FullCodec
+			+ MaxEncodedLen
+			+ Eq
+			+ PartialEq
+			+ Copy
+			+ Clone
+			+ MaybeSerializeDeserialize
+			+ Debug
+			+ Default
+			+ TypeInfo
+			+ Ord;
+
+		/// The type of a pools ID
+		type PoolId: FullCodec + MaxEncodedLen + Debug + TypeInfo + Eq + PartialEq + Copy;
+
+		/// The MultiCurrency handler, used to lock the staked LP tokens and to pay the rewards.
+		type Assets: MultiLockableCurrency<
+			Self::AccountId,
+			Balance = BalanceOf<Self>,
+			CurrencyId = Self::AssetId,
+		>;
+
+		/// The AMM whose LP tokens are staked.
+		type Amm: Amm<
+			AccountId = Self::AccountId,
+			AssetId = Self::AssetId,
+			Balance = BalanceOf<Self>,
+			PoolId = Self::PoolId,
+		>;
+
+		/// The id used to derive the accounts that hold the rewards of the farms.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// The origin that can create farms and change their reward schedules.
+		type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The maximum number of reward assets of a farm.
+		#[pallet::constant]
+		type MaxRewardsPerFarm: Get<u32>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Creates the pools and reward assets of the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<Self::AccountId, Self::AssetId, Self::PoolId>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub (super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// Map the pool id to the farm for the LP token of the pool.
+	#[pallet::storage]
+	#[pallet::getter(fn farms)]
+	pub type Farms<T: Config> = StorageMap<_, Blake2_128Concat, PoolIdOf<T>, Farm<T>>;
+
+	/// The stakes of the accounts in the farm of a pool.
+	#[pallet::storage]
+	#[pallet::getter(fn stakes)]
+	pub type Stakes<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		PoolIdOf<T>,
+		Blake2_128Concat,
+		AccountIdOf<T>,
+		Stake<T>,
+	>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub (super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A farm was created for the LP token of a pool.
+		FarmCreated { pool_id: PoolIdOf<T>, lp_token: AssetIdOf<T> },
+		/// The emission of a reward asset of a farm was changed.
+		RewardScheduleUpdated {
+			pool_id: PoolIdOf<T>,
+			asset_id: AssetIdOf<T>,
+			reward_per_block: BalanceOf<T>,
+		},
+		/// LP tokens were staked in a farm.
+		Deposited { pool_id: PoolIdOf<T>, who: T::AccountId, amount: BalanceOf<T> },
+		/// Staked LP tokens were withdrawn from a farm.
+		Withdrawn { pool_id: PoolIdOf<T>, who: T::AccountId, amount: BalanceOf<T> },
+		/// The rewards of a stake were paid out.
+		RewardPaid {
+			pool_id: PoolIdOf<T>,
+			who: T::AccountId,
+			asset_id: AssetIdOf<T>,
+			amount: BalanceOf<T>,
+		},
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// There already is a farm for the pool.
+		FarmAlreadyExists,
+		/// There is no farm for the pool.
+		FarmNotFound,
+		/// A reward asset was given more than once.
+		DuplicateRewardAsset,
+		/// The farm has reached the maximum number of reward assets.
+		TooManyRewards,
+		/// The amount can't be zero.
+		InvalidAmount,
+		/// The account doesn't hold enough LP tokens.
+		InsufficientBalance,
+		/// The account has no stake in the farm.
+		StakeNotFound,
+		/// The stake is smaller than the amount that should be withdrawn.
+		InsufficientStake,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Create a farm for the LP token of the given pool, emitting `reward_per_block` of each
+		/// of the given reward assets.
+		///
+		/// The rewards are paid from the account of the farm, see `farm_account`, which has to be
+		/// funded separately.
+		///
+		/// Can only be called by the `AdminOrigin`.
+		///
+		/// Emits `FarmCreated` event when successful.
+		#[pallet::weight(T::WeightInfo::create_farm(rewards.len() as u32))]
+		pub fn create_farm(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			rewards: Vec<(AssetIdOf<T>, BalanceOf<T>)>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			ensure!(!Farms::<T>::contains_key(pool_id), Error::<T>::FarmAlreadyExists);
+			let lp_token = T::Amm::lp_token(pool_id)?;
+
+			let mut schedules: BoundedVec<RewardScheduleOf<T>, T::MaxRewardsPerFarm> =
+				Default::default();
+			for (asset_id, reward_per_block) in rewards {
+				ensure!(
+					!schedules.iter().any(|schedule| schedule.asset_id == asset_id),
+					Error::<T>::DuplicateRewardAsset
+				);
+				schedules
+					.try_push(RewardSchedule {
+						asset_id,
+						reward_per_block,
+						reward_per_share: FixedU128::zero(),
+					})
+					.map_err(|_| Error::<T>::TooManyRewards)?;
+			}
+
+			Farms::<T>::insert(
+				pool_id,
+				Farm {
+					lp_token,
+					total_staked: Zero::zero(),
+					rewards: schedules,
+					last_update: frame_system::Pallet::<T>::block_number(),
+				},
+			);
+
+			Self::deposit_event(Event::<T>::FarmCreated { pool_id, lp_token });
+			Ok(())
+		}
+
+		/// Set the amount of `asset_id` that the farm of the pool emits per block. A new reward
+		/// asset is added to the farm, setting the amount to zero stops the emission.
+		///
+		/// Can only be called by the `AdminOrigin`.
+		///
+		/// Emits `RewardScheduleUpdated` event when successful.
+		#[pallet::weight(T::WeightInfo::set_reward_schedule())]
+		pub fn set_reward_schedule(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			asset_id: AssetIdOf<T>,
+			reward_per_block: BalanceOf<T>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			Farms::<T>::try_mutate(pool_id, |maybe_farm| -> DispatchResult {
+				let farm = maybe_farm.as_mut().ok_or(Error::<T>::FarmNotFound)?;
+				// The rewards up to now are distributed at the old rate
+				Self::accumulate_rewards(farm);
+
+				match farm.rewards.iter_mut().find(|schedule| schedule.asset_id == asset_id) {
+					Some(schedule) => schedule.reward_per_block = reward_per_block,
+					None => farm
+						.rewards
+						.try_push(RewardSchedule {
+							asset_id,
+							reward_per_block,
+							reward_per_share: FixedU128::zero(),
+						})
+						.map_err(|_| Error::<T>::TooManyRewards)?,
+				}
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::<T>::RewardScheduleUpdated {
+				pool_id,
+				asset_id,
+				reward_per_block,
+			});
+			Ok(())
+		}
+
+		/// Stake `amount` LP tokens of the pool in its farm. The tokens stay in the account of
+		/// the caller but are locked until they are withdrawn.
+		///
+		/// Emits `Deposited` event when successful.
+		#[pallet::weight(T::WeightInfo::deposit())]
+		#[transactional]
+		pub fn deposit(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(!amount.is_zero(), Error::<T>::InvalidAmount);
+
+			Farms::<T>::try_mutate(pool_id, |maybe_farm| -> DispatchResult {
+				let farm = maybe_farm.as_mut().ok_or(Error::<T>::FarmNotFound)?;
+				Self::accumulate_rewards(farm);
+
+				let mut stake = Self::stakes(pool_id, &who).unwrap_or_default();
+				Self::accumulate_stake_rewards(farm, &mut stake)?;
+
+				stake.amount =
+					stake.amount.checked_add(&amount).ok_or(ArithmeticError::Overflow)?;
+				ensure!(
+					T::Assets::free_balance(farm.lp_token, &who) >= stake.amount,
+					Error::<T>::InsufficientBalance
+				);
+				T::Assets::set_lock(FARMING_LOCK_ID, farm.lp_token, &who, stake.amount)?;
+
+				farm.total_staked =
+					farm.total_staked.checked_add(&amount).ok_or(ArithmeticError::Overflow)?;
+				Stakes::<T>::insert(pool_id, &who, stake);
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::<T>::Deposited { pool_id, who, amount });
+			Ok(())
+		}
+
+		/// Withdraw `amount` staked LP tokens from the farm of the pool and unlock them. The
+		/// earned rewards stay claimable.
+		///
+		/// Emits `Withdrawn` event when successful.
+		#[pallet::weight(T::WeightInfo::withdraw())]
+		#[transactional]
+		pub fn withdraw(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(!amount.is_zero(), Error::<T>::InvalidAmount);
+
+			Farms::<T>::try_mutate(pool_id, |maybe_farm| -> DispatchResult {
+				let farm = maybe_farm.as_mut().ok_or(Error::<T>::FarmNotFound)?;
+				Self::accumulate_rewards(farm);
+
+				let mut stake = Self::stakes(pool_id, &who).ok_or(Error::<T>::StakeNotFound)?;
+				Self::accumulate_stake_rewards(farm, &mut stake)?;
+
+				ensure!(stake.amount >= amount, Error::<T>::InsufficientStake);
+				stake.amount -= amount;
+				if stake.amount.is_zero() {
+					T::Assets::remove_lock(FARMING_LOCK_ID, farm.lp_token, &who)?;
+				} else {
+					T::Assets::set_lock(FARMING_LOCK_ID, farm.lp_token, &who, stake.amount)?;
+				}
+
+				farm.total_staked = farm.total_staked.saturating_sub(amount);
+				Self::store_stake(pool_id, &who, stake);
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::<T>::Withdrawn { pool_id, who, amount });
+			Ok(())
+		}
+
+		/// Pay out all rewards the caller earned in the farm of the pool.
+		///
+		/// Fails if the account of the farm doesn't hold enough of a reward asset.
+		///
+		/// Emits a `RewardPaid` event for every reward asset with a non-zero amount.
+		#[pallet::weight(T::WeightInfo::claim(T::MaxRewardsPerFarm::get()))]
+		#[transactional]
+		pub fn claim(origin: OriginFor<T>, pool_id: PoolIdOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let mut farm = Self::farms(pool_id).ok_or(Error::<T>::FarmNotFound)?;
+			Self::accumulate_rewards(&mut farm);
+
+			let mut stake = Self::stakes(pool_id, &who).ok_or(Error::<T>::StakeNotFound)?;
+			Self::accumulate_stake_rewards(&farm, &mut stake)?;
+
+			let farm_account = Self::farm_account(pool_id);
+			for reward in stake.rewards.iter_mut().filter(|reward| !reward.pending.is_zero()) {
+				T::Assets::transfer(reward.asset_id, &farm_account, &who, reward.pending)?;
+				Self::deposit_event(Event::<T>::RewardPaid {
+					pool_id,
+					who: who.clone(),
+					asset_id: reward.asset_id,
+					amount: reward.pending,
+				});
+				reward.pending = Zero::zero();
+			}
+
+			Farms::<T>::insert(pool_id, farm);
+			Self::store_stake(pool_id, &who, stake);
+			Ok(())
+		}
+	}
+}
+
+/// Creates the pools and reward assets of the benchmarks of the farming pallet.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AccountId, AssetId, PoolId> {
+	/// Creates a pool whose LP tokens are held by `owner` and returns its id.
+	fn create_pool(owner: &AccountId) -> PoolId;
+
+	/// Returns the `i`-th reward asset, which can be deposited into any account.
+	fn reward_asset(i: u32) -> AssetId;
+}
+
+impl<T: Config> Pallet<T> {
+	/// The account that holds the rewards of the farm of the pool.
+	pub fn farm_account(pool_id: PoolIdOf<T>) -> AccountIdOf<T> {
+		T::PalletId::get().into_sub_account_truncating(pool_id)
+	}
+
+	/// The rewards the account earned in the farm of the pool up to the current block and didn't
+	/// claim yet.
+	pub fn pending_rewards(
+		pool_id: PoolIdOf<T>,
+		who: &AccountIdOf<T>,
+	) -> Vec<(AssetIdOf<T>, BalanceOf<T>)> {
+		let (mut farm, mut stake) = match (Self::farms(pool_id), Self::stakes(pool_id, who)) {
+			(Some(farm), Some(stake)) => (farm, stake),
+			_ => return Vec::new(),
+		};
+		Self::accumulate_rewards(&mut farm);
+		if Self::accumulate_stake_rewards(&farm, &mut stake).is_err() {
+			return Vec::new()
+		}
+		stake
+			.rewards
+			.into_iter()
+			.map(|reward| (reward.asset_id, reward.pending))
+			.collect()
+	}
+
+	/// Distributes the rewards emitted since the last update among the staked LP tokens.
+	/// Nothing is distributed while no LP tokens are staked.
+	fn accumulate_rewards(farm: &mut Farm<T>) {
+		let now = frame_system::Pallet::<T>::block_number();
+		let elapsed: u32 = now.saturating_sub(farm.last_update).unique_saturated_into();
+		farm.last_update = now;
+		if elapsed.is_zero() || farm.total_staked.is_zero() {
+			return
+		}
+
+		for schedule in farm.rewards.iter_mut() {
+			let emitted = schedule.reward_per_block.saturating_mul(elapsed.into());
+			let reward_per_share = FixedU128::saturating_from_rational(emitted, farm.total_staked);
+			schedule.reward_per_share = schedule.reward_per_share.saturating_add(reward_per_share);
+		}
+	}
+
+	/// Adds the rewards the stake earned since its last update to its pending rewards. Has to be
+	/// called with an up to date farm before the amount of the stake changes.
+	fn accumulate_stake_rewards(farm: &Farm<T>, stake: &mut Stake<T>) -> DispatchResult {
+		for schedule in farm.rewards.iter() {
+			// Reward assets that were added to the farm since the last update start at zero
+			if !stake.rewards.iter().any(|reward| reward.asset_id == schedule.asset_id) {
+				stake
+					.rewards
+					.try_push(StakeReward {
+						asset_id: schedule.asset_id,
+						reward_per_share_paid: FixedU128::zero(),
+						pending: Zero::zero(),
+					})
+					.map_err(|_| Error::<T>::TooManyRewards)?;
+			}
+
+			let amount = stake.amount;
+			if let Some(reward) =
+				stake.rewards.iter_mut().find(|reward| reward.asset_id == schedule.asset_id)
+			{
+				let earned = schedule
+					.reward_per_share
+					.saturating_sub(reward.reward_per_share_paid)
+					.saturating_mul_int(amount);
+				reward.pending = reward.pending.saturating_add(earned);
+				reward.reward_per_share_paid = schedule.reward_per_share;
+			}
+		}
+		Ok(())
+	}
+
+	/// Stores the stake, or removes it once nothing is staked and all rewards were claimed.
+	fn store_stake(pool_id: PoolIdOf<T>, who: &AccountIdOf<T>, stake: Stake<T>) {
+		if stake.amount.is_zero() && stake.rewards.iter().all(|reward| reward.pending.is_zero()) {
+			Stakes::<T>::remove(pool_id, who);
+		} else {
+			Stakes::<T>::insert(pool_id, who, stake);
+		}
+	}
+}
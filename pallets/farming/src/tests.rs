@@ -0,0 +1,317 @@
+use frame_support::{assert_noop, assert_ok};
+use frame_system::{Config, EventRecord};
+use orml_traits::MultiCurrency;
+use pallet_dex::traits::{Amm, CurrencyPair, PoolCreationParams, PoolKind};
+
+use sp_core::H256;
+use sp_runtime::{traits::Zero, DispatchError, FixedU128, Permill};
+
+use crate::{mock, mock::*, Error, RewardSchedule, FARMING_LOCK_ID};
+
+pub fn assert_last_event<T, F>(matcher: F)
+where
+	T: Config,
+	F: Fn(&EventRecord<mock::Event, H256>) -> bool,
+{
+	assert!(matcher(System::events().last().expect("events expected")));
+}
+
+const REWARD_PER_BLOCK: Balance = 1_000;
+
+/// Creates a constant product pool for ASSET_1/ASSET_2 and gives BOB some of its LP tokens.
+fn create_pool() -> PoolId {
+	let pair = CurrencyPair { token_a: ASSET_1, token_b: ASSET_2 };
+	let params = PoolCreationParams {
+		owner: ALICE,
+		pair,
+		fee: Permill::from_percent(3),
+		kind: PoolKind::ConstantProduct,
+		initial_sqrt_price: None,
+	};
+	assert_ok!(Dex::create_pool_with_liquidity(Origin::signed(ALICE), params, 100_000, 100_000));
+	let pool_id = 0;
+	assert_ok!(Tokens::transfer(Origin::signed(ALICE), BOB, lp_token(pool_id), 40_000));
+	pool_id
+}
+
+/// Creates a farm for a new pool that emits `REWARD_PER_BLOCK` of ASSET_3 and funds it.
+fn create_farm() -> PoolId {
+	let pool_id = create_pool();
+	assert_ok!(Farming::create_farm(Origin::root(), pool_id, vec![(ASSET_3, REWARD_PER_BLOCK)]));
+	assert_ok!(Tokens::transfer(
+		Origin::signed(ALICE),
+		Farming::farm_account(pool_id),
+		ASSET_3,
+		500_000
+	));
+	pool_id
+}
+
+fn lp_token(pool_id: PoolId) -> AssetId {
+	<Dex as Amm>::lp_token(pool_id).unwrap()
+}
+
+fn locked_balance(asset_id: AssetId, who: AccountId) -> Balance {
+	Tokens::locks(who, asset_id)
+		.iter()
+		.find(|lock| lock.id == FARMING_LOCK_ID)
+		.map_or(0, |lock| lock.amount)
+}
+
+fn run_to_block(n: u64) {
+	System::set_block_number(n);
+}
+
+#[test]
+fn create_farm_should_work() {
+	run_test(|| {
+		let pool_id = create_pool();
+
+		assert_ok!(Farming::create_farm(
+			Origin::root(),
+			pool_id,
+			vec![(ASSET_3, REWARD_PER_BLOCK), (ASSET_1, 10)]
+		));
+
+		let farm = Farming::farms(pool_id).unwrap();
+		assert_eq!(farm.lp_token, lp_token(pool_id));
+		assert_eq!(farm.total_staked, 0);
+		assert_eq!(farm.last_update, 1);
+		assert_eq!(
+			farm.rewards.into_inner(),
+			vec![
+				RewardSchedule {
+					asset_id: ASSET_3,
+					reward_per_block: REWARD_PER_BLOCK,
+					reward_per_share: FixedU128::zero(),
+				},
+				RewardSchedule {
+					asset_id: ASSET_1,
+					reward_per_block: 10,
+					reward_per_share: FixedU128::zero(),
+				},
+			]
+		);
+
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Farming(crate::Event::FarmCreated { pool_id: id, lp_token: token })
+            if id == pool_id && token == lp_token(pool_id))
+		});
+	});
+}
+
+#[test]
+fn create_farm_should_fail() {
+	run_test(|| {
+		let pool_id = create_pool();
+
+		assert_noop!(
+			Farming::create_farm(Origin::signed(ALICE), pool_id, vec![]),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Farming::create_farm(Origin::root(), pool_id, vec![(ASSET_3, 1), (ASSET_3, 2)]),
+			Error::<Test>::DuplicateRewardAsset
+		);
+		assert_noop!(
+			Farming::create_farm(
+				Origin::root(),
+				pool_id,
+				vec![(ASSET_1, 1), (ASSET_2, 1), (ASSET_3, 1)]
+			),
+			Error::<Test>::TooManyRewards
+		);
+		assert!(Farming::create_farm(Origin::root(), pool_id + 1, vec![]).is_err());
+
+		assert_ok!(Farming::create_farm(Origin::root(), pool_id, vec![]));
+		assert_noop!(
+			Farming::create_farm(Origin::root(), pool_id, vec![]),
+			Error::<Test>::FarmAlreadyExists
+		);
+	});
+}
+
+#[test]
+fn deposit_should_lock_lp_tokens() {
+	run_test(|| {
+		let pool_id = create_farm();
+		let lp_token = lp_token(pool_id);
+
+		assert_ok!(Farming::deposit(Origin::signed(BOB), pool_id, 30_000));
+
+		assert_eq!(Tokens::free_balance(lp_token, &BOB), 40_000);
+		assert_eq!(locked_balance(lp_token, BOB), 30_000);
+		assert_eq!(Farming::stakes(pool_id, BOB).unwrap().amount, 30_000);
+		assert_eq!(Farming::farms(pool_id).unwrap().total_staked, 30_000);
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Farming(crate::Event::Deposited { who, amount, .. })
+            if who == BOB && amount == 30_000)
+		});
+
+		// The locked tokens can't be transferred
+		assert!(Tokens::transfer(Origin::signed(BOB), ALICE, lp_token, 20_000).is_err());
+		assert_ok!(Tokens::transfer(Origin::signed(BOB), ALICE, lp_token, 10_000));
+
+		assert_noop!(
+			Farming::deposit(Origin::signed(BOB), pool_id, 1),
+			Error::<Test>::InsufficientBalance
+		);
+	});
+}
+
+#[test]
+fn deposit_should_fail() {
+	run_test(|| {
+		let pool_id = create_farm();
+
+		assert_noop!(
+			Farming::deposit(Origin::signed(BOB), pool_id, 0),
+			Error::<Test>::InvalidAmount
+		);
+		assert_noop!(
+			Farming::deposit(Origin::signed(BOB), pool_id + 1, 1_000),
+			Error::<Test>::FarmNotFound
+		);
+		assert_noop!(
+			Farming::deposit(Origin::signed(BOB), pool_id, 40_001),
+			Error::<Test>::InsufficientBalance
+		);
+	});
+}
+
+#[test]
+fn rewards_should_be_shared_by_stake() {
+	run_test(|| {
+		let pool_id = create_farm();
+
+		assert_ok!(Farming::deposit(Origin::signed(BOB), pool_id, 10_000));
+		run_to_block(11);
+		assert_eq!(Farming::pending_rewards(pool_id, &BOB), vec![(ASSET_3, 10 * REWARD_PER_BLOCK)]);
+
+		// ALICE stakes three times as much as BOB and gets three quarters of the rewards
+		assert_ok!(Farming::deposit(Origin::signed(ALICE), pool_id, 30_000));
+		run_to_block(21);
+		assert_eq!(Farming::pending_rewards(pool_id, &BOB), vec![(ASSET_3, 12_500)]);
+		assert_eq!(Farming::pending_rewards(pool_id, &ALICE), vec![(ASSET_3, 7_500)]);
+	});
+}
+
+#[test]
+fn claim_should_pay_rewards() {
+	run_test(|| {
+		let pool_id = create_farm();
+
+		assert_ok!(Farming::deposit(Origin::signed(BOB), pool_id, 10_000));
+		run_to_block(6);
+		assert_ok!(Farming::claim(Origin::signed(BOB), pool_id));
+
+		assert_eq!(Tokens::free_balance(ASSET_3, &BOB), 5 * REWARD_PER_BLOCK);
+		assert_eq!(
+			Tokens::free_balance(ASSET_3, &Farming::farm_account(pool_id)),
+			500_000 - 5 * REWARD_PER_BLOCK
+		);
+		assert_eq!(Farming::pending_rewards(pool_id, &BOB), vec![(ASSET_3, 0)]);
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Farming(crate::Event::RewardPaid { who, asset_id, amount, .. })
+            if who == BOB && asset_id == ASSET_3 && amount == 5 * REWARD_PER_BLOCK)
+		});
+	});
+}
+
+#[test]
+fn claim_should_fail_if_farm_is_not_funded() {
+	run_test(|| {
+		let pool_id = create_pool();
+		assert_ok!(Farming::create_farm(
+			Origin::root(),
+			pool_id,
+			vec![(ASSET_3, REWARD_PER_BLOCK)]
+		));
+
+		assert_ok!(Farming::deposit(Origin::signed(BOB), pool_id, 10_000));
+		run_to_block(6);
+		assert!(Farming::claim(Origin::signed(BOB), pool_id).is_err());
+		assert_eq!(Farming::pending_rewards(pool_id, &BOB), vec![(ASSET_3, 5 * REWARD_PER_BLOCK)]);
+
+		assert_noop!(Farming::claim(Origin::signed(ALICE), pool_id), Error::<Test>::StakeNotFound);
+	});
+}
+
+#[test]
+fn withdraw_should_unlock_lp_tokens_and_keep_rewards() {
+	run_test(|| {
+		let pool_id = create_farm();
+		let lp_token = lp_token(pool_id);
+
+		assert_ok!(Farming::deposit(Origin::signed(BOB), pool_id, 30_000));
+		run_to_block(3);
+		assert_ok!(Farming::withdraw(Origin::signed(BOB), pool_id, 10_000));
+
+		assert_eq!(locked_balance(lp_token, BOB), 20_000);
+		assert_eq!(Farming::farms(pool_id).unwrap().total_staked, 20_000);
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Farming(crate::Event::Withdrawn { who, amount, .. })
+            if who == BOB && amount == 10_000)
+		});
+
+		assert_noop!(
+			Farming::withdraw(Origin::signed(BOB), pool_id, 20_001),
+			Error::<Test>::InsufficientStake
+		);
+		assert_ok!(Farming::withdraw(Origin::signed(BOB), pool_id, 20_000));
+		assert_eq!(locked_balance(lp_token, BOB), 0);
+		assert_ok!(Tokens::transfer(Origin::signed(BOB), ALICE, lp_token, 40_000));
+
+		// No rewards are emitted without stakes, the earned ones can still be claimed
+		run_to_block(10);
+		assert_eq!(Farming::pending_rewards(pool_id, &BOB), vec![(ASSET_3, 2 * REWARD_PER_BLOCK)]);
+		assert_ok!(Farming::claim(Origin::signed(BOB), pool_id));
+		assert_eq!(Tokens::free_balance(ASSET_3, &BOB), 2 * REWARD_PER_BLOCK);
+		assert!(Farming::stakes(pool_id, BOB).is_none());
+
+		assert_noop!(
+			Farming::withdraw(Origin::signed(BOB), pool_id, 1),
+			Error::<Test>::StakeNotFound
+		);
+	});
+}
+
+#[test]
+fn set_reward_schedule_should_apply_from_current_block() {
+	run_test(|| {
+		let pool_id = create_farm();
+
+		assert_ok!(Farming::deposit(Origin::signed(BOB), pool_id, 10_000));
+		run_to_block(5);
+
+		assert_noop!(
+			Farming::set_reward_schedule(Origin::signed(ALICE), pool_id, ASSET_3, 0),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Farming::set_reward_schedule(Origin::root(), pool_id, ASSET_3, 0));
+		assert_ok!(Farming::set_reward_schedule(Origin::root(), pool_id, ASSET_1, 10));
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Farming(crate::Event::RewardScheduleUpdated { asset_id, reward_per_block, .. })
+            if asset_id == ASSET_1 && reward_per_block == 10)
+		});
+		assert_noop!(
+			Farming::set_reward_schedule(Origin::root(), pool_id, ASSET_2, 10),
+			Error::<Test>::TooManyRewards
+		);
+		assert_noop!(
+			Farming::set_reward_schedule(Origin::root(), pool_id + 1, ASSET_2, 10),
+			Error::<Test>::FarmNotFound
+		);
+
+		run_to_block(15);
+		assert_eq!(
+			Farming::pending_rewards(pool_id, &BOB),
+			vec![(ASSET_3, 4 * REWARD_PER_BLOCK), (ASSET_1, 100)]
+		);
+	});
+}
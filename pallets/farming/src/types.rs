@@ -0,0 +1,59 @@
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{BoundedVec, RuntimeDebug};
+use scale_info::TypeInfo;
+use sp_runtime::FixedU128;
+
+use crate::Config;
+
+pub(crate) type BalanceOf<T> = <T as Config>::Balance;
+pub(crate) type AssetIdOf<T> = <T as Config>::AssetId;
+pub(crate) type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
+pub(crate) type PoolIdOf<T> = <T as Config>::PoolId;
+pub(crate) type RewardScheduleOf<T> = RewardSchedule<AssetIdOf<T>, BalanceOf<T>>;
+pub(crate) type StakeRewardOf<T> = StakeReward<AssetIdOf<T>, BalanceOf<T>>;
+
+/// The emission of a single reward asset of a farm.
+#[derive(RuntimeDebug, Encode, Decode, MaxEncodedLen, Clone, PartialEq, Eq, TypeInfo)]
+pub struct RewardSchedule<AssetId, Balance> {
+	pub asset_id: AssetId,
+	/// The amount that is shared among all stakers in every block.
+	pub reward_per_block: Balance,
+	/// The rewards per staked LP token accumulated since the asset was added to the farm.
+	pub reward_per_share: FixedU128,
+}
+
+/// A farm rewarding the accounts that stake the LP token of a pool.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(T))]
+pub struct Farm<T: Config> {
+	pub lp_token: AssetIdOf<T>,
+	/// The sum of all stakes of the farm.
+	pub total_staked: BalanceOf<T>,
+	pub rewards: BoundedVec<RewardScheduleOf<T>, T::MaxRewardsPerFarm>,
+	/// The block up to which the rewards were accumulated.
+	pub last_update: T::BlockNumber,
+}
+
+/// The rewards of a stake in a single reward asset.
+#[derive(RuntimeDebug, Encode, Decode, MaxEncodedLen, Clone, PartialEq, Eq, TypeInfo)]
+pub struct StakeReward<AssetId, Balance> {
+	pub asset_id: AssetId,
+	/// The `reward_per_share` of the farm when the rewards of the stake were last updated.
+	pub reward_per_share_paid: FixedU128,
+	/// The rewards that were earned but not claimed yet.
+	pub pending: Balance,
+}
+
+/// The LP tokens an account locked in a farm and the rewards it earned.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(T))]
+pub struct Stake<T: Config> {
+	pub amount: BalanceOf<T>,
+	pub rewards: BoundedVec<StakeRewardOf<T>, T::MaxRewardsPerFarm>,
+}
+
+impl<T: Config> Default for Stake<T> {
+	fn default() -> Self {
+		Stake { amount: Default::default(), rewards: Default::default() }
+	}
+}
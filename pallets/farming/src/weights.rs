@@ -0,0 +1,113 @@
+//! Placeholder weights for pallet_farming
+//!
+//! The values were estimated by hand and have not been measured. The times are rounded guesses.
+//! The storage annotations list the accesses of the most expensive path through each call with
+//! the configuration of the runtime in this repository, every key is counted once and a component
+//! like `n` stands for the value of the component of the call. Replace this file with the output
+//! of the benchmarks in `benchmarking.rs` on the reference hardware before relying on the
+//! weights, e.g. with
+//! `./target/release/node-template benchmark pallet --chain=dev --steps=50 --repeat=20
+//! --pallet=pallet_farming --extrinsic=* --execution=wasm --wasm-execution=compiled
+//! --output=pallets/farming/src/weights.rs`.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_farming.
+pub trait WeightInfo {
+	fn create_farm(n: u32, ) -> Weight;
+	fn set_reward_schedule() -> Weight;
+	fn deposit() -> Weight;
+	fn withdraw() -> Weight;
+	fn claim(n: u32, ) -> Weight;
+}
+
+/// Placeholder weights for pallet_farming, see the module documentation.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: Farming Farms (r:1 w:1)
+	// Storage: Dex Pools (r:1 w:0)
+	// Storage: Dex MultiAssetPools (r:1 w:0)
+	fn create_farm(n: u32, ) -> Weight {
+		(22_000_000 as Weight)
+			.saturating_add((1_400_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: Farming Farms (r:1 w:1)
+	fn set_reward_schedule() -> Weight {
+		(21_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: Farming Farms (r:1 w:1)
+	// Storage: Farming Stakes (r:1 w:1)
+	// Storage: Tokens Accounts (r:1 w:1)
+	// Storage: Tokens Locks (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn deposit() -> Weight {
+		(46_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	// Storage: Farming Farms (r:1 w:1)
+	// Storage: Farming Stakes (r:1 w:1)
+	// Storage: Tokens Locks (r:1 w:1)
+	// Storage: Tokens Accounts (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn withdraw() -> Weight {
+		(44_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	// Storage: Farming Farms (r:1 w:1)
+	// Storage: Farming Stakes (r:1 w:1)
+	// Storage: AssetRegistry Assets (r:n w:0)
+	// Storage: Tokens Accounts (r:2n w:2n)
+	// Storage: System Account (r:1 w:1)
+	fn claim(n: u32, ) -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add((25_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes((2 as Weight).saturating_mul(n as Weight)))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn create_farm(n: u32, ) -> Weight {
+		(22_000_000 as Weight)
+			.saturating_add((1_400_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn set_reward_schedule() -> Weight {
+		(21_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn deposit() -> Weight {
+		(46_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+	fn withdraw() -> Weight {
+		(44_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+	fn claim(n: u32, ) -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add((25_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes((2 as Weight).saturating_mul(n as Weight)))
+	}
+}
@@ -0,0 +1,89 @@
+//! Benchmarking setup for pallet-nft
+
+use super::*;
+
+use crate::Pallet as Nfts;
+use codec::Decode;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_support::{traits::Get, BoundedVec};
+use frame_system::RawOrigin;
+use sp_runtime::traits::TrailingZeroInput;
+use sp_std::vec;
+
+const SEED: u32 = 0;
+
+fn item_id<T: Config>(i: u32) -> ItemIdOf<T> {
+	ItemIdOf::<T>::decode(&mut TrailingZeroInput::new(&i.to_le_bytes())).unwrap()
+}
+
+fn data<T: Config>() -> DataOf<T> {
+	BoundedVec::truncate_from(vec![0u8; T::StringLimit::get() as usize])
+}
+
+/// Mints `count` unique_items to `owner`, starting with the id `first`. Returns the id of the
+/// last minted unique_item.
+fn mint_items<T: Config>(owner: &AccountIdOf<T>, first: u32, count: u32) -> ItemIdOf<T> {
+	for i in first..first + count {
+		Nfts::<T>::mint(owner, item_id::<T>(i), data::<T>()).unwrap();
+	}
+	item_id::<T>(first + count - 1)
+}
+
+benchmarks! {
+	create_unique_item {
+		let o in 0 .. T::MaxUniqueItemsOwned::get() - 1;
+
+		let caller: AccountIdOf<T> = whitelisted_caller();
+		for i in 0..o {
+			Nfts::<T>::mint(&caller, item_id::<T>(i), data::<T>()).unwrap();
+		}
+		let item = item_id::<T>(o);
+	}: _(RawOrigin::Signed(caller.clone()), item, data::<T>())
+	verify {
+		assert_eq!(UniqueItems::<T>::get(item).unwrap().owner, caller);
+	}
+
+	transfer {
+		// The unique_item is the last one owned by the sender, the recipient can still receive one
+		let o in 1 .. T::MaxUniqueItemsOwned::get();
+
+		let caller: AccountIdOf<T> = whitelisted_caller();
+		let recipient: AccountIdOf<T> = account("recipient", 0, SEED);
+		let item = mint_items::<T>(&caller, 0, o);
+		if o > 1 {
+			mint_items::<T>(&recipient, T::MaxUniqueItemsOwned::get(), o - 1);
+		}
+	}: _(RawOrigin::Signed(caller), recipient.clone(), item)
+	verify {
+		assert_eq!(UniqueItems::<T>::get(item).unwrap().owner, recipient);
+	}
+
+	buy_unique_item {
+		let o in 1 .. T::MaxUniqueItemsOwned::get();
+
+		let seller: AccountIdOf<T> = account("seller", 0, SEED);
+		let caller: AccountIdOf<T> = whitelisted_caller();
+		let item = mint_items::<T>(&seller, 0, o);
+		if o > 1 {
+			mint_items::<T>(&caller, T::MaxUniqueItemsOwned::get(), o - 1);
+		}
+
+		let price: PriceOf<T> = (1_000u32.into(), Default::default());
+		Nfts::<T>::set_price(RawOrigin::Signed(seller).into(), item, Some(price)).unwrap();
+		T::Assets::deposit(price.1, &caller, price.0).unwrap();
+	}: _(RawOrigin::Signed(caller.clone()), item, price)
+	verify {
+		assert_eq!(UniqueItems::<T>::get(item).unwrap().owner, caller);
+	}
+
+	set_price {
+		let caller: AccountIdOf<T> = whitelisted_caller();
+		let item = mint_items::<T>(&caller, 0, 1);
+		let price: PriceOf<T> = (1_000u32.into(), Default::default());
+	}: _(RawOrigin::Signed(caller), item, Some(price))
+	verify {
+		assert_eq!(UniqueItems::<T>::get(item).unwrap().price, Some(price));
+	}
+
+	impl_benchmark_test_suite!(Nfts, crate::mock::new_test_ext(vec![]), crate::mock::Test);
+}
@@ -3,6 +3,7 @@
 use codec::{FullCodec};
 use orml_traits::MultiCurrency;
 pub use pallet::*;
+pub use weights::WeightInfo;
 
 
 use sp_runtime::{traits::AtLeast32BitUnsigned, ArithmeticError, FixedPointOperand};
@@ -16,6 +17,11 @@ mod tests;
 
 mod traits;
 mod types;
+pub mod weights;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
 use traits::UniqueItem;
 use types::*;
 
@@ -29,8 +35,13 @@ pub mod pallet {
 
 	use super::*;
 
+	/// The current storage version. Bump it together with a migration whenever the layout of
+	/// `UniqueItem` or of the storage items changes.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(0);
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	// Configure the pallet by specifying the parameters and types on which it depends.
@@ -79,6 +90,9 @@ pub mod pallet {
 		/// The maximum amount of unique_items a single account can own.
 		#[pallet::constant]
 		type MaxUniqueItemsOwned: Get<u32>;
+
+		/// Weight information for the extrinsics of this pallet.
+		type WeightInfo: WeightInfo;
 	}
 
 	// Errors
@@ -140,6 +154,14 @@ pub mod pallet {
 		ValueQuery,
 	>;
 
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			Self::do_try_state()
+		}
+	}
+
 	// Our pallet's genesis configuration
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
@@ -170,7 +192,7 @@ pub mod pallet {
 		/// Create a new unique unique_item.
 		///
 		/// The actual unique_item creation is done in the `mint()` function.
-		#[pallet::weight(0)]
+		#[pallet::weight(T::WeightInfo::create_unique_item(T::MaxUniqueItemsOwned::get()))]
 		pub fn create_unique_item(
 			origin: OriginFor<T>,
 			item: ItemIdOf<T>,
@@ -188,19 +210,19 @@ pub mod pallet {
 		///
 		/// Any account that holds a unique_item can send it to another Account. This will reset the
 		/// asking price of the unique_item, marking it not for sale.
-		#[pallet::weight(0)]
+		#[pallet::weight(T::WeightInfo::transfer(T::MaxUniqueItemsOwned::get()))]
 		pub fn transfer(
 			origin: OriginFor<T>,
 			to: AccountIdOf<T>,
 			unique_item_id: ItemIdOf<T>,
-		) -> DispatchResult {
+		) -> DispatchResultWithPostInfo {
 			// Make sure the caller is from a signed origin
 			let from = ensure_signed(origin)?;
 			let unique_item =
 				UniqueItems::<T>::get(&unique_item_id).ok_or(Error::<T>::NoUniqueItem)?;
 			ensure!(unique_item.owner == from, Error::<T>::NotOwner);
-			Self::do_transfer(unique_item_id, to, None)?;
-			Ok(())
+			let owned = Self::do_transfer(unique_item_id, to, None)?;
+			Ok(Some(T::WeightInfo::transfer(owned)).into())
 		}
 
 		/// Buy a unique_item for sale. The `limit_price` parameter is set as a safeguard against
@@ -211,24 +233,24 @@ pub mod pallet {
 		/// If successful, this dispatchable will reset the price of the unique_item to `None`,
 		/// making it no longer for sale and handle the balance and unique_item transfer between the
 		/// buyer and seller.
-		#[pallet::weight(0)]
+		#[pallet::weight(T::WeightInfo::buy_unique_item(T::MaxUniqueItemsOwned::get()))]
 		pub fn buy_unique_item(
 			origin: OriginFor<T>,
 			unique_item_id: ItemIdOf<T>,
 			limit_price: PriceOf<T>,
-		) -> DispatchResult {
+		) -> DispatchResultWithPostInfo {
 			// Make sure the caller is from a signed origin
 			let buyer = ensure_signed(origin)?;
 			// Transfer the unique_item from seller to buyer as a sale
-			Self::do_transfer(unique_item_id, buyer, Some(limit_price))?;
+			let owned = Self::do_transfer(unique_item_id, buyer, Some(limit_price))?;
 
-			Ok(())
+			Ok(Some(T::WeightInfo::buy_unique_item(owned)).into())
 		}
 
 		/// Set the price for a unique_item.
 		///
 		/// Updates unique_item price and updates storage.
-		#[pallet::weight(0)]
+		#[pallet::weight(T::WeightInfo::set_price())]
 		pub fn set_price(
 			origin: OriginFor<T>,
 			unique_item_id: ItemIdOf<T>,
@@ -282,12 +304,40 @@ pub mod pallet {
 			Ok(())
 		}
 
-		// Update storage to transfer unique_item
+		/// Checks that every unique_item is in the list of unique_items owned by its owner and
+		/// nobody else's, and that `CountForUniqueItems` matches the number of unique_items.
+		#[cfg(any(feature = "try-runtime", test))]
+		pub fn do_try_state() -> Result<(), &'static str> {
+			let mut count: u64 = 0;
+			for (unique_item_id, unique_item) in UniqueItems::<T>::iter() {
+				ensure!(
+					UniqueItemsOwned::<T>::get(&unique_item.owner).contains(&unique_item_id),
+					"UniqueItem is not owned by its owner"
+				);
+				count += 1;
+			}
+			ensure!(
+				CountForUniqueItems::<T>::get() == count,
+				"CountForUniqueItems doesn't match the number of unique_items"
+			);
+
+			for (owner, owned) in UniqueItemsOwned::<T>::iter() {
+				for unique_item_id in owned {
+					let unique_item = UniqueItems::<T>::get(&unique_item_id)
+						.ok_or("Owned UniqueItem not found")?;
+					ensure!(unique_item.owner == owner, "UniqueItem is owned by another account");
+				}
+			}
+			Ok(())
+		}
+
+		// Update storage to transfer unique_item. Returns the number of unique_items owned by the
+		// sender or the recipient, whichever is higher, which determines the weight.
 		pub fn do_transfer(
 			unique_item_id: ItemIdOf<T>,
 			to: AccountIdOf<T>,
 			maybe_limit_price: Option<PriceOf<T>>,
-		) -> DispatchResult {
+		) -> Result<u32, DispatchError> {
 			// Get the unique_item
 			let mut unique_item =
 				UniqueItems::<T>::get(&unique_item_id).ok_or(Error::<T>::NoUniqueItem)?;
@@ -295,6 +345,7 @@ pub mod pallet {
 
 			ensure!(from != to, Error::<T>::TransferToSelf);
 			let mut from_owned = UniqueItemsOwned::<T>::get(&from);
+			let mut owned = from_owned.len() as u32;
 
 			// Remove unique_item from list of owned unique_items.
 			if let Some(ind) = from_owned.iter().position(|&id| id == unique_item_id) {
@@ -306,6 +357,7 @@ pub mod pallet {
 			// Add unique_item to the list of owned unique_items.
 			let mut to_owned = UniqueItemsOwned::<T>::get(&to);
 			to_owned.try_push(unique_item_id).map_err(|()| Error::<T>::TooManyOwned)?;
+			owned = owned.max(to_owned.len() as u32);
 
 			// Mutating state here via a balance transfer, so nothing is allowed to fail after this.
 			// The buyer will always be charged the actual price. The limit_price parameter is just
@@ -340,7 +392,7 @@ pub mod pallet {
 
 			Self::deposit_event(Event::Transferred { from, to, unique_item: unique_item_id });
 
-			Ok(())
+			Ok(owned)
 		}
 	}
 }
@@ -88,6 +88,7 @@ impl pallet_unique_items::Config for Test {
 	type Assets = Tokens;
 	type StringLimit = StringLimit;
 	type MaxUniqueItemsOwned = MaxUniqueItemsOwned;
+	type WeightInfo = ();
 }
 
 parameter_types! {
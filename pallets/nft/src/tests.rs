@@ -21,6 +21,8 @@ fn assert_ownership(owner: u64, unique_item_id: ItemIdOf<Test>) {
 			assert!(!owned.contains(&unique_item_id));
 		}
 	}
+	// The rest of the storage is consistent as well.
+	assert_ok!(Nfts::do_try_state());
 }
 
 #[test]
@@ -133,6 +135,23 @@ fn transfer_unique_item_should_work() {
 	});
 }
 
+#[test]
+fn transfer_unique_item_should_refund_weight() {
+	new_test_ext(vec![(1, *b"1234567890123456", b"test".to_vec())]).execute_with(|| {
+		let id = UniqueItemsOwned::<Test>::get(1)[0];
+		for i in 0..3 {
+			let data = BoundedVec::truncate_from(b"test".to_vec());
+			assert_ok!(Nfts::create_unique_item(Origin::signed(2), [i; 16], data));
+		}
+
+		// The weight depends on the recipient, who owns more unique_items than the sender
+		let post_info = Nfts::transfer(Origin::signed(1), 2, id).unwrap();
+		let weight = <() as weights::WeightInfo>::transfer(4);
+		assert_eq!(post_info.actual_weight, Some(weight));
+		assert!(weight < <() as weights::WeightInfo>::transfer(MaxUniqueItemsOwned::get()));
+	});
+}
+
 #[test]
 fn transfer_unique_item_should_fail() {
 	new_test_ext(vec![
@@ -279,3 +298,27 @@ fn set_price_works() {
 		);
 	});
 }
+
+#[test]
+fn try_state_should_detect_inconsistent_ownership() {
+	new_test_ext(vec![
+		(1, *b"1234567890123456", b"test".to_vec()),
+		(2, *b"123456789012345a", b"othertest".to_vec()),
+	])
+	.execute_with(|| {
+		assert_ok!(Nfts::do_try_state());
+
+		// The unique_item of account #1 is also listed for account #2
+		let id = UniqueItemsOwned::<Test>::get(1)[0];
+		UniqueItemsOwned::<Test>::mutate(2, |owned| owned.try_push(id).unwrap());
+		assert_eq!(Nfts::do_try_state(), Err("UniqueItem is owned by another account"));
+		UniqueItemsOwned::<Test>::mutate(2, |owned| owned.swap_remove(1));
+		assert_ok!(Nfts::do_try_state());
+
+		CountForUniqueItems::<Test>::put(3);
+		assert_eq!(
+			Nfts::do_try_state(),
+			Err("CountForUniqueItems doesn't match the number of unique_items")
+		);
+	});
+}
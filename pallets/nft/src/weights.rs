@@ -0,0 +1,92 @@
+//! Placeholder weights for pallet_nft
+//!
+//! The values were estimated by hand and have not been measured. The times are rounded guesses.
+//! The storage annotations list the accesses of the most expensive path through each call with
+//! the configuration of the runtime in this repository, every key is counted once and a component
+//! like `n` stands for the value of the component of the call. Replace this file with the output
+//! of the benchmarks in `benchmarking.rs` on the reference hardware before relying on the
+//! weights, e.g. with
+//! `./target/release/node-template benchmark pallet --chain=dev --steps=50 --repeat=20
+//! --pallet=pallet_nft --extrinsic=* --execution=wasm --wasm-execution=compiled
+//! --output=pallets/nft/src/weights.rs`.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_nft.
+pub trait WeightInfo {
+	fn create_unique_item(o: u32, ) -> Weight;
+	fn transfer(o: u32, ) -> Weight;
+	fn buy_unique_item(o: u32, ) -> Weight;
+	fn set_price() -> Weight;
+}
+
+/// Placeholder weights for pallet_nft, see the module documentation.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: Nfts UniqueItems (r:1 w:1)
+	// Storage: Nfts CountForUniqueItems (r:1 w:1)
+	// Storage: Nfts UniqueItemsOwned (r:1 w:1)
+	fn create_unique_item(o: u32, ) -> Weight {
+		(22_000_000 as Weight)
+			.saturating_add((60_000 as Weight).saturating_mul(o as Weight))
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	// Storage: Nfts UniqueItems (r:1 w:1)
+	// Storage: Nfts UniqueItemsOwned (r:2 w:2)
+	fn transfer(o: u32, ) -> Weight {
+		(26_000_000 as Weight)
+			.saturating_add((130_000 as Weight).saturating_mul(o as Weight))
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	// Storage: Nfts UniqueItems (r:1 w:1)
+	// Storage: Nfts UniqueItemsOwned (r:2 w:2)
+	// Storage: AssetRegistry Assets (r:1 w:0)
+	// Storage: Tokens Accounts (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	fn buy_unique_item(o: u32, ) -> Weight {
+		(40_000_000 as Weight)
+			.saturating_add((140_000 as Weight).saturating_mul(o as Weight))
+			.saturating_add(T::DbWeight::get().reads(7 as Weight))
+			.saturating_add(T::DbWeight::get().writes(6 as Weight))
+	}
+	// Storage: Nfts UniqueItems (r:1 w:1)
+	fn set_price() -> Weight {
+		(16_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn create_unique_item(o: u32, ) -> Weight {
+		(22_000_000 as Weight)
+			.saturating_add((60_000 as Weight).saturating_mul(o as Weight))
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	fn transfer(o: u32, ) -> Weight {
+		(26_000_000 as Weight)
+			.saturating_add((130_000 as Weight).saturating_mul(o as Weight))
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	fn buy_unique_item(o: u32, ) -> Weight {
+		(40_000_000 as Weight)
+			.saturating_add((140_000 as Weight).saturating_mul(o as Weight))
+			.saturating_add(RocksDbWeight::get().reads(7 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(6 as Weight))
+	}
+	fn set_price() -> Weight {
+		(16_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+}
@@ -0,0 +1,79 @@
+//! Benchmarking setup for pallet-orderbook
+
+use super::*;
+
+use crate::Pallet as Orderbook;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_support::traits::Get;
+use frame_system::RawOrigin;
+use orml_traits::MultiCurrency;
+use sp_runtime::traits::{One, Saturating};
+
+const SEED: u32 = 0;
+/// The amount of `asset_in` that is sold by the orders of the benchmarks.
+const AMOUNT: u32 = 1_000_000;
+
+/// Funds `who` with the amount and the deposit of an order.
+fn fund<T: Config>(who: &T::AccountId, asset_in: AssetIdOf<T>) {
+	let amount: BalanceOf<T> = AMOUNT.into();
+	T::Assets::deposit(asset_in, who, amount).unwrap();
+	T::Assets::deposit(T::NativeAssetId::get(), who, T::OrderDeposit::get().saturating_add(amount))
+		.unwrap();
+}
+
+/// Places `n` orders on the pool, each of them by a different account.
+fn place_orders<T: Config>(
+	pool_id: PoolIdOf<T>,
+	asset_in: AssetIdOf<T>,
+	asset_out: AssetIdOf<T>,
+	n: u32,
+) {
+	for i in 0..n {
+		let owner: T::AccountId = account("owner", i, SEED);
+		fund::<T>(&owner, asset_in);
+		Orderbook::<T>::place_order(
+			RawOrigin::Signed(owner).into(),
+			pool_id,
+			asset_in,
+			asset_out,
+			AMOUNT.into(),
+			FixedU128::one(),
+		)
+		.unwrap();
+	}
+}
+
+benchmarks! {
+	// The order book of the pool is full except for the new order
+	place_order {
+		let caller: T::AccountId = whitelisted_caller();
+		let pool_id = T::BenchmarkHelper::create_pool(&caller);
+		let assets = T::Amm::pool_assets(pool_id).unwrap();
+		place_orders::<T>(pool_id, assets[0], assets[1], T::MaxOrdersPerPool::get() - 1);
+		fund::<T>(&caller, assets[0]);
+		let order_id = Orderbook::<T>::next_order_id();
+	}: _(RawOrigin::Signed(caller), pool_id, assets[0], assets[1], AMOUNT.into(), FixedU128::one())
+	verify {
+		assert!(Orders::<T>::contains_key(order_id));
+		assert_eq!(OrdersByPool::<T>::get(pool_id).len() as u32, T::MaxOrdersPerPool::get());
+	}
+
+	// The order book of the pool is full
+	cancel_order {
+		let caller: T::AccountId = whitelisted_caller();
+		let pool_id = T::BenchmarkHelper::create_pool(&caller);
+		let assets = T::Amm::pool_assets(pool_id).unwrap();
+		place_orders::<T>(pool_id, assets[0], assets[1], T::MaxOrdersPerPool::get());
+		let owner: T::AccountId = account("owner", 0, SEED);
+		let order_id = OrdersByPool::<T>::get(pool_id)[0];
+	}: _(RawOrigin::Signed(owner), order_id)
+	verify {
+		assert!(!Orders::<T>::contains_key(order_id));
+	}
+
+	impl_benchmark_test_suite!(
+		Orderbook,
+		crate::mock::new_test_ext_multi_currency(),
+		crate::mock::Test
+	);
+}
@@ -0,0 +1,394 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::FullCodec;
+use frame_support::{transactional, weights::Weight};
+use orml_traits::MultiReservableCurrency;
+use pallet_dex::traits::{Amm, OnSwap};
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, Zero},
+	ArithmeticError, FixedPointNumber, FixedPointOperand, FixedU128,
+};
+use sp_std::fmt::Debug;
+
+pub use pallet::*;
+pub use types::{Order, OrderId};
+pub use weights::WeightInfo;
+
+use types::*;
+
+mod types;
+pub mod weights;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// The weight of checking and filling a single order, excluding the swap itself.
+const FILL_ORDER_WEIGHT: Weight = 10_000;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	use super::*;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		type Balance: AtLeast32BitUnsigned
+			+ FixedPointOperand
+			+ MaybeSerializeDeserialize
+			+ FullCodec
+			+ Copy
+			+ Default
+			+ TypeInfo
+			+ MaxEncodedLen
+			+ Debug;
+
+		/// The type of assets used by the Assets handler.
+		type AssetId: FullCodec
+			+ MaxEncodedLen
+			+ Eq
+			+ PartialEq
+			+ Copy
+			+ Clone
+			+ MaybeSerializeDeserialize
+			+ Debug
+			+ Default
+			+ TypeInfo
+			+ Ord;
+
+		/// The type of a pools ID
+		type PoolId: FullCodec + MaxEncodedLen + Debug + TypeInfo + Eq + PartialEq + Copy;
+
+		/// The MultiCurrency handler, used to reserve the funds of open orders.
+		type Assets: MultiReservableCurrency<
+			Self::AccountId,
+			Balance = BalanceOf<Self>,
+			CurrencyId = Self::AssetId,
+		>;
+
+		/// The AMM the orders are settled against.
+		type Amm: Amm<
+			AccountId = Self::AccountId,
+			AssetId = Self::AssetId,
+			Balance = BalanceOf<Self>,
+			PoolId = Self::PoolId,
+		>;
+
+		/// The maximum number of open orders per pool. All orders of a pool are checked at once,
+		/// so this should be low enough for them to fit into the idle weight of a block.
+		#[pallet::constant]
+		type MaxOrdersPerPool: Get<u32>;
+
+		/// The asset the deposit of an order is reserved in.
+		#[pallet::constant]
+		type NativeAssetId: Get<Self::AssetId>;
+
+		/// The deposit that is reserved for every open order and returned once the order is
+		/// filled or cancelled. It makes filling the order books of the pools with small orders
+		/// costly.
+		#[pallet::constant]
+		type OrderDeposit: Get<BalanceOf<Self>>;
+
+		/// The maximum number of open orders of an account.
+		#[pallet::constant]
+		type MaxOrdersPerAccount: Get<u32>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Creates the pools the benchmarks place their orders on.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<Self::AccountId, Self::PoolId>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub (super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The id of the next order that is placed.
+	#[pallet::storage]
+	#[pallet::getter(fn next_order_id)]
+	pub type NextOrderId<T: Config> = StorageValue<_, OrderId, ValueQuery>;
+
+	/// Map the order id to the open order.
+	#[pallet::storage]
+	#[pallet::getter(fn orders)]
+	pub type Orders<T: Config> = StorageMap<_, Blake2_128Concat, OrderId, OrderOf<T>>;
+
+	/// The ids of the open orders of each pool in the order they were placed.
+	#[pallet::storage]
+	#[pallet::getter(fn orders_by_pool)]
+	pub type OrdersByPool<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		PoolIdOf<T>,
+		BoundedVec<OrderId, T::MaxOrdersPerPool>,
+		ValueQuery,
+	>;
+
+	/// The number of open orders of each account.
+	#[pallet::storage]
+	#[pallet::getter(fn order_count)]
+	pub type OrderCount<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	/// Pools whose price changed or that got a new order since their orders were last checked.
+	#[pallet::storage]
+	pub type PoolsToMatch<T: Config> = StorageMap<_, Blake2_128Concat, PoolIdOf<T>, ()>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub (super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A limit order was placed and its funds were reserved.
+		OrderPlaced {
+			order_id: OrderId,
+			owner: T::AccountId,
+			pool_id: PoolIdOf<T>,
+			asset_in: AssetIdOf<T>,
+			asset_out: AssetIdOf<T>,
+			amount_in: BalanceOf<T>,
+			limit_price: FixedU128,
+		},
+		/// An order was filled by swapping its funds on the pool.
+		OrderFilled {
+			order_id: OrderId,
+			owner: T::AccountId,
+			amount_in: BalanceOf<T>,
+			amount_out: BalanceOf<T>,
+		},
+		/// An order was cancelled and its funds were unreserved.
+		OrderCancelled { order_id: OrderId, owner: T::AccountId },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The amount of an order can't be zero.
+		InvalidAmount,
+		/// The limit price of an order can't be zero.
+		InvalidPrice,
+		/// The assets of the order are not traded by the pool.
+		InvalidAsset,
+		/// The pool has reached the maximum number of open orders.
+		TooManyOrders,
+		/// The order does not exist.
+		OrderNotFound,
+		/// Only the owner of an order can cancel it.
+		NotOrderOwner,
+		/// The pool doesn't offer the limit price of the order.
+		LimitPriceNotReached,
+		/// The funds of the order are no longer reserved.
+		InsufficientReservedBalance,
+		/// The account has reached the maximum number of open orders.
+		TooManyAccountOrders,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_idle(_n: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			let mut consumed_weight: Weight = 0;
+			// The keys are read one at a time, so only the pools that are checked are read
+			let mut pools = PoolsToMatch::<T>::iter_keys();
+
+			loop {
+				// Reading the next marked pool and its orders
+				if consumed_weight.saturating_add(T::DbWeight::get().reads(2)) > remaining_weight {
+					break
+				}
+				consumed_weight = consumed_weight.saturating_add(T::DbWeight::get().reads(1));
+				let pool_id = match pools.next() {
+					Some(pool_id) => pool_id,
+					None => break,
+				};
+				consumed_weight = consumed_weight.saturating_add(T::DbWeight::get().reads(1));
+				let order_ids = OrdersByPool::<T>::get(pool_id);
+
+				let required_weight = T::DbWeight::get().writes(1).saturating_add(
+					Self::fill_order_weight().saturating_mul(order_ids.len() as u64),
+				);
+				if consumed_weight.saturating_add(required_weight) > remaining_weight {
+					// The pool stays marked and is checked in one of the next blocks
+					break
+				}
+				consumed_weight = consumed_weight.saturating_add(required_weight);
+
+				// Filling an order is a swap that marks the pool again, so the other orders are
+				// checked against the new price in the next block.
+				PoolsToMatch::<T>::remove(pool_id);
+				for order_id in order_ids {
+					// Orders whose limit price isn't reached stay in the book
+					let _ = Self::fill_order(order_id);
+				}
+			}
+			consumed_weight
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Place a limit order to sell `amount_in` of `asset_in` for `asset_out` on the given
+		/// pool, receiving at least `limit_price` of `asset_out` per unit of `asset_in`.
+		///
+		/// The funds and the `OrderDeposit` are reserved until the order is filled or cancelled.
+		/// Orders are matched against the pool whenever a swap changed its price, once there is
+		/// idle weight left in a block. An order is always filled completely.
+		///
+		/// Emits `OrderPlaced` event when successful.
+		#[pallet::weight(T::WeightInfo::place_order())]
+		#[transactional]
+		pub fn place_order(
+			origin: OriginFor<T>,
+			pool_id: PoolIdOf<T>,
+			asset_in: AssetIdOf<T>,
+			asset_out: AssetIdOf<T>,
+			amount_in: BalanceOf<T>,
+			limit_price: FixedU128,
+		) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+
+			ensure!(!amount_in.is_zero(), Error::<T>::InvalidAmount);
+			ensure!(!limit_price.is_zero(), Error::<T>::InvalidPrice);
+			let assets = T::Amm::pool_assets(pool_id)?;
+			ensure!(
+				asset_in != asset_out && assets.contains(&asset_in) && assets.contains(&asset_out),
+				Error::<T>::InvalidAsset
+			);
+
+			OrderCount::<T>::try_mutate(&owner, |count| -> DispatchResult {
+				ensure!(*count < T::MaxOrdersPerAccount::get(), Error::<T>::TooManyAccountOrders);
+				*count = count.saturating_add(1);
+				Ok(())
+			})?;
+			let deposit = T::OrderDeposit::get();
+			T::Assets::reserve(T::NativeAssetId::get(), &owner, deposit)?;
+			T::Assets::reserve(asset_in, &owner, amount_in)?;
+
+			let order_id = Self::next_order_id();
+			NextOrderId::<T>::put(order_id.checked_add(1).ok_or(ArithmeticError::Overflow)?);
+			OrdersByPool::<T>::try_mutate(pool_id, |order_ids| {
+				order_ids.try_push(order_id).map_err(|_| Error::<T>::TooManyOrders)
+			})?;
+			Orders::<T>::insert(
+				order_id,
+				Order {
+					owner: owner.clone(),
+					pool_id,
+					asset_in,
+					asset_out,
+					amount_in,
+					limit_price,
+					deposit,
+				},
+			);
+			// The order might already be fillable at the current price
+			PoolsToMatch::<T>::insert(pool_id, ());
+
+			Self::deposit_event(Event::<T>::OrderPlaced {
+				order_id,
+				owner,
+				pool_id,
+				asset_in,
+				asset_out,
+				amount_in,
+				limit_price,
+			});
+			Ok(())
+		}
+
+		/// Cancel an open order and unreserve its funds and deposit.
+		///
+		/// Emits `OrderCancelled` event when successful.
+		#[pallet::weight(T::WeightInfo::cancel_order())]
+		#[transactional]
+		pub fn cancel_order(origin: OriginFor<T>, order_id: OrderId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let order = Self::orders(order_id).ok_or(Error::<T>::OrderNotFound)?;
+			ensure!(order.owner == who, Error::<T>::NotOrderOwner);
+
+			Self::remove_order(order_id, &order);
+			T::Assets::unreserve(order.asset_in, &order.owner, order.amount_in);
+
+			Self::deposit_event(Event::<T>::OrderCancelled { order_id, owner: who });
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The weight of checking and filling a single order, including the swap.
+	fn fill_order_weight() -> Weight {
+		T::DbWeight::get().reads_writes(12, 10).saturating_add(FILL_ORDER_WEIGHT)
+	}
+
+	/// Fills the order by selling its funds on the pool if the pool offers at least the limit
+	/// price of the order.
+	#[transactional]
+	fn fill_order(order_id: OrderId) -> DispatchResult {
+		let order = Self::orders(order_id).ok_or(Error::<T>::OrderNotFound)?;
+		let min_amount_out = order.limit_price.saturating_mul_int(order.amount_in);
+
+		let amount_out = T::Amm::quote_exact_in(
+			order.pool_id,
+			order.asset_in,
+			order.asset_out,
+			order.amount_in,
+		)?;
+		ensure!(amount_out >= min_amount_out, Error::<T>::LimitPriceNotReached);
+
+		let not_unreserved = T::Assets::unreserve(order.asset_in, &order.owner, order.amount_in);
+		ensure!(not_unreserved.is_zero(), Error::<T>::InsufficientReservedBalance);
+		let amount_out = T::Amm::swap_exact_in(
+			&order.owner,
+			order.pool_id,
+			order.asset_in,
+			order.asset_out,
+			order.amount_in,
+		)?;
+		ensure!(amount_out >= min_amount_out, Error::<T>::LimitPriceNotReached);
+
+		Self::remove_order(order_id, &order);
+
+		Self::deposit_event(Event::<T>::OrderFilled {
+			order_id,
+			owner: order.owner,
+			amount_in: order.amount_in,
+			amount_out,
+		});
+		Ok(())
+	}
+
+	/// Removes the order from the order book and unreserves its deposit.
+	fn remove_order(order_id: OrderId, order: &OrderOf<T>) {
+		Orders::<T>::remove(order_id);
+		OrdersByPool::<T>::mutate(order.pool_id, |order_ids| {
+			order_ids.retain(|id| *id != order_id);
+		});
+		OrderCount::<T>::mutate(&order.owner, |count| *count = count.saturating_sub(1));
+		T::Assets::unreserve(T::NativeAssetId::get(), &order.owner, order.deposit);
+	}
+}
+
+/// Creates the pools the benchmarks of the orderbook place their orders on.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AccountId, PoolId> {
+	/// Creates a pool with enough liquidity for orders and returns its id.
+	fn create_pool(owner: &AccountId) -> PoolId;
+}
+
+impl<T: Config> OnSwap<PoolIdOf<T>> for Pallet<T> {
+	fn on_swap(pool_id: PoolIdOf<T>) {
+		if OrdersByPool::<T>::decode_len(pool_id).unwrap_or(0) > 0 {
+			PoolsToMatch::<T>::insert(pool_id, ());
+		}
+	}
+}
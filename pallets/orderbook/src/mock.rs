@@ -0,0 +1,210 @@
+use frame_support::{
+	parameter_types,
+	traits::{ConstU128, ConstU16, ConstU32, ConstU64, Everything, GenesisBuild},
+	weights::constants::RocksDbWeight,
+	PalletId,
+};
+use frame_system as system;
+use orml_traits::parameter_type_with_key;
+use primitives::{CurrencyId, TokenSymbol};
+
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, ConvertInto, IdentityLookup, Zero},
+	Permill,
+};
+
+use crate as pallet_orderbook;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Tokens: orml_tokens::{Pallet, Storage, Config<T>, Event<T>},
+
+		Dex: pallet_dex::{Pallet, Call, Storage, Event<T>},
+		Orderbook: pallet_orderbook::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+impl system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = ConstU64<250>;
+	type DbWeight = RocksDbWeight;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+pub type AccountId = u64;
+pub type AssetId = CurrencyId;
+pub type Balance = u128;
+pub type PoolId = u128;
+
+parameter_types! {
+	pub const MaxLocks: u32 = 50;
+	pub const DexPalletId: PalletId = PalletId(*b"dex_pall");
+	pub const ProtocolFeeShare: Permill = Permill::zero();
+	pub const ProtocolFeeReceiver: Option<AccountId> = None;
+	pub const NativeAssetId: AssetId = NATIVE;
+}
+
+impl pallet_dex::Config for Test {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type PoolId = PoolId;
+	type PalletId = DexPalletId;
+	type Assets = Tokens;
+	type Convert = ConvertInto;
+	type LiquidityTokenConversion = primitives::token_conversion::LiquidityTokenConversion;
+	type MinimumLiquidity = ConstU128<1_000>;
+	type MaxAssetsInPool = ConstU32<4>;
+	type MaxTicksPerPool = ConstU32<16>;
+	type MaxPathLength = ConstU32<4>;
+	type MaxPoolsPerPair = ConstU32<3>;
+	type MaxOrdersPerBatch = ConstU32<4>;
+	type ProtocolFeeShare = ProtocolFeeShare;
+	type ProtocolFeeReceiver = ProtocolFeeReceiver;
+	type AdminOrigin = system::EnsureRoot<AccountId>;
+	type MaxObservations = ConstU32<16>;
+	type OnSwap = Orderbook;
+	type FlashSwapHandler = ();
+	type RegisteredAssets = Everything;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+impl pallet_orderbook::Config for Test {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type PoolId = PoolId;
+	type Assets = Tokens;
+	type Amm = Dex;
+	type MaxOrdersPerPool = ConstU32<4>;
+	type NativeAssetId = NativeAssetId;
+	type OrderDeposit = ConstU128<ORDER_DEPOSIT>;
+	type MaxOrdersPerAccount = ConstU32<3>;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = BenchmarkHelper;
+}
+
+/// Creates an ASSET_1/ASSET_2 pool for the benchmarks.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct BenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::BenchmarkHelper<AccountId, PoolId> for BenchmarkHelper {
+	fn create_pool(owner: &AccountId) -> PoolId {
+		use orml_traits::MultiCurrency;
+		use pallet_dex::traits::{CurrencyPair, PoolCreationParams, PoolKind};
+
+		Tokens::deposit(ASSET_1, owner, 100_000).unwrap();
+		Tokens::deposit(ASSET_2, owner, 100_000).unwrap();
+		let pair = CurrencyPair { token_a: ASSET_1, token_b: ASSET_2 };
+		let params = PoolCreationParams {
+			owner: *owner,
+			pair,
+			fee: Permill::from_percent(3),
+			kind: PoolKind::ConstantProduct,
+			initial_sqrt_price: None,
+		};
+		let pool_id = Dex::pool_count();
+		Dex::create_pool_with_liquidity(Origin::signed(*owner), params, 100_000, 100_000).unwrap();
+		pool_id
+	}
+}
+
+parameter_type_with_key! {
+	pub ExistentialDeposits: |_a: AssetId| -> Balance {
+		Zero::zero()
+	};
+}
+
+impl orml_tokens::Config for Test {
+	type Event = Event;
+	type Balance = Balance;
+	type Amount = primitives::Amount;
+	type CurrencyId = AssetId;
+	type WeightInfo = ();
+	type ExistentialDeposits = ExistentialDeposits;
+	type OnDust = ();
+	type OnNewTokenAccount = ();
+	type OnKilledTokenAccount = ();
+	type MaxLocks = MaxLocks;
+	type MaxReserves = ConstU32<0>;
+	type ReserveIdentifier = [u8; 8];
+	type DustRemovalWhitelist = Everything;
+}
+
+pub const ALICE: AccountId = 1;
+pub const BOB: AccountId = 2;
+
+pub const ASSET_1: AssetId = CurrencyId::Token(TokenSymbol::Short([0; 4]));
+pub const ASSET_2: AssetId = CurrencyId::Token(TokenSymbol::Short([1; 4]));
+pub const ASSET_3: AssetId = CurrencyId::Token(TokenSymbol::Short([2; 4]));
+pub const NATIVE: AssetId = CurrencyId::Native;
+
+pub const ORDER_DEPOSIT: Balance = 100;
+
+pub fn new_test_ext_multi_currency() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into();
+
+	let base_balance = 1_000_000;
+
+	let balances: Vec<(AccountId, AssetId, Balance)> = vec![
+		(ALICE, ASSET_1, base_balance),
+		(ALICE, ASSET_2, base_balance),
+		(ALICE, ASSET_3, base_balance),
+		(BOB, ASSET_1, base_balance),
+		(ALICE, NATIVE, base_balance),
+		(BOB, NATIVE, base_balance),
+	];
+
+	orml_tokens::GenesisConfig::<Test> { balances }
+		.assimilate_storage(&mut t)
+		.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	// set block number to 1 to make sure that events are populated
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+pub fn run_test<T>(test: T)
+where
+	T: FnOnce(),
+{
+	new_test_ext_multi_currency().execute_with(|| {
+		test();
+	});
+}
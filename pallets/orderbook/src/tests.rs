@@ -0,0 +1,343 @@
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{Get, Hooks},
+};
+use frame_system::{Config, EventRecord};
+use orml_traits::{MultiCurrency, MultiReservableCurrency};
+use pallet_dex::traits::{Amm, CurrencyPair, PoolCreationParams, PoolKind};
+
+use sp_core::H256;
+use sp_runtime::{
+	traits::{One, Zero},
+	FixedPointNumber, FixedU128, Permill,
+};
+
+use crate::{mock, mock::*, Error, Order, PoolsToMatch};
+
+pub fn assert_last_event<T, F>(matcher: F)
+where
+	T: Config,
+	F: Fn(&EventRecord<mock::Event, H256>) -> bool,
+{
+	assert!(matcher(System::events().last().expect("events expected")));
+}
+
+/// Creates a constant product pool for ASSET_1/ASSET_2 with reserves of 100_000 each.
+fn create_pool() -> PoolId {
+	let pair = CurrencyPair { token_a: ASSET_1, token_b: ASSET_2 };
+	let params = PoolCreationParams {
+		owner: ALICE,
+		pair,
+		fee: Permill::from_percent(3),
+		kind: PoolKind::ConstantProduct,
+		initial_sqrt_price: None,
+	};
+	assert_ok!(Dex::create_pool_with_liquidity(Origin::signed(ALICE), params, 100_000, 100_000));
+	let pool_id = 0;
+	pool_id
+}
+
+fn run_on_idle() {
+	Orderbook::on_idle(System::block_number(), u64::MAX);
+}
+
+#[test]
+fn place_order_should_work() {
+	run_test(|| {
+		let pool_id = create_pool();
+		let limit_price = FixedU128::saturating_from_rational(12, 10);
+
+		assert_ok!(Orderbook::place_order(
+			Origin::signed(BOB),
+			pool_id,
+			ASSET_1,
+			ASSET_2,
+			1_000,
+			limit_price
+		));
+
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), 999_000);
+		assert_eq!(Tokens::reserved_balance(ASSET_1, &BOB), 1_000);
+		assert_eq!(Tokens::reserved_balance(NATIVE, &BOB), ORDER_DEPOSIT);
+		assert_eq!(
+			Orderbook::orders(0),
+			Some(Order {
+				owner: BOB,
+				pool_id,
+				asset_in: ASSET_1,
+				asset_out: ASSET_2,
+				amount_in: 1_000,
+				limit_price,
+				deposit: ORDER_DEPOSIT,
+			})
+		);
+		assert_eq!(Orderbook::orders_by_pool(pool_id).into_inner(), vec![0]);
+		assert_eq!(Orderbook::order_count(BOB), 1);
+		assert_eq!(Orderbook::next_order_id(), 1);
+		assert!(PoolsToMatch::<Test>::contains_key(pool_id));
+
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Orderbook(crate::Event::OrderPlaced { order_id, owner, amount_in, .. })
+            if order_id == 0 && owner == BOB && amount_in == 1_000)
+		});
+	});
+}
+
+#[test]
+fn place_order_should_fail_for_invalid_orders() {
+	run_test(|| {
+		let pool_id = create_pool();
+		let limit_price = FixedU128::one();
+
+		assert_noop!(
+			Orderbook::place_order(Origin::signed(BOB), pool_id, ASSET_1, ASSET_2, 0, limit_price),
+			Error::<Test>::InvalidAmount
+		);
+		assert_noop!(
+			Orderbook::place_order(
+				Origin::signed(BOB),
+				pool_id,
+				ASSET_1,
+				ASSET_2,
+				1_000,
+				FixedU128::zero()
+			),
+			Error::<Test>::InvalidPrice
+		);
+		for (asset_in, asset_out) in [(ASSET_1, ASSET_3), (ASSET_1, ASSET_1)] {
+			assert_noop!(
+				Orderbook::place_order(
+					Origin::signed(BOB),
+					pool_id,
+					asset_in,
+					asset_out,
+					1_000,
+					limit_price
+				),
+				Error::<Test>::InvalidAsset
+			);
+		}
+		// The funds of the order have to be available
+		assert_noop!(
+			Orderbook::place_order(
+				Origin::signed(BOB),
+				pool_id,
+				ASSET_2,
+				ASSET_1,
+				1_000,
+				limit_price
+			),
+			orml_tokens::Error::<Test>::BalanceTooLow
+		);
+
+		for owner in [ALICE, ALICE, BOB, BOB] {
+			assert_ok!(Orderbook::place_order(
+				Origin::signed(owner),
+				pool_id,
+				ASSET_1,
+				ASSET_2,
+				1_000,
+				limit_price
+			));
+		}
+		assert_noop!(
+			Orderbook::place_order(
+				Origin::signed(BOB),
+				pool_id,
+				ASSET_1,
+				ASSET_2,
+				1_000,
+				limit_price
+			),
+			Error::<Test>::TooManyOrders
+		);
+	});
+}
+
+#[test]
+fn place_order_should_respect_limits_of_account() {
+	run_test(|| {
+		let pool_id = create_pool();
+		let limit_price = FixedU128::one();
+
+		// The deposit has to be available
+		let withdrawn = 1_000_000 - ORDER_DEPOSIT + 1;
+		assert_ok!(Tokens::withdraw(NATIVE, &BOB, withdrawn));
+		assert_noop!(
+			Orderbook::place_order(
+				Origin::signed(BOB),
+				pool_id,
+				ASSET_1,
+				ASSET_2,
+				1_000,
+				limit_price
+			),
+			orml_tokens::Error::<Test>::BalanceTooLow
+		);
+		assert_ok!(Tokens::deposit(NATIVE, &BOB, withdrawn));
+
+		for _ in 0..3 {
+			assert_ok!(Orderbook::place_order(
+				Origin::signed(BOB),
+				pool_id,
+				ASSET_1,
+				ASSET_2,
+				1_000,
+				limit_price
+			));
+		}
+		assert_eq!(Tokens::reserved_balance(NATIVE, &BOB), 3 * ORDER_DEPOSIT);
+		assert_noop!(
+			Orderbook::place_order(
+				Origin::signed(BOB),
+				pool_id,
+				ASSET_1,
+				ASSET_2,
+				1_000,
+				limit_price
+			),
+			Error::<Test>::TooManyAccountOrders
+		);
+
+		// Cancelling an order frees up a slot of the account
+		assert_ok!(Orderbook::cancel_order(Origin::signed(BOB), 0));
+		assert_eq!(Orderbook::order_count(BOB), 2);
+		assert_ok!(Orderbook::place_order(
+			Origin::signed(BOB),
+			pool_id,
+			ASSET_1,
+			ASSET_2,
+			1_000,
+			limit_price
+		));
+	});
+}
+
+#[test]
+fn cancel_order_should_work() {
+	run_test(|| {
+		let pool_id = create_pool();
+		assert_ok!(Orderbook::place_order(
+			Origin::signed(BOB),
+			pool_id,
+			ASSET_1,
+			ASSET_2,
+			1_000,
+			FixedU128::one()
+		));
+
+		assert_noop!(
+			Orderbook::cancel_order(Origin::signed(ALICE), 0),
+			Error::<Test>::NotOrderOwner
+		);
+		assert_noop!(Orderbook::cancel_order(Origin::signed(BOB), 1), Error::<Test>::OrderNotFound);
+
+		assert_ok!(Orderbook::cancel_order(Origin::signed(BOB), 0));
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), 1_000_000);
+		assert_eq!(Tokens::reserved_balance(ASSET_1, &BOB), 0);
+		assert_eq!(Tokens::reserved_balance(NATIVE, &BOB), 0);
+		assert!(Orderbook::orders(0).is_none());
+		assert!(Orderbook::orders_by_pool(pool_id).is_empty());
+		assert_eq!(Orderbook::order_count(BOB), 0);
+
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Orderbook(crate::Event::OrderCancelled { order_id, owner })
+            if order_id == 0 && owner == BOB)
+		});
+	});
+}
+
+#[test]
+fn order_should_be_filled_once_price_is_reached() {
+	run_test(|| {
+		let pool_id = create_pool();
+		let limit_price = FixedU128::saturating_from_rational(12, 10);
+		assert_ok!(Orderbook::place_order(
+			Origin::signed(BOB),
+			pool_id,
+			ASSET_1,
+			ASSET_2,
+			1_000,
+			limit_price
+		));
+
+		// The pool only offers less than 1 ASSET_2 per ASSET_1
+		run_on_idle();
+		assert!(Orderbook::orders(0).is_some());
+		assert_eq!(Tokens::reserved_balance(ASSET_1, &BOB), 1_000);
+		assert!(!PoolsToMatch::<Test>::contains_key(pool_id));
+
+		// Buying ASSET_1 raises its price above the limit price
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_2, 30_000, 0, None));
+		assert!(PoolsToMatch::<Test>::contains_key(pool_id));
+
+		let amount_out = Dex::quote_sell(pool_id, ASSET_1, 1_000).unwrap();
+		assert!(amount_out >= limit_price.saturating_mul_int(1_000));
+		run_on_idle();
+
+		assert!(Orderbook::orders(0).is_none());
+		assert!(Orderbook::orders_by_pool(pool_id).is_empty());
+		assert_eq!(Tokens::free_balance(ASSET_1, &BOB), 999_000);
+		assert_eq!(Tokens::reserved_balance(ASSET_1, &BOB), 0);
+		assert_eq!(Tokens::free_balance(ASSET_2, &BOB), amount_out);
+		assert_eq!(Tokens::reserved_balance(NATIVE, &BOB), 0);
+		assert_eq!(Orderbook::order_count(BOB), 0);
+
+		assert_last_event::<Test, _>(|e| {
+			matches!(e.event,
+            mock::Event::Orderbook(crate::Event::OrderFilled { order_id, owner, amount_in, amount_out: out })
+            if order_id == 0 && owner == BOB && amount_in == 1_000 && out == amount_out)
+		});
+	});
+}
+
+#[test]
+fn on_idle_should_respect_remaining_weight() {
+	run_test(|| {
+		let pool_id = create_pool();
+		assert_ok!(Orderbook::place_order(
+			Origin::signed(BOB),
+			pool_id,
+			ASSET_1,
+			ASSET_2,
+			1_000,
+			FixedU128::saturating_from_rational(12, 10)
+		));
+		assert_ok!(Dex::sell(Origin::signed(ALICE), pool_id, ASSET_2, 30_000, 0, None));
+
+		// Not enough weight to check the orders of the pool
+		assert_eq!(Orderbook::on_idle(System::block_number(), 0), 0);
+		assert!(Orderbook::orders(0).is_some());
+		assert!(PoolsToMatch::<Test>::contains_key(pool_id));
+
+		run_on_idle();
+		assert!(Orderbook::orders(0).is_none());
+	});
+}
+
+#[test]
+fn on_idle_should_only_read_the_pools_it_can_check() {
+	run_test(|| {
+		// Pools without orders only cost the reads of the key and the orders and the removal
+		for pool_id in 1..=3 {
+			PoolsToMatch::<Test>::insert(pool_id, ());
+		}
+		let db_weight = <Test as Config>::DbWeight::get();
+		let pool_weight = db_weight.reads_writes(2, 1);
+
+		// Not enough weight to read the first pool and its orders
+		let remaining_weight = db_weight.reads(2) - 1;
+		assert!(Orderbook::on_idle(System::block_number(), remaining_weight) <= remaining_weight);
+		assert_eq!(PoolsToMatch::<Test>::iter_keys().count(), 3);
+
+		// Two pools are checked, the third one is read but its removal doesn't fit anymore
+		let remaining_weight = pool_weight * 2 + db_weight.reads(2);
+		assert_eq!(Orderbook::on_idle(System::block_number(), remaining_weight), remaining_weight);
+		assert_eq!(PoolsToMatch::<Test>::iter_keys().count(), 1);
+
+		run_on_idle();
+		assert_eq!(PoolsToMatch::<Test>::iter_keys().count(), 0);
+	});
+}
@@ -0,0 +1,31 @@
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::RuntimeDebug;
+use scale_info::TypeInfo;
+use sp_runtime::FixedU128;
+
+use crate::Config;
+
+pub(crate) type BalanceOf<T> = <T as Config>::Balance;
+pub(crate) type AssetIdOf<T> = <T as Config>::AssetId;
+pub(crate) type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
+pub(crate) type PoolIdOf<T> = <T as Config>::PoolId;
+pub(crate) type OrderOf<T> = Order<AccountIdOf<T>, AssetIdOf<T>, BalanceOf<T>, PoolIdOf<T>>;
+
+pub type OrderId = u64;
+
+/// A limit order selling `amount_in` of `asset_in` for at least `limit_price` of `asset_out`
+/// per unit.
+#[derive(RuntimeDebug, Encode, Decode, MaxEncodedLen, Clone, PartialEq, Eq, TypeInfo)]
+pub struct Order<AccountId, AssetId, Balance, PoolId> {
+	pub owner: AccountId,
+	/// The pool the order is settled against.
+	pub pool_id: PoolId,
+	pub asset_in: AssetId,
+	pub asset_out: AssetId,
+	/// The amount of `asset_in` that is reserved until the order is filled or cancelled.
+	pub amount_in: Balance,
+	/// The minimum amount of `asset_out` received per unit of `asset_in`.
+	pub limit_price: FixedU128,
+	/// The deposit of the order, reserved in the native asset.
+	pub deposit: Balance,
+}
@@ -0,0 +1,65 @@
+//! Placeholder weights for pallet_orderbook
+//!
+//! The values were estimated by hand and have not been measured. The times are rounded guesses.
+//! The storage annotations list the accesses of the most expensive path through each call with
+//! the configuration of the runtime in this repository, every key is counted once and a component
+//! like `n` stands for the value of the component of the call. Replace this file with the output
+//! of the benchmarks in `benchmarking.rs` on the reference hardware before relying on the
+//! weights, e.g. with
+//! `./target/release/node-template benchmark pallet --chain=dev --steps=50 --repeat=20
+//! --pallet=pallet_orderbook --extrinsic=* --execution=wasm --wasm-execution=compiled
+//! --output=pallets/orderbook/src/weights.rs`.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_orderbook.
+pub trait WeightInfo {
+	fn place_order() -> Weight;
+	fn cancel_order() -> Weight;
+}
+
+/// Placeholder weights for pallet_orderbook, see the module documentation.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: Dex Pools (r:1 w:0)
+	// Storage: Dex MultiAssetPools (r:1 w:0)
+	// Storage: Orderbook OrderCount (r:1 w:1)
+	// Storage: Tokens Accounts (r:2 w:2)
+	// Storage: Orderbook NextOrderId (r:1 w:1)
+	// Storage: Orderbook OrdersByPool (r:1 w:1)
+	// Storage: Orderbook PoolsToMatch (r:0 w:1)
+	// Storage: Orderbook Orders (r:0 w:1)
+	fn place_order() -> Weight {
+		(41_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(7 as Weight))
+			.saturating_add(T::DbWeight::get().writes(7 as Weight))
+	}
+	// Storage: Orderbook Orders (r:1 w:1)
+	// Storage: Orderbook OrdersByPool (r:1 w:1)
+	// Storage: Orderbook OrderCount (r:1 w:1)
+	// Storage: Tokens Accounts (r:2 w:2)
+	fn cancel_order() -> Weight {
+		(35_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn place_order() -> Weight {
+		(41_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(7 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(7 as Weight))
+	}
+	fn cancel_order() -> Weight {
+		(35_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+}
@@ -150,6 +150,8 @@ pub type LongSymbol = [u8; 8];
 pub enum CurrencyId {
 	Token(TokenSymbol),
 	Native,
+	/// The liquidity token of the DEX pool with the given id.
+	LpToken(PoolId),
 }
 
 impl sp_std::default::Default for CurrencyId {
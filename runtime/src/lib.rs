@@ -35,11 +35,9 @@ use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 pub use sp_runtime::BuildStorage;
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
-	traits::{
-		AccountIdLookup, BlakeTwo256, Block as BlockT, ConvertInto, NumberFor, Zero,
-	},
+	traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, ConvertInto, NumberFor},
 	transaction_validity::{TransactionSource, TransactionValidity},
-	ApplyExtrinsicResult,
+	ApplyExtrinsicResult, DispatchError,
 };
 pub use sp_runtime::{Perbill, Permill};
 use sp_std::prelude::*;
@@ -50,6 +48,7 @@ use sp_version::RuntimeVersion;
 pub mod constants;
 use constants::{time::*};
 pub use pallet_dex;
+use pallet_dex::traits::{Amm, Pool};
 pub use primitives::{
 	self, AccountId, Amount, Balance, BlockNumber, CurrencyId, CurrencyId::Token, CurrencyInfo,
 	Hash, Index, Moment, Nonce, Signature, SignedFixedPoint, SignedInner, TokenSymbol,
@@ -236,15 +235,17 @@ parameter_types! {
 }
 
 parameter_type_with_key! {
-	pub ExistentialDeposits: |_currency_id: CurrencyId| -> Balance {
-		Zero::zero()
+	pub ExistentialDeposits: |currency_id: CurrencyId| -> Balance {
+		AssetRegistry::existential_deposit(*currency_id)
 	};
 }
 
 pub struct DustRemovalWhitelist;
 impl Contains<AccountId> for DustRemovalWhitelist {
 	fn contains(a: &AccountId) -> bool {
-		vec![].contains(a)
+		// The fee account holds less than the existential deposits while it swaps fees, the batch
+		// account holds the queued swaps of the dex, which can be of any size
+		vec![AssetTxPayment::account_id(), Dex::batch_account()].contains(a)
 	}
 }
 
@@ -281,10 +282,46 @@ impl pallet_nft::Config for Runtime {
 	type Assets = Tokens;
 	type StringLimit = StringLimit;
 	type MaxUniqueItemsOwned = MaxUniqueItemsOwned;
+	type WeightInfo = pallet_nft::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	// The name and the symbol of an asset are limited to 32 bytes
+	pub const AssetRegistryStringLimit: u32 = 32;
+}
+
+impl pallet_asset_registry::Config for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = CurrencyId;
+	type RegistryOrigin = frame_system::EnsureRoot<AccountId>;
+	type StringLimit = AssetRegistryStringLimit;
+	type WeightInfo = pallet_asset_registry::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = AssetRegistryBenchmarkHelper;
+}
+
+/// Provides the asset that is registered by the asset registry benchmarks.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct AssetRegistryBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_asset_registry::BenchmarkHelper<CurrencyId> for AssetRegistryBenchmarkHelper {
+	fn asset_id() -> CurrencyId {
+		CurrencyId::Token(TokenSymbol::Short(*b"ARB\0"))
+	}
 }
 
 parameter_types! {
 	pub const DexPalletId: PalletId = PalletId(*b"dex_pall");
+	/// One sixth of the swap fees goes to the pool owners.
+	pub const DexProtocolFeeShare: Permill = Permill::from_parts(166_667);
+	pub const DexProtocolFeeReceiver: Option<AccountId> = None;
+	/// Locked forever on the first deposit of a pool, like in Uniswap v2.
+	pub const DexMinimumLiquidity: Balance = 1_000;
+	/// Keep the prices of at least the last hour for the TWAP oracle. Observations are only
+	/// recorded in blocks that change the reserves, so quiet pools keep more than an hour.
+	pub const DexMaxObservations: u32 = HOURS;
 }
 
 /// Configure the pallet-dex
@@ -296,7 +333,155 @@ impl pallet_dex::Config for Runtime {
 	type PalletId = DexPalletId;
 	type Assets = Tokens;
 	type Convert = ConvertInto;
-	type LiquidityTokenConversion = primitives::token_conversion::CurrencyConversion;
+	type LiquidityTokenConversion = primitives::token_conversion::LiquidityTokenConversion;
+	type MinimumLiquidity = DexMinimumLiquidity;
+	type MaxAssetsInPool = ConstU32<8>;
+	type MaxTicksPerPool = ConstU32<512>;
+	type MaxPathLength = ConstU32<4>;
+	type MaxPoolsPerPair = ConstU32<4>;
+	type MaxOrdersPerBatch = ConstU32<64>;
+	type ProtocolFeeShare = DexProtocolFeeShare;
+	type ProtocolFeeReceiver = DexProtocolFeeReceiver;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxObservations = DexMaxObservations;
+	type OnSwap = Orderbook;
+	type FlashSwapHandler = ();
+	type RegisteredAssets = AssetRegistry;
+	type WeightInfo = pallet_dex::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = DexBenchmarkHelper;
+}
+
+/// Registers the assets of the dex benchmarks in the asset registry.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct DexBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_dex::traits::BenchmarkHelper<CurrencyId> for DexBenchmarkHelper {
+	fn register_asset(asset_id: CurrencyId) {
+		// The benchmarks use the same assets many times, they only have to be registered once
+		let _ = AssetRegistry::do_register_asset(
+			asset_id,
+			b"Benchmark".to_vec(),
+			b"BENCH".to_vec(),
+			12,
+			0,
+		);
+	}
+}
+
+parameter_types! {
+	// One UNIT of the native asset is reserved for every open order
+	pub const OrderDeposit: Balance = 1_000_000_000_000;
+}
+
+/// Configure the pallet-orderbook
+impl pallet_orderbook::Config for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = CurrencyId;
+	type PoolId = PoolId;
+	type Assets = Tokens;
+	type Amm = Dex;
+	type MaxOrdersPerPool = ConstU32<64>;
+	type NativeAssetId = GetNativeCurrencyId;
+	type OrderDeposit = OrderDeposit;
+	type MaxOrdersPerAccount = ConstU32<16>;
+	type WeightInfo = pallet_orderbook::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = OrderbookBenchmarkHelper;
+}
+
+/// Creates a funded pool of two registered assets for the orderbook benchmarks.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct OrderbookBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_orderbook::BenchmarkHelper<AccountId, PoolId> for OrderbookBenchmarkHelper {
+	fn create_pool(owner: &AccountId) -> PoolId {
+		use orml_traits::MultiCurrency;
+		use pallet_dex::traits::{BenchmarkHelper, CurrencyPair, PoolCreationParams, PoolKind};
+
+		let liquidity: Balance = 1_000_000_000_000_000_000;
+		let token_a = CurrencyId::Token(TokenSymbol::Short(*b"OBA\0"));
+		let token_b = CurrencyId::Token(TokenSymbol::Short(*b"OBB\0"));
+		for asset_id in [token_a, token_b] {
+			DexBenchmarkHelper::register_asset(asset_id);
+			Tokens::deposit(asset_id, owner, liquidity).unwrap();
+		}
+		let params = PoolCreationParams {
+			owner: owner.clone(),
+			pair: CurrencyPair { token_a, token_b },
+			fee: Permill::from_parts(3_000),
+			kind: PoolKind::ConstantProduct,
+			initial_sqrt_price: None,
+		};
+		let pool_id = Dex::pool_count();
+		Dex::create_pool_with_liquidity(
+			Origin::signed(owner.clone()),
+			params,
+			liquidity,
+			liquidity,
+		)
+		.unwrap();
+		pool_id
+	}
+}
+
+parameter_types! {
+	pub const FarmingPalletId: PalletId = PalletId(*b"farm_pal");
+}
+
+/// Configure the pallet-farming
+impl pallet_farming::Config for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = CurrencyId;
+	type PoolId = PoolId;
+	type Assets = Tokens;
+	type Amm = Dex;
+	type PalletId = FarmingPalletId;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxRewardsPerFarm = ConstU32<4>;
+	type WeightInfo = pallet_farming::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = FarmingBenchmarkHelper;
+}
+
+/// Creates the pools and registers the reward assets of the farming benchmarks.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct FarmingBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_farming::BenchmarkHelper<AccountId, CurrencyId, PoolId> for FarmingBenchmarkHelper {
+	fn create_pool(owner: &AccountId) -> PoolId {
+		// The owner of the pool receives its LP tokens
+		<OrderbookBenchmarkHelper as pallet_orderbook::BenchmarkHelper<_, _>>::create_pool(owner)
+	}
+
+	fn reward_asset(i: u32) -> CurrencyId {
+		use pallet_dex::traits::BenchmarkHelper;
+
+		let [_, _, high, low] = i.to_be_bytes();
+		let asset_id = CurrencyId::Token(TokenSymbol::Short([b'R', b'W', high, low]));
+		DexBenchmarkHelper::register_asset(asset_id);
+		asset_id
+	}
+}
+
+parameter_types! {
+	pub const AssetTxPaymentPalletId: PalletId = PalletId(*b"fee_pall");
+}
+
+/// Configure the pallet-asset-tx-payment
+impl pallet_asset_tx_payment::Config for Runtime {
+	type Event = Event;
+	type AssetId = CurrencyId;
+	type PoolId = PoolId;
+	type NativeAssetId = GetNativeCurrencyId;
+	type Assets = Tokens;
+	type Amm = Dex;
+	type PalletId = AssetTxPaymentPalletId;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -314,9 +499,14 @@ construct_runtime!(
 		Sudo: pallet_sudo,
 
 		Nfts: pallet_nft,
+		// The registry provides the existential deposits of the tokens, so it is built first
+		AssetRegistry: pallet_asset_registry,
 		Tokens: orml_tokens,
 		// Include the custom logic from the pallet-dex in the runtime.
 		Dex: pallet_dex,
+		Orderbook: pallet_orderbook,
+		Farming: pallet_farming,
+		AssetTxPayment: pallet_asset_tx_payment,
 	}
 );
 
@@ -335,12 +525,20 @@ pub type SignedExtra = (
 	frame_system::CheckEra<Runtime>,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
-	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_asset_tx_payment::ChargeAssetTxPayment<Runtime>,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
 /// The payload being signed in transactions.
 pub type SignedPayload = generic::SignedPayload<Call, SignedExtra>;
+/// The storage migrations that run on the next runtime upgrade. Each migration checks the
+/// on-chain storage version of its pallet, so it can stay here until it's removed.
+pub type Migrations = (
+	pallet_dex::migrations::v1::MigrateToV1<Runtime>,
+	pallet_dex::migrations::v2::MigrateToV2<Runtime>,
+	pallet_dex::migrations::v3::MigrateToV3<Runtime>,
+	pallet_dex::migrations::v4::MigrateToV4<Runtime>,
+);
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
 	Runtime,
@@ -348,6 +546,7 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
+	Migrations,
 >;
 
 #[cfg(feature = "runtime-benchmarks")]
@@ -357,6 +556,10 @@ mod benches {
 		[frame_system, SystemBench::<Runtime>]
 		[pallet_timestamp, Timestamp]
 		[pallet_dex, Dex]
+		[pallet_nft, Nfts]
+		[pallet_orderbook, Orderbook]
+		[pallet_farming, Farming]
+		[pallet_asset_registry, AssetRegistry]
 	);
 }
 
@@ -491,6 +694,52 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_dex_rpc_runtime_api::DexApi<Block, AccountId, CurrencyId, Balance, PoolId> for Runtime {
+		fn pools() -> Vec<(PoolId, Pool<AccountId, CurrencyId>)> {
+			Dex::all_pools()
+		}
+
+		fn pool_reserves(pool_id: PoolId) -> Result<(Balance, Balance), DispatchError> {
+			<Dex as Amm>::pool_reserves(pool_id)
+		}
+
+		fn get_exchange_value(
+			pool_id: PoolId,
+			asset_id: CurrencyId,
+			amount: Balance,
+		) -> Result<Balance, DispatchError> {
+			<Dex as Amm>::get_exchange_value(pool_id, asset_id, amount)
+		}
+
+		fn lp_token(pool_id: PoolId) -> Result<CurrencyId, DispatchError> {
+			<Dex as Amm>::lp_token(pool_id)
+		}
+
+		fn quote_buy(
+			pool_id: PoolId,
+			asset_id: CurrencyId,
+			amount: Balance,
+		) -> Result<Balance, DispatchError> {
+			<Dex as Amm>::quote_buy(pool_id, asset_id, amount)
+		}
+
+		fn quote_sell(
+			pool_id: PoolId,
+			asset_id: CurrencyId,
+			amount: Balance,
+		) -> Result<Balance, DispatchError> {
+			<Dex as Amm>::quote_sell(pool_id, asset_id, amount)
+		}
+
+		fn quote_best_route(
+			asset_in: CurrencyId,
+			asset_out: CurrencyId,
+			amount_in: Balance,
+		) -> Result<(Vec<(PoolId, Balance)>, Balance), DispatchError> {
+			Dex::quote_best_route(asset_in, asset_out, amount_in)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (